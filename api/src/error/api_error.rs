@@ -1,7 +1,14 @@
-use tdx_prover::error::{
-    db_error::DbError,
-    quote_error::QuoteError,
-    request_error::RequestError,
+use tdx_prover::{
+    entity::attempt::ErrorCategory,
+    error::{
+        agent_error::AgentError,
+        auth_error::AuthError,
+        db_error::DbError,
+        quote_error::QuoteError,
+        request_error::RequestError,
+        usage_error::UsageError,
+        webhook_error::WebhookError,
+    },
 };
 use crate::response::api_response::ApiErrorResponse;
 use axum::response::{IntoResponse, Response};
@@ -13,12 +20,22 @@ use thiserror::Error;
 pub enum ApiError {
     #[error("Invalid UUID: {0}")]
     InvalidUuid(String),
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
     #[error(transparent)]
     DbError(#[from] DbError),
     #[error(transparent)]
     RequestError(#[from] RequestError),
     #[error(transparent)]
     QuoteError(#[from] QuoteError),
+    #[error(transparent)]
+    AgentError(#[from] AgentError),
+    #[error(transparent)]
+    AuthError(#[from] AuthError),
+    #[error(transparent)]
+    UsageError(#[from] UsageError),
+    #[error(transparent)]
+    WebhookError(#[from] WebhookError),
     #[error("Something went wrong: {0}")]
     InvariantViolationError(String),
 }
@@ -30,10 +47,15 @@ impl IntoResponse for ApiError {
                 let status_code = StatusCode::BAD_REQUEST;
                 ApiErrorResponse::send(status_code.as_u16(), Some(error.to_string()))
             }
+            ApiError::InvalidQuery(error) => {
+                let status_code = StatusCode::BAD_REQUEST;
+                ApiErrorResponse::send(status_code.as_u16(), Some(error.to_string()))
+            }
             ApiError::DbError(ref error) => {
                 let status_code = match error {
                     DbError::SomethingWentWrong(_error) => StatusCode::INTERNAL_SERVER_ERROR,
                     DbError::UniqueConstraintViolation(_error) => StatusCode::INTERNAL_SERVER_ERROR,
+                    DbError::Pool(_error) => StatusCode::SERVICE_UNAVAILABLE,
                 };
                 ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
             },
@@ -42,6 +64,11 @@ impl IntoResponse for ApiError {
                     RequestError::NotFound => StatusCode::NOT_FOUND,
                     RequestError::Invalid => StatusCode::BAD_REQUEST,
                     RequestError::Unauthorized => StatusCode::UNAUTHORIZED,
+                    RequestError::Expired => StatusCode::GONE,
+                    RequestError::Cancelled => StatusCode::GONE,
+                    RequestError::Db(DbError::UniqueConstraintViolation(_)) => StatusCode::CONFLICT,
+                    RequestError::Db(DbError::Pool(_)) => StatusCode::SERVICE_UNAVAILABLE,
+                    RequestError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
                 };
                 ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
             },
@@ -55,6 +82,57 @@ impl IntoResponse for ApiError {
                     QuoteError::SubmitProof => StatusCode::INTERNAL_SERVER_ERROR,
                     QuoteError::Prove => StatusCode::INTERNAL_SERVER_ERROR,
                     QuoteError::VerifyProof => StatusCode::INTERNAL_SERVER_ERROR,
+                    QuoteError::InvalidTransition(_, _) => StatusCode::CONFLICT,
+                    QuoteError::StaleStatus => StatusCode::CONFLICT,
+                    QuoteError::UpdateStatus => StatusCode::INTERNAL_SERVER_ERROR,
+                    QuoteError::Attempt(ErrorCategory::QuoteParse, _) => StatusCode::BAD_REQUEST,
+                    QuoteError::Attempt(_, _) => StatusCode::INTERNAL_SERVER_ERROR,
+                    QuoteError::RetryNotAllowed(_) => StatusCode::CONFLICT,
+                    QuoteError::RetryScheduled(_, _) => StatusCode::SERVICE_UNAVAILABLE,
+                    QuoteError::Db(DbError::Pool(_)) => StatusCode::SERVICE_UNAVAILABLE,
+                    QuoteError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+            },
+            ApiError::AgentError(ref error) => {
+                let status_code = match error {
+                    AgentError::NotFound => StatusCode::NOT_FOUND,
+                    AgentError::Db(DbError::UniqueConstraintViolation(_)) => StatusCode::CONFLICT,
+                    AgentError::Db(DbError::Pool(_)) => StatusCode::SERVICE_UNAVAILABLE,
+                    AgentError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+            },
+            ApiError::AuthError(ref error) => {
+                let status_code = match error {
+                    AuthError::MissingKey => StatusCode::UNAUTHORIZED,
+                    AuthError::InvalidKey => StatusCode::UNAUTHORIZED,
+                    AuthError::MissingScope(_) => StatusCode::FORBIDDEN,
+                    AuthError::NotFound => StatusCode::NOT_FOUND,
+                    AuthError::Db(DbError::Pool(_)) => StatusCode::SERVICE_UNAVAILABLE,
+                    AuthError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+            },
+            ApiError::UsageError(ref error) => {
+                let status_code = match error {
+                    UsageError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+                    UsageError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+                    UsageError::NotFound => StatusCode::NOT_FOUND,
+                    UsageError::Db(DbError::Pool(_)) => StatusCode::SERVICE_UNAVAILABLE,
+                    UsageError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
+            },
+            ApiError::WebhookError(ref error) => {
+                let status_code = match error {
+                    WebhookError::NotFound => StatusCode::NOT_FOUND,
+                    WebhookError::DeliveryNotFound => StatusCode::NOT_FOUND,
+                    WebhookError::MissingTarget => StatusCode::BAD_REQUEST,
+                    WebhookError::RequestNotFound => StatusCode::NOT_FOUND,
+                    WebhookError::ForbiddenUrl(_) => StatusCode::BAD_REQUEST,
+                    WebhookError::Db(DbError::Pool(_)) => StatusCode::SERVICE_UNAVAILABLE,
+                    WebhookError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
                 };
                 ApiErrorResponse::send(status_code.as_u16(), Some(self.to_string()))
             },
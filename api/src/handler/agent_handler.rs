@@ -0,0 +1,207 @@
+use tdx_prover::dto::agent_dto::{AgentReadDto, AgentRegisterDto, AgentUpdateDto};
+use tdx_prover::dto::quote_dto::QuoteListDto;
+use tdx_prover::dto::request_dto::RequestReadDto;
+use tdx_prover::entity::agent::AgentStatus;
+use tdx_prover::entity::quote::{QuoteCursor, QuoteFilter, QuotePage, TdxQuoteStatus};
+use tdx_prover::entity::request::RequestFilter;
+use tdx_prover::repository::agent_repository::AgentRepositoryTrait;
+use tdx_prover::state::agent_state::AgentState;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use crate::error::api_error::ApiError;
+use crate::error::api_request_error::ValidatedRequest;
+use crate::response::api_response::ApiErrorResponse;
+
+const MAX_HISTORY_PAGE_SIZE: i64 = 200;
+
+fn parse_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|e| ApiError::InvalidUuid(e.to_string()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/agent",
+    tag = "agent",
+    request_body = AgentRegisterDto,
+    responses(
+        (status = 200, body = AgentReadDto),
+        (status = 400, description = "Invalid agent", body = ApiErrorResponse),
+        (status = 409, description = "Operator address already registered", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["admin"]))
+)]
+pub async fn register(
+    State(state): State<AgentState>,
+    ValidatedRequest(payload): ValidatedRequest<AgentRegisterDto>,
+) -> Result<Json<AgentReadDto>, ApiError> {
+    let agent = state.agent_service.register(&payload.to_new_agent()).await?;
+    Ok(Json(AgentReadDto::from(agent)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AgentListParams {
+    status: Option<AgentStatus>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/agents",
+    tag = "agent",
+    params(AgentListParams),
+    responses(
+        (status = 200, body = Vec<AgentReadDto>),
+        (status = 400, description = "Invalid status", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn list(
+    State(state): State<AgentState>,
+    Query(params): Query<AgentListParams>,
+) -> Result<Json<Vec<AgentReadDto>>, ApiError> {
+    let agents = state.agent_repo.find_all(params.status).await?;
+    Ok(Json(agents.into_iter().map(AgentReadDto::from).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/agent/{id}",
+    tag = "agent",
+    params(("id" = String, Path, description = "Agent id")),
+    responses(
+        (status = 200, body = AgentReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Agent not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn query(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+) -> Result<Json<AgentReadDto>, ApiError> {
+    let agent = state.agent_service.find(parse_id(&id)?).await?;
+    Ok(Json(AgentReadDto::from(agent)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/agent/{id}",
+    tag = "agent",
+    params(("id" = String, Path, description = "Agent id")),
+    request_body = AgentUpdateDto,
+    responses(
+        (status = 200, body = AgentReadDto),
+        (status = 400, description = "Invalid id or update", body = ApiErrorResponse),
+        (status = 404, description = "Agent not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["admin"]))
+)]
+pub async fn update(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    ValidatedRequest(payload): ValidatedRequest<AgentUpdateDto>,
+) -> Result<Json<AgentReadDto>, ApiError> {
+    let agent = state.agent_service.update(parse_id(&id)?, &payload.to_update()).await?;
+    Ok(Json(AgentReadDto::from(agent)))
+}
+
+/// Retires the agent, it is kept for the history of its requests and quotes
+#[utoipa::path(
+    delete,
+    path = "/api/agent/{id}",
+    tag = "agent",
+    params(("id" = String, Path, description = "Agent id")),
+    responses(
+        (status = 200, body = AgentReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Agent not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["admin"]))
+)]
+pub async fn retire(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+) -> Result<Json<AgentReadDto>, ApiError> {
+    let agent = state.agent_service.retire(parse_id(&id)?).await?;
+    Ok(Json(AgentReadDto::from(agent)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AgentHistoryParams {
+    status: Option<TdxQuoteStatus>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+/// Quotes produced for the agent, latest first
+#[utoipa::path(
+    get,
+    path = "/api/agent/{id}/quotes",
+    tag = "agent",
+    params(("id" = String, Path, description = "Agent id"), AgentHistoryParams),
+    responses(
+        (status = 200, body = QuoteListDto),
+        (status = 400, description = "Invalid id, filter or cursor", body = ApiErrorResponse),
+        (status = 404, description = "Agent not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn quotes(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    Query(params): Query<AgentHistoryParams>,
+) -> Result<Json<QuoteListDto>, ApiError> {
+    let agent = state.agent_service.find(parse_id(&id)?).await?;
+    let filter = QuoteFilter {
+        agent_id: Some(agent.id),
+        status: params.status,
+        ..Default::default()
+    };
+    let defaults = QuotePage::default();
+    let after = match params.cursor {
+        Some(cursor) => Some(QuoteCursor::decode(&cursor).map_err(ApiError::InvalidQuery)?),
+        None => None,
+    };
+    // Always latest first, cursors of listings sorted otherwise are rejected
+    let limit = params.limit.unwrap_or(defaults.limit).clamp(1, MAX_HISTORY_PAGE_SIZE);
+    let page = QuotePage::after(Some(defaults.sort_by), Some(defaults.order), limit, after)
+        .map_err(ApiError::InvalidQuery)?;
+    let (quotes, next_cursor) = state.quote_repo.find_page(&filter, &page).await?;
+    Ok(Json(QuoteListDto::from(quotes, next_cursor)))
+}
+
+/// Requests of the agent including cancelled ones, latest first. The status filter does not
+/// apply to requests.
+#[utoipa::path(
+    get,
+    path = "/api/agent/{id}/requests",
+    tag = "agent",
+    params(("id" = String, Path, description = "Agent id"), AgentHistoryParams),
+    responses(
+        (status = 200, body = Vec<RequestReadDto>),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Agent not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn requests(
+    State(state): State<AgentState>,
+    Path(id): Path<String>,
+    Query(params): Query<AgentHistoryParams>,
+) -> Result<Json<Vec<RequestReadDto>>, ApiError> {
+    let agent = state.agent_service.find(parse_id(&id)?).await?;
+    let filter = RequestFilter {
+        agent_id: Some(agent.id),
+        include_cancelled: true,
+        ..Default::default()
+    };
+    let limit = params.limit.unwrap_or(50).clamp(1, MAX_HISTORY_PAGE_SIZE);
+    let requests = state.request_repo.find_all(&filter, limit).await?;
+    Ok(Json(requests.into_iter().map(RequestReadDto::from).collect()))
+}
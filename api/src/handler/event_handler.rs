@@ -0,0 +1,222 @@
+use std::convert::Infallible;
+
+use tdx_prover::entity::quote::TdxQuoteStatus;
+use tdx_prover::entity::quote_update::{QuoteUpdate, QuoteUpdateFilter, QuoteUpdateKind};
+use tdx_prover::error::quote_error::QuoteError;
+use tdx_prover::events;
+use tdx_prover::service::lifecycle_service::is_terminal;
+use tdx_prover::state::event_state::EventState;
+use axum::{
+    extract::{Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+use utoipa::IntoParams;
+use uuid::Uuid;
+use crate::error::api_error::ApiError;
+use crate::response::api_response::ApiErrorResponse;
+
+fn parse_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|e| ApiError::InvalidUuid(e.to_string()))
+}
+
+/// Server-sent event named after the kind of `update`, with the update as JSON data
+fn to_event(update: &QuoteUpdate) -> Result<Event, Infallible> {
+    let event = Event::default().event(update.kind.to_string());
+    Ok(event.json_data(update).unwrap_or_else(|e| {
+        tracing::error!("Failed to encode update of quote {}: {}", update.quote_id, e);
+        Event::default().comment("unencodable update")
+    }))
+}
+
+/// When a stream of updates ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StreamEnd {
+    /// After the first update, e.g. the snapshot of a quote that already reached a final status
+    AfterFirst,
+    /// After the transition of the quote into a final status
+    AfterFinal,
+    Never,
+}
+
+/// Streams `first` followed by the updates of `receiver` that match `filter` until `end`. Streams
+/// that fall behind get a `lagged` event with the number of updates they missed.
+fn stream(
+    receiver: broadcast::Receiver<QuoteUpdate>,
+    filter: QuoteUpdateFilter,
+    first: Option<QuoteUpdate>,
+    end: StreamEnd,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut ended = false;
+    let updates = BroadcastStream::new(receiver)
+        .filter(move |update| match update {
+            Ok(update) => filter.matches(update),
+            Err(BroadcastStreamRecvError::Lagged(_)) => true,
+        })
+        // The final transition itself is still sent
+        .take_while(move |update| {
+            let open = !ended;
+            ended = end == StreamEnd::AfterFinal
+                && update.as_ref().is_ok_and(|update| update.kind == QuoteUpdateKind::Transition && is_terminal(update.status));
+            open
+        })
+        .map(|update| match update {
+            Ok(update) => to_event(&update),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => Ok(Event::default().event("lagged").data(skipped.to_string())),
+        })
+        // Taking none ends the stream without waiting for an update
+        .take(if end == StreamEnd::AfterFirst { 0 } else { usize::MAX });
+    let first = tokio_stream::iter(first).map(|update| to_event(&update));
+    Sse::new(first.chain(updates)).keep_alive(KeepAlive::default())
+}
+
+/// Live progress of a quote as server-sent events. The first event is a `snapshot` of the quote,
+/// followed by `transition`, `prover_request` and `confirmations` events as they happen. The
+/// stream ends after the transition into a final status, or after the snapshot of a quote that
+/// already reached one.
+#[utoipa::path(
+    get,
+    path = "/api/quote/{id}/events",
+    tag = "event",
+    params(("id" = String, Path, description = "Quote id")),
+    responses(
+        (status = 200, description = "Stream of updates, each event's data is one update", content_type = "text/event-stream", body = QuoteUpdate),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Quote not found", body = ApiErrorResponse),
+        (status = 500, description = "Database error", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn quote(
+    State(state): State<EventState>,
+    Path(id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let id = parse_id(&id)?;
+    // Subscribed before the quote is read so no update falls in between
+    let receiver = events::subscribe();
+    let quote = state.quote_repo.find_optional(id).await?.ok_or(QuoteError::NotFound)?;
+    let filter = QuoteUpdateFilter { quote_id: Some(id), ..Default::default() };
+    let end = if is_terminal(quote.status) { StreamEnd::AfterFirst } else { StreamEnd::AfterFinal };
+    Ok(stream(receiver, filter, Some(QuoteUpdate::snapshot(&quote)), end))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventListParams {
+    quote_id: Option<String>,
+    onchain_request_id: Option<String>,
+    agent_id: Option<String>,
+    /// Only updates leaving the quote in this status
+    status: Option<TdxQuoteStatus>,
+    kind: Option<QuoteUpdateKind>,
+}
+
+/// Live updates of every quote as server-sent events, narrowed down by the filters that are set
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "event",
+    params(EventListParams),
+    responses(
+        (status = 200, description = "Stream of updates, each event's data is one update", content_type = "text/event-stream", body = QuoteUpdate),
+        (status = 400, description = "Invalid filter", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn list(
+    Query(params): Query<EventListParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let filter = QuoteUpdateFilter {
+        quote_id: params.quote_id.as_deref().map(parse_id).transpose()?,
+        onchain_request_id: params.onchain_request_id.as_deref().map(parse_id).transpose()?,
+        agent_id: params.agent_id.as_deref().map(parse_id).transpose()?,
+        status: params.status,
+        kind: params.kind,
+    };
+    Ok(stream(events::subscribe(), filter, None, StreamEnd::Never))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use axum::{body::to_bytes, http::StatusCode, response::IntoResponse};
+    use chrono::Utc;
+    use tdx_prover::entity::quote::{quote_hash, TdxQuote};
+    use tdx_prover::entity::quote_event::QuoteActor;
+    use tdx_prover::repository::memory_repository::{InMemoryQuoteRepository, MemoryStore};
+
+    use super::*;
+
+    fn fixture(id: Uuid) -> TdxQuote {
+        TdxQuote {
+            id,
+            onchain_request_id: Uuid::new_v4(),
+            status: TdxQuoteStatus::Pending,
+            quote: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            proof_type: None,
+            txn_hash: None,
+            request_id: None,
+            quote_hash: quote_hash(&[]),
+            duplicate_of: None,
+            agent_id: None,
+        }
+    }
+
+    fn state_with(status: TdxQuoteStatus) -> (EventState, Uuid) {
+        let store = MemoryStore::default();
+        let id = Uuid::new_v4();
+        store.insert_quote(TdxQuote { status, ..fixture(id) });
+        (EventState { quote_repo: Arc::new(InMemoryQuoteRepository::with_store(&store)) }, id)
+    }
+
+    #[tokio::test]
+    async fn unknown_quote_is_not_found() {
+        let (state, _) = state_with(TdxQuoteStatus::Pending);
+        let response = quote(State(state), Path(Uuid::new_v4().to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn quote_stream_ends_after_the_final_transition() {
+        let (sender, receiver) = broadcast::channel(8);
+        let mut quote = TdxQuote { status: TdxQuoteStatus::Submitted, ..fixture(Uuid::new_v4()) };
+        let other = fixture(Uuid::new_v4());
+        let filter = QuoteUpdateFilter { quote_id: Some(quote.id), ..Default::default() };
+        let response = stream(receiver, filter, Some(QuoteUpdate::snapshot(&quote)), StreamEnd::AfterFinal).into_response();
+
+        sender.send(QuoteUpdate::confirmations(&quote, 1, 2)).unwrap();
+        sender.send(QuoteUpdate::snapshot(&other)).unwrap();
+        quote.status = TdxQuoteStatus::Confirmed;
+        sender.send(QuoteUpdate::transition(TdxQuoteStatus::Submitted, &quote, QuoteActor::Api, None)).unwrap();
+        sender.send(QuoteUpdate::confirmations(&quote, 3, 2)).unwrap();
+
+        // The sender is still open, the body only completes if the stream ended by itself
+        let body = tokio::time::timeout(Duration::from_secs(5), to_bytes(response.into_body(), usize::MAX))
+            .await
+            .expect("stream ended")
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        let events: Vec<&str> = body.lines().filter_map(|line| line.strip_prefix("event: ")).collect();
+        assert_eq!(events, ["snapshot", "confirmations", "transition"]);
+    }
+
+    #[tokio::test]
+    async fn final_quote_stream_ends_after_the_snapshot() {
+        let (state, id) = state_with(TdxQuoteStatus::Confirmed);
+        let response = quote(State(state), Path(id.to_string())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert_eq!(body.matches("event: ").count(), 1);
+        assert!(body.starts_with("event: snapshot"));
+    }
+}
@@ -0,0 +1,68 @@
+use tdx_prover::dto::fee_dto::{FeeReportDto, RequestFeeReportDto};
+use tdx_prover::entity::fee::FeeGroupBy;
+use tdx_prover::repository::fee_repository::TransactionFeeRepositoryTrait;
+use tdx_prover::state::fee_state::FeeState;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use crate::error::api_error::ApiError;
+use crate::response::api_response::ApiErrorResponse;
+
+/// Transactions sent for the request and what they cost
+#[utoipa::path(
+    get,
+    path = "/api/request/{id}/fees",
+    tag = "fee",
+    params(("id" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, body = RequestFeeReportDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn request_fees(
+    State(state): State<FeeState>,
+    Path(id): Path<String>,
+) -> Result<Json<RequestFeeReportDto>, ApiError> {
+    match Uuid::parse_str(&id) {
+        Ok(id) => {
+            let fees = state.fee_repo.find_all_by_onchain_request_id(id).await?;
+            Ok(Json(RequestFeeReportDto::from(id.to_string(), fees)))
+        }
+        Err(e) => Err(ApiError::InvalidUuid(e.to_string())),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FeeReportParams {
+    group_by: Option<FeeGroupBy>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+/// Fees of the transactions sent in the period, grouped by day unless `group_by` is set
+#[utoipa::path(
+    get,
+    path = "/api/fees/report",
+    tag = "fee",
+    params(FeeReportParams),
+    responses(
+        (status = 200, body = FeeReportDto),
+        (status = 400, description = "Invalid grouping or period", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn report(
+    State(state): State<FeeState>,
+    Query(params): Query<FeeReportParams>,
+) -> Result<Json<FeeReportDto>, ApiError> {
+    let group_by = params.group_by.unwrap_or(FeeGroupBy::Day);
+    let rows = state.fee_repo.report(group_by, params.from, params.to).await?;
+    Ok(Json(FeeReportDto::from(group_by, params.from, params.to, rows)))
+}
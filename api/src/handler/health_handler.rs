@@ -0,0 +1,42 @@
+use axum::{extract::State, http::StatusCode, Json};
+use tdx_prover::entity::health::HealthReport;
+use tdx_prover::state::health_state::HealthState;
+
+#[utoipa::path(
+    get,
+    path = "/api/health",
+    tag = "health",
+    responses((status = 200, description = "The API is up", body = String))
+)]
+pub async fn health() -> &'static str {
+    "Healthy..."
+}
+
+/// Only checks that the process answers, restarting it is the only fix for a failed liveness probe
+#[utoipa::path(
+    get,
+    path = "/api/health/live",
+    tag = "health",
+    responses((status = 200, description = "The process is serving requests", body = HealthReport))
+)]
+pub async fn live() -> Json<HealthReport> {
+    Json(HealthReport::new(vec![]))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every component is up or degraded", body = HealthReport),
+        (status = 503, description = "A component is down, see its detail", body = HealthReport),
+    )
+)]
+pub async fn ready(State(state): State<HealthState>) -> (StatusCode, Json<HealthReport>) {
+    let report = state.health_service.readiness().await;
+    let status = match report.is_ready() {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report))
+}
@@ -0,0 +1,15 @@
+use axum::{extract::State, http::header::CONTENT_TYPE, response::IntoResponse};
+use tdx_prover::{state::quote_state::QuoteState, telemetry};
+
+/// Prometheus text exposition, the quote status gauges are read from the database on each scrape
+#[utoipa::path(
+    get,
+    path = "/api/metrics",
+    tag = "metrics",
+    responses((status = 200, description = "Prometheus text format 0.0.4", body = String, content_type = "text/plain")),
+    security(("api_key" = ["admin"]))
+)]
+pub async fn metrics(State(state): State<QuoteState>) -> impl IntoResponse {
+    telemetry::refresh_quote_statuses(state.quote_repo.as_ref()).await;
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], telemetry::render())
+}
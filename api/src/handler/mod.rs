@@ -1,3 +1,10 @@
 #![allow(dead_code)]
+pub mod agent_handler;
+pub mod event_handler;
+pub mod fee_handler;
+pub mod health_handler;
+pub mod metrics_handler;
 pub mod quote_handler;
 pub mod request_handler;
+pub mod verification_handler;
+pub mod webhook_handler;
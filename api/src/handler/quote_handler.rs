@@ -1,9 +1,12 @@
-use tdx_prover::dto::quote_dto::QuoteReadDto;
+use tdx_prover::dto::quote_dto::{QuoteHistoryDto, QuoteListDto, QuoteReadDto};
 use tdx_prover::entity::zk::DcapProof;
 use tdx_prover::dto::quote_dto::QuoteRegisterDto;
-use tdx_prover::entity::quote::{ProofType, TdxQuote};
+use tdx_prover::entity::quote::{
+    ProofType, QuoteCursor, QuoteFilter, QuotePage, QuoteSortBy, SortOrder, TdxQuote, TdxQuoteStatus,
+};
 use tdx_prover::entity::dcap::DcapVerifiedOutput;
 use tdx_prover::error::db_error::DbError;
+use tdx_prover::repository::quote_event_repository::QuoteEventRepositoryTrait;
 use tdx_prover::repository::quote_repository::QuoteRepositoryTrait;
 use tdx_prover::state::quote_state::QuoteState;
 use axum::extract::Query;
@@ -11,10 +14,12 @@ use axum::{
     extract::{Extension, Path, State},
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 use crate::error::{api_error::ApiError, api_request_error::ValidatedRequest};
-use crate::response::api_response::ApiSuccessResponse;
+use crate::response::api_response::{ApiErrorResponse, ApiSuccessResponse};
 
 pub async fn get(
     Extension(quote): Extension<TdxQuote>,
@@ -22,6 +27,18 @@ pub async fn get(
     Json(ApiSuccessResponse::send(QuoteReadDto::from(quote)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/quote/{id}",
+    tag = "quote",
+    params(("id" = String, Path, description = "Quote id")),
+    responses(
+        (status = 200, body = QuoteReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 500, description = "Quote not found or database error", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
 pub async fn query(
     State(state): State<QuoteState>,
     Path(id): Path<String>,
@@ -39,6 +56,111 @@ pub async fn query(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/quote/{id}/history",
+    tag = "quote",
+    params(("id" = String, Path, description = "Quote id")),
+    responses(
+        (status = 200, body = QuoteHistoryDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 500, description = "Quote not found or database error", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn history(
+    State(state): State<QuoteState>,
+    Path(id): Path<String>,
+) -> Result<Json<QuoteHistoryDto>, ApiError> {
+    match Uuid::parse_str(&id) {
+        Ok(id) => {
+            let quote = state.quote_repo.find(id).await?;
+            let events = state.quote_event_repo.find_all_by_quote_id(id).await?;
+            Ok(Json(QuoteHistoryDto::from(&quote, events)))
+        }
+        Err(e) => Err(ApiError::InvalidUuid(e.to_string())),
+    }
+}
+
+/// Largest page `GET /quotes` returns
+const MAX_QUOTE_PAGE_SIZE: i64 = 200;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QuoteListParams {
+    status: Option<TdxQuoteStatus>,
+    proof_type: Option<ProofType>,
+    onchain_request_id: Option<String>,
+    model_id: Option<String>,
+    creator_address: Option<String>,
+    operator_address: Option<String>,
+    agent_id: Option<String>,
+    created_from: Option<DateTime<Utc>>,
+    created_to: Option<DateTime<Utc>>,
+    /// `created_at` (default) pages consistently. `updated_at` changes whenever a quote changes
+    /// status, so a quote updated while the pages are read may be skipped or returned twice.
+    sort_by: Option<QuoteSortBy>,
+    order: Option<SortOrder>,
+    limit: Option<i64>,
+    /// `next_cursor` of the previous page, only valid with the sorting of that page
+    cursor: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/quotes",
+    tag = "quote",
+    params(QuoteListParams),
+    responses(
+        (status = 200, body = QuoteListDto),
+        (status = 400, description = "Invalid filter or cursor", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn list(
+    State(state): State<QuoteState>,
+    Query(params): Query<QuoteListParams>,
+) -> Result<Json<QuoteListDto>, ApiError> {
+    let filter = QuoteFilter {
+        status: params.status,
+        proof_type: params.proof_type,
+        onchain_request_id: params.onchain_request_id
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| ApiError::InvalidUuid(e.to_string()))?,
+        model_id: params.model_id,
+        creator_address: params.creator_address,
+        operator_address: params.operator_address,
+        agent_id: params.agent_id
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| ApiError::InvalidUuid(e.to_string()))?,
+        created_from: params.created_from,
+        created_to: params.created_to,
+    };
+    let after = match params.cursor {
+        Some(cursor) => Some(QuoteCursor::decode(&cursor).map_err(ApiError::InvalidQuery)?),
+        None => None,
+    };
+    let limit = params.limit.unwrap_or(QuotePage::default().limit).clamp(1, MAX_QUOTE_PAGE_SIZE);
+    let page = QuotePage::after(params.sort_by, params.order, limit, after).map_err(ApiError::InvalidQuery)?;
+
+    let (quotes, next_cursor) = state.quote_repo.find_page(&filter, &page).await?;
+    Ok(Json(QuoteListDto::from(quotes, next_cursor)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/quote/register",
+    tag = "quote",
+    request_body = QuoteRegisterDto,
+    responses(
+        (status = 200, body = QuoteReadDto),
+        (status = 400, description = "Invalid quote", body = ApiErrorResponse),
+        (status = 409, description = "Quote is not allowed in its current status", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["register"]))
+)]
 pub async fn register(
     State(state): State<QuoteState>,
     ValidatedRequest(payload): ValidatedRequest<QuoteRegisterDto>,
@@ -50,6 +172,18 @@ pub async fn register(
     Ok(Json(QuoteReadDto::from(quote)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/quote/verify_dcap/{id}",
+    tag = "quote",
+    params(("id" = String, Path, description = "Quote id")),
+    responses(
+        (status = 200, body = DcapVerifiedOutput),
+        (status = 400, description = "Invalid id or quote", body = ApiErrorResponse),
+        (status = 500, description = "Verification failed", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
 pub async fn verify_dcap(
     State(state): State<QuoteState>,
     Path(id): Path<String>,
@@ -60,7 +194,7 @@ pub async fn verify_dcap(
                 state.quote_repo.find(id).await;
             match quote {
                 Ok(quote) => {
-                    let tcb = state.quote_service.verify_dcap(quote, None);
+                    let tcb = state.quote_service.verify_dcap(quote, None).await;
                     match tcb {
                         Ok(tcb) => Ok(Json(DcapVerifiedOutput::from_output(tcb))),
                         Err(e) => Err(ApiError::QuoteError(e)),
@@ -73,11 +207,26 @@ pub async fn verify_dcap(
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ProveParams {
     proof_type: ProofType,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/quote/prove/{id}",
+    tag = "quote",
+    params(("id" = String, Path, description = "Quote id"), ProveParams),
+    responses(
+        (status = 200, body = DcapProof),
+        (status = 400, description = "Invalid id or quote", body = ApiErrorResponse),
+        (status = 409, description = "Quote cannot be proved in its current status", body = ApiErrorResponse),
+        (status = 429, description = "Rate limit or proving quota exceeded", body = ApiErrorResponse),
+        (status = 500, description = "Proof generation failed", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["prove"]))
+)]
 pub async fn prove(
     State(state): State<QuoteState>,
     Path(id): Path<String>,
@@ -96,6 +245,18 @@ pub async fn prove(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/quote/verify",
+    tag = "quote",
+    request_body = DcapProof,
+    responses(
+        (status = 200, body = DcapVerifiedOutput),
+        (status = 400, description = "Invalid proof", body = ApiErrorResponse),
+        (status = 500, description = "Proof does not verify", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
 pub async fn verify(
     State(state): State<QuoteState>,
     ValidatedRequest(payload): ValidatedRequest<DcapProof>,
@@ -107,6 +268,18 @@ pub async fn verify(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/quote/submit_proof",
+    tag = "quote",
+    request_body = DcapProof,
+    responses(
+        (status = 200, body = DcapVerifiedOutput),
+        (status = 400, description = "Invalid proof", body = ApiErrorResponse),
+        (status = 500, description = "Submission failed", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["submit"]))
+)]
 pub async fn submit_proof(
     State(state): State<QuoteState>,
     ValidatedRequest(payload): ValidatedRequest<DcapProof>,
@@ -1,15 +1,19 @@
-use tdx_prover::dto::request_dto::RequestReadDto;
-use tdx_prover::entity::request::OnchainRequest;
+use tdx_prover::dto::request_dto::{RequestReadDto, RequestRegisterDto, RequestRegisteredDto, RequestUpdateDto};
+use tdx_prover::entity::api_key::ApiKey;
+use tdx_prover::entity::request::{OnchainRequest, RequestFilter};
 use tdx_prover::error::db_error::DbError;
 use tdx_prover::repository::request_repository::OnchainRequestRepositoryTrait;
 use tdx_prover::state::request_state::RequestState;
 use axum::{
-    extract::{Extension, Path, State},
+    extract::{Extension, Path, Query, State},
     Json,
 };
+use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 use crate::error::api_error::ApiError;
-use crate::response::api_response::ApiSuccessResponse;
+use crate::error::api_request_error::ValidatedRequest;
+use crate::response::api_response::{ApiErrorResponse, ApiSuccessResponse};
 
 pub async fn get(
     Extension(request): Extension<OnchainRequest>,
@@ -17,6 +21,18 @@ pub async fn get(
     Json(ApiSuccessResponse::send(RequestReadDto::from(request)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/request/{id}",
+    tag = "request",
+    params(("id" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, body = RequestReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 500, description = "Request not found or database error", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
 pub async fn query(
     State(state): State<RequestState>,
     Path(id): Path<String>,
@@ -32,3 +48,123 @@ pub async fn query(
         Err(e) => Err(ApiError::InvalidUuid(e.to_string())),
     }
 }
+
+const MAX_REQUEST_PAGE_SIZE: i64 = 200;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RequestListParams {
+    model_id: Option<String>,
+    creator_address: Option<String>,
+    operator_address: Option<String>,
+    agent_id: Option<String>,
+    include_cancelled: Option<bool>,
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/requests",
+    tag = "request",
+    params(RequestListParams),
+    responses(
+        (status = 200, body = Vec<RequestReadDto>),
+        (status = 400, description = "Invalid filter", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn list(
+    State(state): State<RequestState>,
+    Query(params): Query<RequestListParams>,
+) -> Result<Json<Vec<RequestReadDto>>, ApiError> {
+    let filter = RequestFilter {
+        model_id: params.model_id,
+        creator_address: params.creator_address,
+        operator_address: params.operator_address,
+        agent_id: params.agent_id
+            .map(|id| Uuid::parse_str(&id))
+            .transpose()
+            .map_err(|e| ApiError::InvalidUuid(e.to_string()))?,
+        include_cancelled: params.include_cancelled.unwrap_or(false),
+    };
+    let limit = params.limit.unwrap_or(50).clamp(1, MAX_REQUEST_PAGE_SIZE);
+    let requests = state.request_repo.find_all(&filter, limit).await?;
+    Ok(Json(requests.into_iter().map(RequestReadDto::from).collect()))
+}
+
+/// Registers a request, and its quote when the payload has one
+#[utoipa::path(
+    post,
+    path = "/api/request",
+    tag = "request",
+    request_body = RequestRegisterDto,
+    responses(
+        (status = 200, body = RequestRegisteredDto),
+        (status = 400, description = "Invalid request", body = ApiErrorResponse),
+        (status = 409, description = "Request id already registered", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["register"]))
+)]
+pub async fn register(
+    State(state): State<RequestState>,
+    api_key: Option<Extension<ApiKey>>,
+    ValidatedRequest(payload): ValidatedRequest<RequestRegisterDto>,
+) -> Result<Json<RequestRegisteredDto>, ApiError> {
+    let mut request = payload.to_new_request();
+    request.api_key_id = api_key.map(|Extension(api_key)| api_key.id);
+    let (request, quote) = state
+        .request_service
+        .register(request, payload.quote)
+        .await?;
+    Ok(Json(RequestRegisteredDto::from(request, quote)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/request/{id}",
+    tag = "request",
+    params(("id" = String, Path, description = "Request id")),
+    request_body = RequestUpdateDto,
+    responses(
+        (status = 200, body = RequestReadDto),
+        (status = 400, description = "Invalid id or update", body = ApiErrorResponse),
+        (status = 404, description = "Request not found or registered with another key", body = ApiErrorResponse),
+        (status = 410, description = "Request is cancelled", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["register"]))
+)]
+pub async fn update(
+    State(state): State<RequestState>,
+    Path(id): Path<String>,
+    api_key: Option<Extension<ApiKey>>,
+    ValidatedRequest(payload): ValidatedRequest<RequestUpdateDto>,
+) -> Result<Json<RequestReadDto>, ApiError> {
+    let id = Uuid::parse_str(&id).map_err(|e| ApiError::InvalidUuid(e.to_string()))?;
+    let owner = api_key.as_ref().map(|Extension(api_key)| api_key);
+    let request = state.request_service.update(id, &payload.to_update(), owner).await?;
+    Ok(Json(RequestReadDto::from(request)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/request/{id}",
+    tag = "request",
+    params(("id" = String, Path, description = "Request id")),
+    responses(
+        (status = 200, body = RequestReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Request not found or registered with another key", body = ApiErrorResponse),
+        (status = 410, description = "Request is already cancelled", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["register"]))
+)]
+pub async fn cancel(
+    State(state): State<RequestState>,
+    Path(id): Path<String>,
+    api_key: Option<Extension<ApiKey>>,
+) -> Result<Json<RequestReadDto>, ApiError> {
+    let id = Uuid::parse_str(&id).map_err(|e| ApiError::InvalidUuid(e.to_string()))?;
+    let owner = api_key.as_ref().map(|Extension(api_key)| api_key);
+    let request = state.request_service.cancel(id, owner).await?;
+    Ok(Json(RequestReadDto::from(request)))
+}
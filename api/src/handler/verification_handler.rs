@@ -0,0 +1,85 @@
+use tdx_prover::dto::verification_dto::{MrtdReportDto, TcbStatusReportDto, VerificationReadDto};
+use tdx_prover::repository::verification_repository::VerificationRepositoryTrait;
+use tdx_prover::state::verification_state::VerificationState;
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use crate::error::api_error::ApiError;
+use crate::response::api_response::ApiErrorResponse;
+
+/// Outputs the quote was verified with, by DCAP, a zkVM or the contract
+#[utoipa::path(
+    get,
+    path = "/api/quote/{id}/verifications",
+    tag = "verification",
+    params(("id" = String, Path, description = "Quote id")),
+    responses(
+        (status = 200, body = Vec<VerificationReadDto>),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn quote_verifications(
+    State(state): State<VerificationState>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<VerificationReadDto>>, ApiError> {
+    match Uuid::parse_str(&id) {
+        Ok(id) => {
+            let verifications = state.verification_repo.find_all_by_quote_id(id).await?;
+            Ok(Json(verifications.into_iter().map(VerificationReadDto::from).collect()))
+        }
+        Err(e) => Err(ApiError::InvalidUuid(e.to_string())),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerificationReportParams {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+/// Quotes per FMSPC and TCB status, e.g. how many platforms are out of date
+#[utoipa::path(
+    get,
+    path = "/api/verifications/tcb_status",
+    tag = "verification",
+    params(VerificationReportParams),
+    responses(
+        (status = 200, body = TcbStatusReportDto),
+        (status = 400, description = "Invalid period", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn tcb_status_report(
+    State(state): State<VerificationState>,
+    Query(params): Query<VerificationReportParams>,
+) -> Result<Json<TcbStatusReportDto>, ApiError> {
+    let rows = state.verification_repo.tcb_status_report(params.from, params.to).await?;
+    Ok(Json(TcbStatusReportDto::from(params.from, params.to, rows)))
+}
+
+/// MRTDs verified in the period, most recently seen first
+#[utoipa::path(
+    get,
+    path = "/api/verifications/mrtds",
+    tag = "verification",
+    params(VerificationReportParams),
+    responses(
+        (status = 200, body = MrtdReportDto),
+        (status = 400, description = "Invalid period", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn mrtd_report(
+    State(state): State<VerificationState>,
+    Query(params): Query<VerificationReportParams>,
+) -> Result<Json<MrtdReportDto>, ApiError> {
+    let rows = state.verification_repo.mrtd_report(params.from, params.to).await?;
+    Ok(Json(MrtdReportDto::from(params.from, params.to, rows)))
+}
@@ -0,0 +1,158 @@
+use tdx_prover::dto::webhook_dto::{WebhookCreatedDto, WebhookDeliveryReadDto, WebhookReadDto, WebhookRegisterDto};
+use tdx_prover::entity::api_key::ApiKey;
+use tdx_prover::state::webhook_state::WebhookState;
+use axum::{
+    extract::{Extension, Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use crate::error::api_error::ApiError;
+use crate::error::api_request_error::ValidatedRequest;
+use crate::response::api_response::ApiErrorResponse;
+
+const MAX_DELIVERY_PAGE_SIZE: i64 = 200;
+
+fn parse_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|e| ApiError::InvalidUuid(e.to_string()))
+}
+
+/// Registers a webhook for every request of the calling key, or for one request
+#[utoipa::path(
+    post,
+    path = "/api/webhook",
+    tag = "webhook",
+    request_body = WebhookRegisterDto,
+    responses(
+        (status = 200, description = "The webhook and its signing secret, which is not shown again", body = WebhookCreatedDto),
+        (status = 400, description = "Invalid URL or events, or no request id without an API key", body = ApiErrorResponse),
+        (status = 404, description = "Request not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["register"]))
+)]
+pub async fn register(
+    State(state): State<WebhookState>,
+    api_key: Option<Extension<ApiKey>>,
+    ValidatedRequest(payload): ValidatedRequest<WebhookRegisterDto>,
+) -> Result<Json<WebhookCreatedDto>, ApiError> {
+    let api_key = api_key.map(|Extension(api_key)| api_key);
+    let webhook = payload.to_new_webhook(api_key.as_ref().map(|api_key| api_key.id));
+    let webhook = state.webhook_service.register(api_key.as_ref(), webhook).await?;
+    Ok(Json(WebhookCreatedDto::from(webhook)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "webhook",
+    responses((status = 200, description = "Webhooks of the calling key, every webhook for admin keys", body = Vec<WebhookReadDto>)),
+    security(("api_key" = ["read"]))
+)]
+pub async fn list(
+    State(state): State<WebhookState>,
+    api_key: Option<Extension<ApiKey>>,
+) -> Result<Json<Vec<WebhookReadDto>>, ApiError> {
+    let api_key = api_key.map(|Extension(api_key)| api_key);
+    let webhooks = state.webhook_service.find_all(api_key.as_ref()).await?;
+    Ok(Json(webhooks.into_iter().map(WebhookReadDto::from).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhook/{id}",
+    tag = "webhook",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = WebhookReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Webhook not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn query(
+    State(state): State<WebhookState>,
+    api_key: Option<Extension<ApiKey>>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookReadDto>, ApiError> {
+    let api_key = api_key.map(|Extension(api_key)| api_key);
+    let webhook = state.webhook_service.find(api_key.as_ref(), parse_id(&id)?).await?;
+    Ok(Json(WebhookReadDto::from(webhook)))
+}
+
+/// Stops deliveries to the webhook, its delivery log is kept
+#[utoipa::path(
+    delete,
+    path = "/api/webhook/{id}",
+    tag = "webhook",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = WebhookReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Webhook not found or already disabled", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["register"]))
+)]
+pub async fn disable(
+    State(state): State<WebhookState>,
+    api_key: Option<Extension<ApiKey>>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookReadDto>, ApiError> {
+    let api_key = api_key.map(|Extension(api_key)| api_key);
+    let webhook = state.webhook_service.disable(api_key.as_ref(), parse_id(&id)?).await?;
+    Ok(Json(WebhookReadDto::from(webhook)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeliveryListParams {
+    limit: Option<i64>,
+}
+
+/// Delivery log of a webhook, most recent first
+#[utoipa::path(
+    get,
+    path = "/api/webhook/{id}/deliveries",
+    tag = "webhook",
+    params(("id" = String, Path, description = "Webhook id"), DeliveryListParams),
+    responses(
+        (status = 200, body = Vec<WebhookDeliveryReadDto>),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Webhook not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["read"]))
+)]
+pub async fn deliveries(
+    State(state): State<WebhookState>,
+    api_key: Option<Extension<ApiKey>>,
+    Path(id): Path<String>,
+    Query(params): Query<DeliveryListParams>,
+) -> Result<Json<Vec<WebhookDeliveryReadDto>>, ApiError> {
+    let api_key = api_key.map(|Extension(api_key)| api_key);
+    let limit = params.limit.unwrap_or(50).clamp(1, MAX_DELIVERY_PAGE_SIZE);
+    let deliveries = state.webhook_service.deliveries(api_key.as_ref(), parse_id(&id)?, limit).await?;
+    Ok(Json(deliveries.into_iter().map(WebhookDeliveryReadDto::from).collect()))
+}
+
+/// Sends a delivery again with the same payload, as a new delivery linked to the original
+#[utoipa::path(
+    post,
+    path = "/api/webhook/delivery/{id}/replay",
+    tag = "webhook",
+    params(("id" = String, Path, description = "Delivery id")),
+    responses(
+        (status = 200, description = "The queued replay", body = WebhookDeliveryReadDto),
+        (status = 400, description = "Invalid id", body = ApiErrorResponse),
+        (status = 404, description = "Delivery not found", body = ApiErrorResponse)
+    ),
+    security(("api_key" = ["register"]))
+)]
+pub async fn replay(
+    State(state): State<WebhookState>,
+    api_key: Option<Extension<ApiKey>>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookDeliveryReadDto>, ApiError> {
+    let api_key = api_key.map(|Extension(api_key)| api_key);
+    let delivery = state.webhook_service.replay(api_key.as_ref(), parse_id(&id)?).await?;
+    Ok(Json(WebhookDeliveryReadDto::from(delivery)))
+}
@@ -2,6 +2,10 @@ use std::sync::Arc;
 use tracing::info;
 use tdx_prover::config::database::{Database, DatabaseTrait};
 use tdx_prover::config::parameter;
+use tdx_prover::entity::quote_event::QuoteActor;
+use tdx_prover::service::finality_service::FinalityService;
+use tdx_prover::service::webhook_service::WebhookService;
+use tdx_prover::{events, telemetry};
 
 mod error;
 mod handler;
@@ -15,10 +19,30 @@ async fn main() {
 
     // initialize tracing for logging
     tracing_subscriber::fmt().init();
+    telemetry::install();
 
-    let connection = Database::init()
-        .await
-        .unwrap_or_else(|e| panic!("Database error: {}", e));
+    let connection = Arc::new(
+        Database::init()
+            .await
+            .unwrap_or_else(|e| panic!("Database error: {}", e)),
+    );
+
+    if parameter::get("FINALITY_CHECK_ENABLED", Some("true")) == "true" {
+        info!("Starting background finality check of submitted quotes");
+        tokio::spawn(FinalityService::new(&connection, QuoteActor::Api).run());
+    }
+
+    // The lambda and the CLI only queue deliveries, the API sends them
+    if parameter::get("WEBHOOK_DISPATCH_ENABLED", Some("true")) == "true" {
+        info!("Starting background webhook dispatch");
+        tokio::spawn(WebhookService::new(&connection).run());
+    }
+
+    // Without notifications the event streams only carry the updates made by this process
+    if events::notify_enabled() {
+        info!("Relaying quote updates of every process to the event streams");
+        tokio::spawn(events::listen());
+    }
 
     let port = std::env::var("PORT")
         .or_else(|_| Ok::<String, std::env::VarError>("8002".to_string()))
@@ -26,7 +50,7 @@ async fn main() {
 
     let host = format!("0.0.0.0:{}", port);
     let listener = tokio::net::TcpListener::bind(&host).await.unwrap();
-    axum::serve(listener, routes::root::routes(Arc::new(connection)))
+    axum::serve(listener, routes::root::routes(connection))
         .await
         .unwrap_or_else(|e| panic!("Server error: {}", e));
 
@@ -0,0 +1,60 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header::AUTHORIZATION, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use tdx_prover::state::auth_state::AuthState;
+
+use crate::error::api_error::ApiError;
+use crate::routes::root::required_scope;
+
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Rejects requests to a route without a key that grants the scope the route requires. The
+/// authenticated key is added to the request extensions for the handlers and later layers.
+pub async fn authorize(
+    State(state): State<AuthState>,
+    matched_path: Option<MatchedPath>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let path = matched_path.as_ref().map_or(request.uri().path(), |path| path.as_str());
+    let Some(scope) = required_scope(request.method(), path) else {
+        return Ok(next.run(request).await);
+    };
+    if !state.enabled {
+        return Ok(next.run(request).await);
+    }
+
+    let api_key = state.api_key_service.authenticate(presented_key(request.headers()), scope).await?;
+    request.extensions_mut().insert(api_key);
+    Ok(next.run(request).await)
+}
+
+/// The key from `Authorization: Bearer <key>`, or else from `x-api-key`
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()))
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_bearer_before_api_key_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(presented_key(&headers), None);
+
+        headers.insert(API_KEY_HEADER, "tdxp_header".parse().unwrap());
+        assert_eq!(presented_key(&headers), Some("tdxp_header"));
+
+        headers.insert(AUTHORIZATION, "Bearer tdxp_bearer".parse().unwrap());
+        assert_eq!(presented_key(&headers), Some("tdxp_bearer"));
+    }
+}
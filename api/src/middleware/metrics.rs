@@ -0,0 +1,20 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use tdx_prover::telemetry;
+
+/// Records the status and latency of every request under its route template, requests that
+/// match no route are grouped under `unmatched` to keep the label set small
+pub async fn track(matched_path: Option<MatchedPath>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = matched_path.map_or("unmatched".to_string(), |path| path.as_str().to_string());
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+    telemetry::record_http(&method, &route, response.status().as_u16(), started.elapsed());
+    response
+}
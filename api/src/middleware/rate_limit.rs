@@ -0,0 +1,117 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tdx_prover::{
+    entity::{
+        api_key::{ApiKey, ApiScope},
+        usage::{Allowance, QuotaSubject},
+    },
+    error::usage_error::UsageError,
+    state::usage_state::UsageState,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::error::api_error::ApiError;
+use crate::routes::root::required_scope;
+
+/// Applies the per minute rate limit of the authenticated key to the route and, on proving
+/// routes, the proving quotas of the key and of the creator of the quote's request. Runs after
+/// `auth::authorize`, requests without a key are only subject to creator quotas. A proof is
+/// counted before the handler runs, so concurrent requests cannot exceed a quota, and given back
+/// when the handler does not succeed.
+pub async fn limit(
+    State(state): State<UsageState>,
+    matched_path: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = matched_path.as_ref().map_or(request.uri().path(), |path| path.as_str()).to_string();
+    let Some(scope) = required_scope(request.method(), &path) else {
+        return next.run(request).await;
+    };
+    let route = format!("{} {}", request.method(), path);
+    let api_key = request.extensions().get::<ApiKey>().cloned();
+
+    let rate = match &api_key {
+        Some(api_key) => state.usage_service.hit_rate_limit(api_key, &route, scope).await,
+        None => Ok(None),
+    };
+    let rate = match rate {
+        Ok(rate) => rate,
+        Err(e) => return rejected(e),
+    };
+
+    let mut quota = None;
+    let mut charge = None;
+    if scope == ApiScope::Prove {
+        let subjects = quota_subjects(&state, api_key.as_ref(), request.uri().path()).await;
+        let charged_at = Utc::now();
+        quota = match state.usage_service.consume_proof_at(&subjects, charged_at).await {
+            Ok(quota) => quota,
+            Err(e) => return rejected(e),
+        };
+        charge = quota.map(|_| (subjects, charged_at));
+    }
+
+    let mut response = next.run(request).await;
+    if let Some((subjects, charged_at)) = charge.filter(|_| !response.status().is_success()) {
+        match state.usage_service.refund_proof(&subjects, charged_at).await {
+            Ok(()) => quota = quota.map(|quota| Allowance { remaining: quota.remaining + 1, ..quota }),
+            Err(e) => tracing::warn!("Failed to refund the proof of {}: {}", route, e),
+        }
+    }
+    insert_allowance(response.headers_mut(), "ratelimit", rate);
+    insert_allowance(response.headers_mut(), "quota", quota);
+    response
+}
+
+/// The key and, when the quote can be found, the creator of its request
+async fn quota_subjects(state: &UsageState, api_key: Option<&ApiKey>, path: &str) -> Vec<QuotaSubject> {
+    let mut subjects = api_key.map(|api_key| vec![QuotaSubject::ApiKey(api_key.id)]).unwrap_or_default();
+    // Proving routes end with the quote id, an invalid one is rejected by the handler
+    let Some(quote_id) = path.rsplit('/').next().and_then(|id| Uuid::parse_str(id).ok()) else {
+        return subjects;
+    };
+    if let Ok(quote) = state.quote_repo.find(quote_id).await {
+        if let Ok(request) = state.request_repo.find(quote.onchain_request_id).await {
+            subjects.push(QuotaSubject::Creator(request.creator_address));
+        }
+    }
+    subjects
+}
+
+fn rejected(error: UsageError) -> Response {
+    let allowance = match &error {
+        UsageError::RateLimited(allowance) => Some(("ratelimit", *allowance)),
+        UsageError::QuotaExceeded(allowance) => Some(("quota", *allowance)),
+        _ => None,
+    };
+    let mut response = ApiError::UsageError(error).into_response();
+    if let Some((name, allowance)) = allowance {
+        insert_allowance(response.headers_mut(), name, Some(allowance));
+        let retry_after = (allowance.reset_at - chrono::Utc::now()).num_seconds().max(1);
+        response.headers_mut().insert("retry-after", HeaderValue::from(retry_after));
+    }
+    response
+}
+
+/// Sets `x-<name>-limit`, `x-<name>-remaining` and `x-<name>-reset` (unix seconds)
+fn insert_allowance(headers: &mut HeaderMap, name: &str, allowance: Option<Allowance>) {
+    let Some(allowance) = allowance else {
+        return;
+    };
+    let values = [
+        ("limit", allowance.limit as i64),
+        ("remaining", allowance.remaining as i64),
+        ("reset", allowance.reset_at.timestamp()),
+    ];
+    for (field, value) in values {
+        if let Ok(header) = format!("x-{}-{}", name, field).parse::<axum::http::HeaderName>() {
+            headers.insert(header, HeaderValue::from(value));
+        }
+    }
+}
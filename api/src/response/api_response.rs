@@ -8,7 +8,7 @@ pub struct ApiSuccessResponse<T: Serialize> {
     data: T,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, utoipa::ToSchema)]
 pub struct ApiErrorResponse {
     message: Option<String>,
     #[serde(rename = "code")]
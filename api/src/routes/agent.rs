@@ -0,0 +1,16 @@
+use tdx_prover::state::agent_state::AgentState;
+use axum::{routing::{get, post}, Router};
+
+use crate::handler::agent_handler;
+
+pub fn routes() -> Router<AgentState> {
+    Router::new()
+        .route("/agent", post(agent_handler::register))
+        .route("/agents", get(agent_handler::list))
+        .route(
+            "/agent/{id}",
+            get(agent_handler::query).put(agent_handler::update).delete(agent_handler::retire),
+        )
+        .route("/agent/{id}/quotes", get(agent_handler::quotes))
+        .route("/agent/{id}/requests", get(agent_handler::requests))
+}
@@ -0,0 +1,264 @@
+use axum::{routing::get, Json, Router};
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
+use tdx_prover::entity::quote::{QuoteSortBy, SortOrder};
+use tdx_prover::entity::webhook::WebhookPayload;
+use utoipa_scalar::{Scalar, Servable};
+
+use crate::handler::{
+    agent_handler, event_handler, fee_handler, health_handler, metrics_handler, quote_handler, request_handler,
+    verification_handler, webhook_handler,
+};
+
+/// OpenAPI document of every route the API serves
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "TDX Prover API", description = "Registers TDX quotes and their requests, proves and submits them on-chain"),
+    paths(
+        health_handler::health,
+        health_handler::live,
+        health_handler::ready,
+        quote_handler::list,
+        quote_handler::register,
+        quote_handler::query,
+        quote_handler::history,
+        quote_handler::verify_dcap,
+        quote_handler::prove,
+        quote_handler::verify,
+        quote_handler::submit_proof,
+        request_handler::register,
+        request_handler::list,
+        request_handler::query,
+        request_handler::update,
+        request_handler::cancel,
+        fee_handler::request_fees,
+        fee_handler::report,
+        verification_handler::quote_verifications,
+        verification_handler::tcb_status_report,
+        verification_handler::mrtd_report,
+        agent_handler::register,
+        agent_handler::list,
+        agent_handler::query,
+        agent_handler::update,
+        agent_handler::retire,
+        agent_handler::quotes,
+        agent_handler::requests,
+        webhook_handler::register,
+        webhook_handler::list,
+        webhook_handler::query,
+        webhook_handler::disable,
+        webhook_handler::deliveries,
+        webhook_handler::replay,
+        event_handler::quote,
+        event_handler::list,
+        metrics_handler::metrics,
+    ),
+    // Body of the requests sent to webhooks, no route returns it. Schemas only used in query
+    // parameters are not collected from the paths either.
+    components(schemas(WebhookPayload, QuoteSortBy, SortOrder)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "quote", description = "TDX quotes, their proofs and verification"),
+        (name = "request", description = "On-chain requests the quotes are proved for"),
+        (name = "fee", description = "Gas paid for the proof transactions"),
+        (name = "verification", description = "Verified outputs of the quotes and reports over them"),
+        (name = "agent", description = "Operators requesting attestations, with their expected measurements"),
+        (name = "webhook", description = "Signed callbacks on proof and submission events, and their delivery log"),
+        (name = "event", description = "Live quote progress as server-sent events"),
+        (name = "health", description = "Liveness and readiness of the API and its dependencies"),
+        (name = "metrics", description = "Prometheus metrics of the API and the quotes"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The API key of `middleware::auth`, the scope each operation needs is listed on it
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        let scheme = HttpBuilder::new()
+            .scheme(HttpAuthScheme::Bearer)
+            .description(Some("API key created with the CLI, also accepted in the x-api-key header"))
+            .build();
+        components.add_security_scheme("api_key", SecurityScheme::Http(scheme));
+    }
+}
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/openapi.json", get(|| async { Json(ApiDoc::openapi()) }))
+        .merge(Scalar::with_url("/docs", ApiDoc::openapi()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::{json, Map, Value};
+    use std::collections::BTreeSet;
+    use tdx_prover_client::models;
+
+    /// A value for `schema` with every property set, references are resolved against `document`
+    fn sample(schema: &Value, document: &Value) -> Value {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            let component = &document["components"]["schemas"][name];
+            assert!(!component.is_null(), "{} is referenced but not a component", name);
+            return sample(component, document);
+        }
+        if let Some(variants) = schema["oneOf"].as_array().or(schema["anyOf"].as_array()) {
+            let variant = variants.iter().find(|variant| variant["type"] != "null").expect("a non-null variant");
+            return sample(variant, document);
+        }
+        if let Some(parts) = schema["allOf"].as_array() {
+            let mut merged = Map::new();
+            for part in parts {
+                merged.extend(sample(part, document).as_object().expect("an object part").clone());
+            }
+            return Value::Object(merged);
+        }
+        if let Some(values) = schema["enum"].as_array() {
+            return values.iter().find(|value| !value.is_null()).expect("an enum value").clone();
+        }
+        let types = match &schema["type"] {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            Value::String(name) => vec![name.as_str()],
+            _ => vec![],
+        };
+        match types.into_iter().find(|name| *name != "null") {
+            Some("string") => match schema["format"].as_str() {
+                Some("date-time") => json!("2025-05-19T12:00:00Z"),
+                _ => json!("text"),
+            },
+            Some("integer") => json!(1),
+            Some("number") => json!(1.5),
+            Some("boolean") => json!(true),
+            Some("array") => json!([sample(&schema["items"], document)]),
+            _ => {
+                let properties = schema["properties"].as_object().cloned().unwrap_or_default();
+                Value::Object(properties.iter().map(|(name, property)| (name.clone(), sample(property, document))).collect())
+            }
+        }
+    }
+
+    /// Required properties of `schema`, including those of the parts it is made of
+    fn required(schema: &Value, document: &Value) -> Vec<String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            return required(&document["components"]["schemas"][name], document);
+        }
+        let parts = schema["allOf"].as_array().into_iter().flatten();
+        let own = schema["required"].as_array().into_iter().flatten().filter_map(Value::as_str).map(str::to_string);
+        own.chain(parts.flat_map(|part| required(part, document))).collect()
+    }
+
+    /// Checks that `T` reads a sample of `schema` and writes it back unchanged, and that it reads
+    /// the sample without each property that is not required
+    fn assert_model<T: Serialize + DeserializeOwned>(name: &str, schema: &Value, document: &Value) {
+        let values = match schema["enum"].as_array() {
+            Some(values) => values.clone(),
+            None => vec![sample(schema, document)],
+        };
+        for value in values {
+            let model: T = serde_json::from_value(value.clone())
+                .unwrap_or_else(|e| panic!("client model {} cannot read {}: {}", name, value, e));
+            let written = serde_json::to_value(&model).unwrap();
+            assert_eq!(written, value, "client model {} does not match its schema", name);
+        }
+
+        let sample = sample(schema, document);
+        let required = required(schema, document);
+        for property in sample.as_object().into_iter().flat_map(|sample| sample.keys()) {
+            if required.contains(property) {
+                continue;
+            }
+            let mut partial = sample.clone();
+            partial.as_object_mut().unwrap().remove(property);
+            if let Err(e) = serde_json::from_value::<T>(partial) {
+                panic!("client model {} needs {}, which is optional: {}", name, property, e);
+            }
+        }
+    }
+
+    /// Object schema of the query parameters of the `GET` operation at `path`
+    fn query_schema(document: &Value, path: &str) -> Value {
+        let parameters = document["paths"][path]["get"]["parameters"].as_array().cloned().unwrap_or_default();
+        let query = parameters.iter().filter(|parameter| parameter["in"] == "query");
+        let properties: Map<String, Value> = query.clone()
+            .map(|parameter| (parameter["name"].as_str().unwrap().to_string(), parameter["schema"].clone()))
+            .collect();
+        let required: Vec<Value> = query
+            .filter(|parameter| parameter["required"] == true)
+            .map(|parameter| parameter["name"].clone())
+            .collect();
+        json!({ "type": "object", "properties": properties, "required": required })
+    }
+
+    #[test]
+    fn client_models_match_the_document() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let components = document["components"]["schemas"].as_object().unwrap();
+        let mut checked = BTreeSet::new();
+        macro_rules! check {
+            ($($model:ident),* $(,)?) => {$(
+                let name = stringify!($model);
+                let schema = components.get(name).unwrap_or_else(|| panic!("{} is not a schema of the document", name));
+                assert_model::<models::$model>(name, schema, &document);
+                checked.insert(name.to_string());
+            )*};
+        }
+        check!(
+            ProofType, TdxQuoteStatus, QuoteActor, QuoteSortBy, SortOrder, QuoteBodyType,
+            QuoteReadDto, QuoteRegisterDto, QuoteListDto, QuoteEventReadDto, QuoteHistoryDto,
+            DcapVerifiedOutput, DcapProof,
+            RequestReadDto, RequestRegisterDto, RequestUpdateDto, RequestRegisteredDto,
+            TransactionFeeReadDto, RequestFeeReportDto, FeeGroupBy, FeeReportEntryDto, FeeReportDto,
+            VerificationSource, VerificationReadDto, TcbStatusEntryDto, TcbStatusReportDto, MrtdEntryDto, MrtdReportDto,
+            AgentStatus, AgentRegisterDto, AgentUpdateDto, AgentReadDto,
+            WebhookEvent, DeliveryStatus, WebhookRegisterDto, WebhookReadDto, WebhookCreatedDto,
+            WebhookDeliveryReadDto, WebhookPayload,
+            QuoteUpdateKind, QuoteUpdate,
+            HealthStatus, ComponentHealth, HealthReport,
+            ApiErrorResponse,
+        );
+        let documented: BTreeSet<String> = components.keys().cloned().collect();
+        assert_eq!(documented, checked, "every schema needs a client model");
+
+        assert_model::<models::QuoteListParams>("QuoteListParams", &query_schema(&document, "/api/quotes"), &document);
+        assert_model::<models::RequestListParams>("RequestListParams", &query_schema(&document, "/api/requests"), &document);
+        assert_model::<models::EventListParams>("EventListParams", &query_schema(&document, "/api/events"), &document);
+        assert_model::<models::FeeReportParams>("FeeReportParams", &query_schema(&document, "/api/fees/report"), &document);
+        let reports = query_schema(&document, "/api/verifications/tcb_status");
+        assert_model::<models::VerificationReportParams>("VerificationReportParams", &reports, &document);
+        let history = query_schema(&document, "/api/agent/{id}/quotes");
+        assert_model::<models::AgentHistoryParams>("AgentHistoryParams", &history, &document);
+    }
+
+    #[test]
+    fn client_covers_every_operation() {
+        let documented = ApiDoc::openapi()
+            .paths
+            .paths
+            .iter()
+            .flat_map(|(path, item)| {
+                [
+                    ("GET", item.get.is_some()),
+                    ("POST", item.post.is_some()),
+                    ("PUT", item.put.is_some()),
+                    ("DELETE", item.delete.is_some()),
+                ]
+                .into_iter()
+                .filter(|(_, documented)| *documented)
+                .map(move |(method, _)| format!("{} {}", method, path))
+            })
+            .collect::<BTreeSet<_>>();
+        let covered = tdx_prover_client::OPERATIONS
+            .iter()
+            .map(|(method, path)| format!("{} {}", method, path))
+            .collect::<BTreeSet<_>>();
+        assert_eq!(documented, covered);
+    }
+}
@@ -0,0 +1,10 @@
+use tdx_prover::state::event_state::EventState;
+use axum::{routing::get, Router};
+
+use crate::handler::event_handler;
+
+pub fn routes() -> Router<EventState> {
+    Router::new()
+        .route("/events", get(event_handler::list))
+        .route("/quote/{id}/events", get(event_handler::quote))
+}
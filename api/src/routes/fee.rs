@@ -0,0 +1,10 @@
+use tdx_prover::state::fee_state::FeeState;
+use axum::{routing::get, Router};
+
+use crate::handler::fee_handler;
+
+pub fn routes() -> Router<FeeState> {
+    Router::new()
+        .route("/request/{id}/fees", get(fee_handler::request_fees))
+        .route("/fees/report", get(fee_handler::report))
+}
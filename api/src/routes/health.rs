@@ -0,0 +1,11 @@
+use tdx_prover::state::health_state::HealthState;
+use axum::{routing::get, Router};
+
+use crate::handler::health_handler;
+
+pub fn routes() -> Router<HealthState> {
+    Router::new()
+        .route("/health", get(health_handler::health))
+        .route("/health/live", get(health_handler::live))
+        .route("/health/ready", get(health_handler::ready))
+}
@@ -0,0 +1,8 @@
+use tdx_prover::state::quote_state::QuoteState;
+use axum::{routing::get, Router};
+
+use crate::handler::metrics_handler;
+
+pub fn routes() -> Router<QuoteState> {
+    Router::new().route("/metrics", get(metrics_handler::metrics))
+}
@@ -1,4 +1,12 @@
 #![allow(dead_code)]
+pub mod agent;
+pub mod docs;
+pub mod event;
+pub mod fee;
+pub mod health;
+pub mod metrics;
 pub mod quote;
 pub mod request;
 pub mod root;
+pub mod verification;
+pub mod webhook;
@@ -6,8 +6,10 @@ use crate::handler::quote_handler;
 pub fn routes() -> Router<QuoteState> {
     
     Router::new()
+        .route("/quotes", get(quote_handler::list))
         .route("/quote/register", post(quote_handler::register))
         .route("/quote/{id}", get(quote_handler::query))
+        .route("/quote/{id}/history", get(quote_handler::history))
         .route("/quote/verify_dcap/{id}", get(quote_handler::verify_dcap))
         .route("/quote/prove/{id}", get(quote_handler::prove))
         .route("/quote/verify", post(quote_handler::verify))
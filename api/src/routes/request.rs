@@ -1,9 +1,14 @@
 use tdx_prover::state::request_state::RequestState;
-use axum::{routing::get, Router};
+use axum::{routing::{get, post}, Router};
 
 use crate::handler::request_handler;
 
 pub fn routes() -> Router<RequestState> {
     Router::new()
-        .route("/request/{id}", get(request_handler::query))
+        .route("/request", post(request_handler::register))
+        .route("/requests", get(request_handler::list))
+        .route(
+            "/request/{id}",
+            get(request_handler::query).put(request_handler::update).delete(request_handler::cancel),
+        )
 }
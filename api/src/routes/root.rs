@@ -1,8 +1,19 @@
 use tdx_prover::config::database::Database;
+use tdx_prover::entity::api_key::ApiScope;
+use tdx_prover::state::auth_state::AuthState;
+use tdx_prover::state::agent_state::AgentState;
+use tdx_prover::state::event_state::EventState;
+use tdx_prover::state::fee_state::FeeState;
 use tdx_prover::state::quote_state::QuoteState;
 use tdx_prover::state::request_state::RequestState;
+use tdx_prover::state::health_state::HealthState;
+use tdx_prover::state::usage_state::UsageState;
+use tdx_prover::state::verification_state::VerificationState;
+use tdx_prover::state::webhook_state::WebhookState;
 use axum::body::Bytes;
-use axum::routing::{IntoMakeService, get};
+use axum::http::Method;
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::routing::IntoMakeService;
 use axum::Router;
 use tower_http::classify::ServerErrorsFailureClass;
 use std::sync::Arc;
@@ -10,17 +21,63 @@ use std::time::Duration;
 use tower_http::LatencyUnit;
 use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
 
-use super::{quote, request};
+use crate::middleware::{auth, metrics as http_metrics, rate_limit};
+use super::{agent, docs, event, fee, health, metrics, quote, request, verification, webhook};
+
+/// Scope an API key needs to call a route, `None` for public routes. Other reads need the read
+/// scope and any other write, like managing agents, needs admin.
+pub(crate) fn required_scope(method: &Method, path: &str) -> Option<ApiScope> {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    let scope = match (method, path) {
+        (_, "/openapi.json") => return None,
+        (_, path) if path.starts_with("/health") || path.starts_with("/docs") => return None,
+        (&Method::POST, "/quote/register") | (&Method::POST, "/request") => ApiScope::Register,
+        (&Method::PUT, "/request/{id}") | (&Method::DELETE, "/request/{id}") => ApiScope::Register,
+        (&Method::POST, "/webhook") | (&Method::DELETE, "/webhook/{id}") => ApiScope::Register,
+        (&Method::POST, "/webhook/delivery/{id}/replay") => ApiScope::Register,
+        (_, "/quote/prove/{id}") => ApiScope::Prove,
+        (_, "/quote/submit_proof") => ApiScope::Submit,
+        // Counts across every key and creator, not only the caller's
+        (&Method::GET, "/metrics") => ApiScope::Admin,
+        (&Method::GET, _) => ApiScope::Read,
+        (&Method::POST, "/quote/verify") => ApiScope::Read,
+        _ => ApiScope::Admin,
+    };
+    Some(scope)
+}
 
 pub fn routes(db_conn: Arc<Database>) -> IntoMakeService<Router> {
     let merged_router = {
         let quote_state = QuoteState::new(&db_conn);
         let request_state = RequestState::new(&db_conn);
+        let fee_state = FeeState::new(&db_conn);
+        let verification_state = VerificationState::new(&db_conn);
+        let agent_state = AgentState::new(&db_conn);
+        let auth_state = AuthState::new(&db_conn);
+        let usage_state = UsageState::new(&db_conn);
+        let health_state = HealthState::new(&db_conn);
+        let webhook_state = WebhookState::new(&db_conn);
+        let event_state = EventState::new(&db_conn);
+        if !auth_state.enabled {
+            tracing::warn!("API_AUTH_ENABLED is false, routes are served without an API key");
+        }
 
         request::routes()
             .with_state(request_state)
-            .merge(quote::routes().with_state(quote_state))
-            .merge(Router::new().route("/health", get(|| async { "Healthy..." })))
+            .merge(quote::routes().with_state(quote_state.clone()))
+            .merge(fee::routes().with_state(fee_state))
+            .merge(verification::routes().with_state(verification_state))
+            .merge(agent::routes().with_state(agent_state))
+            .merge(webhook::routes().with_state(webhook_state))
+            .merge(event::routes().with_state(event_state))
+            .merge(health::routes().with_state(health_state))
+            .merge(metrics::routes().with_state(quote_state))
+            .merge(docs::routes())
+            // The last layer runs first, limits apply to the key found by the auth layer
+            .route_layer(from_fn_with_state(usage_state, rate_limit::limit))
+            .route_layer(from_fn_with_state(auth_state, auth::authorize))
+            // Outside the auth layer so rejected requests are counted too
+            .layer(from_fn(http_metrics::track))
     };
 
     let app_router = Router::new()
@@ -42,3 +99,24 @@ pub fn routes(db_conn: Arc<Database>) -> IntoMakeService<Router> {
 
     app_router.into_make_service()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_require_their_scope() {
+        assert_eq!(required_scope(&Method::GET, "/api/health"), None);
+        assert_eq!(required_scope(&Method::GET, "/api/health/ready"), None);
+        assert_eq!(required_scope(&Method::GET, "/api/openapi.json"), None);
+        assert_eq!(required_scope(&Method::GET, "/api/quote/{id}"), Some(ApiScope::Read));
+        assert_eq!(required_scope(&Method::GET, "/api/quote/prove/{id}"), Some(ApiScope::Prove));
+        assert_eq!(required_scope(&Method::POST, "/api/quote/submit_proof"), Some(ApiScope::Submit));
+        assert_eq!(required_scope(&Method::DELETE, "/api/request/{id}"), Some(ApiScope::Register));
+        assert_eq!(required_scope(&Method::POST, "/api/webhook/delivery/{id}/replay"), Some(ApiScope::Register));
+        assert_eq!(required_scope(&Method::GET, "/api/webhook/{id}/deliveries"), Some(ApiScope::Read));
+        assert_eq!(required_scope(&Method::GET, "/api/quote/{id}/events"), Some(ApiScope::Read));
+        assert_eq!(required_scope(&Method::PUT, "/api/agent/{id}"), Some(ApiScope::Admin));
+        assert_eq!(required_scope(&Method::GET, "/api/metrics"), Some(ApiScope::Admin));
+    }
+}
@@ -0,0 +1,11 @@
+use tdx_prover::state::verification_state::VerificationState;
+use axum::{routing::get, Router};
+
+use crate::handler::verification_handler;
+
+pub fn routes() -> Router<VerificationState> {
+    Router::new()
+        .route("/quote/{id}/verifications", get(verification_handler::quote_verifications))
+        .route("/verifications/tcb_status", get(verification_handler::tcb_status_report))
+        .route("/verifications/mrtds", get(verification_handler::mrtd_report))
+}
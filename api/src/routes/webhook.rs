@@ -0,0 +1,13 @@
+use tdx_prover::state::webhook_state::WebhookState;
+use axum::{routing::{get, post}, Router};
+
+use crate::handler::webhook_handler;
+
+pub fn routes() -> Router<WebhookState> {
+    Router::new()
+        .route("/webhook", post(webhook_handler::register))
+        .route("/webhooks", get(webhook_handler::list))
+        .route("/webhook/{id}", get(webhook_handler::query).delete(webhook_handler::disable))
+        .route("/webhook/{id}/deliveries", get(webhook_handler::deliveries))
+        .route("/webhook/delivery/{id}/replay", post(webhook_handler::replay))
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tdx_prover::{
+    config::database::{Database, DatabaseTrait},
+    entity::api_key::ApiScope,
+    service::api_key_service::ApiKeyService,
+};
+use uuid::Uuid;
+
+async fn init_service() -> Result<ApiKeyService> {
+    let db_conn = Arc::new(Database::init().await?);
+    Ok(ApiKeyService::new(&db_conn))
+}
+
+pub(crate) async fn create(name: &str, scopes: &[ApiScope]) -> Result<()> {
+    let (api_key, key) = init_service().await?.create(name, scopes).await?;
+    println!("Created api key {} for {}", api_key.id, api_key.name);
+    println!("{}", key);
+    println!("Store it now, it is not shown again");
+    Ok(())
+}
+
+pub(crate) async fn list() -> Result<()> {
+    let api_keys = init_service().await?.find_all().await?;
+
+    for api_key in &api_keys {
+        let scopes = api_key.scopes.iter().map(ToString::to_string).collect::<Vec<_>>().join(",");
+        let state = match (api_key.revoked_at, api_key.last_used_at) {
+            (Some(revoked_at), _) => format!("revoked {}", revoked_at.to_rfc3339()),
+            (None, Some(last_used_at)) => format!("last used {}", last_used_at.to_rfc3339()),
+            (None, None) => "never used".to_string(),
+        };
+        println!("{} {:<16} {:<24} {:<32} {}", api_key.id, api_key.prefix, api_key.name, scopes, state);
+    }
+    println!("{} api keys", api_keys.len());
+    Ok(())
+}
+
+pub(crate) async fn revoke(id: Uuid) -> Result<()> {
+    let api_key = init_service().await?.revoke(id).await?;
+    println!("Revoked api key {} ({})", api_key.id, api_key.prefix);
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use tdx_prover::config::{
+    database::{Database, DatabaseTrait},
+    migration,
+};
+
+pub(crate) async fn migrate() -> Result<()> {
+    let db_conn = Database::init().await?;
+    migration::run(&db_conn).await?;
+    println!("Database migrations are up to date");
+    Ok(())
+}
+
+pub(crate) async fn status() -> Result<()> {
+    let db_conn = Database::init().await?;
+    let statuses = migration::status(&db_conn).await?;
+
+    for status in &statuses {
+        let state = match (status.applied, status.checksum_mismatch) {
+            (true, false) => "applied",
+            (true, true) => "applied (changed since)",
+            (false, _) => "pending",
+        };
+        println!("{} {:<24} {}", status.version, state, status.description);
+    }
+    let pending = statuses.iter().filter(|status| !status.applied).count();
+    println!("{} migrations, {} pending", statuses.len(), pending);
+    Ok(())
+}
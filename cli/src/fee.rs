@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use tdx_prover::{
+    config::database::{Database, DatabaseTrait},
+    dto::fee_dto::{FeeReportDto, RequestFeeReportDto},
+    entity::fee::FeeGroupBy,
+    repository::{fee_repository::TransactionFeeRepositoryTrait, request_repository::OnchainRequestRepositoryTrait},
+    state::{fee_state::FeeState, request_state::RequestState},
+};
+
+async fn init_db() -> Arc<Database> {
+    Arc::new(
+        Database::init()
+            .await
+            .unwrap_or_else(|e| panic!("Database error: {}", e)),
+    )
+}
+
+pub(crate) async fn request_report(request_id: Vec<u8>) -> Result<()> {
+    let db_conn = init_db().await;
+    let onchain_request = RequestState::new(&db_conn).request_repo.find_by_request_id(request_id).await?;
+    let fees = FeeState::new(&db_conn).fee_repo.find_all_by_onchain_request_id(onchain_request.id).await?;
+
+    let report = RequestFeeReportDto::from(onchain_request.id.to_string(), fees);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+pub(crate) async fn report(group_by: FeeGroupBy, from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Result<()> {
+    let db_conn = init_db().await;
+    let rows = FeeState::new(&db_conn).fee_repo.report(group_by, from, to).await?;
+
+    let report = FeeReportDto::from(group_by, from, to, rows);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -3,12 +3,26 @@ use std::{cmp::min, path::PathBuf, sync::Arc};
 use rand::Rng;
 use anyhow::Result;
 use clap::{Args, Parser, Subcommand, ValueEnum};
-use tdx_prover::{config::parameter, entity::{quote::{ProofType, TdxQuoteStatus}, zk::ProofSystem}};
+use chrono::{DateTime, Utc};
+use tdx_prover::{
+    chain::{pccs::pcs::IPCSDao::CA, simulation::CodeOverride},
+    config::{database::{Database, DatabaseTrait}, parameter},
+    entity::{api_key::ApiScope, fee::FeeGroupBy, usage::{QuotaPeriod, QuotaSubject}, quote::{ProofType, QuoteFilter, TdxQuoteStatus}, quote_event::QuoteActor, zk::ProofSystem},
+    service::finality_service::FinalityService,
+    telemetry,
+};
 use hex::FromHex;
 use tokio::task;
 mod prove;
 mod aws;
 mod request;
+mod pccs;
+mod fee;
+mod db;
+mod api_key;
+mod quota;
+mod remote;
+mod webhook;
 
 #[derive(Parser)]
 #[command(name = "TDXProver")]
@@ -44,11 +58,152 @@ enum Commands {
     /// Proves a TDX quote and submits it on-chain
     Prove(ProveArgs),
 
+    /// Proves the quotes whose failed attempt is due for a retry
+    Retry(RetryArgs),
+
     /// Load tests the prover flow
     LoadTest(LoadTestArgs),
 
     /// Load tests the remote prover flow in lambda
     LoadTestLambda(LoadTestLambdaArgs),
+
+    /// Manages collateral in the on-chain PCCS
+    #[command(subcommand)]
+    Pccs(PccsCommands),
+
+    /// Reports gas and fees paid for proof submissions
+    #[command(subcommand)]
+    Fees(FeesCommands),
+
+    /// Confirms submitted quotes once final, or returns them to pending after a reorg
+    Finalize(FinalizeArgs),
+
+    /// Manages the database schema
+    #[command(subcommand)]
+    Db(DbCommands),
+
+    /// Manages the API keys accepted by the API
+    #[command(subcommand)]
+    ApiKey(ApiKeyCommands),
+
+    /// Manages proving quotas of API keys and request creators
+    #[command(subcommand)]
+    Quota(QuotaCommands),
+
+    /// Sends queued webhook deliveries and replays past ones
+    #[command(subcommand)]
+    Webhook(WebhookCommands),
+
+    /// Calls a running API with `TDX_PROVER_API_URL` and `TDX_PROVER_API_KEY`
+    #[command(subcommand)]
+    Api(ApiCommands),
+}
+
+#[derive(Subcommand)]
+enum ApiCommands {
+    /// Checks that the API is up
+    Health,
+
+    /// Prints the readiness of the API per component, fails when a component is down
+    Ready,
+
+    /// Prints a quote
+    Quote(ApiQuoteArgs),
+
+    /// Proves a quote through the API and prints the proof
+    Prove(ApiProveArgs),
+}
+
+#[derive(Subcommand)]
+enum QuotaCommands {
+    /// Sets the number of proofs a key or creator may trigger per day or month
+    Set(QuotaSetArgs),
+
+    /// Lists quotas with the proofs counted in their current window
+    List,
+
+    /// Removes a quota, its subject is no longer limited for that period
+    Remove(QuotaRemoveArgs),
+
+    /// Deletes rate limit and quota counters of past windows
+    Prune(QuotaPruneArgs),
+}
+
+#[derive(Subcommand)]
+enum WebhookCommands {
+    /// Sends the webhook deliveries that are due
+    Dispatch(WebhookDispatchArgs),
+
+    /// Queues a delivery again with the same payload
+    Replay(WebhookReplayArgs),
+}
+
+/// Enum representing the period a proving quota is counted over
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum QuotaPeriodArg {
+    Day,
+    Month,
+}
+
+#[derive(Subcommand)]
+enum ApiKeyCommands {
+    /// Creates a key and prints it, the key cannot be shown again
+    Create(ApiKeyCreateArgs),
+
+    /// Lists keys with their scopes and last use
+    List,
+
+    /// Revokes a key, requests using it are rejected from then on
+    Revoke(ApiKeyRevokeArgs),
+}
+
+/// Enum representing the scopes an API key can be granted
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum ApiScopeArg {
+    Read,
+    Register,
+    Prove,
+    Submit,
+    Admin,
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Applies pending migrations
+    Migrate,
+
+    /// Lists migrations and whether they have been applied
+    Status,
+}
+
+#[derive(Subcommand)]
+enum FeesCommands {
+    /// Fees paid for a single onchain request
+    Request(FeeRequestArgs),
+
+    /// Fees aggregated per day, model_id or proof type
+    Report(FeeReportArgs),
+}
+
+/// Enum representing the available fee report groupings
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum FeeGroupByArg {
+    Day,
+    ModelId,
+    ProofType,
+}
+
+#[derive(Subcommand)]
+enum PccsCommands {
+    /// Upserts Intel TCB info, QE identities, CRLs and CA certificates to the on-chain PCCS
+    Upsert(PccsUpsertArgs),
+}
+
+/// Enum representing the PCK CA whose CRL is fetched
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum PckCaArg {
+    Platform,
+    Processor,
 }
 
 /// Enum representing the available proof types
@@ -56,6 +211,18 @@ enum Commands {
 enum ProofTypeArg {
     Sp1,
     Risc0,
+    /// Skip proving and verify the quote on-chain
+    RawQuote,
+}
+
+impl From<ProofTypeArg> for ProofType {
+    fn from(arg: ProofTypeArg) -> Self {
+        match arg {
+            ProofTypeArg::Sp1 => ProofType::Sp1,
+            ProofTypeArg::Risc0 => ProofType::Risc0,
+            ProofTypeArg::RawQuote => ProofType::RawQuote,
+        }
+    }
 }
 
 /// Enum representing the available proof systems
@@ -71,6 +238,13 @@ enum TdxQuoteStatusArg {
     Pending,
     Failure,
     Success,
+    Expired,
+    Cancelled,
+    Submitted,
+    Confirmed,
+    CollateralFetched,
+    Proving,
+    Proved,
 }
 
 #[derive(Args, Debug)]
@@ -110,6 +284,56 @@ struct ProveArgs {
         help = "If true, skip submitting proof on mainnet"
     )]
     skip_proof_submit: Option<bool>,
+
+    #[arg(
+        short = 's',
+        long = "simulate",
+        default_value = "false",
+        help = "Execute the proof calldata in a local EVM instead of submitting it"
+    )]
+    simulate: bool,
+
+    #[arg(
+        long = "snapshot",
+        help = "State snapshot for --simulate, forked from SIMULATION_RPC_URL and saved here if missing"
+    )]
+    snapshot: Option<PathBuf>,
+
+    #[arg(
+        long = "code",
+        help = "Bytecode for --simulate as <address>=<path>, e.g. a locally built verifier, may be repeated"
+    )]
+    code: Vec<CodeOverride>,
+}
+
+#[derive(Args, Debug)]
+struct RetryArgs {
+    #[arg(short = 'c', long = "count", default_value = "10", help = "Maximum number of quotes to retry")]
+    count: usize,
+
+    #[arg(
+        short = 'w',
+        long = "watch",
+        default_value = "false",
+        help = "Keep retrying due quotes every PROVE_RETRY_INTERVAL_SECS seconds"
+    )]
+    watch: bool,
+
+    #[arg(
+        short = 'v',
+        long = "verify-only",
+        default_value = "false",
+        help = "If true, make static call to automata testnet contract"
+    )]
+    verify_only: bool,
+
+    #[arg(
+        short = 'k',
+        long = "skip-proof-submit",
+        default_value = "false",
+        help = "If true, skip submitting proof on mainnet"
+    )]
+    skip_proof_submit: bool,
 }
 
 #[derive(Args, Debug)]
@@ -166,6 +390,12 @@ struct LoadTestArgs {
         value_enum,
     )]
     quote_status: Option<TdxQuoteStatusArg>,
+
+    #[arg(short = 'm', long = "model-id", help = "Only select requests for this model")]
+    model_id: Option<String>,
+
+    #[arg(long = "proof-type-filter", value_enum, help = "Only select quotes last proven with this proof type")]
+    proof_type_filter: Option<ProofTypeArg>,
 }
 
 #[derive(Args, Debug)]
@@ -203,6 +433,12 @@ struct LoadTestLambdaArgs {
     )]
     quote_status: Option<TdxQuoteStatusArg>,
 
+    #[arg(short = 'm', long = "model-id", help = "Only select requests for this model")]
+    model_id: Option<String>,
+
+    #[arg(long = "proof-type-filter", value_enum, help = "Only select quotes last proven with this proof type")]
+    proof_type_filter: Option<ProofTypeArg>,
+
     #[arg(
         short = 'i',
         long = "invoke",
@@ -227,6 +463,181 @@ struct LoadTestLambdaArgs {
     verify_only: Option<bool>,
 }
 
+#[derive(Args, Debug)]
+struct PccsUpsertArgs {
+    /// Signed TCB info JSON file, may be repeated
+    #[arg(long = "tcb-info")]
+    tcb_info: Vec<PathBuf>,
+
+    /// Signed QE identity JSON file, may be repeated
+    #[arg(long = "qe-identity")]
+    qe_identity: Vec<PathBuf>,
+
+    /// PEM or DER CA certificate as <root|signing|platform|processor>=<path>, may be repeated
+    #[arg(long = "cert", value_parser = pccs::parse_ca_path)]
+    cert: Vec<(CA, PathBuf)>,
+
+    /// PEM, DER or hex CRL as <root|platform|processor>=<path>, may be repeated
+    #[arg(long = "crl", value_parser = pccs::parse_ca_path)]
+    crl: Vec<(CA, PathBuf)>,
+
+    #[arg(
+        long = "fetch-from",
+        help = "Fetch collateral from a PCS compatible endpoint, e.g. https://api.trustedservices.intel.com"
+    )]
+    fetch_from: Option<String>,
+
+    /// FMSPC to fetch TCB info for, may be repeated
+    #[arg(long = "fmspc")]
+    fmspc: Vec<String>,
+
+    #[arg(long = "sgx", default_value = "false", help = "Fetch SGX instead of TDX collateral")]
+    sgx: bool,
+
+    #[arg(long = "pck-ca", value_enum, default_value = "platform")]
+    pck_ca: PckCaArg,
+
+    #[arg(
+        long = "identity-version",
+        default_value = "4",
+        help = "PCS API version the QE identities were fetched with"
+    )]
+    identity_version: u32,
+
+    #[arg(
+        long = "rpc-url",
+        help = "Endpoint of the network holding the DAOs, defaults to AUTOMATA_DEFAULT_RPC_URL with VERIFY_ONLY and DEFAULT_RPC_URL otherwise"
+    )]
+    rpc_url: Option<String>,
+
+    #[arg(short = 'n', long = "dry-run", default_value = "false", help = "Print calldata instead of sending")]
+    dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+struct FeeRequestArgs {
+    /// The onchain_request_id string to report on
+    #[arg(short = 'i', long = "onchain-request-id")]
+    request_id: String,
+}
+
+#[derive(Args, Debug)]
+struct FeeReportArgs {
+    #[arg(short = 'g', long = "group-by", value_enum, default_value = "day")]
+    group_by: FeeGroupByArg,
+
+    /// Inclusive RFC 3339 lower bound, e.g. 2025-05-01T00:00:00Z
+    #[arg(long = "from")]
+    from: Option<DateTime<Utc>>,
+
+    /// Exclusive RFC 3339 upper bound
+    #[arg(long = "to")]
+    to: Option<DateTime<Utc>>,
+}
+
+#[derive(Args, Debug)]
+struct ApiKeyCreateArgs {
+    /// Who or what the key is for, e.g. the name of the calling service
+    #[arg(short = 'n', long = "name")]
+    name: String,
+
+    /// Scope granted to the key, repeat for several scopes
+    #[arg(short = 's', long = "scope", value_enum, required = true)]
+    scopes: Vec<ApiScopeArg>,
+}
+
+#[derive(Args, Debug)]
+struct ApiKeyRevokeArgs {
+    /// Id of the key, as printed by `api-key list`
+    #[arg(short = 'i', long = "id")]
+    id: uuid::Uuid,
+}
+
+#[derive(Args, Debug)]
+#[group(id = "subject", required = true, multiple = false, args = ["api_key_id", "creator_address"])]
+struct QuotaSetArgs {
+    /// Id of the API key the quota applies to
+    #[arg(short = 'k', long = "api-key-id")]
+    api_key_id: Option<uuid::Uuid>,
+
+    /// Creator address the quota applies to, across every key and the lambda
+    #[arg(short = 'c', long = "creator-address")]
+    creator_address: Option<String>,
+
+    #[arg(short = 'p', long = "period", value_enum, default_value = "day")]
+    period: QuotaPeriodArg,
+
+    /// Proofs allowed per period, 0 blocks proving
+    #[arg(short = 'l', long = "limit")]
+    limit: i32,
+}
+
+#[derive(Args, Debug)]
+struct QuotaRemoveArgs {
+    /// Id of the quota, as printed by `quota list`
+    #[arg(short = 'i', long = "id")]
+    id: uuid::Uuid,
+}
+
+#[derive(Args, Debug)]
+struct QuotaPruneArgs {
+    /// Keep counters of windows that started within this many days
+    #[arg(short = 'd', long = "days", default_value = "62")]
+    days: i64,
+}
+
+#[derive(Args, Debug)]
+struct ApiQuoteArgs {
+    #[arg(short = 'i', long = "id")]
+    id: String,
+}
+
+#[derive(Args, Debug)]
+struct ApiProveArgs {
+    #[arg(short = 'i', long = "id")]
+    id: String,
+
+    #[arg(short = 't', long = "proof-type", value_enum, default_value = "sp1")]
+    proof_type: ProofTypeArg,
+}
+
+#[derive(Args, Debug)]
+struct WebhookDispatchArgs {
+    #[arg(short = 'c', long = "count", default_value = "100", help = "Maximum number of deliveries to send")]
+    count: i64,
+
+    #[arg(
+        short = 'w',
+        long = "watch",
+        default_value = "false",
+        help = "Keep dispatching every WEBHOOK_DISPATCH_INTERVAL_SECS seconds"
+    )]
+    watch: bool,
+}
+
+#[derive(Args, Debug)]
+struct WebhookReplayArgs {
+    /// Delivery id
+    #[arg(long = "id")]
+    id: uuid::Uuid,
+}
+
+#[derive(Args, Debug)]
+struct FinalizeArgs {
+    #[arg(short = 'c', long = "count", default_value = "100", help = "Maximum number of submitted quotes to check")]
+    count: i64,
+
+    #[arg(
+        short = 'w',
+        long = "watch",
+        default_value = "false",
+        help = "Keep checking every FINALITY_CHECK_INTERVAL_SECS seconds"
+    )]
+    watch: bool,
+}
+
+const METRICS_JOB: &str = "tdx-prover-cli";
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let _ = rustls::crypto::ring::default_provider().install_default();
@@ -235,7 +646,23 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    match &cli.command {
+    // Long running commands report their pipeline metrics to the Pushgateway
+    let worker = matches!(
+        cli.command,
+        Commands::Prove(_) | Commands::LoadTest(_) | Commands::Finalize(_)
+    );
+    if worker {
+        telemetry::install_push(METRICS_JOB);
+    }
+    let result = run(&cli.command).await;
+    if worker {
+        telemetry::push(METRICS_JOB).await;
+    }
+    result
+}
+
+async fn run(command: &Commands) -> Result<()> {
+    match command {
         Commands::Prove(args) => {
             let request_id = Vec::from_hex(
                 args.request_id.strip_prefix("0x").unwrap_or(args.request_id.as_str()))
@@ -245,6 +672,7 @@ async fn main() -> Result<()> {
             let proof_type = match args.proof_type.unwrap_or(ProofTypeArg::Sp1) {
                 ProofTypeArg::Sp1 => ProofType::Sp1,
                 ProofTypeArg::Risc0 => ProofType::Risc0,
+                ProofTypeArg::RawQuote => ProofType::RawQuote,
             };
             let proof_system = match args.proof_system.unwrap_or(ProofSystemArg::Groth16) {
                 ProofSystemArg::Groth16 => ProofSystem::Groth16,
@@ -256,8 +684,12 @@ async fn main() -> Result<()> {
             println!("Proving request_id: {} with proof_type: {} and proof_system: {} (verify_only: {})",
                 hex::encode(&request_id), proof_type, proof_system, verify_only);
 
-            prove::handler(request_id, proof_type, proof_system, verify_only, skip_proof_submit).await
+            prove::handler(
+                request_id, proof_type, proof_system, verify_only, skip_proof_submit, args.simulate, args.snapshot.clone(),
+                args.code.clone()
+            ).await
         }
+        Commands::Retry(args) => prove::retry_due(args.count, args.watch, args.verify_only, args.skip_proof_submit).await,
         Commands::LoadTest(args) => {
             let count = args.count.unwrap_or(10);
 
@@ -275,6 +707,13 @@ async fn main() -> Result<()> {
                 Some(TdxQuoteStatusArg::Pending) => Some(TdxQuoteStatus::Pending),
                 Some(TdxQuoteStatusArg::Failure) => Some(TdxQuoteStatus::Failure),
                 Some(TdxQuoteStatusArg::Success) => Some(TdxQuoteStatus::Success),
+                Some(TdxQuoteStatusArg::Expired) => Some(TdxQuoteStatus::Expired),
+                Some(TdxQuoteStatusArg::Cancelled) => Some(TdxQuoteStatus::Cancelled),
+                Some(TdxQuoteStatusArg::Submitted) => Some(TdxQuoteStatus::Submitted),
+                Some(TdxQuoteStatusArg::Confirmed) => Some(TdxQuoteStatus::Confirmed),
+                Some(TdxQuoteStatusArg::CollateralFetched) => Some(TdxQuoteStatus::CollateralFetched),
+                Some(TdxQuoteStatusArg::Proving) => Some(TdxQuoteStatus::Proving),
+                Some(TdxQuoteStatusArg::Proved) => Some(TdxQuoteStatus::Proved),
                 None => None,
             };
 
@@ -283,7 +722,12 @@ async fn main() -> Result<()> {
             
             let mut onchain_request_ids = match &args.input_file {
                 Some(input_file) => request::read_lines(input_file).unwrap(),
-                None => request::fetch_onchain_request_ids(quote_status, count).await,
+                None => request::fetch_onchain_request_ids(QuoteFilter {
+                    status: quote_status,
+                    proof_type: args.proof_type_filter.map(ProofType::from),
+                    model_id: args.model_id.clone(),
+                    ..Default::default()
+                }, count).await,
             };
 
             while onchain_request_ids.len() < count {
@@ -298,6 +742,7 @@ async fn main() -> Result<()> {
                     Some(proof_type) => match proof_type {
                         ProofTypeArg::Sp1 => ProofType::Sp1,
                         ProofTypeArg::Risc0 => ProofType::Risc0,
+                        ProofTypeArg::RawQuote => ProofType::RawQuote,
                     },
                     None => {
                         let mut rng = rand::rng();
@@ -310,7 +755,7 @@ async fn main() -> Result<()> {
                     }
                 };
                 
-                let _ = prove::handler(request_id.request_id, proof_type, proof_system, verify_only, skip_proof_submit).await;
+                let _ = prove::handler(request_id.request_id, proof_type, proof_system, verify_only, skip_proof_submit, false, None).await;
                 tokio::time::sleep(tokio::time::Duration::from_millis(delay_milliseconds)).await;
             }
             println!("Finished load testing");
@@ -325,6 +770,13 @@ async fn main() -> Result<()> {
                 Some(TdxQuoteStatusArg::Pending) => Some(TdxQuoteStatus::Pending),
                 Some(TdxQuoteStatusArg::Failure) => Some(TdxQuoteStatus::Failure),
                 Some(TdxQuoteStatusArg::Success) => Some(TdxQuoteStatus::Success),
+                Some(TdxQuoteStatusArg::Expired) => Some(TdxQuoteStatus::Expired),
+                Some(TdxQuoteStatusArg::Cancelled) => Some(TdxQuoteStatus::Cancelled),
+                Some(TdxQuoteStatusArg::Submitted) => Some(TdxQuoteStatus::Submitted),
+                Some(TdxQuoteStatusArg::Confirmed) => Some(TdxQuoteStatus::Confirmed),
+                Some(TdxQuoteStatusArg::CollateralFetched) => Some(TdxQuoteStatus::CollateralFetched),
+                Some(TdxQuoteStatusArg::Proving) => Some(TdxQuoteStatus::Proving),
+                Some(TdxQuoteStatusArg::Proved) => Some(TdxQuoteStatus::Proved),
                 None => None,
             };
 
@@ -335,7 +787,12 @@ async fn main() -> Result<()> {
             "#, count, concurrency, delay_milliseconds, quote_status);
             
             let mut onchain_request_ids =
-                request::fetch_onchain_request_ids(quote_status, count).await;
+                request::fetch_onchain_request_ids(QuoteFilter {
+                    status: quote_status,
+                    proof_type: args.proof_type_filter.map(ProofType::from),
+                    model_id: args.model_id.clone(),
+                    ..Default::default()
+                }, count).await;
 
             println!("Found {} onchain requests", onchain_request_ids.len());
 
@@ -364,6 +821,7 @@ async fn main() -> Result<()> {
                         Some(proof_type) => match proof_type {
                             ProofTypeArg::Sp1 => ProofType::Sp1,
                             ProofTypeArg::Risc0 => ProofType::Risc0,
+                            ProofTypeArg::RawQuote => ProofType::RawQuote,
                         },
                         None => {
                             let mut rng = rand::rng();
@@ -439,5 +897,105 @@ async fn main() -> Result<()> {
             println!("Finished load testing tdx-prover lambda");
             Ok(())
         }
+        Commands::Pccs(PccsCommands::Upsert(args)) => {
+            if args.fetch_from.is_some() && args.fmspc.is_empty() {
+                return Err(anyhow::anyhow!("--fetch-from requires at least one --fmspc"));
+            }
+
+            pccs::upsert(pccs::UpsertOptions {
+                tcb_info_files: args.tcb_info.clone(),
+                identity_files: args.qe_identity.clone(),
+                cert_files: args.cert.clone(),
+                crl_files: args.crl.clone(),
+                fetch_from: args.fetch_from.clone(),
+                fmspcs: args.fmspc.clone(),
+                tdx: !args.sgx,
+                pck_ca: match args.pck_ca {
+                    PckCaArg::Platform => CA::PLATFORM,
+                    PckCaArg::Processor => CA::PROCESSOR,
+                },
+                identity_version: args.identity_version,
+                rpc_url: args.rpc_url.clone(),
+                dry_run: args.dry_run,
+            }).await
+        }
+        Commands::Fees(FeesCommands::Request(args)) => {
+            let request_id = Vec::from_hex(
+                args.request_id.strip_prefix("0x").unwrap_or(args.request_id.as_str()))
+                    .unwrap_or_else(|e| panic!("Invalid hex string: {}", e)
+            );
+            fee::request_report(request_id).await
+        }
+        Commands::Fees(FeesCommands::Report(args)) => {
+            let group_by = match args.group_by {
+                FeeGroupByArg::Day => FeeGroupBy::Day,
+                FeeGroupByArg::ModelId => FeeGroupBy::ModelId,
+                FeeGroupByArg::ProofType => FeeGroupBy::ProofType,
+            };
+            fee::report(group_by, args.from, args.to).await
+        }
+        Commands::Finalize(args) => {
+            let db_conn = Arc::new(
+                Database::init()
+                    .await
+                    .unwrap_or_else(|e| panic!("Database error: {}", e)),
+            );
+            let finality_service = FinalityService::new(&db_conn, QuoteActor::Cli);
+
+            if args.watch {
+                finality_service.run().await;
+                return Ok(());
+            }
+
+            let summary = finality_service.check_submitted(args.count).await?;
+            println!("Finality check: {:?}", summary);
+            Ok(())
+        }
+        Commands::Db(DbCommands::Migrate) => db::migrate().await,
+        Commands::Db(DbCommands::Status) => db::status().await,
+        Commands::ApiKey(ApiKeyCommands::Create(args)) => {
+            let scopes = args
+                .scopes
+                .iter()
+                .map(|scope| match scope {
+                    ApiScopeArg::Read => ApiScope::Read,
+                    ApiScopeArg::Register => ApiScope::Register,
+                    ApiScopeArg::Prove => ApiScope::Prove,
+                    ApiScopeArg::Submit => ApiScope::Submit,
+                    ApiScopeArg::Admin => ApiScope::Admin,
+                })
+                .collect::<Vec<_>>();
+            api_key::create(&args.name, &scopes).await
+        }
+        Commands::ApiKey(ApiKeyCommands::List) => api_key::list().await,
+        Commands::ApiKey(ApiKeyCommands::Revoke(args)) => api_key::revoke(args.id).await,
+        Commands::Quota(QuotaCommands::Set(args)) => {
+            let subject = match (args.api_key_id, args.creator_address) {
+                (Some(id), _) => QuotaSubject::ApiKey(id),
+                (None, Some(address)) => QuotaSubject::Creator(address),
+                (None, None) => unreachable!("clap requires a subject"),
+            };
+            let period = match args.period {
+                QuotaPeriodArg::Day => QuotaPeriod::Day,
+                QuotaPeriodArg::Month => QuotaPeriod::Month,
+            };
+            quota::set(subject, period, args.limit).await
+        }
+        Commands::Quota(QuotaCommands::List) => quota::list().await,
+        Commands::Quota(QuotaCommands::Remove(args)) => quota::remove(args.id).await,
+        Commands::Quota(QuotaCommands::Prune(args)) => quota::prune(args.days).await,
+        Commands::Webhook(WebhookCommands::Dispatch(args)) => webhook::dispatch(args.count, args.watch).await,
+        Commands::Webhook(WebhookCommands::Replay(args)) => webhook::replay(args.id).await,
+        Commands::Api(ApiCommands::Health) => remote::health().await,
+        Commands::Api(ApiCommands::Ready) => remote::ready().await,
+        Commands::Api(ApiCommands::Quote(args)) => remote::quote(&args.id).await,
+        Commands::Api(ApiCommands::Prove(args)) => {
+            let proof_type = match args.proof_type {
+                ProofTypeArg::Sp1 => tdx_prover_client::models::ProofType::Sp1,
+                ProofTypeArg::Risc0 => tdx_prover_client::models::ProofType::Risc0,
+                ProofTypeArg::RawQuote => tdx_prover_client::models::ProofType::RawQuote,
+            };
+            remote::prove(&args.id, proof_type).await
+        }
     }
 }
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use tdx_prover::{
+    chain::{
+        pccs::{collateral::{self, PccsCollateral}, pccs_rpc_url, pcs::IPCSDao::CA},
+        signer::SignerConfig,
+        TxSender,
+    },
+};
+
+pub(crate) struct UpsertOptions {
+    pub tcb_info_files: Vec<PathBuf>,
+    pub identity_files: Vec<PathBuf>,
+    pub cert_files: Vec<(CA, PathBuf)>,
+    pub crl_files: Vec<(CA, PathBuf)>,
+    pub fetch_from: Option<String>,
+    pub fmspcs: Vec<String>,
+    pub tdx: bool,
+    pub pck_ca: CA,
+    pub identity_version: u32,
+    pub rpc_url: Option<String>,
+    pub dry_run: bool,
+}
+
+pub(crate) async fn upsert(options: UpsertOptions) -> Result<()> {
+    let mut collateral = match &options.fetch_from {
+        Some(base_url) => collateral::fetch_from_pcs(base_url, options.tdx, &options.fmspcs, options.pck_ca).await?,
+        None => PccsCollateral::default(),
+    };
+
+    for path in &options.cert_files {
+        collateral.add_certificates(&[path.0], &std::fs::read(&path.1)?)?;
+    }
+    for path in &options.crl_files {
+        collateral.add_crl(path.0, &std::fs::read(&path.1)?)?;
+    }
+    for path in &options.tcb_info_files {
+        collateral.add_tcb_info(&std::fs::read_to_string(path)?)?;
+    }
+    for path in &options.identity_files {
+        collateral.add_enclave_identity(&std::fs::read_to_string(path)?)?;
+    }
+
+    let calls = collateral.upsert_calls(options.identity_version)?;
+    if calls.is_empty() {
+        return Err(anyhow!("No collateral given, pass files or --fetch-from"));
+    }
+
+    if options.dry_run {
+        for call in calls {
+            println!("{} -> {}", call.description, call.dao);
+            println!("0x{}\n", hex::encode(&call.calldata));
+        }
+        return Ok(());
+    }
+
+    // The DAO addresses and the default endpoints follow the same VERIFY_ONLY setting
+    let rpc_url = options.rpc_url.unwrap_or_else(pccs_rpc_url);
+    let first_dao = calls[0].dao.to_string();
+    let mut tx_sender = TxSender::from_config(&rpc_url, &first_dao, None, &SignerConfig::from_env()?).await?;
+
+    for call in calls {
+        tx_sender.contract = call.dao;
+        let (tx_hash, receipt) = tx_sender.send(call.calldata).await?;
+        match receipt {
+            Some(receipt) if receipt.status() => println!("{}: {}", call.description, tx_hash),
+            Some(_) => return Err(anyhow!("{} reverted: {}", call.description, tx_hash)),
+            None => println!("{}: {} (receipt not received)", call.description, tx_hash),
+        }
+    }
+    Ok(())
+}
+
+/// Parses `<ca>=<path>` pairs for certificate and CRL files
+pub(crate) fn parse_ca_path(value: &str) -> Result<(CA, PathBuf), String> {
+    let (ca, path) = value.split_once('=')
+        .ok_or_else(|| format!("Expected <ca>=<path>, got {}", value))?;
+    let ca = match ca.to_lowercase().as_str() {
+        "root" => CA::ROOT,
+        "signing" => CA::SIGNING,
+        "platform" => CA::PLATFORM,
+        "processor" => CA::PROCESSOR,
+        _ => return Err(format!("Unknown CA {}, expected root, signing, platform or processor", ca)),
+    };
+    Ok((ca, PathBuf::from(path)))
+}
@@ -1,11 +1,16 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 use anyhow::Error;
 use tdx_prover::{
-    config::database::{Database, DatabaseTrait},
+    chain::simulation::CodeOverride,
+    config::{database::{Database, DatabaseTrait}, parameter},
+    entity::attempt::{AttemptStage, ErrorCategory},
     entity::quote::{ProofType, TdxQuoteStatus},
+    entity::quote_event::QuoteActor,
+    entity::verification::VerificationSource,
     entity::zk::ProofSystem,
-    error::{db_error::DbError, quote_error::QuoteError},
+    error::{db_error::DbError, quote_error::QuoteError, request_error::RequestError},
     repository::{quote_repository::QuoteRepositoryTrait, request_repository::OnchainRequestRepositoryTrait},
+    service::lifecycle_service::{is_terminal, LifecycleService},
     state::{quote_state::QuoteState, request_state::RequestState}, zk,
 };
 
@@ -14,7 +19,10 @@ pub(crate) async fn handler(
     proof_type: ProofType,
     proof_system: ProofSystem,
     verify_only: bool,
-    skip_proof_submit: bool
+    skip_proof_submit: bool,
+    simulate: bool,
+    snapshot: Option<PathBuf>,
+    code: Vec<CodeOverride>,
 ) -> Result<(), Error> {
     let request_id_hex = hex::encode(&request_id);
 
@@ -26,6 +34,7 @@ pub(crate) async fn handler(
 
     let quote_state = QuoteState::new(&db_conn);
     let request_state = RequestState::new(&db_conn);
+    let lifecycle = LifecycleService::new(&db_conn, QuoteActor::Cli);
 
     let onchain_request = request_state.request_repo.find_by_request_id(request_id).await.map_err(|e| {
         println!("Failed to fetch onchain request: {}", e);
@@ -41,76 +50,151 @@ pub(crate) async fn handler(
 
     let quote_id = attestation.id;
     println!("Attestation found for request ID: {} {}", request_id_hex, attestation.status);
-    let proof = zk::prove(attestation.quote, proof_type, Some(proof_system)).await
-        .map_err(|e| {
+
+    // Submitted quotes are left to the finality check so a proof is never sent twice
+    if is_terminal(attestation.status) || attestation.status == TdxQuoteStatus::Submitted {
+        println!("Skipping request ID: {} quote is already {}", request_id_hex, attestation.status);
+        return Ok(());
+    }
+
+    if let Err(e) = onchain_request.ensure_active() {
+        println!("Skipping request ID: {} {}", request_id_hex, e);
+        lifecycle.mark_inactive(&attestation, &e).await?;
+        return Ok(());
+    }
+
+    let quote = lifecycle.restart(&attestation).await?;
+    let (quote, proof) = match lifecycle.prove(quote, proof_type, Some(proof_system)).await {
+        Ok(proven) => proven,
+        // The quote stays pending until `retry` proves it again
+        Err(QuoteError::RetryScheduled(category, at)) => {
+            println!("Proving request ID: {} failed with a {} error, retry scheduled at {}", request_id_hex, category, at);
+            return Ok(());
+        }
+        Err(e) => {
             println!("Failed to generate proof for request ID: {:?} {}", request_id_hex, e.to_string());
-            QuoteError::Prove
-        })?;
+            return Err(e.into());
+        }
+    };
     
     println!("Proof generated for request ID: {:?} {:#?}", request_id_hex, proof.proof);
 
     // only verify proof in dev because in lambda, filesystem is not writable
-    if std::env::var("ENV").unwrap_or("dev".to_string()) != "prod" {
+    if std::env::var("ENV").unwrap_or("dev".to_string()) != "prod" && proof_type != ProofType::RawQuote {
         println!("Verifying proof...");
         
-        zk::verify_proof(&proof.proof).await.map_err(|e| {
+        if let Err(e) = zk::verify_proof(&proof.proof).await {
             println!("Failed to verify proof: {}", e);
-            QuoteError::VerifyProof
-        })?;
+            lifecycle.fail(&quote, format!("Failed to verify proof: {}", e)).await?;
+            return Err(QuoteError::VerifyProof.into());
+        }
         println!("Successfully verified proof.");
     }
 
+    if simulate {
+        let result = zk::simulate_proof(
+            &onchain_request, proof_type, &proof.proof, verify_only, snapshot.as_deref(), &code
+        ).await?;
+        println!(
+            "Simulated proof for request ID: {} success: {} gas_used: {} output: 0x{}",
+            request_id_hex, result.success, result.gas_used, hex::encode(&result.output)
+        );
+        if let Some(reason) = result.revert_reason {
+            println!("Revert reason: {}", reason);
+        }
+        return Ok(());
+    }
+
     if skip_proof_submit {
         println!("Skipping proof submission. Early exit.");
         return Ok(());
     }
 
+    // Proving can take several minutes, so re-check the request before paying for the transaction
+    let onchain_request = request_state.request_repo.find(onchain_request.id).await.map_err(|e| {
+        println!("Failed to refetch onchain request: {}", e);
+        DbError::SomethingWentWrong("Failed to fetch onchain request".to_string())
+    })?;
+    if let Err(e) = onchain_request.ensure_active() {
+        println!("Aborting proof submission for request ID: {} {}", request_id_hex, e);
+        lifecycle.mark_inactive(&quote, &e).await?;
+        return Ok(());
+    }
+
+    let backend = if verify_only { "verify_only" } else { "onchain" };
+    let attempt = lifecycle.start_attempt(&quote, AttemptStage::Submit, backend).await?;
     let (verified, raw_verified_output, tx_hash, response) =
-        zk::submit_proof(onchain_request, proof_type, proof.proof, Some(verify_only)).await
-            .map_err(|e| {
+        match zk::submit_proof(onchain_request, proof_type, proof.proof, Some(verify_only)).await {
+            Ok(result) => result,
+            Err(e) => {
+                let category = ErrorCategory::classify(&e).unwrap_or(ErrorCategory::Unknown);
+                lifecycle.finish_attempt(&attempt, Some((category, &e))).await?;
+                if let Some(request_error) = e.downcast_ref::<RequestError>() {
+                    println!("Aborting proof submission for request ID: {} {}", request_id_hex, request_error);
+                    lifecycle.mark_inactive(&quote, request_error).await?;
+                    return Ok(());
+                }
                 println!("Failed to submit proof: {}", e);
-                QuoteError::SubmitProof
-            })?;
+                lifecycle.fail(&quote, format!("Failed to submit proof: {}", e)).await?;
+                return Err(QuoteError::SubmitProof.into());
+            }
+        };
 
     println!(
         "Proof submitted for request ID: {} verified: {} raw_verified_output: {}",
         request_id_hex, verified, hex::encode(&raw_verified_output)
     );
+    // A reverted transaction returns the local output, only record what the contract accepted
+    if verified && response.as_ref().is_none_or(|response| response.cost.success) {
+        lifecycle.record_verification(&quote, VerificationSource::Onchain, &raw_verified_output).await;
+    }
 
-    if response.is_some() {
+    if let Some(response) = &response {
         println!("Submit proof response: {:#?}", response);
-        let response = response.unwrap();
-        println!("Transaction hash: {}", hex::encode(&response.transaction_hash));
-
-        // Update onchain request status
-        quote_state.quote_repo.update_status(
-            quote_id,
-            proof_type,
-            TdxQuoteStatus::Failure,
-            Some(response.transaction_hash.to_vec()),
-            None,
-        ).await.map_err(|e| {
-            println!("Failed to update quote status on success: {}", e);
-            QuoteError::UpdateStatusOnSuccess
-        })?;
-
-        println!("tdx_quote updated successfully {quote_id} {}", TdxQuoteStatus::Success);
-    } else if tx_hash.is_some() {
-        println!("Transaction hash: {}", hex::encode(&tx_hash.unwrap().to_vec()));
-        // Update onchain request status
-        quote_state.quote_repo.update_status(
-            quote_id,
-            proof_type,
-            TdxQuoteStatus::Failure,
-            Some(tx_hash.unwrap().to_vec()),
-            None,
-        ).await.map_err(|e| {
-            println!("Failed to update quote status on failure: {}", e);
-            QuoteError::UpdateStatusOnFailure
-        })?;
-
-        println!("tdx_quote updated successfully {quote_id} {}", TdxQuoteStatus::Failure);
+        println!("Transaction hash: {}", hex::encode(response.transaction_hash));
+    } else if let Some(tx_hash) = tx_hash {
+        println!("Transaction hash: {}", hex::encode(tx_hash));
     }
+    let quote = lifecycle.record_submission(&quote, &attempt, proof_type, verify_only, tx_hash, response).await?;
+    println!("tdx_quote updated successfully {quote_id} {}", quote.status);
 
     Ok(())
 }
+
+/// Proves up to `count` quotes whose failed attempt is due, each with the backend of that attempt.
+/// With `watch` it keeps looking every `PROVE_RETRY_INTERVAL_SECS` seconds.
+pub(crate) async fn retry_due(count: usize, watch: bool, verify_only: bool, skip_proof_submit: bool) -> Result<(), Error> {
+    let db_conn = Arc::new(Database::init().await?);
+    let lifecycle = LifecycleService::new(&db_conn, QuoteActor::Cli);
+    let interval = parameter::get("PROVE_RETRY_INTERVAL_SECS", Some("30")).parse().unwrap_or(30);
+
+    loop {
+        let mut retried = 0;
+        while retried < count {
+            let Some(retry) = lifecycle.claim_due_retry().await? else {
+                break;
+            };
+            retried += 1;
+            let (proof_type, proof_system) = match retry.proof_backend() {
+                Ok(backend) => backend,
+                Err(e) => {
+                    println!("Skipping retry of quote {}: {}", retry.tdx_quote_id, e);
+                    continue;
+                }
+            };
+            println!("Retrying quote {} with {}", retry.tdx_quote_id, retry.backend);
+            let proof_system = proof_system.unwrap_or(ProofSystem::Groth16);
+            let proven = handler(
+                retry.request_id, proof_type, proof_system, verify_only, skip_proof_submit, false, None, vec![]
+            ).await;
+            if let Err(e) = proven {
+                println!("Retry of quote {} failed: {}", retry.tdx_quote_id, e);
+            }
+        }
+        if !watch {
+            println!("Retried {} quotes", retried);
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use tdx_prover::{
+    config::database::{Database, DatabaseTrait},
+    entity::usage::{QuotaPeriod, QuotaSubject},
+    service::usage_service::UsageService,
+};
+use uuid::Uuid;
+
+async fn init_service() -> Result<UsageService> {
+    let db_conn = Arc::new(Database::init().await?);
+    Ok(UsageService::new(&db_conn))
+}
+
+pub(crate) async fn set(subject: QuotaSubject, period: QuotaPeriod, limit: i32) -> Result<()> {
+    let quota = init_service().await?.set_quota(&subject, period, limit).await?;
+    println!("Set quota {}: {} proofs per {} for {}", quota.id, quota.proof_limit, quota.period, quota.subject);
+    Ok(())
+}
+
+pub(crate) async fn list() -> Result<()> {
+    let quotas = init_service().await?.find_all_quotas().await?;
+
+    for (quota, used) in &quotas {
+        println!(
+            "{} {:<52} {:<6} {}/{}",
+            quota.id, quota.subject, quota.period, used, quota.proof_limit
+        );
+    }
+    println!("{} quotas", quotas.len());
+    Ok(())
+}
+
+pub(crate) async fn remove(id: Uuid) -> Result<()> {
+    init_service().await?.delete_quota(id).await?;
+    println!("Removed quota {}", id);
+    Ok(())
+}
+
+pub(crate) async fn prune(days: i64) -> Result<()> {
+    let pruned = init_service().await?.prune(Utc::now() - Duration::days(days)).await?;
+    println!("Pruned {} usage counters", pruned);
+    Ok(())
+}
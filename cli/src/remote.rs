@@ -0,0 +1,37 @@
+use anyhow::Result;
+use tdx_prover::config::parameter;
+use tdx_prover_client::{models::{HealthStatus, ProofType}, Client};
+
+fn client() -> Client {
+    let client = Client::new(parameter::get("TDX_PROVER_API_URL", Some("http://localhost:8002")));
+    match std::env::var("TDX_PROVER_API_KEY") {
+        Ok(api_key) if !api_key.is_empty() => client.with_api_key(api_key),
+        _ => client,
+    }
+}
+
+pub(crate) async fn health() -> Result<()> {
+    println!("{}", client().health().await?);
+    Ok(())
+}
+
+pub(crate) async fn ready() -> Result<()> {
+    let report = client().ready().await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if report.status == HealthStatus::Down {
+        return Err(anyhow::anyhow!("The API is not ready"));
+    }
+    Ok(())
+}
+
+pub(crate) async fn quote(id: &str) -> Result<()> {
+    let quote = client().get_quote(id).await?;
+    println!("{}", serde_json::to_string_pretty(&quote)?);
+    Ok(())
+}
+
+pub(crate) async fn prove(id: &str, proof_type: ProofType) -> Result<()> {
+    let proof = client().prove(id, proof_type).await?;
+    println!("{}", serde_json::to_string_pretty(&proof)?);
+    Ok(())
+}
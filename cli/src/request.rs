@@ -2,10 +2,10 @@ use std::fs::File;
 use std::io::{BufReader, BufRead};
 use std::path::Path;
 use std::sync::Arc;
-use tdx_prover::entity::quote::TdxQuoteStatus;
-use tdx_prover::state::request_state::RequestState;
+use tdx_prover::entity::quote::QuoteFilter;
+use tdx_prover::state::quote_state::QuoteState;
 use tdx_prover::config::database::{Database, DatabaseTrait};
-use tdx_prover::repository::request_repository::{OnchainRequestRepositoryTrait, OnchainRequestId};
+use tdx_prover::repository::{quote_repository::QuoteRepositoryTrait, request_repository::OnchainRequestId};
 use hex::FromHex;
 use anyhow::Result;
 
@@ -28,15 +28,15 @@ where
     Ok(onchain_request_ids)
 }
 
-pub(crate) async fn fetch_onchain_request_ids(status: Option<TdxQuoteStatus>, count: usize) -> Vec<OnchainRequestId> {
+pub(crate) async fn fetch_onchain_request_ids(filter: QuoteFilter, count: usize) -> Vec<OnchainRequestId> {
     let db_conn = Arc::new(
         Database::init()
             .await
             .unwrap_or_else(|e| panic!("Database error: {}", e)),
     );
-    let request_state = RequestState::new(&db_conn);
+    let quote_state = QuoteState::new(&db_conn);
 
-    request_state.request_repo
-        .find_request_ids_by_status(status, Some(count as i64))
+    quote_state.quote_repo
+        .find_request_ids(&filter, Some(count as i64))
         .await
 }
@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tdx_prover::{
+    config::database::{Database, DatabaseTrait},
+    service::webhook_service::WebhookService,
+};
+use uuid::Uuid;
+
+async fn init_service() -> Result<WebhookService> {
+    let db_conn = Arc::new(Database::init().await?);
+    Ok(WebhookService::new(&db_conn))
+}
+
+pub(crate) async fn dispatch(count: i64, watch: bool) -> Result<()> {
+    let webhook_service = init_service().await?;
+    if watch {
+        webhook_service.run().await;
+        return Ok(());
+    }
+
+    let summary = webhook_service.dispatch_due(count).await?;
+    println!("Webhook dispatch: {:?}", summary);
+    Ok(())
+}
+
+pub(crate) async fn replay(id: Uuid) -> Result<()> {
+    let replay = init_service().await?.replay(None, id).await?;
+    println!("Queued delivery {} as a replay of {}", replay.id, id);
+    Ok(())
+}
@@ -0,0 +1,379 @@
+//! Typed client of the TDX prover API.
+//!
+//! Follows the OpenAPI document the API serves at `/api/openapi.json`. The api crate checks that
+//! every documented operation is listed in [`OPERATIONS`], so a route added there fails its
+//! tests until it is added here.
+
+pub mod models;
+
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::models::*;
+
+/// Method and path of every operation the client implements
+pub const OPERATIONS: &[(&str, &str)] = &[
+    ("GET", "/api/health"),
+    ("GET", "/api/health/live"),
+    ("GET", "/api/health/ready"),
+    ("GET", "/api/quotes"),
+    ("POST", "/api/quote/register"),
+    ("GET", "/api/quote/{id}"),
+    ("GET", "/api/quote/{id}/history"),
+    ("GET", "/api/quote/verify_dcap/{id}"),
+    ("GET", "/api/quote/prove/{id}"),
+    ("POST", "/api/quote/verify"),
+    ("POST", "/api/quote/submit_proof"),
+    ("POST", "/api/request"),
+    ("GET", "/api/requests"),
+    ("GET", "/api/request/{id}"),
+    ("PUT", "/api/request/{id}"),
+    ("DELETE", "/api/request/{id}"),
+    ("GET", "/api/request/{id}/fees"),
+    ("GET", "/api/fees/report"),
+    ("GET", "/api/quote/{id}/verifications"),
+    ("GET", "/api/verifications/tcb_status"),
+    ("GET", "/api/verifications/mrtds"),
+    ("POST", "/api/agent"),
+    ("GET", "/api/agents"),
+    ("GET", "/api/agent/{id}"),
+    ("PUT", "/api/agent/{id}"),
+    ("DELETE", "/api/agent/{id}"),
+    ("GET", "/api/agent/{id}/quotes"),
+    ("GET", "/api/agent/{id}/requests"),
+    ("POST", "/api/webhook"),
+    ("GET", "/api/webhooks"),
+    ("GET", "/api/webhook/{id}"),
+    ("DELETE", "/api/webhook/{id}"),
+    ("GET", "/api/webhook/{id}/deliveries"),
+    ("POST", "/api/webhook/delivery/{id}/replay"),
+    ("GET", "/api/events"),
+    ("GET", "/api/quote/{id}/events"),
+    ("GET", "/api/metrics"),
+];
+
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The API answered with an error status, `message` is taken from its error body
+    #[error("API returned {status}: {}", message.as_deref().unwrap_or("no message"))]
+    Api { status: u16, message: Option<String> },
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    /// An event stream carried data that is not an update
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct Client {
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// `base_url` is the address of the API without the `/api` prefix, e.g. `http://localhost:8002`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Sent as a bearer token on every request
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub async fn health(&self) -> Result<String, Error> {
+        let response = self.send(self.request(Method::GET, "/api/health")).await?;
+        Ok(response.text().await?)
+    }
+
+    pub async fn live(&self) -> Result<HealthReport, Error> {
+        self.json(self.request(Method::GET, "/api/health/live")).await
+    }
+
+    /// The report is returned whether or not the API is ready, check its `status`
+    pub async fn ready(&self) -> Result<HealthReport, Error> {
+        let response = self.request(Method::GET, "/api/health/ready").send().await?;
+        match response.status() {
+            StatusCode::OK | StatusCode::SERVICE_UNAVAILABLE => Ok(response.json().await?),
+            _ => Err(self.send_error(response).await),
+        }
+    }
+
+    pub async fn list_quotes(&self, params: &QuoteListParams) -> Result<QuoteListDto, Error> {
+        self.json(self.request(Method::GET, "/api/quotes").query(params)).await
+    }
+
+    pub async fn register_quote(&self, quote: &QuoteRegisterDto) -> Result<QuoteReadDto, Error> {
+        self.json(self.request(Method::POST, "/api/quote/register").json(quote)).await
+    }
+
+    pub async fn get_quote(&self, id: &str) -> Result<QuoteReadDto, Error> {
+        self.json(self.request(Method::GET, &format!("/api/quote/{}", id))).await
+    }
+
+    pub async fn quote_history(&self, id: &str) -> Result<QuoteHistoryDto, Error> {
+        self.json(self.request(Method::GET, &format!("/api/quote/{}/history", id))).await
+    }
+
+    pub async fn verify_dcap(&self, id: &str) -> Result<DcapVerifiedOutput, Error> {
+        self.json(self.request(Method::GET, &format!("/api/quote/verify_dcap/{}", id))).await
+    }
+
+    /// Blocks until the proof is generated, which can take several minutes
+    pub async fn prove(&self, id: &str, proof_type: ProofType) -> Result<DcapProof, Error> {
+        let request = self
+            .request(Method::GET, &format!("/api/quote/prove/{}", id))
+            .query(&[("proof_type", proof_type)]);
+        self.json(request).await
+    }
+
+    pub async fn verify(&self, proof: &DcapProof) -> Result<DcapVerifiedOutput, Error> {
+        self.json(self.request(Method::POST, "/api/quote/verify").json(proof)).await
+    }
+
+    pub async fn submit_proof(&self, proof: &DcapProof) -> Result<DcapVerifiedOutput, Error> {
+        self.json(self.request(Method::POST, "/api/quote/submit_proof").json(proof)).await
+    }
+
+    pub async fn register_request(&self, request: &RequestRegisterDto) -> Result<RequestRegisteredDto, Error> {
+        self.json(self.request(Method::POST, "/api/request").json(request)).await
+    }
+
+    pub async fn list_requests(&self, params: &RequestListParams) -> Result<Vec<RequestReadDto>, Error> {
+        self.json(self.request(Method::GET, "/api/requests").query(params)).await
+    }
+
+    pub async fn get_request(&self, id: &str) -> Result<RequestReadDto, Error> {
+        self.json(self.request(Method::GET, &format!("/api/request/{}", id))).await
+    }
+
+    pub async fn update_request(&self, id: &str, update: &RequestUpdateDto) -> Result<RequestReadDto, Error> {
+        self.json(self.request(Method::PUT, &format!("/api/request/{}", id)).json(update)).await
+    }
+
+    pub async fn cancel_request(&self, id: &str) -> Result<RequestReadDto, Error> {
+        self.json(self.request(Method::DELETE, &format!("/api/request/{}", id))).await
+    }
+
+    pub async fn request_fees(&self, id: &str) -> Result<RequestFeeReportDto, Error> {
+        self.json(self.request(Method::GET, &format!("/api/request/{}/fees", id))).await
+    }
+
+    pub async fn fee_report(&self, params: &FeeReportParams) -> Result<FeeReportDto, Error> {
+        self.json(self.request(Method::GET, "/api/fees/report").query(params)).await
+    }
+
+    pub async fn quote_verifications(&self, id: &str) -> Result<Vec<VerificationReadDto>, Error> {
+        self.json(self.request(Method::GET, &format!("/api/quote/{}/verifications", id))).await
+    }
+
+    pub async fn tcb_status_report(&self, params: &VerificationReportParams) -> Result<TcbStatusReportDto, Error> {
+        self.json(self.request(Method::GET, "/api/verifications/tcb_status").query(params)).await
+    }
+
+    pub async fn mrtd_report(&self, params: &VerificationReportParams) -> Result<MrtdReportDto, Error> {
+        self.json(self.request(Method::GET, "/api/verifications/mrtds").query(params)).await
+    }
+
+    pub async fn register_agent(&self, agent: &AgentRegisterDto) -> Result<AgentReadDto, Error> {
+        self.json(self.request(Method::POST, "/api/agent").json(agent)).await
+    }
+
+    pub async fn list_agents(&self, status: Option<AgentStatus>) -> Result<Vec<AgentReadDto>, Error> {
+        let mut request = self.request(Method::GET, "/api/agents");
+        if let Some(status) = status {
+            request = request.query(&[("status", status)]);
+        }
+        self.json(request).await
+    }
+
+    pub async fn get_agent(&self, id: &str) -> Result<AgentReadDto, Error> {
+        self.json(self.request(Method::GET, &format!("/api/agent/{}", id))).await
+    }
+
+    pub async fn update_agent(&self, id: &str, update: &AgentUpdateDto) -> Result<AgentReadDto, Error> {
+        self.json(self.request(Method::PUT, &format!("/api/agent/{}", id)).json(update)).await
+    }
+
+    pub async fn retire_agent(&self, id: &str) -> Result<AgentReadDto, Error> {
+        self.json(self.request(Method::DELETE, &format!("/api/agent/{}", id))).await
+    }
+
+    pub async fn agent_quotes(&self, id: &str, params: &AgentHistoryParams) -> Result<QuoteListDto, Error> {
+        self.json(self.request(Method::GET, &format!("/api/agent/{}/quotes", id)).query(params)).await
+    }
+
+    pub async fn agent_requests(&self, id: &str, params: &AgentHistoryParams) -> Result<Vec<RequestReadDto>, Error> {
+        self.json(self.request(Method::GET, &format!("/api/agent/{}/requests", id)).query(params)).await
+    }
+
+    /// The returned secret signs every delivery and cannot be read again
+    pub async fn register_webhook(&self, webhook: &WebhookRegisterDto) -> Result<WebhookCreatedDto, Error> {
+        self.json(self.request(Method::POST, "/api/webhook").json(webhook)).await
+    }
+
+    pub async fn list_webhooks(&self) -> Result<Vec<WebhookReadDto>, Error> {
+        self.json(self.request(Method::GET, "/api/webhooks")).await
+    }
+
+    pub async fn get_webhook(&self, id: &str) -> Result<WebhookReadDto, Error> {
+        self.json(self.request(Method::GET, &format!("/api/webhook/{}", id))).await
+    }
+
+    pub async fn disable_webhook(&self, id: &str) -> Result<WebhookReadDto, Error> {
+        self.json(self.request(Method::DELETE, &format!("/api/webhook/{}", id))).await
+    }
+
+    pub async fn webhook_deliveries(&self, id: &str, limit: Option<i64>) -> Result<Vec<WebhookDeliveryReadDto>, Error> {
+        let mut request = self.request(Method::GET, &format!("/api/webhook/{}/deliveries", id));
+        if let Some(limit) = limit {
+            request = request.query(&[("limit", limit)]);
+        }
+        self.json(request).await
+    }
+
+    pub async fn replay_delivery(&self, id: &str) -> Result<WebhookDeliveryReadDto, Error> {
+        self.json(self.request(Method::POST, &format!("/api/webhook/delivery/{}/replay", id))).await
+    }
+
+    /// Live updates of every quote matching `params`
+    pub async fn events(&self, params: &EventListParams) -> Result<EventStream, Error> {
+        let response = self.send(self.request(Method::GET, "/api/events").query(params)).await?;
+        Ok(EventStream { response, buffer: vec![] })
+    }
+
+    /// Live updates of a quote, the first one is a snapshot of its current status. The stream ends
+    /// once the quote reached a final status.
+    pub async fn quote_events(&self, id: &str) -> Result<EventStream, Error> {
+        let response = self.send(self.request(Method::GET, &format!("/api/quote/{}/events", id))).await?;
+        Ok(EventStream { response, buffer: vec![] })
+    }
+
+    /// Prometheus text exposition, needs an admin key
+    pub async fn metrics(&self) -> Result<String, Error> {
+        let response = self.send(self.request(Method::GET, "/api/metrics")).await?;
+        Ok(response.text().await?)
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        Err(self.send_error(response).await)
+    }
+
+    async fn send_error(&self, response: Response) -> Error {
+        let status = response.status().as_u16();
+        let message = response.json::<ApiErrorResponse>().await.ok().and_then(|body| body.message);
+        Error::Api { status, message }
+    }
+
+    async fn json<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, Error> {
+        Ok(self.send(request).await?.json().await?)
+    }
+}
+
+/// What an event stream yields
+#[derive(Clone, Debug)]
+pub enum StreamEvent {
+    Update(QuoteUpdate),
+    /// The stream fell behind the API and this many updates were skipped
+    Lagged(u64),
+}
+
+/// Server-sent events of `events` or `quote_events`, read one at a time with `next`
+#[derive(Debug)]
+pub struct EventStream {
+    response: Response,
+    /// Received bytes of events that are not complete yet
+    buffer: Vec<u8>,
+}
+
+impl EventStream {
+    /// Waits for the next event, `None` once the API closed the stream
+    pub async fn next(&mut self) -> Result<Option<StreamEvent>, Error> {
+        loop {
+            while let Some(end) = self.buffer.windows(2).position(|bytes| bytes == b"\n\n") {
+                let frame = self.buffer.drain(..end + 2).collect::<Vec<_>>();
+                if let Some(event) = parse_event(&String::from_utf8_lossy(&frame))? {
+                    return Ok(Some(event));
+                }
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// Parses one server-sent event, keep-alive comments carry no data and yield `None`
+fn parse_event(frame: &str) -> Result<Option<StreamEvent>, Error> {
+    let mut name = "message";
+    let mut data = vec![];
+    for line in frame.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            name = value.trim_start();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    if data.is_empty() {
+        return Ok(None);
+    }
+    let data = data.join("\n");
+    match name {
+        "lagged" => Ok(Some(StreamEvent::Lagged(data.trim().parse().unwrap_or(0)))),
+        _ => Ok(Some(StreamEvent::Update(serde_json::from_str(&data)?))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enums_serialize_like_the_api() {
+        let value = |value: serde_json::Value| value.as_str().map(str::to_string);
+        assert_eq!(value(serde_json::json!(ProofType::RawQuote)).as_deref(), Some("RawQuote"));
+        assert_eq!(value(serde_json::json!(QuoteSortBy::UpdatedAt)).as_deref(), Some("updated_at"));
+        assert_eq!(value(serde_json::json!(TdxQuoteStatus::CollateralFetched)).as_deref(), Some("CollateralFetched"));
+    }
+
+    #[test]
+    fn parses_server_sent_events() {
+        assert!(parse_event(":\n\n").unwrap().is_none());
+        assert!(matches!(parse_event("event: lagged\ndata: 3\n\n").unwrap(), Some(StreamEvent::Lagged(3))));
+
+        let frame = concat!(
+            "event: transition\n",
+            r#"data: {"kind":"transition","quote_id":"q","onchain_request_id":"r","agent_id":null,"status":"Proved","#,
+            r#""from":"Proving","proof_type":"Sp1","txn_hash":null,"prover_request_id":null,"confirmations":null,"#,
+            r#""required_confirmations":null,"actor":"api","detail":null,"occurred_at":"2025-05-19T12:00:00Z"}"#,
+            "\n\n",
+        );
+        let Some(StreamEvent::Update(update)) = parse_event(frame).unwrap() else {
+            panic!("expected an update");
+        };
+        assert_eq!(update.kind, QuoteUpdateKind::Transition);
+        assert_eq!(update.from, Some(TdxQuoteStatus::Proving));
+        assert_eq!(update.actor, Some(QuoteActor::Api));
+    }
+}
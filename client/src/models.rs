@@ -0,0 +1,536 @@
+//! Schemas of the OpenAPI document served at `/api/openapi.json`, named after the DTOs of the
+//! `tdx-prover` crate they describe
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProofType {
+    Sp1,
+    Risc0,
+    RawQuote,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TdxQuoteStatus {
+    Pending,
+    Failure,
+    Success,
+    Expired,
+    Cancelled,
+    Submitted,
+    Confirmed,
+    CollateralFetched,
+    Proving,
+    Proved,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteActor {
+    Lambda,
+    Cli,
+    Api,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteSortBy {
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuoteBodyType {
+    SGXQuoteBody,
+    TD10QuoteBody,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuoteReadDto {
+    pub id: String,
+    pub onchain_request_id: String,
+    pub status: TdxQuoteStatus,
+    pub quote: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub proof_type: Option<ProofType>,
+    pub txn_hash: Option<Vec<u8>>,
+    pub request_id: Option<Vec<u8>>,
+    pub quote_hash: Vec<u8>,
+    pub duplicate_of: Option<String>,
+    pub agent_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuoteRegisterDto {
+    pub proof_type: Option<ProofType>,
+    pub onchain_request_id: String,
+    pub quote: Vec<u8>,
+    pub txn_hash: Option<Vec<u8>>,
+    pub request_id: Option<Vec<u8>>,
+    pub status: TdxQuoteStatus,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuoteListDto {
+    pub quotes: Vec<QuoteReadDto>,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuoteEventReadDto {
+    pub from_status: Option<TdxQuoteStatus>,
+    pub to_status: TdxQuoteStatus,
+    pub actor: QuoteActor,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuoteHistoryDto {
+    pub quote_id: String,
+    pub status: TdxQuoteStatus,
+    pub events: Vec<QuoteEventReadDto>,
+}
+
+/// Query of `GET /api/quotes`, filters that are not set are left out
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct QuoteListParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TdxQuoteStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_type: Option<ProofType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creator_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_from: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_to: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_by: Option<QuoteSortBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<SortOrder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DcapVerifiedOutput {
+    pub quote_version: u16,
+    pub tee_type: u32,
+    pub tcb_status: String,
+    pub fmspc: Vec<u8>,
+    pub quote_body_type: QuoteBodyType,
+    pub quote_body_bytes: Vec<u8>,
+    pub advisory_ids: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DcapProof {
+    pub verified_output: Vec<u8>,
+    /// Passed back to `verify` and `submit_proof` as returned by `prove`
+    pub proof: serde_json::Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestReadDto {
+    pub id: String,
+    pub creator_address: String,
+    pub operator_address: String,
+    pub model_id: String,
+    pub fee_wei: i64,
+    pub nonce: i64,
+    pub request_id: Vec<u8>,
+    pub deadline: DateTime<Utc>,
+    pub is_cancelled: bool,
+    pub cancelled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub agent_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestRegisterDto {
+    pub creator_address: String,
+    pub operator_address: String,
+    pub model_id: String,
+    pub fee_wei: i64,
+    pub nonce: i64,
+    /// 0x prefixed bytes32
+    pub request_id: String,
+    pub deadline: DateTime<Utc>,
+    /// Hex encoded quote to register together with the request
+    pub quote: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RequestUpdateDto {
+    pub operator_address: Option<String>,
+    pub fee_wei: Option<i64>,
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestRegisteredDto {
+    pub request: RequestReadDto,
+    pub quote: Option<QuoteReadDto>,
+}
+
+/// Query of `GET /api/requests`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RequestListParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creator_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_cancelled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+}
+
+/// Transactions of `GET /api/request/{id}/fees`, wei amounts are decimal strings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionFeeReadDto {
+    pub id: String,
+    pub tdx_quote_id: String,
+    pub onchain_request_id: String,
+    pub proof_type: ProofType,
+    pub txn_hash: String,
+    pub block_number: Option<i64>,
+    pub gas_used: i64,
+    pub effective_gas_price: String,
+    pub fee_wei: String,
+    pub signer: String,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RequestFeeReportDto {
+    pub onchain_request_id: String,
+    pub tx_count: u64,
+    pub gas_used: i64,
+    pub fee_wei: String,
+    pub transactions: Vec<TransactionFeeReadDto>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeGroupBy {
+    Day,
+    ModelId,
+    ProofType,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeReportEntryDto {
+    pub key: String,
+    pub tx_count: i64,
+    pub gas_used: String,
+    pub fee_wei: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FeeReportDto {
+    pub group_by: FeeGroupBy,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub entries: Vec<FeeReportEntryDto>,
+}
+
+/// Query of `GET /api/fees/report`, grouped by day when `group_by` is not set
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FeeReportParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_by: Option<FeeGroupBy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationSource {
+    Dcap,
+    Zkvm,
+    Onchain,
+}
+
+/// Measurements are 0x prefixed hex strings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VerificationReadDto {
+    pub id: String,
+    pub tdx_quote_id: String,
+    pub source: VerificationSource,
+    pub quote_version: i32,
+    pub tee_type: i64,
+    pub tcb_status: String,
+    pub fmspc: String,
+    pub advisory_ids: Vec<String>,
+    pub mrtd: Option<String>,
+    pub rtmrs: Option<Vec<String>>,
+    pub report_data: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TcbStatusEntryDto {
+    pub fmspc: String,
+    pub tcb_status: String,
+    pub quote_count: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TcbStatusReportDto {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub entries: Vec<TcbStatusEntryDto>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MrtdEntryDto {
+    pub mrtd: String,
+    pub quote_count: i64,
+    pub last_verified_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MrtdReportDto {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub entries: Vec<MrtdEntryDto>,
+}
+
+/// Query of the `/api/verifications` reports, the period is unbounded on the sides not set
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VerificationReportParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentStatus {
+    Active,
+    Suspended,
+    Retired,
+}
+
+/// Measurements and the FMSPC are 0x prefixed hex strings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgentRegisterDto {
+    pub operator_address: String,
+    pub name: String,
+    pub expected_mrtd: Option<String>,
+    /// RTMR0 to RTMR3
+    pub expected_rtmrs: Option<Vec<String>>,
+    pub fmspc: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AgentUpdateDto {
+    pub name: Option<String>,
+    pub expected_mrtd: Option<String>,
+    pub expected_rtmrs: Option<Vec<String>>,
+    pub fmspc: Option<String>,
+    pub status: Option<AgentStatus>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgentReadDto {
+    pub id: String,
+    pub operator_address: String,
+    pub name: String,
+    pub expected_mrtd: Option<String>,
+    pub expected_rtmrs: Option<Vec<String>>,
+    pub fmspc: Option<String>,
+    pub status: AgentStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Query of `GET /api/agent/{id}/quotes` and `GET /api/agent/{id}/requests`, requests are not
+/// filtered by `status` and not paged by `cursor`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AgentHistoryParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TdxQuoteStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    Proved,
+    Submitted,
+    Confirmed,
+    Failed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookRegisterDto {
+    pub url: String,
+    /// Every event when not set
+    pub events: Option<Vec<WebhookEvent>>,
+    /// Only deliver the events of this request
+    pub onchain_request_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookReadDto {
+    pub id: String,
+    pub api_key_id: Option<String>,
+    pub onchain_request_id: Option<String>,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: DateTime<Utc>,
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookCreatedDto {
+    #[serde(flatten)]
+    pub webhook: WebhookReadDto,
+    pub secret: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookDeliveryReadDto {
+    pub id: String,
+    pub webhook_id: String,
+    pub quote_id: String,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub replay_of: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Body of the requests sent to webhooks
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub id: String,
+    pub event: WebhookEvent,
+    pub occurred_at: DateTime<Utc>,
+    pub quote_id: String,
+    pub onchain_request_id: String,
+    pub status: TdxQuoteStatus,
+    pub proof_type: Option<ProofType>,
+    pub txn_hash: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteUpdateKind {
+    Snapshot,
+    Transition,
+    ProverRequest,
+    Confirmations,
+}
+
+/// Data of the server-sent events of `GET /api/events` and `GET /api/quote/{id}/events`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QuoteUpdate {
+    pub kind: QuoteUpdateKind,
+    pub quote_id: String,
+    pub onchain_request_id: String,
+    pub agent_id: Option<String>,
+    pub status: TdxQuoteStatus,
+    pub from: Option<TdxQuoteStatus>,
+    pub proof_type: Option<ProofType>,
+    pub txn_hash: Option<String>,
+    pub prover_request_id: Option<String>,
+    pub confirmations: Option<u64>,
+    pub required_confirmations: Option<u64>,
+    pub actor: Option<QuoteActor>,
+    pub detail: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Query of `GET /api/events`, filters that are not set are left out
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EventListParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_request_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<TdxQuoteStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<QuoteUpdateKind>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Up,
+    Degraded,
+    Down,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub detail: Option<String>,
+    pub latency_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub checked_at: DateTime<Utc>,
+    pub components: Vec<ComponentHealth>,
+}
+
+/// Body of every error response
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApiErrorResponse {
+    pub message: Option<String>,
+    pub code: u16,
+}
@@ -44,6 +44,7 @@ stack = TdxProver(
     vpc_id=os.getenv("USW2_VPC_ID"),
     env=cdk.Environment(account=os.getenv("AWS_ACCOUNT"), region="us-west-2"),
     event_bus_arn=os.getenv("OPS_EVENT_BUS_ARN"),
+    prover_signer_type=os.getenv("PROVER_SIGNER_TYPE", "private_key"),
 )
 
 ## We don't need a secondary region for the Prover stack
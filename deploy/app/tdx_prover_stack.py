@@ -28,6 +28,7 @@ class TdxProver(Stack):
         vpc_id: str,
         aws_region: str,
         event_bus_arn: str,
+        prover_signer_type: str = "private_key",
         **kwargs,
     ) -> None:
         super().__init__(scope, construct_id, **kwargs)
@@ -105,6 +106,20 @@ class TdxProver(Stack):
             removal_policy=cdk.RemovalPolicy.DESTROY,
         )
 
+        # The prover account key stays in the service secrets unless a web3signer is opted into,
+        # its PROVER_REMOTE_SIGNER_URL and PROVER_REMOTE_SIGNER_PUBLIC_KEY secrets must exist first
+        if prover_signer_type == "remote":
+            signer_environment = {
+                "PROVER_SIGNER_TYPE": "remote",
+                "PROVER_REMOTE_SIGNER_URL": self.service_secrets.secret_value_from_json("PROVER_REMOTE_SIGNER_URL").unsafe_unwrap(),
+                "PROVER_REMOTE_SIGNER_PUBLIC_KEY": self.service_secrets.secret_value_from_json("PROVER_REMOTE_SIGNER_PUBLIC_KEY").unsafe_unwrap(),
+            }
+        else:
+            signer_environment = {
+                "PROVER_SIGNER_TYPE": "private_key",
+                "PROVER_PRIVATE_KEY": self.service_secrets.secret_value_from_json("PROVER_PRIVATE_KEY").unsafe_unwrap(),
+            }
+
         # Create Rust Lambda Function for Tdx Prover
         # Note: This Lambda function expects the Rust binary to be built using cargo-lambda:
         # 1. Install cargo-lambda: cargo install cargo-lambda
@@ -131,7 +146,7 @@ class TdxProver(Stack):
                 "SKIP_ONCHAIN_VERIFICATION": "false",
                 "SP1_PROVER": "network",
                 "NETWORK_PRIVATE_KEY": self.service_secrets.secret_value_from_json("NETWORK_PRIVATE_KEY").unsafe_unwrap(),
-                "PROVER_PRIVATE_KEY": self.service_secrets.secret_value_from_json("PROVER_PRIVATE_KEY").unsafe_unwrap(),
+                **signer_environment,
                 "BONSAI_API_KEY": self.service_secrets.secret_value_from_json("BONSAI_API_KEY").unsafe_unwrap(),
                 "BONSAI_API_URL": "https://api.bonsai.xyz/",
                 "RISC_ZERO_VERSION": "1.2.4",
@@ -172,6 +187,44 @@ class TdxProver(Stack):
             targets=[event_targets.LambdaFunction(rust_lambda)]
         )
 
+        # Submitted proofs are only confirmed, or retried after a reorg, by the finality check
+        event_source.Rule(
+            self,
+            f"{APP_SHORTNAME}-finality-check-rule",
+            rule_name=f"{APP_SHORTNAME}-finality-check-rule",
+            description="Check the finality of submitted proof transactions",
+            schedule=event_source.Schedule.rate(Duration.minutes(1)),
+            targets=[
+                event_targets.LambdaFunction(
+                    rust_lambda,
+                    event=event_source.RuleTargetInput.from_object({
+                        "source": "com.magic.tdx-prover",
+                        "detail-type": "finality-check",
+                        "detail": {"check_finality": True, "count": 100},
+                    }),
+                )
+            ],
+        )
+
+        # Quotes whose proving attempt failed are proven again once their retry is due
+        event_source.Rule(
+            self,
+            f"{APP_SHORTNAME}-retry-due-rule",
+            rule_name=f"{APP_SHORTNAME}-retry-due-rule",
+            description="Prove quotes whose failed proving attempt is due for a retry",
+            schedule=event_source.Schedule.rate(Duration.minutes(1)),
+            targets=[
+                event_targets.LambdaFunction(
+                    rust_lambda,
+                    event=event_source.RuleTargetInput.from_object({
+                        "source": "com.magic.tdx-prover",
+                        "detail-type": "retry-due",
+                        "detail": {"retry_due": True, "count": 1},
+                    }),
+                )
+            ],
+        )
+
         # Allow EventBridge to invoke this Lambda function
         rust_lambda.add_permission(
             id=f"{APP_SHORTNAME}-eventbridge-invoke-permission",
@@ -4,30 +4,39 @@ use tdx_prover::{
         database::{Database, DatabaseTrait},
         parameter,
     },
-    entity::quote::{ProofType, TdxQuoteStatus},
-    error::{db_error::DbError, quote_error::QuoteError},
+    entity::{
+        attempt::{AttemptStage, ErrorCategory},
+        quote::{ProofType, TdxQuoteStatus},
+        quote_event::QuoteActor,
+        usage::QuotaSubject,
+        verification::VerificationSource,
+        zk::ProofSystem,
+    },
+    error::{
+        db_error::DbError,
+        quote_error::QuoteError,
+        request_error::RequestError,
+        usage_error::UsageError,
+    },
     repository::{quote_repository::QuoteRepositoryTrait, request_repository::OnchainRequestRepositoryTrait},
+    service::{
+        finality_service::FinalityService,
+        lifecycle_service::{is_terminal, LifecycleService},
+        usage_service::UsageService,
+    },
     state::{quote_state::QuoteState, request_state::RequestState}, zk,
 };
 use aws_lambda_events::eventbridge::EventBridgeEvent;
+use chrono::{DateTime, Utc};
 use hex::FromHex;
 use lambda_runtime::{Error, LambdaEvent};
 use serde_json::Value;
 
-#[tracing::instrument(skip(event), fields(req_id = %event.payload.detail.get("request_id").unwrap().as_str().unwrap()))]
+#[tracing::instrument(skip(event), fields(req_id = ?event.payload.detail.get("request_id")))]
 pub(crate) async fn handler(event: LambdaEvent<EventBridgeEvent>) -> Result<(), Error> {
     tracing::info!("Event: {:?}", event);
     parameter::init();
 
-    let request_id_hex = event.payload.detail.get("request_id").unwrap().as_str().unwrap();
-    tracing::info!("Request ID hex: {}", request_id_hex);
-    let request_id = Vec::from_hex(request_id_hex.strip_prefix("0x").unwrap_or(request_id_hex))?;
-
-    let default_proof_type = Value::String("sp1".to_string());
-    let proof_type_str = event.payload.detail.get("proof_type").unwrap_or(&default_proof_type).as_str().unwrap();
-    tracing::info!("Proof type: {}", proof_type_str);
-    let proof_type = ProofType::from_str(proof_type_str.to_lowercase().as_str()).unwrap();
-
     let db_conn = Arc::new(
         Database::init()
             .await
@@ -48,8 +57,70 @@ pub(crate) async fn handler(event: LambdaEvent<EventBridgeEvent>) -> Result<(),
         }
     }
 
-    let quote_state = QuoteState::new(&db_conn);
-    let request_state = RequestState::new(&db_conn);
+    // Sent on a schedule, proves the quotes whose failed attempt is due instead of one request
+    if event.payload.detail.get("retry_due").and_then(Value::as_bool) == Some(true) {
+        let count = event.payload.detail.get("count").and_then(Value::as_u64).unwrap_or(1);
+        return retry_due(&db_conn, count).await;
+    }
+
+    // Sent on a schedule, moves submitted quotes on once their proof transaction is final or gone
+    if event.payload.detail.get("check_finality").and_then(Value::as_bool) == Some(true) {
+        let count = event.payload.detail.get("count").and_then(Value::as_i64).unwrap_or(100);
+        let summary = FinalityService::new(&db_conn, QuoteActor::Lambda).check_submitted(count).await?;
+        tracing::info!("Finality check: {:?}", summary);
+        return Ok(());
+    }
+
+    let request_id_hex = event.payload.detail.get("request_id").unwrap().as_str().unwrap();
+    tracing::info!("Request ID hex: {}", request_id_hex);
+    let request_id = Vec::from_hex(request_id_hex.strip_prefix("0x").unwrap_or(request_id_hex))?;
+
+    let default_proof_type = Value::String("sp1".to_string());
+    let proof_type_str = event.payload.detail.get("proof_type").unwrap_or(&default_proof_type).as_str().unwrap();
+    tracing::info!("Proof type: {}", proof_type_str);
+    let proof_type = ProofType::from_str(proof_type_str.to_lowercase().as_str()).unwrap();
+
+    prove_request(&db_conn, request_id, proof_type, None, false).await
+}
+
+/// Proves up to `count` quotes whose failed attempt is due, each with the backend of that attempt
+async fn retry_due(db_conn: &Arc<Database>, count: u64) -> Result<(), Error> {
+    let lifecycle = LifecycleService::new(db_conn, QuoteActor::Lambda);
+    let mut retried = 0;
+    while retried < count {
+        let Some(retry) = lifecycle.claim_due_retry().await? else {
+            break;
+        };
+        retried += 1;
+        let (proof_type, proof_system) = match retry.proof_backend() {
+            Ok(backend) => backend,
+            Err(e) => {
+                tracing::error!("Skipping retry of quote {}: {}", retry.tdx_quote_id, e);
+                continue;
+            }
+        };
+        tracing::info!("Retrying quote {} with {}", retry.tdx_quote_id, retry.backend);
+        if let Err(e) = prove_request(db_conn, retry.request_id, proof_type, proof_system, true).await {
+            tracing::error!("Retry of quote {} failed: {}", retry.tdx_quote_id, e);
+        }
+    }
+    tracing::info!("Retried {} quotes", retried);
+    Ok(())
+}
+
+/// Proves the quote of `request_id` and submits the proof. Retries were charged to the creator's
+/// quota by their first attempt, a charge is given back when proving fails before a proof exists.
+async fn prove_request(
+    db_conn: &Arc<Database>,
+    request_id: Vec<u8>,
+    proof_type: ProofType,
+    proof_system: Option<ProofSystem>,
+    is_retry: bool,
+) -> Result<(), Error> {
+    let request_id_hex = format!("0x{}", hex::encode(&request_id));
+    let quote_state = QuoteState::new(db_conn);
+    let request_state = RequestState::new(db_conn);
+    let lifecycle = LifecycleService::new(db_conn, QuoteActor::Lambda);
 
     let onchain_request = request_state.request_repo.find_by_request_id(request_id).await.map_err(|e| {
         tracing::error!("Failed to fetch onchain request: {}", e);
@@ -65,22 +136,87 @@ pub(crate) async fn handler(event: LambdaEvent<EventBridgeEvent>) -> Result<(),
 
     let quote_id = attestation.id;
     tracing::info!("Attestation found for request ID: {} {}", request_id_hex, attestation.status);
-    let proof = zk::prove(attestation.quote, proof_type, None).await
-        .map_err(|e| {
+
+    // Submitted quotes are left to the finality check so a proof is never sent twice
+    if is_terminal(attestation.status) || attestation.status == TdxQuoteStatus::Submitted {
+        tracing::info!("Skipping request ID: {} quote is already {}", request_id_hex, attestation.status);
+        return Ok(());
+    }
+
+    if let Err(e) = onchain_request.ensure_active() {
+        tracing::warn!("Skipping request ID: {} {}", request_id_hex, e);
+        lifecycle.mark_inactive(&attestation, &e).await?;
+        return Ok(());
+    }
+
+    // A failed attempt waiting for its retry is only proven again by the `retry_due` event
+    let due = if is_retry { Ok(()) } else { lifecycle.ensure_retry_due(&attestation).await };
+    match due {
+        Ok(()) => {}
+        Err(QuoteError::RetryScheduled(category, at)) => {
+            tracing::info!("Skipping request ID: {} retry of its {} error is scheduled at {}", request_id_hex, category, at);
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    // Counted in the same table as the API, so a creator cannot exceed its quota through events
+    let creator = [QuotaSubject::Creator(onchain_request.creator_address.clone())];
+    let usage = UsageService::new(db_conn);
+    let charged_at = (!is_retry).then(Utc::now);
+    if let Some(charged_at) = charged_at {
+        match usage.consume_proof_at(&creator, charged_at).await {
+            Ok(_) => {}
+            Err(UsageError::QuotaExceeded(allowance)) => {
+                tracing::warn!(
+                    "Skipping request ID: {} creator reached its proving quota of {} until {}",
+                    request_id_hex, allowance.limit, allowance.reset_at
+                );
+                let detail = format!(
+                    "Not proven, the creator reached its proving quota of {} until {}",
+                    allowance.limit, allowance.reset_at.to_rfc3339()
+                );
+                lifecycle.note(&attestation, detail).await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let quote = match lifecycle.restart(&attestation).await {
+        Ok(quote) => quote,
+        Err(e) => {
+            refund(&usage, &creator, charged_at).await;
+            return Err(e.into());
+        }
+    };
+    let (quote, proof) = match lifecycle.prove(quote, proof_type, proof_system).await {
+        Ok(proven) => proven,
+        // The quote stays pending until a `retry_due` event proves it again, the retry is covered
+        // by this charge
+        Err(QuoteError::RetryScheduled(category, at)) => {
+            tracing::warn!("Proving request ID: {} failed with a {} error, retry scheduled at {}", request_id_hex, category, at);
+            return Ok(());
+        }
+        Err(e) => {
             tracing::error!("Failed to generate proof for request ID: {:?} {}", request_id_hex, e.to_string());
-            QuoteError::Prove
-        })?;
+            refund(&usage, &creator, charged_at).await;
+            return Err(e.into());
+        }
+    };
     
     tracing::info!("Proof generated for request ID: {:?}", request_id_hex);
     
-    // only verify proof in dev because in lambda, filesystem is not writable
-    if std::env::var("ENV").unwrap_or("dev".to_string()) != "prod" {
+    // only verify proof in dev because in lambda, filesystem is not writable, raw quotes are verified on-chain only
+    if std::env::var("ENV").unwrap_or("dev".to_string()) != "prod" && proof_type != ProofType::RawQuote {
         tracing::info!("Verifying proof...");
         
-        zk::verify_proof(&proof.proof).await.map_err(|e| {
+        if let Err(e) = zk::verify_proof(&proof.proof).await {
             tracing::error!("Failed to verify proof: {}", e);
-            QuoteError::VerifyProof
-        })?;
+            refund(&usage, &creator, charged_at).await;
+            lifecycle.fail(&quote, format!("Failed to verify proof: {}", e)).await?;
+            return Err(QuoteError::VerifyProof.into());
+        }
         tracing::info!("Successfully verified proof.");
     }
 
@@ -93,52 +229,63 @@ pub(crate) async fn handler(event: LambdaEvent<EventBridgeEvent>) -> Result<(),
         return Ok(());
     }
 
+    // Proving can take several minutes, so re-check the request before paying for the transaction
+    let onchain_request = request_state.request_repo.find(onchain_request.id).await.map_err(|e| {
+        tracing::error!("Failed to refetch onchain request: {}", e);
+        DbError::SomethingWentWrong("Failed to fetch onchain request".to_string())
+    })?;
+    if let Err(e) = onchain_request.ensure_active() {
+        tracing::warn!("Aborting proof submission for request ID: {} {}", request_id_hex, e);
+        lifecycle.mark_inactive(&quote, &e).await?;
+        return Ok(());
+    }
+
+    let backend = if verify_only { "verify_only" } else { "onchain" };
+    let attempt = lifecycle.start_attempt(&quote, AttemptStage::Submit, backend).await?;
     let (verified, raw_verified_output, tx_hash, response) =
-        zk::submit_proof(onchain_request, proof_type, proof.proof, Some(verify_only)).await
-            .map_err(|e| {
+        match zk::submit_proof(onchain_request, proof_type, proof.proof, Some(verify_only)).await {
+            Ok(result) => result,
+            Err(e) => {
+                let category = ErrorCategory::classify(&e).unwrap_or(ErrorCategory::Unknown);
+                lifecycle.finish_attempt(&attempt, Some((category, &e))).await?;
+                if let Some(request_error) = e.downcast_ref::<RequestError>() {
+                    tracing::warn!("Aborting proof submission for request ID: {} {}", request_id_hex, request_error);
+                    lifecycle.mark_inactive(&quote, request_error).await?;
+                    return Ok(());
+                }
                 tracing::error!("Failed to submit proof: {}", e);
-                QuoteError::SubmitProof
-            })?;
+                lifecycle.fail(&quote, format!("Failed to submit proof: {}", e)).await?;
+                return Err(QuoteError::SubmitProof.into());
+            }
+        };
 
     tracing::info!(
         "Proof submitted for request ID: {} verified: {} raw_verified_output: {}",
         request_id_hex, verified, hex::encode(&raw_verified_output)
     );
+    // A reverted transaction returns the local output, only record what the contract accepted
+    if verified && response.as_ref().is_none_or(|response| response.cost.success) {
+        lifecycle.record_verification(&quote, VerificationSource::Onchain, &raw_verified_output).await;
+    }
 
-    if response.is_some() {
+    if let Some(response) = &response {
         tracing::info!("Submit proof response: {:#?}", response);
-        let response = response.unwrap();
-        tracing::info!("Transaction hash: {}", hex::encode(&response.transaction_hash));
-
-        // Update onchain request status
-        quote_state.quote_repo.update_status(
-            quote_id,
-            proof_type,
-            TdxQuoteStatus::Failure,
-            Some(response.transaction_hash.to_vec()),
-            None,
-        ).await.map_err(|e| {
-            tracing::error!("Failed to update quote status on success: {}", e);
-            QuoteError::UpdateStatusOnSuccess
-        })?;
-
-        tracing::info!("tdx_quote updated successfully {quote_id} {}", TdxQuoteStatus::Success);
-    } else if tx_hash.is_some() {
-        tracing::info!("Transaction hash: {}", hex::encode(&tx_hash.unwrap().to_vec()));
-        // Update onchain request status
-        quote_state.quote_repo.update_status(
-            quote_id,
-            proof_type,
-            TdxQuoteStatus::Failure,
-            Some(tx_hash.unwrap().to_vec()),
-            None,
-        ).await.map_err(|e| {
-            tracing::error!("Failed to update quote status on failure: {}", e);
-            QuoteError::UpdateStatusOnFailure
-        })?;
-
-        tracing::info!("tdx_quote updated successfully {quote_id} {}", TdxQuoteStatus::Failure);
+        tracing::info!("Transaction hash: {}", hex::encode(response.transaction_hash));
+    } else if let Some(tx_hash) = tx_hash {
+        tracing::info!("Transaction hash: {}", hex::encode(tx_hash));
     }
+    let quote = lifecycle.record_submission(&quote, &attempt, proof_type, verify_only, tx_hash, response).await?;
+    tracing::info!("tdx_quote updated successfully {quote_id} {}", quote.status);
 
     Ok(())
 }
+
+/// Gives back the proof charged at `charged_at` when proving failed before a proof existed
+async fn refund(usage: &UsageService, creator: &[QuotaSubject], charged_at: Option<DateTime<Utc>>) {
+    let Some(charged_at) = charged_at else {
+        return;
+    };
+    if let Err(e) = usage.refund_proof(creator, charged_at).await {
+        tracing::warn!("Failed to refund the proof of {:?}: {}", creator, e);
+    }
+}
@@ -1,7 +1,10 @@
 use lambda_runtime::{service_fn, Error};
+use tdx_prover::telemetry;
 
 mod function;
 
+const LAMBDA_METRICS_JOB: &str = "tdx-prover-lambda";
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     let _ = rustls::crypto::ring::default_provider().install_default();
@@ -18,9 +21,13 @@ async fn main() -> Result<(), Error> {
         // remove the name of the function from every log entry
         .with_target(false)
         .init();
+    telemetry::install_push(LAMBDA_METRICS_JOB);
 
     lambda_runtime::run(service_fn(move |event| async move {
-        function::handler(event).await
+        let result = function::handler(event).await;
+        // The container may be frozen right after returning, push before that
+        telemetry::push(LAMBDA_METRICS_JOB).await;
+        result
     }))
     .await
 }
@@ -0,0 +1,30 @@
+-- Gas and fee paid for every proof transaction sent on-chain
+CREATE TABLE tdx_transaction_fee (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    tdx_quote_id uuid NOT NULL,
+    onchain_request_id uuid NOT NULL,
+    proof_type prooftype NOT NULL,
+    txn_hash bytea NOT NULL,
+    block_number bigint,
+    gas_used bigint NOT NULL,
+    effective_gas_price numeric(78, 0) NOT NULL,
+    fee_wei numeric(78, 0) NOT NULL,
+    signer character varying(42) NOT NULL,
+    success boolean NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+ALTER TABLE ONLY tdx_transaction_fee
+    ADD CONSTRAINT tdx_transaction_fee_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY tdx_transaction_fee
+    ADD CONSTRAINT tdx_transaction_fee_txn_hash_key UNIQUE (txn_hash);
+
+ALTER TABLE ONLY tdx_transaction_fee
+    ADD CONSTRAINT tdx_transaction_fee_tdx_quote_id_fkey FOREIGN KEY (tdx_quote_id) REFERENCES tdx_quote(id);
+
+ALTER TABLE ONLY tdx_transaction_fee
+    ADD CONSTRAINT tdx_transaction_fee_onchain_request_id_fkey FOREIGN KEY (onchain_request_id) REFERENCES onchain_request(id);
+
+CREATE INDEX tdx_transaction_fee_onchain_request_id_idx ON tdx_transaction_fee (onchain_request_id);
+CREATE INDEX tdx_transaction_fee_created_at_idx ON tdx_transaction_fee (created_at);
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TYPE tdxquotestatus ADD VALUE IF NOT EXISTS 'submitted';
+ALTER TYPE tdxquotestatus ADD VALUE IF NOT EXISTS 'confirmed';
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TYPE prooftype ADD VALUE IF NOT EXISTS 'rawquote';
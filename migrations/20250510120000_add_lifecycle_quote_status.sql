@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TYPE tdxquotestatus ADD VALUE IF NOT EXISTS 'collateralfetched';
+ALTER TYPE tdxquotestatus ADD VALUE IF NOT EXISTS 'proving';
+ALTER TYPE tdxquotestatus ADD VALUE IF NOT EXISTS 'proved';
@@ -0,0 +1,24 @@
+-- Every status transition of a quote, in the order it happened
+CREATE TYPE quoteactor AS ENUM (
+    'lambda',
+    'cli',
+    'api'
+);
+
+CREATE TABLE tdx_quote_event (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    tdx_quote_id uuid NOT NULL,
+    from_status tdxquotestatus,
+    to_status tdxquotestatus NOT NULL,
+    actor quoteactor NOT NULL,
+    detail text,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+ALTER TABLE ONLY tdx_quote_event
+    ADD CONSTRAINT tdx_quote_event_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY tdx_quote_event
+    ADD CONSTRAINT tdx_quote_event_tdx_quote_id_fkey FOREIGN KEY (tdx_quote_id) REFERENCES tdx_quote(id);
+
+CREATE INDEX tdx_quote_event_tdx_quote_id_idx ON tdx_quote_event (tdx_quote_id, created_at);
@@ -0,0 +1,36 @@
+-- One row per proving or submission attempt of a quote
+CREATE TYPE attemptstage AS ENUM (
+    'prove',
+    'submit'
+);
+
+CREATE TYPE errorcategory AS ENUM (
+    'collateral_missing',
+    'quote_parse',
+    'prover_network',
+    'timeout',
+    'onchain_revert',
+    'db',
+    'unknown'
+);
+
+CREATE TABLE tdx_proof_attempt (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    tdx_quote_id uuid NOT NULL,
+    stage attemptstage NOT NULL,
+    attempt_number integer NOT NULL,
+    backend character varying(32) NOT NULL,
+    started_at timestamp with time zone DEFAULT now() NOT NULL,
+    finished_at timestamp with time zone,
+    error_category errorcategory,
+    error_chain text
+);
+
+ALTER TABLE ONLY tdx_proof_attempt
+    ADD CONSTRAINT tdx_proof_attempt_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY tdx_proof_attempt
+    ADD CONSTRAINT tdx_proof_attempt_number_key UNIQUE (tdx_quote_id, stage, attempt_number);
+
+ALTER TABLE ONLY tdx_proof_attempt
+    ADD CONSTRAINT tdx_proof_attempt_tdx_quote_id_fkey FOREIGN KEY (tdx_quote_id) REFERENCES tdx_quote(id);
@@ -0,0 +1,66 @@
+-- Defaults the services rely on when inserting rows
+ALTER TABLE tdx_quote
+    ALTER COLUMN id SET DEFAULT gen_random_uuid(),
+    ALTER COLUMN created_at SET DEFAULT now(),
+    ALTER COLUMN updated_at SET DEFAULT now();
+
+ALTER TABLE onchain_request
+    ALTER COLUMN id SET DEFAULT gen_random_uuid(),
+    ALTER COLUMN is_cancelled SET DEFAULT false,
+    ALTER COLUMN created_at SET DEFAULT now(),
+    ALTER COLUMN updated_at SET DEFAULT now();
+
+--
+-- Keeps updated_at current on every update, whether or not the query sets it
+--
+
+CREATE OR REPLACE FUNCTION set_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at = now();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER tdx_quote_set_updated_at
+    BEFORE UPDATE ON tdx_quote
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+CREATE TRIGGER onchain_request_set_updated_at
+    BEFORE UPDATE ON onchain_request
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+--
+-- Requests the listener indexed more than once keep their first row, quotes and fees of the
+-- copies move to it so the unique constraint below can be added
+--
+
+CREATE TEMP TABLE onchain_request_duplicate ON COMMIT DROP AS
+SELECT id, kept_id FROM (
+    SELECT id, first_value(id) OVER (PARTITION BY request_id ORDER BY created_at, id) AS kept_id
+    FROM onchain_request
+) request
+WHERE id <> kept_id;
+
+UPDATE tdx_quote SET onchain_request_id = duplicate.kept_id
+FROM onchain_request_duplicate duplicate
+WHERE tdx_quote.onchain_request_id = duplicate.id;
+
+UPDATE tdx_transaction_fee SET onchain_request_id = duplicate.kept_id
+FROM onchain_request_duplicate duplicate
+WHERE tdx_transaction_fee.onchain_request_id = duplicate.id;
+
+DELETE FROM onchain_request
+USING onchain_request_duplicate duplicate
+WHERE onchain_request.id = duplicate.id;
+
+--
+-- Name: onchain_request onchain_request_request_id_key; Type: CONSTRAINT; Owner: postgres
+--
+
+ALTER TABLE ONLY onchain_request
+    ADD CONSTRAINT onchain_request_request_id_key UNIQUE (request_id);
+
+CREATE INDEX tdx_quote_onchain_request_id_idx ON tdx_quote (onchain_request_id);
+CREATE INDEX tdx_quote_request_id_idx ON tdx_quote (request_id);
+CREATE INDEX tdx_quote_status_idx ON tdx_quote (status);
+CREATE INDEX tdx_transaction_fee_tdx_quote_id_idx ON tdx_transaction_fee (tdx_quote_id);
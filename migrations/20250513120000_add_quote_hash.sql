@@ -0,0 +1,41 @@
+-- Identical quotes share a SHA-256 content hash, later registrations point at the first one
+ALTER TABLE tdx_quote ADD COLUMN quote_hash bytea;
+ALTER TABLE tdx_quote ADD COLUMN duplicate_of uuid;
+
+UPDATE tdx_quote SET quote_hash = sha256(quote);
+ALTER TABLE tdx_quote ALTER COLUMN quote_hash SET NOT NULL;
+
+UPDATE tdx_quote quote
+SET duplicate_of = original.id
+FROM (
+    SELECT DISTINCT ON (quote_hash) id, quote_hash
+    FROM tdx_quote
+    ORDER BY quote_hash, created_at, id
+) original
+WHERE quote.quote_hash = original.quote_hash AND quote.id <> original.id;
+
+ALTER TABLE ONLY tdx_quote
+    ADD CONSTRAINT tdx_quote_duplicate_of_fkey FOREIGN KEY (duplicate_of) REFERENCES tdx_quote(id);
+
+CREATE INDEX tdx_quote_quote_hash_idx ON tdx_quote (quote_hash);
+
+-- Proofs by the hash of the prover input (quote and collaterals), so they can be reused
+CREATE TABLE tdx_proof (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    tdx_quote_id uuid NOT NULL,
+    quote_hash bytea NOT NULL,
+    input_hash bytea NOT NULL,
+    proof_type prooftype NOT NULL,
+    proof_system character varying(16),
+    proof bytea NOT NULL,
+    prover_request_id bytea,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+ALTER TABLE ONLY tdx_proof
+    ADD CONSTRAINT tdx_proof_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY tdx_proof
+    ADD CONSTRAINT tdx_proof_tdx_quote_id_fkey FOREIGN KEY (tdx_quote_id) REFERENCES tdx_quote(id);
+
+CREATE INDEX tdx_proof_input_hash_idx ON tdx_proof (input_hash, proof_type, created_at);
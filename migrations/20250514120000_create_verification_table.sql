@@ -0,0 +1,35 @@
+-- Decoded verified output of every successful verification, for fleet analytics in SQL
+CREATE TYPE verificationsource AS ENUM (
+    'dcap',
+    'zkvm',
+    'onchain'
+);
+
+CREATE TABLE tdx_verification (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    tdx_quote_id uuid NOT NULL,
+    source verificationsource NOT NULL,
+    quote_version integer NOT NULL,
+    tee_type bigint NOT NULL,
+    tcb_status character varying(64) NOT NULL,
+    fmspc character varying(12) NOT NULL,
+    advisory_ids text[] DEFAULT '{}' NOT NULL,
+    -- Measurements of TD quotes, NULL for SGX enclaves
+    mrtd bytea,
+    rtmr0 bytea,
+    rtmr1 bytea,
+    rtmr2 bytea,
+    rtmr3 bytea,
+    report_data bytea NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+ALTER TABLE ONLY tdx_verification
+    ADD CONSTRAINT tdx_verification_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY tdx_verification
+    ADD CONSTRAINT tdx_verification_tdx_quote_id_fkey FOREIGN KEY (tdx_quote_id) REFERENCES tdx_quote(id);
+
+CREATE INDEX tdx_verification_tdx_quote_id_idx ON tdx_verification (tdx_quote_id, created_at);
+CREATE INDEX tdx_verification_fmspc_idx ON tdx_verification (fmspc, tcb_status);
+CREATE INDEX tdx_verification_mrtd_idx ON tdx_verification (mrtd);
@@ -0,0 +1,43 @@
+-- Agents are the operators that request and produce attestations
+CREATE TYPE agentstatus AS ENUM (
+    'active',
+    'suspended',
+    'retired'
+);
+
+CREATE TABLE agent (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    operator_address character varying(42) NOT NULL,
+    name character varying(128) NOT NULL,
+    -- Measurements the agent's TD is expected to report, NULL when not pinned
+    expected_mrtd bytea,
+    expected_rtmrs bytea[],
+    -- Hex encoded FMSPC of the registered TEE platform
+    fmspc character varying(12),
+    status agentstatus DEFAULT 'active' NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    updated_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+ALTER TABLE ONLY agent
+    ADD CONSTRAINT agent_pkey PRIMARY KEY (id);
+
+-- Addresses are stored checksummed, one agent per operator regardless of case
+CREATE UNIQUE INDEX agent_operator_address_key ON agent (LOWER(operator_address));
+
+CREATE TRIGGER agent_set_updated_at
+    BEFORE UPDATE ON agent
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
+
+-- Requests and quotes belong to the agent of their operator
+ALTER TABLE onchain_request ADD COLUMN agent_id uuid;
+ALTER TABLE tdx_quote ADD COLUMN agent_id uuid;
+
+ALTER TABLE ONLY onchain_request
+    ADD CONSTRAINT onchain_request_agent_id_fkey FOREIGN KEY (agent_id) REFERENCES agent(id);
+
+ALTER TABLE ONLY tdx_quote
+    ADD CONSTRAINT tdx_quote_agent_id_fkey FOREIGN KEY (agent_id) REFERENCES agent(id);
+
+CREATE INDEX onchain_request_agent_id_idx ON onchain_request (agent_id);
+CREATE INDEX tdx_quote_agent_id_idx ON tdx_quote (agent_id);
@@ -0,0 +1,27 @@
+-- API keys authenticate callers of the API, each key grants a set of scopes
+CREATE TYPE apiscope AS ENUM (
+    'read',
+    'register',
+    'prove',
+    'submit',
+    'admin'
+);
+
+CREATE TABLE api_key (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    name character varying(128) NOT NULL,
+    -- Leading characters of the key, enough to tell keys apart in listings
+    prefix character varying(16) NOT NULL,
+    -- SHA-256 of the key, the key itself is only shown once when created
+    key_hash bytea NOT NULL,
+    scopes apiscope[] NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    last_used_at timestamp with time zone,
+    revoked_at timestamp with time zone
+);
+
+ALTER TABLE ONLY api_key
+    ADD CONSTRAINT api_key_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY api_key
+    ADD CONSTRAINT api_key_key_hash_key UNIQUE (key_hash);
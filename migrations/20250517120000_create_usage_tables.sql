@@ -0,0 +1,43 @@
+-- Fixed window counters behind rate limits and proving quotas, shared by the API and the
+-- lambda so limits hold across restarts and invocations
+CREATE TABLE usage_counter (
+    bucket character varying(256) NOT NULL,
+    window_start timestamp with time zone NOT NULL,
+    count integer DEFAULT 0 NOT NULL
+);
+
+ALTER TABLE ONLY usage_counter
+    ADD CONSTRAINT usage_counter_pkey PRIMARY KEY (bucket, window_start);
+
+CREATE TYPE quotaperiod AS ENUM (
+    'day',
+    'month'
+);
+
+-- Number of proofs an API key or a request creator may trigger per period
+CREATE TABLE proving_quota (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    -- 'key:<api key id>' or 'creator:<lowercase address>', names the counter of the quota
+    subject character varying(64) NOT NULL,
+    api_key_id uuid,
+    creator_address character varying(42),
+    period quotaperiod NOT NULL,
+    proof_limit integer NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT proving_quota_subject_check CHECK ((api_key_id IS NULL) <> (creator_address IS NULL)),
+    CONSTRAINT proving_quota_proof_limit_check CHECK (proof_limit >= 0)
+);
+
+ALTER TABLE ONLY proving_quota
+    ADD CONSTRAINT proving_quota_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY proving_quota
+    ADD CONSTRAINT proving_quota_subject_period_key UNIQUE (subject, period);
+
+ALTER TABLE ONLY proving_quota
+    ADD CONSTRAINT proving_quota_api_key_id_fkey FOREIGN KEY (api_key_id) REFERENCES api_key(id) ON DELETE CASCADE;
+
+CREATE TRIGGER proving_quota_set_updated_at
+    BEFORE UPDATE ON proving_quota
+    FOR EACH ROW EXECUTE FUNCTION set_updated_at();
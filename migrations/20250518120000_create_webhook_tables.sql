@@ -0,0 +1,87 @@
+-- Requests registered through the API remember the key they were registered with, so the
+-- webhooks of that key receive their events
+ALTER TABLE onchain_request
+    ADD COLUMN api_key_id uuid;
+
+ALTER TABLE ONLY onchain_request
+    ADD CONSTRAINT onchain_request_api_key_id_fkey FOREIGN KEY (api_key_id) REFERENCES api_key(id) ON DELETE SET NULL;
+
+CREATE INDEX onchain_request_api_key_id_idx ON onchain_request USING btree (api_key_id);
+
+CREATE TYPE webhookevent AS ENUM (
+    'proved',
+    'submitted',
+    'confirmed',
+    'failed'
+);
+
+-- A URL that receives the events of every request of an API key, or of one request
+CREATE TABLE webhook (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    api_key_id uuid,
+    -- Set for webhooks of a single request, the key's other requests are not delivered
+    onchain_request_id uuid,
+    url character varying(2048) NOT NULL,
+    -- HMAC-SHA256 key of the payload signatures
+    secret character varying(128) NOT NULL,
+    events webhookevent[] NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    disabled_at timestamp with time zone,
+    CONSTRAINT webhook_target_check CHECK (api_key_id IS NOT NULL OR onchain_request_id IS NOT NULL)
+);
+
+ALTER TABLE ONLY webhook
+    ADD CONSTRAINT webhook_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY webhook
+    ADD CONSTRAINT webhook_api_key_id_fkey FOREIGN KEY (api_key_id) REFERENCES api_key(id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY webhook
+    ADD CONSTRAINT webhook_onchain_request_id_fkey FOREIGN KEY (onchain_request_id) REFERENCES onchain_request(id) ON DELETE CASCADE;
+
+CREATE INDEX webhook_api_key_id_idx ON webhook USING btree (api_key_id);
+
+CREATE INDEX webhook_onchain_request_id_idx ON webhook USING btree (onchain_request_id);
+
+CREATE TYPE deliverystatus AS ENUM (
+    'pending',
+    'delivered',
+    'failed'
+);
+
+-- Every event sent to a webhook and the outcome of its last attempt, written in the transaction
+-- of the status change it reports
+CREATE TABLE webhook_delivery (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    webhook_id uuid NOT NULL,
+    tdx_quote_id uuid NOT NULL,
+    event webhookevent NOT NULL,
+    -- JSON body, kept as sent so replays carry the same bytes
+    payload text NOT NULL,
+    status deliverystatus DEFAULT 'pending'::deliverystatus NOT NULL,
+    attempts integer DEFAULT 0 NOT NULL,
+    next_attempt_at timestamp with time zone DEFAULT now() NOT NULL,
+    response_status integer,
+    last_error text,
+    -- Delivery this one was replayed from
+    replay_of uuid,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    delivered_at timestamp with time zone
+);
+
+ALTER TABLE ONLY webhook_delivery
+    ADD CONSTRAINT webhook_delivery_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY webhook_delivery
+    ADD CONSTRAINT webhook_delivery_webhook_id_fkey FOREIGN KEY (webhook_id) REFERENCES webhook(id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY webhook_delivery
+    ADD CONSTRAINT webhook_delivery_tdx_quote_id_fkey FOREIGN KEY (tdx_quote_id) REFERENCES tdx_quote(id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY webhook_delivery
+    ADD CONSTRAINT webhook_delivery_replay_of_fkey FOREIGN KEY (replay_of) REFERENCES webhook_delivery(id) ON DELETE SET NULL;
+
+CREATE INDEX webhook_delivery_webhook_id_idx ON webhook_delivery USING btree (webhook_id, created_at);
+
+-- Deliveries the dispatcher still has to send
+CREATE INDEX webhook_delivery_due_idx ON webhook_delivery USING btree (next_attempt_at) WHERE status = 'pending';
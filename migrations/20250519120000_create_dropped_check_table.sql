@@ -0,0 +1,15 @@
+-- Consecutive finality checks that found the proof transaction of a submitted quote neither mined
+-- nor in the mempool. The quote is only retried once enough checks over enough time agree.
+CREATE TABLE tdx_dropped_check (
+    tdx_quote_id uuid NOT NULL,
+    txn_hash bytea NOT NULL,
+    checks integer DEFAULT 1 NOT NULL,
+    first_checked_at timestamp with time zone DEFAULT now() NOT NULL,
+    last_checked_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+ALTER TABLE ONLY tdx_dropped_check
+    ADD CONSTRAINT tdx_dropped_check_pkey PRIMARY KEY (tdx_quote_id);
+
+ALTER TABLE ONLY tdx_dropped_check
+    ADD CONSTRAINT tdx_dropped_check_tdx_quote_id_fkey FOREIGN KEY (tdx_quote_id) REFERENCES tdx_quote(id) ON DELETE CASCADE;
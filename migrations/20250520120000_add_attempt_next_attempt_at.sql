@@ -0,0 +1,6 @@
+-- Failed attempts that are retried record when the next one is due, the retry scheduler picks the
+-- quote up again from there instead of a process sleeping on it
+ALTER TABLE tdx_proof_attempt
+    ADD COLUMN next_attempt_at timestamp with time zone;
+
+CREATE INDEX tdx_proof_attempt_due_idx ON tdx_proof_attempt USING btree (next_attempt_at) WHERE next_attempt_at IS NOT NULL;
@@ -11,6 +11,10 @@ sol! {
             uint8 zk_coprocessor_type,
             bytes calldata proof
         ) returns (bool success, bytes memory output);
+
+        function verifyAndAttestOnChain(
+            bytes calldata rawQuote
+        ) returns (bool success, bytes memory output);
     }
 
     interface IProve {
@@ -40,11 +44,25 @@ sol! {
     }
 }
 
-pub fn generate_attestation_calldata(output: &[u8], proof_type: ProofType, proof: &[u8]) -> Vec<u8> {
-    let proof_type = match proof_type {
+fn to_sol_proof_type(proof_type: ProofType) -> IProve::ProofType {
+    match proof_type {
         ProofType::Sp1 => IProve::ProofType::SP1ZKP,
         ProofType::Risc0 => IProve::ProofType::RISC0ZKP,
-    };
+        ProofType::RawQuote => IProve::ProofType::RAWQUOTE,
+    }
+}
+
+/// For `ProofType::RawQuote` the `proof` is the quote itself and `output` is ignored
+pub fn generate_attestation_calldata(output: &[u8], proof_type: ProofType, proof: &[u8]) -> Vec<u8> {
+    if proof_type == ProofType::RawQuote {
+        return IAttestation::IAttestationCalls::verifyAndAttestOnChain(
+            IAttestation::verifyAndAttestOnChainCall {
+                rawQuote: Bytes::from(proof.to_vec()),
+            },
+        )
+        .abi_encode();
+    }
+    let proof_type = to_sol_proof_type(proof_type);
 
     IAttestation::IAttestationCalls::verifyAndAttestWithZKProof(
         IAttestation::verifyAndAttestWithZKProofCall {
@@ -66,17 +84,18 @@ pub fn generate_prove_calldata(request: &OnchainRequest, proof_type: ProofType,
         fee: Uint::from(request.fee_wei),
         deadline: Uint::from(request.deadline.timestamp()),
     };
-    let proof_type = match proof_type {
-        ProofType::Sp1 => IProve::ProofType::SP1ZKP,
-        ProofType::Risc0 => IProve::ProofType::RISC0ZKP,
+    let proof_bytes = match proof_type {
+        // The contract parses and verifies the quote itself, there is no program output
+        ProofType::RawQuote => Bytes::from(proof.to_vec()),
+        _ => Bytes::from(concat_with_length_prefix(output, proof)),
     };
+    let proof_type = to_sol_proof_type(proof_type);
 
     tracing::info!("ProveRequest RequestConfig: {:#?}", request_config);
     tracing::info!("ProveRequest Output: {:#?}", hex::encode(output));
     tracing::info!("ProveRequest Proof: {:#?}", hex::encode(proof));
     tracing::info!("ProveRequest Proof Type: {:#?}", proof_type);
 
-    let proof_bytes = Bytes::from(concat_with_length_prefix(output, proof));
     tracing::info!("ProveRequest Proof (Bytes): {:#?}", proof_bytes);
 
     let calldata = IProve::IProveCalls::proveRequest(
@@ -112,3 +131,26 @@ pub fn decode_attestation_ret_data(ret: Vec<u8>) -> (bool, Vec<u8>) {
     let (verified, output) = <(bool, Bytes)>::abi_decode_params(&ret, true).unwrap();
     (verified, output.to_vec())
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy::sol_types::SolCall;
+
+    use super::{generate_attestation_calldata, generate_prove_calldata, IAttestation, IProve};
+    use crate::entity::{quote::ProofType, request::OnchainRequest};
+
+    #[test]
+    fn raw_quote_calldata() {
+        let quote = vec![4, 0, 2, 0, 0x81, 0, 0, 0];
+        let request = OnchainRequest::fixture();
+
+        let calldata = generate_prove_calldata(&request, ProofType::RawQuote, &[], &quote);
+        let call = IProve::proveRequestCall::abi_decode(&calldata, true).unwrap();
+        assert!(matches!(call.zk_coprocessor_type, IProve::ProofType::RAWQUOTE));
+        assert_eq!(call.proof.to_vec(), quote);
+
+        let calldata = generate_attestation_calldata(&[], ProofType::RawQuote, &quote);
+        let call = IAttestation::verifyAndAttestOnChainCall::abi_decode(&calldata, true).unwrap();
+        assert_eq!(call.rawQuote.to_vec(), quote);
+    }
+}
@@ -0,0 +1,88 @@
+use alloy::{
+    primitives::TxHash,
+    providers::{Provider, ProviderBuilder},
+};
+use alloy_chains::NamedChain;
+use anyhow::{anyhow, Result};
+
+use crate::{config::parameter, entity::fee::TransactionCost};
+
+use super::rpc::RpcPool;
+
+/// Where a submitted transaction stands relative to the chain's confirmation depth
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxFinality {
+    /// Mined on the canonical chain with fewer confirmations than required
+    Pending { confirmations: u64, required: u64 },
+    /// Mined with at least the required confirmations, `cost.success` tells whether it reverted
+    Final { cost: TransactionCost },
+    /// Neither mined on the canonical chain nor known to the mempool anymore
+    Dropped,
+}
+
+/// Confirmations required before a transaction on `chain_id` is treated as final.
+///
+/// `CONFIRMATION_DEPTH_<chain_id>` overrides the per chain default, `CONFIRMATION_DEPTH` overrides
+/// the fallback for unknown chains.
+pub fn confirmation_depth(chain_id: u64) -> u64 {
+    let fallback = parameter::get("CONFIRMATION_DEPTH", Some("1")).parse().unwrap_or(1);
+    let default_depth = default_confirmation_depth(chain_id, fallback);
+    parameter::try_get(&format!("CONFIRMATION_DEPTH_{}", chain_id))
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(default_depth)
+}
+
+/// Built in depth of `chain_id`, `fallback` for chains without one
+fn default_confirmation_depth(chain_id: u64, fallback: u64) -> u64 {
+    match NamedChain::try_from(chain_id) {
+        Ok(NamedChain::Mainnet) => 12,
+        Ok(NamedChain::Base) | Ok(NamedChain::Optimism) => 10,
+        _ => fallback,
+    }
+}
+
+/// Checks whether `tx_hash` is still part of the canonical chain and how deep it is buried
+pub async fn check_finality(pool: &RpcPool, tx_hash: TxHash) -> Result<TxFinality> {
+    pool.with_failover(|rpc_url| async move {
+        let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+
+        let receipt = match provider.get_transaction_receipt(tx_hash).await? {
+            Some(receipt) => receipt,
+            None => {
+                // A reorged out transaction usually returns to the mempool before it is dropped
+                return match provider.get_transaction_by_hash(tx_hash).await? {
+                    Some(_) => Ok(TxFinality::Pending { confirmations: 0, required: 1 }),
+                    None => Ok(TxFinality::Dropped),
+                };
+            }
+        };
+
+        let block_number = receipt.block_number
+            .ok_or_else(|| anyhow!("Receipt for {} has no block number", tx_hash))?;
+        let canonical_hash = provider.get_block_by_number(block_number.into()).await?
+            .map(|block| block.header.hash);
+        if canonical_hash.is_none() || canonical_hash != receipt.block_hash {
+            tracing::warn!("Transaction {} was mined in a block no longer on the canonical chain", tx_hash);
+            return Ok(TxFinality::Dropped);
+        }
+
+        let required = confirmation_depth(provider.get_chain_id().await?);
+        let confirmations = provider.get_block_number().await?.saturating_sub(block_number) + 1;
+        match confirmations >= required {
+            true => Ok(TxFinality::Final { cost: TransactionCost::from_receipt(&receipt) }),
+            false => Ok(TxFinality::Pending { confirmations, required }),
+        }
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::default_confirmation_depth;
+
+    #[test]
+    fn per_chain_confirmation_depth() {
+        assert_eq!(default_confirmation_depth(31337, 3), 3);
+        assert_eq!(default_confirmation_depth(1, 3), 12);
+        assert_eq!(default_confirmation_depth(8453, 3), 10);
+    }
+}
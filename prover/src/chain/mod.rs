@@ -1,70 +1,106 @@
 #![allow(dead_code)]
 
 pub mod attestation;
+pub mod finality;
 pub mod pccs;
+pub mod rpc;
 pub mod constants;
+pub mod signer;
+pub mod simulation;
 pub mod utils;
 
 use std::{cmp::max, thread, time::Duration};
 
 use alloy::{
-    eips::eip1559::Eip1559Estimation,
-    network::{Ethereum, TransactionBuilder},
+    eips::{eip1559::Eip1559Estimation, eip2718::Encodable2718},
+    network::{Ethereum, EthereumWallet, TransactionBuilder, TxSigner},
     primitives::{Address, Bytes, TxHash},
     providers::{PendingTransactionBuilder, Provider, ProviderBuilder},
     rpc::types::{TransactionReceipt, TransactionRequest},
-    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner, utils::secret_key_to_address},
+    signers::{k256::ecdsa::SigningKey, local::PrivateKeySigner, utils::secret_key_to_address, Signature},
 };
 use alloy_chains::NamedChain;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use rand::prelude::*;
+use rpc::RpcPool;
+use signer::SignerConfig;
 
 pub struct TxSender {
     pub rpc_url: String,
     pub chain: Option<NamedChain>,
-    pub signer: PrivateKeySigner,
+    pub wallet: EthereumWallet,
     pub account: Address,
     pub contract: Address,
 }
 
 impl TxSender {
-    /// Creates a new `TxSender`.
+    /// Creates a new `TxSender` from a raw private key, or a random key if none is given.
     pub fn new(rpc_url: &str, contract: &str, chain: Option<NamedChain>, pk: Option<&str>) -> Result<Self> {
-        let contract = contract.parse::<Address>()?;
-
         let signer: PrivateKeySigner = if let Some(pk) = pk {
             pk.parse()?
         } else {
             PrivateKeySigner::random()
         };
+
+        Self::with_signer(rpc_url, contract, chain, signer)
+    }
+
+    /// Creates a new `TxSender` signing with any alloy transaction signer.
+    pub fn with_signer<S>(rpc_url: &str, contract: &str, chain: Option<NamedChain>, signer: S) -> Result<Self>
+    where
+        S: TxSigner<Signature> + Send + Sync + 'static,
+    {
+        let contract = contract.parse::<Address>()?;
         let account = signer.address();
 
         Ok(TxSender {
             chain,
             rpc_url: rpc_url.to_string(),
-            signer,
+            wallet: EthereumWallet::new(signer),
             account,
             contract,
         })
     }
 
-    /// Sends the transaction
-    pub async fn send(&self, calldata: Vec<u8>) -> Result<(TxHash, Option<TransactionReceipt>)> {
-        let rpc_url = self.rpc_url.parse()?;
-
-        let provider = ProviderBuilder::new()
-            .wallet(self.signer.clone())
-            .on_http(rpc_url);
+    /// Creates a new `TxSender` with the signer described by `config`.
+    pub async fn from_config(rpc_url: &str, contract: &str, chain: Option<NamedChain>, config: &SignerConfig) -> Result<Self> {
+        let signer = config.build().await?;
+        Self::with_signer(rpc_url, contract, chain, signer)
+    }
 
+    /// Sends the transaction. It is filled in and signed once, then the same signed bytes are
+    /// broadcast with failover, so a retry can not send a second transaction with another nonce.
+    pub async fn send(&self, calldata: Vec<u8>) -> Result<(TxHash, Option<TransactionReceipt>)> {
         let tx_request = TransactionRequest::default()
             .with_to(self.contract)
             .with_from(self.account)
             .with_input(calldata);
 
-        let builder = provider.send_transaction(tx_request).await?
+        // Nothing is sent while filling in nonce, gas and fees, so any endpoint may do it
+        let pool = RpcPool::new(&self.rpc_url)?;
+        let (tx_hash, raw_tx) = pool.with_failover(|rpc_url| {
+            let tx_request = tx_request.clone();
+            let wallet = self.wallet.clone();
+            async move {
+                let provider = ProviderBuilder::new()
+                    .wallet(wallet)
+                    .on_http(rpc_url.parse()?);
+                let envelope = provider.fill(tx_request).await?
+                    .try_into_envelope()
+                    .map_err(|_| anyhow!("Transaction was not signed"))?;
+                Ok((*envelope.tx_hash(), envelope.encoded_2718()))
+            }
+        }).await?;
+
+        // The receipt is awaited on the endpoint that accepted the transaction
+        let (rpc_url, builder) = broadcast(&pool, &raw_tx, tx_hash).await?;
+        let builder = builder
             .with_required_confirmations(1)
             .with_timeout(Some(std::time::Duration::from_secs(120)));
-        let tx_hash = *builder.tx_hash();
+
+        let provider = ProviderBuilder::new()
+            .wallet(self.wallet.clone())
+            .on_http(rpc_url.parse()?);
         tracing::info!("TxSender: transaction hash: {}", tx_hash);
 
         match provider.get_transaction_by_hash(tx_hash).await {
@@ -93,16 +129,20 @@ impl TxSender {
 
     /// Sends raw transaction with retry
     pub async fn send_raw(&self, calldata: Vec<u8>, max_retries: Option<usize>) -> Result<(TxHash, Option<TransactionReceipt>)> {
-        let rpc_url = self.rpc_url.parse()?;
+        // The nonce and fee bumping below must stay on one endpoint, so pick the healthiest
+        let rpc_url = RpcPool::new(&self.rpc_url)?.ranked_urls()
+            .first()
+            .ok_or_else(|| anyhow!("No RPC endpoint configured"))?
+            .parse()?;
         let mut max_retries = max_retries.unwrap_or(0);
 
         let provider = match self.chain {
             Some(chain) => ProviderBuilder::new()
-                .wallet(self.signer.clone())
+                .wallet(self.wallet.clone())
                 .with_chain(chain)
                 .on_http(rpc_url),
             None => ProviderBuilder::new()
-                .wallet(self.signer.clone())
+                .wallet(self.wallet.clone())
                 .on_http(rpc_url),
         };
 
@@ -167,7 +207,7 @@ impl TxSender {
                     max_fee_per_gas = (max_fee_per_gas as f64 * multiplier) as u128;
                     max_priority_fee_per_gas = (max_priority_fee_per_gas as f64 * multiplier) as u128;
                     gas_limit = (gas_limit as f64 * multiplier) as u64;
-                    max_retries -= 1;
+                    max_retries = max_retries.saturating_sub(1);
                     None
                 }
             };
@@ -208,24 +248,107 @@ impl TxSender {
 
     /// Makes a staticcall with the given transaction request
     pub async fn call(&self, calldata: Vec<u8>) -> Result<Bytes> {
-        let rpc_url = self.rpc_url.parse()?;
-        let provider = ProviderBuilder::new()
-            .wallet(self.signer.clone())
-            .on_http(rpc_url);
-
         let tx = TransactionRequest::default()
             .with_to(self.contract)
             .with_input(calldata);
 
-        let call_output = provider.call(tx).await?;
-
-        Ok(call_output)
+        RpcPool::new(&self.rpc_url)?.with_failover(|rpc_url| {
+            let tx = tx.clone();
+            let wallet = self.wallet.clone();
+            async move {
+                let provider = ProviderBuilder::new()
+                    .wallet(wallet)
+                    .on_http(rpc_url.parse()?);
+                Ok(provider.call(tx).await?)
+            }
+        }).await
     }
 }
 
+/// Broadcasts the signed `raw_tx` with failover and returns the endpoint that accepted it. An
+/// endpoint that already knows `tx_hash`, because an earlier broadcast reached it before failing,
+/// counts as accepting it.
+async fn broadcast(pool: &RpcPool, raw_tx: &[u8], tx_hash: TxHash) -> Result<(String, PendingTransactionBuilder<Ethereum>)> {
+    pool.with_failover(|rpc_url| async move {
+        let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+        match provider.send_raw_transaction(raw_tx).await {
+            Ok(builder) => Ok((rpc_url, builder)),
+            Err(e) => match provider.get_transaction_by_hash(tx_hash).await {
+                Ok(Some(_)) => Ok((rpc_url, PendingTransactionBuilder::new(provider.root().clone(), tx_hash))),
+                _ => Err(e.into()),
+            },
+        }
+    }).await
+}
+
 pub fn get_evm_address_from_key(key: &str) -> String {
     let key_slice = hex::decode(key).unwrap();
     let signing_key = SigningKey::from_slice(&key_slice).expect("Invalid key");
     let address = secret_key_to_address(&signing_key);
     address.to_checksum(None)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use alloy::primitives::TxHash;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::{broadcast, rpc::RpcPool};
+
+    /// Minimal JSON-RPC node recording the raw transactions it is sent, only accepted when `accept`
+    async fn spawn_node(accept: bool, tx_hash: TxHash, received: Arc<Mutex<Vec<String>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 8192];
+                let mut len = 0;
+                // read until the JSON body has been received
+                while !String::from_utf8_lossy(&buf[..len]).contains('}') {
+                    len += socket.read(&mut buf[len..]).await.unwrap();
+                }
+                let request = String::from_utf8_lossy(&buf[..len]).to_string();
+                let id: String = request.split("\"id\":").nth(1).unwrap().chars().take_while(char::is_ascii_digit).collect();
+                let outcome = if request.contains("eth_sendRawTransaction") {
+                    let raw_tx = request.split("\"params\":[\"").nth(1).unwrap().split('"').next().unwrap();
+                    received.lock().unwrap().push(raw_tx.to_string());
+                    match accept {
+                        true => format!("\"result\":\"{}\"", tx_hash),
+                        false => "\"error\":{\"code\":-32000,\"message\":\"upstream timeout\"}".to_string(),
+                    }
+                } else {
+                    "\"result\":null".to_string()
+                };
+                let body = format!("{{\"jsonrpc\":\"2.0\",\"id\":{},{}}}", id, outcome);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(), body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn broadcast_fails_over_with_the_same_signed_transaction() {
+        let tx_hash = TxHash::repeat_byte(0xab);
+        let received = Arc::new(Mutex::new(vec![]));
+        let failing = spawn_node(false, tx_hash, received.clone()).await;
+        let accepting = spawn_node(true, tx_hash, received.clone()).await;
+        let pool = RpcPool::new(&format!("{},{}", failing, accepting)).unwrap().with_max_retries(0);
+
+        let raw_tx = vec![0x02, 0xf8, 0x01, 0x02];
+        let (rpc_url, builder) = broadcast(&pool, &raw_tx, tx_hash).await.unwrap();
+
+        assert_eq!(rpc_url, accepting);
+        assert_eq!(*builder.tx_hash(), tx_hash);
+        assert_eq!(*received.lock().unwrap(), vec!["0x02f80102".to_string(); 2]);
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use percent_encoding::percent_decode_str;
+use serde_json::value::RawValue;
+use x509_parser::pem::Pem;
+
+use super::{
+    enclave_id::{enclave_id_dao_address, generate_upsert_enclave_identity_calldata, EnclaveIdType},
+    fmspc_tcb::{fmspc_tcb_dao_address, generate_upsert_fmspc_tcb_calldata},
+    pcs::{generate_upsert_crl_calldata, generate_upsert_pcs_certificate_calldata, pcs_dao_address, IPCSDao::CA},
+};
+use alloy::primitives::Address;
+
+pub const INTEL_PCS_URL: &str = "https://api.trustedservices.intel.com";
+pub const INTEL_ROOT_CA_CRL_URL: &str = "https://certificates.trustedservices.intel.com/IntelSGXRootCA.der";
+
+/// Signed Intel collateral, split into the raw signed object and its signature
+#[derive(Clone, Debug)]
+pub struct SignedCollateral {
+    pub body: String,
+    pub signature: Vec<u8>,
+}
+
+impl SignedCollateral {
+    /// Parses `{"<key>": {...}, "signature": "<hex>"}` as served by Intel PCS.
+    ///
+    /// The signature covers the exact bytes of the inner object, so it is kept as the raw JSON
+    /// of the document rather than re-serialized.
+    pub fn parse(raw: &str, key: &str) -> Result<Self> {
+        let document: HashMap<String, &RawValue> = serde_json::from_str(raw)
+            .map_err(|e| anyhow!("Invalid collateral: {}", e))?;
+        let body = document.get(key)
+            .ok_or_else(|| anyhow!("Missing \"{}\" in collateral", key))?
+            .get();
+        if !body.starts_with('{') {
+            return Err(anyhow!("\"{}\" in collateral is not an object", key));
+        }
+        let signature: String = document.get("signature")
+            .map(|signature| serde_json::from_str(signature.get()))
+            .ok_or_else(|| anyhow!("Missing signature in collateral"))?
+            .map_err(|e| anyhow!("Invalid signature in collateral: {}", e))?;
+
+        Ok(SignedCollateral {
+            body: body.to_string(),
+            signature: hex::decode(signature)?,
+        })
+    }
+
+    /// String `field` of the signed object
+    fn field(&self, field: &str) -> Option<String> {
+        let body: HashMap<String, serde_json::Value> = serde_json::from_str(&self.body).ok()?;
+        body.get(field)?.as_str().map(str::to_string)
+    }
+}
+
+/// Collateral to be upserted to the on-chain PCCS
+#[derive(Default, Debug)]
+pub struct PccsCollateral {
+    pub certificates: Vec<(CA, Vec<u8>)>,
+    pub crls: Vec<(CA, Vec<u8>)>,
+    pub tcb_infos: Vec<SignedCollateral>,
+    pub enclave_identities: Vec<SignedCollateral>,
+}
+
+/// A single DAO call, ready to be sent or printed
+#[derive(Debug)]
+pub struct UpsertCall {
+    pub description: String,
+    pub dao: Address,
+    pub calldata: Vec<u8>,
+}
+
+impl PccsCollateral {
+    pub fn add_tcb_info(&mut self, raw: &str) -> Result<()> {
+        self.tcb_infos.push(SignedCollateral::parse(raw, "tcbInfo")?);
+        Ok(())
+    }
+
+    pub fn add_enclave_identity(&mut self, raw: &str) -> Result<()> {
+        self.enclave_identities.push(SignedCollateral::parse(raw, "enclaveIdentity")?);
+        Ok(())
+    }
+
+    /// Adds a PEM or DER encoded certificate, or every certificate of a PEM chain in order
+    pub fn add_certificates(&mut self, cas: &[CA], raw: &[u8]) -> Result<()> {
+        let ders = to_der(raw)?;
+        if ders.len() < cas.len() {
+            return Err(anyhow!("Expected {} certificates, found {}", cas.len(), ders.len()));
+        }
+        self.certificates.extend(cas.iter().copied().zip(ders));
+        Ok(())
+    }
+
+    /// Adds a PEM, DER or hex encoded CRL issued by `ca`
+    pub fn add_crl(&mut self, ca: CA, raw: &[u8]) -> Result<()> {
+        let der = match std::str::from_utf8(raw).ok().map(str::trim) {
+            Some(text) if !text.is_empty() && text.chars().all(|c| c.is_ascii_hexdigit()) => hex::decode(text)?,
+            _ => to_der(raw)?.into_iter().next().ok_or_else(|| anyhow!("Empty CRL"))?,
+        };
+        self.crls.push((ca, der));
+        Ok(())
+    }
+
+    /// Builds the DAO calls, certificates first since the DAOs check collateral signatures
+    /// against the certificates already on-chain.
+    pub fn upsert_calls(&self, identity_version: u32) -> Result<Vec<UpsertCall>> {
+        let mut calls = vec![];
+
+        for (ca, cert) in &self.certificates {
+            calls.push(UpsertCall {
+                description: format!("upsertPcsCertificates({:?})", ca),
+                dao: pcs_dao_address(),
+                calldata: generate_upsert_pcs_certificate_calldata(*ca, cert),
+            });
+        }
+
+        for (ca, crl) in &self.crls {
+            calls.push(UpsertCall {
+                description: match ca {
+                    CA::ROOT => "upsertRootCACrl".to_string(),
+                    _ => format!("upsertPckCrl({:?})", ca),
+                },
+                dao: pcs_dao_address(),
+                calldata: generate_upsert_crl_calldata(*ca, crl),
+            });
+        }
+
+        for tcb_info in &self.tcb_infos {
+            let fmspc = tcb_info.field("fmspc").unwrap_or_else(|| "unknown".to_string());
+            calls.push(UpsertCall {
+                description: format!("upsertFmspcTcb(fmspc: {})", fmspc),
+                dao: fmspc_tcb_dao_address(),
+                calldata: generate_upsert_fmspc_tcb_calldata(&tcb_info.body, &tcb_info.signature),
+            });
+        }
+
+        for identity in &self.enclave_identities {
+            let id = identity.field("id")
+                .and_then(|id| EnclaveIdType::from_identity_id(&id))
+                .ok_or_else(|| anyhow!("Unknown enclave identity id"))?;
+            calls.push(UpsertCall {
+                description: format!("upsertEnclaveIdentity({:?}, version: {})", id, identity_version),
+                dao: enclave_id_dao_address(),
+                calldata: generate_upsert_enclave_identity_calldata(
+                    id, identity_version, &identity.body, &identity.signature
+                ),
+            });
+        }
+
+        Ok(calls)
+    }
+}
+
+/// Fetches collateral from an Intel PCS v4 compatible endpoint.
+///
+/// The TCB signing and root certificates come from the TCB info issuer chain, and the PCK CA
+/// certificate from the PCK CRL issuer chain.
+pub async fn fetch_from_pcs(base_url: &str, tdx: bool, fmspcs: &[String], pck_ca: CA) -> Result<PccsCollateral> {
+    let client = reqwest::Client::new();
+    let base_url = base_url.trim_end_matches('/');
+    let tee = if tdx { "tdx" } else { "sgx" };
+    let mut collateral = PccsCollateral::default();
+
+    for (i, fmspc) in fmspcs.iter().enumerate() {
+        let url = format!("{}/{}/certification/v4/tcb?fmspc={}", base_url, tee, fmspc);
+        tracing::info!("Fetching TCB info from {}", url);
+        let response = client.get(&url).send().await?.error_for_status()?;
+        if i == 0 {
+            let chain = issuer_chain(&response, "TCB-Info-Issuer-Chain")?;
+            collateral.add_certificates(&[CA::SIGNING, CA::ROOT], &chain)?;
+        }
+        collateral.add_tcb_info(&response.text().await?)?;
+    }
+
+    let url = format!("{}/{}/certification/v4/qe/identity", base_url, tee);
+    tracing::info!("Fetching QE identity from {}", url);
+    let response = client.get(&url).send().await?.error_for_status()?;
+    collateral.add_enclave_identity(&response.text().await?)?;
+
+    let ca_param = match pck_ca {
+        CA::PROCESSOR => "processor",
+        _ => "platform",
+    };
+    let url = format!("{}/sgx/certification/v4/pckcrl?ca={}&encoding=der", base_url, ca_param);
+    tracing::info!("Fetching PCK CRL from {}", url);
+    let response = client.get(&url).send().await?.error_for_status()?;
+    let chain = issuer_chain(&response, "SGX-PCK-CRL-Issuer-Chain")?;
+    collateral.add_certificates(&[pck_ca], &chain)?;
+    collateral.add_crl(pck_ca, &response.bytes().await?)?;
+
+    let url = root_ca_crl_url(base_url);
+    tracing::info!("Fetching root CA CRL from {}", url);
+    let response = client.get(&url).send().await?.error_for_status()?;
+    collateral.add_crl(CA::ROOT, &response.bytes().await?)?;
+
+    Ok(collateral)
+}
+
+/// Intel PCS does not serve the root CA CRL, it is published next to the root certificate.
+/// PCCS compatible caches serve it as `rootcacrl`.
+fn root_ca_crl_url(base_url: &str) -> String {
+    match base_url == INTEL_PCS_URL {
+        true => INTEL_ROOT_CA_CRL_URL.to_string(),
+        false => format!("{}/sgx/certification/v4/rootcacrl", base_url),
+    }
+}
+
+fn issuer_chain(response: &reqwest::Response, header: &str) -> Result<Vec<u8>> {
+    let value = response.headers().get(header)
+        .ok_or_else(|| anyhow!("Missing {} header", header))?
+        .to_str()?;
+    Ok(percent_decode_str(value).collect())
+}
+
+/// Returns the DER contents of every PEM block, or the input itself if it is not PEM
+fn to_der(raw: &[u8]) -> Result<Vec<Vec<u8>>> {
+    if !raw.starts_with(b"-----BEGIN") {
+        return Ok(vec![raw.to_vec()]);
+    }
+    Pem::iter_from_buffer(raw)
+        .map(|pem| pem.map(|pem| pem.contents).map_err(|e| anyhow!("Invalid PEM: {}", e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{root_ca_crl_url, PccsCollateral, SignedCollateral, CA, INTEL_PCS_URL, INTEL_ROOT_CA_CRL_URL};
+
+    #[test]
+    fn parses_signed_tcb_info() {
+        let raw = include_str!("../../../data/tcbinfov3_00806f050000.json");
+        let collateral = SignedCollateral::parse(raw, "tcbInfo").unwrap();
+
+        assert!(collateral.body.starts_with("{\"id\":\"TDX\""));
+        assert!(collateral.body.ends_with("]}]}"));
+        assert_eq!(collateral.signature.len(), 64);
+        assert!(raw.contains(&collateral.body));
+    }
+
+    #[test]
+    fn rejects_malformed_collateral() {
+        let raw = include_str!("../../../data/tcbinfov3_00806f050000.json");
+        // truncated response
+        assert!(SignedCollateral::parse(&raw[..raw.len() / 2], "tcbInfo").is_err());
+        assert!(SignedCollateral::parse(raw, "enclaveIdentity").is_err());
+        assert!(SignedCollateral::parse(r#"{"tcbInfo": {"id": "TDX"}}"#, "tcbInfo").is_err());
+        assert!(SignedCollateral::parse(r#"{"tcbInfo": "TDX", "signature": "00"}"#, "tcbInfo").is_err());
+        assert!(SignedCollateral::parse(r#"{"tcbInfo": {"id": "TDX"}, "signature": 1}"#, "tcbInfo").is_err());
+    }
+
+    #[test]
+    fn builds_upsert_calls_in_order() {
+        let mut collateral = PccsCollateral::default();
+        collateral.add_tcb_info(include_str!("../../../data/tcbinfov3_00806f050000.json")).unwrap();
+        collateral.add_enclave_identity(include_str!("../../../data/qeidentityv2_apiv4.json")).unwrap();
+        collateral.add_certificates(&[CA::SIGNING], include_bytes!("../../../data/signing_cert.pem")).unwrap();
+        collateral.add_crl(CA::PLATFORM, include_bytes!("../../../data/pck_platform_crl.der")).unwrap();
+
+        let calls = collateral.upsert_calls(4).unwrap();
+        let descriptions: Vec<&str> = calls.iter().map(|call| call.description.as_str()).collect();
+        assert_eq!(descriptions, vec![
+            "upsertPcsCertificates(SIGNING)",
+            "upsertPckCrl(PLATFORM)",
+            "upsertFmspcTcb(fmspc: 00806f050000)",
+            "upsertEnclaveIdentity(TDQE, version: 4)",
+        ]);
+    }
+
+    #[test]
+    fn root_ca_crl_follows_fetch_source() {
+        assert_eq!(root_ca_crl_url(INTEL_PCS_URL), INTEL_ROOT_CA_CRL_URL);
+        assert_eq!(
+            root_ca_crl_url("https://pccs.example.com"),
+            "https://pccs.example.com/sgx/certification/v4/rootcacrl"
+        );
+    }
+}
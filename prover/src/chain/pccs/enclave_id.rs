@@ -5,11 +5,13 @@ use anyhow::Result;
 use crate::chain::constants::AUTOMATA_ENCLAVE_ID_DAO_ADDRESS;
 use crate::config::parameter;
 use crate::chain::utils::remove_prefix_if_found;
+use super::{on_automata_testnet, pccs_rpc_pool};
 
 use alloy::{
-    primitives::{Address, U256},
+    primitives::{Address, Bytes, U256},
     providers::ProviderBuilder,
     sol,
+    sol_types::SolCall,
 };
 
 sol! {
@@ -23,10 +25,13 @@ sol! {
 
         #[derive(Debug)]
         function getEnclaveIdentity(uint256 id, uint256 version) returns (EnclaveIdentityJsonObj memory enclaveIdObj);
+
+        #[derive(Debug)]
+        function upsertEnclaveIdentity(uint256 id, uint256 version, EnclaveIdentityJsonObj calldata enclaveIdentityObj) returns (bytes32 attestationId);
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum EnclaveIdType {
     QE,
@@ -34,42 +39,67 @@ pub enum EnclaveIdType {
     TDQE,
 }
 
-pub async fn get_enclave_identity(id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
-    let verify_only = parameter::get("VERIFY_ONLY", Some("false")) == "true";
-    let rpc_url = match verify_only {
-        true => parameter::get(
-            "AUTOMATA_DEFAULT_RPC_URL", Some("https://1rpc.io/ata/testnet")
-        ).parse().expect("Failed to parse RPC URL"),
-        false => parameter::get(
-            "DEFAULT_RPC_URL", Some("https://mainnet.base.org")
-        ).parse().expect("Failed to parse RPC URL")
-    };
-    let provider = ProviderBuilder::new().on_http(rpc_url);
+impl EnclaveIdType {
+    /// Maps the `id` field of an Intel enclave identity to its DAO type
+    pub fn from_identity_id(id: &str) -> Option<Self> {
+        match id {
+            "QE" => Some(EnclaveIdType::QE),
+            "QVE" => Some(EnclaveIdType::QVE),
+            "TD_QE" => Some(EnclaveIdType::TDQE),
+            _ => None,
+        }
+    }
+
+    fn as_u256(&self) -> U256 {
+        match self {
+            EnclaveIdType::QE => U256::from(0),
+            EnclaveIdType::QVE => U256::from(1),
+            EnclaveIdType::TDQE => U256::from(2),
+        }
+    }
+}
 
-    let enclave_id_dao_address = match verify_only {
+pub fn enclave_id_dao_address() -> Address {
+    match on_automata_testnet() {
         true => Address::from_str(AUTOMATA_ENCLAVE_ID_DAO_ADDRESS).unwrap(),
         false => parameter::get(
             "ENCLAVE_ID_DAO_ADDRESS", Some("0xd74e880029cd3b6b434f16bea5f53a06989458ee")
         ).parse::<Address>().unwrap()
-    };
-    let enclave_id_dao_contract = IEnclaveIdentityDao::new(
-        enclave_id_dao_address,
-        &provider,
-    );
+    }
+}
 
-    let enclave_id_type_uint256 = match id {
-        EnclaveIdType::QE => U256::from(0),
-        EnclaveIdType::QVE => U256::from(1),
-        EnclaveIdType::TDQE => U256::from(2),
-    };
+/// Encodes `upsertEnclaveIdentity` for the raw `enclaveIdentity` object and its signature.
+///
+/// `version` is the PCS API version the identity was fetched with, not the identity format version.
+pub fn generate_upsert_enclave_identity_calldata(
+    id: EnclaveIdType,
+    version: u32,
+    identity: &str,
+    signature: &[u8],
+) -> Vec<u8> {
+    IEnclaveIdentityDao::upsertEnclaveIdentityCall {
+        id: id.as_u256(),
+        version: U256::from(version),
+        enclaveIdentityObj: IEnclaveIdentityDao::EnclaveIdentityJsonObj {
+            identityStr: identity.to_string(),
+            signature: Bytes::copy_from_slice(signature),
+        },
+    }
+    .abi_encode()
+}
 
-    let call_builder =
-        enclave_id_dao_contract.getEnclaveIdentity(enclave_id_type_uint256, U256::from(version));
+pub async fn get_enclave_identity(id: EnclaveIdType, version: u32) -> Result<Vec<u8>> {
+    let dao_address = enclave_id_dao_address();
+    let (identity_str, signature_bytes) = pccs_rpc_pool()?.read(|rpc_url| async move {
+        let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+        let enclave_id_dao_contract = IEnclaveIdentityDao::new(dao_address, &provider);
 
-    let call_return = call_builder.call().await?;
+        let call_builder =
+            enclave_id_dao_contract.getEnclaveIdentity(id.as_u256(), U256::from(version));
 
-    let identity_str = call_return.enclaveIdObj.identityStr;
-    let signature_bytes = call_return.enclaveIdObj.signature;
+        let call_return = call_builder.call().await?;
+        Ok((call_return.enclaveIdObj.identityStr, call_return.enclaveIdObj.signature))
+    }).await?;
 
     if identity_str.is_empty() || signature_bytes.len() == 0 {
         return Err(anyhow::Error::msg(format!(
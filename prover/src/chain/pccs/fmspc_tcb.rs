@@ -4,11 +4,13 @@ use anyhow::Result;
 
 use crate::{config::parameter, chain::constants::AUTOMATA_FMSPC_TCB_DAO_ADDRESS};
 use crate::chain::utils::remove_prefix_if_found;
+use super::{on_automata_testnet, pccs_rpc_pool};
 
 use alloy::{
-    primitives::{Address, U256},
+    primitives::{Address, Bytes, U256},
     providers::ProviderBuilder,
     sol,
+    sol_types::SolCall,
 };
 
 sol! {
@@ -22,42 +24,51 @@ sol! {
 
         #[derive(Debug)]
         function getTcbInfo(uint256 tcbType, string calldata fmspc, uint256 version) returns (TcbInfoJsonObj memory tcbObj);
+
+        #[derive(Debug)]
+        function upsertFmspcTcb(TcbInfoJsonObj calldata tcbInfoObj) returns (bytes32 attestationId);
     }
 }
 
-pub async fn get_tcb_info(tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
-    let verify_only = parameter::get("VERIFY_ONLY", Some("false")) == "true";
-    let rpc_url = match verify_only {
-        true => parameter::get(
-            "AUTOMATA_DEFAULT_RPC_URL", Some("https://1rpc.io/ata/testnet")
-        ).parse().expect("Failed to parse RPC URL"),
+pub fn fmspc_tcb_dao_address() -> Address {
+    match on_automata_testnet() {
+        true => Address::from_str(AUTOMATA_FMSPC_TCB_DAO_ADDRESS).unwrap(),
         false => parameter::get(
-            "DEFAULT_RPC_URL", Some("https://mainnet.base.org")
-        ).parse().expect("Failed to parse RPC URL")
-    };
-    let provider = ProviderBuilder::new().on_http(rpc_url);
+            "FMSPC_TCB_DAO_ADDRESS",
+            Some("0xd3A3f34E8615065704cCb5c304C0cEd41bB81483")
+        ).parse::<Address>().unwrap()
+    }
+}
 
-    let fmspc_tcb_dao_contract =
-        IFmspcTcbDao::new(
-            match verify_only {
-                true => Address::from_str(AUTOMATA_FMSPC_TCB_DAO_ADDRESS).unwrap(),
-                false => parameter::get(
-                    "FMSPC_TCB_DAO_ADDRESS",
-                    Some("0xd3A3f34E8615065704cCb5c304C0cEd41bB81483")
-                ).parse::<Address>().unwrap()
-            },
-            &provider
-        );
+/// Encodes `upsertFmspcTcb` for the raw `tcbInfo` object and its signature
+pub fn generate_upsert_fmspc_tcb_calldata(tcb_info: &str, signature: &[u8]) -> Vec<u8> {
+    IFmspcTcbDao::upsertFmspcTcbCall {
+        tcbInfoObj: IFmspcTcbDao::TcbInfoJsonObj {
+            tcbInfoStr: tcb_info.to_string(),
+            signature: Bytes::copy_from_slice(signature),
+        },
+    }
+    .abi_encode()
+}
 
-    let call_builder = fmspc_tcb_dao_contract.getTcbInfo(
-        U256::from(tcb_type),
-        String::from(fmspc),
-        U256::from(version),
-    );
+pub async fn get_tcb_info(tcb_type: u8, fmspc: &str, version: u32) -> Result<Vec<u8>> {
+    let dao_address = fmspc_tcb_dao_address();
+    let (tcb_info_str, signature_bytes) = pccs_rpc_pool()?.read(|rpc_url| {
+        let fmspc = String::from(fmspc);
+        async move {
+            let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+            let fmspc_tcb_dao_contract = IFmspcTcbDao::new(dao_address, &provider);
 
-    let call_return = call_builder.call().await?;
-    let tcb_info_str = call_return.tcbObj.tcbInfoStr;
-    let signature_bytes = call_return.tcbObj.signature;
+            let call_builder = fmspc_tcb_dao_contract.getTcbInfo(
+                U256::from(tcb_type),
+                fmspc,
+                U256::from(version),
+            );
+
+            let call_return = call_builder.call().await?;
+            Ok((call_return.tcbObj.tcbInfoStr, call_return.tcbObj.signature))
+        }
+    }).await?;
 
     if tcb_info_str.is_empty() || signature_bytes.len() == 0 {
         return Err(anyhow::Error::msg(format!(
@@ -1,6 +1,30 @@
 #![allow(dead_code)]
 
+pub mod collateral;
 pub mod enclave_id;
 pub mod fmspc_tcb;
 pub mod pcs;
 pub mod parser;
+
+use anyhow::Result;
+
+use crate::{chain::rpc::RpcPool, config::parameter};
+
+/// Whether the PCCS DAOs are the Automata testnet ones, set with `VERIFY_ONLY`. Their addresses
+/// and the endpoints they are reached through both follow this setting.
+pub fn on_automata_testnet() -> bool {
+    parameter::get("VERIFY_ONLY", Some("false")) == "true"
+}
+
+/// Comma separated RPC endpoints of the network holding the PCCS DAOs
+pub fn pccs_rpc_url() -> String {
+    match on_automata_testnet() {
+        true => parameter::get("AUTOMATA_DEFAULT_RPC_URL", Some("https://1rpc.io/ata/testnet")),
+        false => parameter::get("DEFAULT_RPC_URL", Some("https://mainnet.base.org")),
+    }
+}
+
+/// RPC endpoints the PCCS DAOs are read from
+pub fn pccs_rpc_pool() -> Result<RpcPool> {
+    RpcPool::new(&pccs_rpc_url())
+}
@@ -3,8 +3,14 @@ use std::str::FromStr;
 use anyhow::Result;
 
 use crate::{config::parameter, chain::constants::AUTOMATA_PCS_DAO_ADDRESS};
+use super::{on_automata_testnet, pccs_rpc_pool};
 
-use alloy::{primitives::Address, providers::ProviderBuilder, sol};
+use alloy::{
+    primitives::{Address, Bytes},
+    providers::ProviderBuilder,
+    sol,
+    sol_types::SolCall,
+};
 
 sol! {
     #[sol(rpc)]
@@ -19,38 +25,65 @@ sol! {
 
         #[derive(Debug)]
         function getCertificateById(CA ca) external view returns (bytes memory cert, bytes memory crl);
+
+        #[derive(Debug)]
+        function upsertPcsCertificates(CA ca, bytes calldata cert) external returns (bytes32 attestationId);
+
+        #[derive(Debug)]
+        function upsertRootCACrl(bytes calldata rootcacrl) external returns (bytes32 attestationId);
+
+        #[derive(Debug)]
+        function upsertPckCrl(CA ca, bytes calldata crl) external returns (bytes32 attestationId);
     }
 }
 
-pub async fn get_certificate_by_id(ca_id: IPCSDao::CA) -> Result<(Vec<u8>, Vec<u8>)> {
-    let verify_only = parameter::get("VERIFY_ONLY", Some("false")) == "true";
-    let rpc_url = match verify_only {
-        true => parameter::get(
-            "AUTOMATA_DEFAULT_RPC_URL", Some("https://1rpc.io/ata/testnet")
-        ).parse().expect("Failed to parse RPC URL"),
+pub fn pcs_dao_address() -> Address {
+    match on_automata_testnet() {
+        true => Address::from_str(AUTOMATA_PCS_DAO_ADDRESS).unwrap(),
         false => parameter::get(
-            "DEFAULT_RPC_URL", Some("https://mainnet.base.org")
-        ).parse().expect("Failed to parse RPC URL")
-    };
-    let provider = ProviderBuilder::new().on_http(rpc_url);
-
-    let pcs_dao_contract = IPCSDao::new(
-        match verify_only {
-            true => Address::from_str(AUTOMATA_PCS_DAO_ADDRESS).unwrap(),
-            false => parameter::get(
-                "PCS_DAO_ADDRESS",
-                Some("0xB270cD8550DA117E3accec36A90c4b0b48daD342")
-            ).parse::<Address>().unwrap()
-        },
-        &provider
-    );
-
-    let call_builder = pcs_dao_contract.getCertificateById(ca_id);
-
-    let call_return = call_builder.call().await?;
-
-    let cert = call_return.cert.to_vec();
-    let crl = call_return.crl.to_vec();
-
-    Ok((cert, crl))
+            "PCS_DAO_ADDRESS",
+            Some("0xB270cD8550DA117E3accec36A90c4b0b48daD342")
+        ).parse::<Address>().unwrap()
+    }
+}
+
+/// Encodes `upsertPcsCertificates` for a DER encoded CA certificate
+pub fn generate_upsert_pcs_certificate_calldata(ca: IPCSDao::CA, cert: &[u8]) -> Vec<u8> {
+    IPCSDao::upsertPcsCertificatesCall {
+        ca,
+        cert: Bytes::copy_from_slice(cert),
+    }
+    .abi_encode()
+}
+
+/// Encodes `upsertRootCACrl` or `upsertPckCrl` for a DER encoded CRL, depending on the issuing CA
+pub fn generate_upsert_crl_calldata(ca: IPCSDao::CA, crl: &[u8]) -> Vec<u8> {
+    match ca {
+        IPCSDao::CA::ROOT => IPCSDao::upsertRootCACrlCall {
+            rootcacrl: Bytes::copy_from_slice(crl),
+        }
+        .abi_encode(),
+        _ => IPCSDao::upsertPckCrlCall {
+            ca,
+            crl: Bytes::copy_from_slice(crl),
+        }
+        .abi_encode(),
+    }
+}
+
+pub async fn get_certificate_by_id(ca_id: IPCSDao::CA) -> Result<(Vec<u8>, Vec<u8>)> {
+    let dao_address = pcs_dao_address();
+    pccs_rpc_pool()?.read(|rpc_url| async move {
+        let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+        let pcs_dao_contract = IPCSDao::new(dao_address, &provider);
+
+        let call_builder = pcs_dao_contract.getCertificateById(ca_id);
+
+        let call_return = call_builder.call().await?;
+
+        let cert = call_return.cert.to_vec();
+        let crl = call_return.crl.to_vec();
+
+        Ok((cert, crl))
+    }).await
 }
@@ -0,0 +1,250 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use tokio::task::JoinSet;
+
+use crate::config::parameter;
+
+/// Longest time an endpoint is skipped after repeated failures
+const MAX_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default, Clone)]
+pub struct EndpointHealth {
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_failure: Option<Instant>,
+}
+
+impl EndpointHealth {
+    /// Endpoints that just failed are skipped for an exponentially growing cooldown
+    fn is_cooling_down(&self, now: Instant) -> bool {
+        match self.last_failure {
+            Some(last_failure) if self.consecutive_failures > 0 => {
+                let cooldown = Duration::from_secs(1 << self.consecutive_failures.min(6)).min(MAX_COOLDOWN);
+                now.duration_since(last_failure) < cooldown
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Health is shared by every pool in the process so a dead endpoint is not retried by each caller
+fn health_registry() -> &'static Mutex<HashMap<String, EndpointHealth>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, EndpointHealth>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn endpoint_health(url: &str) -> EndpointHealth {
+    health_registry().lock().unwrap().get(url).cloned().unwrap_or_default()
+}
+
+fn record(url: &str, success: bool) {
+    let mut registry = health_registry().lock().unwrap();
+    let health = registry.entry(url.to_string()).or_default();
+    if success {
+        health.successes += 1;
+        health.consecutive_failures = 0;
+    } else {
+        health.failures += 1;
+        health.consecutive_failures += 1;
+        health.last_failure = Some(Instant::now());
+    }
+}
+
+/// A list of interchangeable RPC endpoints.
+///
+/// Configuration values take a comma separated list, so `DEFAULT_RPC_URL="https://a,https://b"`
+/// fails over from `a` to `b`.
+#[derive(Debug, Clone)]
+pub struct RpcPool {
+    urls: Vec<String>,
+    max_retries: usize,
+}
+
+impl RpcPool {
+    pub fn new(urls: &str) -> Result<Self> {
+        let urls: Vec<String> = urls
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+            .collect();
+        if urls.is_empty() {
+            return Err(anyhow!("No RPC endpoint configured"));
+        }
+        let max_retries = parameter::get("RPC_MAX_RETRIES", Some("1")).parse()?;
+        Ok(Self { urls, max_retries })
+    }
+
+    /// Reads the endpoint list from `param`
+    pub fn from_param(param: &str, default_value: &str) -> Result<Self> {
+        Self::new(&parameter::get(param, Some(default_value)))
+    }
+
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Endpoints in the order they should be tried: healthy ones first, then those cooling down,
+    /// keeping the configured order otherwise
+    pub fn ranked_urls(&self) -> Vec<String> {
+        let now = Instant::now();
+        let mut urls = self.urls.clone();
+        urls.sort_by_key(|url| endpoint_health(url).is_cooling_down(now));
+        urls
+    }
+
+    /// Runs `f` against each endpoint in turn until one succeeds, retrying each endpoint up to
+    /// `max_retries` times with a short backoff
+    pub async fn with_failover<T, F, Fut>(&self, f: F) -> Result<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+        for url in self.ranked_urls() {
+            for attempt in 0..=self.max_retries {
+                if attempt > 0 {
+                    tokio::time::sleep(Duration::from_millis(200 << attempt)).await;
+                }
+                match f(url.clone()).await {
+                    Ok(value) => {
+                        record(&url, true);
+                        return Ok(value);
+                    }
+                    Err(e) => {
+                        tracing::warn!("RPC {} failed (attempt {}): {}", url, attempt + 1, e);
+                        record(&url, false);
+                        last_error = Some(e);
+                    }
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No RPC endpoint configured")))
+    }
+
+    /// Runs `f` against every endpoint concurrently and returns the first value reported
+    /// identically by at least `quorum` endpoints
+    pub async fn with_quorum<T, F, Fut>(&self, quorum: usize, f: F) -> Result<T>
+    where
+        T: PartialEq + Send + 'static,
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        if quorum > self.urls.len() {
+            return Err(anyhow!("Quorum of {} needs at least as many RPC endpoints, got {}", quorum, self.urls.len()));
+        }
+
+        let mut calls = JoinSet::new();
+        for url in self.urls.clone() {
+            let call = f(url.clone());
+            calls.spawn(async move { (url, call.await) });
+        }
+
+        let mut answers: Vec<(T, usize)> = vec![];
+        let mut errors = 0;
+        while let Some(joined) = calls.join_next().await {
+            let (url, result) = joined?;
+            match result {
+                Ok(value) => {
+                    record(&url, true);
+                    let index = match answers.iter().position(|(answer, _)| *answer == value) {
+                        Some(index) => {
+                            answers[index].1 += 1;
+                            index
+                        }
+                        None => {
+                            answers.push((value, 1));
+                            answers.len() - 1
+                        }
+                    };
+                    if answers[index].1 >= quorum {
+                        calls.abort_all();
+                        return Ok(answers.swap_remove(index).0);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("RPC {} failed in quorum read: {}", url, e);
+                    record(&url, false);
+                    errors += 1;
+                }
+            }
+        }
+
+        Err(anyhow!(
+            "RPC quorum of {} not reached: {} distinct answers, {} failed endpoints",
+            quorum, answers.len(), errors
+        ))
+    }
+
+    /// Uses `with_quorum` when `PCCS_RPC_QUORUM` is above one and `with_failover` otherwise
+    pub async fn read<T, F, Fut>(&self, f: F) -> Result<T>
+    where
+        T: PartialEq + Send + 'static,
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let quorum: usize = parameter::get("PCCS_RPC_QUORUM", Some("1")).parse()?;
+        match quorum {
+            0 | 1 => self.with_failover(f).await,
+            quorum => self.with_quorum(quorum, f).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::anyhow;
+
+    use super::{endpoint_health, RpcPool};
+
+    #[tokio::test]
+    async fn fails_over_to_next_endpoint() {
+        let pool = RpcPool::new("http://failover-a.invalid,http://failover-b.invalid").unwrap().with_max_retries(0);
+        let calls = AtomicUsize::new(0);
+
+        let value = pool.with_failover(|url| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match url.as_str() {
+                    "http://failover-a.invalid" => Err(anyhow!("connection refused")),
+                    _ => Ok(url),
+                }
+            }
+        }).await.unwrap();
+
+        assert_eq!(value, "http://failover-b.invalid");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(endpoint_health("http://failover-a.invalid").consecutive_failures, 1);
+        // the failed endpoint is tried last until its cooldown expires
+        assert_eq!(pool.ranked_urls()[0], "http://failover-b.invalid");
+    }
+
+    #[tokio::test]
+    async fn quorum_requires_matching_answers() {
+        let pool = RpcPool::new("http://quorum-a.invalid,http://quorum-b.invalid,http://quorum-c.invalid").unwrap();
+
+        let value = pool.with_quorum(2, |url| async move {
+            match url.as_str() {
+                "http://quorum-b.invalid" => Ok(vec![0u8]),
+                _ => Ok(vec![1u8]),
+            }
+        }).await.unwrap();
+        assert_eq!(value, vec![1u8]);
+
+        let result = pool.with_quorum(3, |url| async move { Ok(url.into_bytes()) }).await;
+        assert!(result.is_err());
+    }
+}
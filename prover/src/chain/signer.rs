@@ -0,0 +1,327 @@
+use std::{path::PathBuf, sync::Arc};
+
+use alloy::{
+    consensus::SignableTransaction,
+    network::{FullSigner, TxSigner},
+    primitives::{eip191_hash_message, utils::EIP191_PREFIX, Address, ChainId, B256},
+    signers::{
+        k256::ecdsa::VerifyingKey,
+        local::PrivateKeySigner,
+        utils::public_key_to_address,
+        Signature, Signer, UnsupportedSignerOperation,
+    },
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::config::parameter;
+
+/// Signer used by `TxSender`, boxed so that any alloy signer can be plugged in
+pub type ProverSigner = Arc<dyn FullSigner<Signature> + Send + Sync>;
+
+/// Where the prover account key lives
+#[derive(Clone, Debug)]
+pub enum SignerConfig {
+    /// Raw hex encoded private key
+    PrivateKey(String),
+    /// Encrypted JSON keystore, decrypted with the password stored in `password_file`
+    Keystore { path: PathBuf, password_file: PathBuf },
+    /// Web3signer instance at `url` holding the key of the hex encoded secp256k1 `public_key`
+    Remote { url: String, public_key: String },
+}
+
+fn required(param: &str) -> Result<String> {
+    parameter::try_get(param).ok_or_else(|| anyhow!("{} is not set", param))
+}
+
+impl SignerConfig {
+    /// Reads the signer configuration from the environment.
+    ///
+    /// `PROVER_SIGNER_TYPE` selects `private_key` (default), `keystore` or `remote`.
+    pub fn from_env() -> Result<Self> {
+        let signer_type = parameter::get("PROVER_SIGNER_TYPE", Some("private_key")).to_lowercase();
+        match signer_type.as_str() {
+            "private_key" => Ok(SignerConfig::PrivateKey(required("PROVER_PRIVATE_KEY")?)),
+            "keystore" => Ok(SignerConfig::Keystore {
+                path: PathBuf::from(required("PROVER_KEYSTORE_PATH")?),
+                password_file: PathBuf::from(required("PROVER_KEYSTORE_PASSWORD_FILE")?),
+            }),
+            "remote" => Ok(SignerConfig::Remote {
+                url: required("PROVER_REMOTE_SIGNER_URL")?,
+                public_key: required("PROVER_REMOTE_SIGNER_PUBLIC_KEY")?,
+            }),
+            _ => Err(anyhow!("Unknown signer type: {}", signer_type)),
+        }
+    }
+
+    /// Address of the prover account without unlocking it, keystores are not decrypted
+    pub fn address(&self) -> Result<Address> {
+        match self {
+            SignerConfig::PrivateKey(pk) => Ok(pk.parse::<PrivateKeySigner>()?.address()),
+            SignerConfig::Keystore { path, .. } => {
+                let keystore: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+                let address = keystore["address"].as_str().ok_or_else(|| anyhow!("Keystore has no address"))?;
+                Ok(address.parse()?)
+            }
+            SignerConfig::Remote { public_key, .. } => public_key_address(public_key),
+        }
+    }
+
+    /// Builds the signer described by the configuration
+    pub async fn build(&self) -> Result<ProverSigner> {
+        match self {
+            SignerConfig::PrivateKey(pk) => {
+                let signer: PrivateKeySigner = pk.parse()?;
+                Ok(Arc::new(signer))
+            }
+            SignerConfig::Keystore { path, password_file } => {
+                let password = std::fs::read_to_string(password_file)?;
+                let path = path.clone();
+                // scrypt key derivation is intentionally slow, keep it off the async runtime
+                let signer = tokio::task::spawn_blocking(move || {
+                    PrivateKeySigner::decrypt_keystore(path, password.trim_end_matches(['\r', '\n']))
+                }).await??;
+                Ok(Arc::new(signer))
+            }
+            SignerConfig::Remote { url, public_key } => Ok(Arc::new(RemoteSigner::new(url, public_key)?)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SignRequest {
+    data: String,
+}
+
+/// Address of the uncompressed (64 or 65 bytes) or compressed secp256k1 `public_key`, hex encoded
+fn public_key_address(public_key: &str) -> Result<Address> {
+    let mut bytes = hex::decode(public_key.trim_start_matches("0x"))?;
+    if bytes.len() == 64 {
+        bytes.insert(0, 0x04);
+    }
+    let key = VerifyingKey::from_sec1_bytes(&bytes).map_err(|_| anyhow!("Invalid public key: {}", public_key))?;
+    Ok(public_key_to_address(&key))
+}
+
+/// Signs through a [web3signer](https://docs.web3signer.consensys.io) instance so the key never
+/// leaves it.
+///
+/// Uses the eth1 signing endpoint `POST {url}/api/v1/eth1/sign/{public_key}` with the body
+/// `{"data": "0x.."}`. Web3signer signs the keccak256 hash of `data`, so transactions send their
+/// RLP signing payload and messages their EIP-191 prefixed bytes. Signing a bare hash is not
+/// supported. Signatures that do not recover to the address of `public_key` are rejected.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: String,
+    public_key: String,
+    address: Address,
+    chain_id: Option<ChainId>,
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, public_key: &str) -> Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            public_key: public_key.to_string(),
+            address: public_key_address(public_key)?,
+            chain_id: None,
+        })
+    }
+
+    /// Asks web3signer to sign `data`, `hash` is its keccak256 hash the signature must recover from
+    async fn request_signature(&self, data: &[u8], hash: &B256) -> alloy::signers::Result<Signature> {
+        let endpoint = format!("{}/api/v1/eth1/sign/{}", self.url, self.public_key);
+        let body = self.client
+            .post(&endpoint)
+            .json(&SignRequest { data: format!("0x{}", hex::encode(data)) })
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(alloy::signers::Error::other)?
+            .text()
+            .await
+            .map_err(alloy::signers::Error::other)?;
+
+        let signature_bytes = hex::decode(body.trim().trim_matches('"').trim_start_matches("0x"))
+            .map_err(alloy::signers::Error::other)?;
+        let signature = Signature::try_from(signature_bytes.as_slice())
+            .map_err(alloy::signers::Error::other)?;
+
+        // Never hand out a signature from a misconfigured endpoint
+        let recovered = signature.recover_address_from_prehash(hash)
+            .map_err(alloy::signers::Error::other)?;
+        if recovered != self.address {
+            return Err(alloy::signers::Error::other(format!(
+                "Remote signer returned a signature for {} instead of {}", recovered, self.address
+            )));
+        }
+        Ok(signature)
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    /// Web3signer hashes everything it signs, so a hash cannot be signed as is
+    async fn sign_hash(&self, _hash: &B256) -> alloy::signers::Result<Signature> {
+        Err(alloy::signers::Error::UnsupportedOperation(UnsupportedSignerOperation::SignHash))
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> alloy::signers::Result<Signature> {
+        let mut data = format!("{}{}", EIP191_PREFIX, message.len()).into_bytes();
+        data.extend_from_slice(message);
+        self.request_signature(&data, &eip191_hash_message(message)).await
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        self.chain_id = chain_id;
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> alloy::signers::Result<Signature> {
+        if let Some(chain_id) = self.chain_id {
+            if !tx.set_chain_id_checked(chain_id) {
+                return Err(alloy::signers::Error::TransactionChainIdMismatch {
+                    signer: chain_id,
+                    tx: tx.chain_id().unwrap(),
+                });
+            }
+        }
+        let mut payload = Vec::new();
+        tx.encode_for_signing(&mut payload);
+        self.request_signature(&payload, &tx.signature_hash()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::{
+        consensus::{SignableTransaction, TxEip1559},
+        network::TxSigner,
+        primitives::{keccak256, Address, TxKind, U256},
+        signers::{local::PrivateKeySigner, Signer, SignerSync},
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::{RemoteSigner, SignerConfig};
+
+    /// Uncompressed public key of `signer` without the 0x04 tag, as web3signer lists its keys
+    fn public_key(signer: &PrivateKeySigner) -> String {
+        let point = signer.credential().verifying_key().to_encoded_point(false);
+        format!("0x{}", hex::encode(&point.as_bytes()[1..]))
+    }
+
+    /// Minimal stand-in for web3signer's eth1 signing endpoint, signs with `signer` when the key
+    /// in the path is `public_key`
+    async fn spawn_signer(signer: PrivateKeySigner, public_key: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 8192];
+                let mut len = 0;
+                // read until the JSON body has been received
+                while !String::from_utf8_lossy(&buf[..len]).contains('}') {
+                    len += socket.read(&mut buf[len..]).await.unwrap();
+                }
+                let request = String::from_utf8_lossy(&buf[..len]).to_string();
+                let response = if request.starts_with(&format!("POST /api/v1/eth1/sign/{} ", public_key)) {
+                    let data = request.split("\"data\":\"0x").nth(1).unwrap();
+                    let data = hex::decode(&data[..data.find('"').unwrap()]).unwrap();
+                    let signature = signer.sign_hash_sync(&keccak256(&data)).unwrap();
+                    let body = format!("0x{}", hex::encode(signature.as_bytes()));
+                    format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        body.len(), body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n".to_string()
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    fn transaction() -> TxEip1559 {
+        TxEip1559 {
+            chain_id: 1,
+            nonce: 7,
+            gas_limit: 21_000,
+            max_fee_per_gas: 2_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            to: TxKind::Call(Address::repeat_byte(0x22)),
+            value: U256::from(1),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn remote_signer_signs_transactions_and_messages() {
+        let local = PrivateKeySigner::random();
+        let url = spawn_signer(local.clone(), public_key(&local)).await;
+
+        let remote = RemoteSigner::new(&url, &public_key(&local)).unwrap();
+        assert_eq!(Signer::address(&remote), local.address());
+
+        let mut tx = transaction();
+        let signature = remote.sign_transaction(&mut tx).await.unwrap();
+        assert_eq!(signature.recover_address_from_prehash(&tx.signature_hash()).unwrap(), local.address());
+
+        let signature = remote.sign_message(b"tdx-prover").await.unwrap();
+        assert_eq!(signature.recover_address_from_msg(b"tdx-prover").unwrap(), local.address());
+
+        assert!(remote.sign_hash(&keccak256(b"tdx-prover")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn remote_signer_rejects_foreign_signature() {
+        let local = PrivateKeySigner::random();
+        let other = PrivateKeySigner::random();
+        // the service holds `other` under the public key of `local`
+        let url = spawn_signer(other, public_key(&local)).await;
+
+        let remote = RemoteSigner::new(&url, &public_key(&local)).unwrap();
+        assert!(remote.sign_transaction(&mut transaction()).await.is_err());
+    }
+
+    #[test]
+    fn config_address_without_unlocking() {
+        let local = PrivateKeySigner::random();
+        let config = SignerConfig::PrivateKey(hex::encode(local.to_bytes()));
+        assert_eq!(config.address().unwrap(), local.address());
+
+        let remote = SignerConfig::Remote { url: "http://signer.invalid".to_string(), public_key: public_key(&local) };
+        assert_eq!(remote.address().unwrap(), local.address());
+
+        let compressed = local.credential().verifying_key().to_encoded_point(true);
+        let remote = SignerConfig::Remote {
+            url: "http://signer.invalid".to_string(),
+            public_key: hex::encode(compressed.as_bytes()),
+        };
+        assert_eq!(remote.address().unwrap(), local.address());
+    }
+}
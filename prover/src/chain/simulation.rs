@@ -0,0 +1,257 @@
+use std::{collections::BTreeMap, path::{Path, PathBuf}, str::FromStr};
+
+use alloy::{
+    eips::BlockId,
+    network::TransactionBuilder,
+    primitives::{Address, Bytes, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    sol_types::decode_revert_reason,
+};
+use anyhow::{anyhow, Result};
+use revm::{
+    db::{CacheDB, EmptyDB},
+    primitives::{AccountInfo, Bytecode, ExecutionResult, Output, TxKind},
+    Evm,
+};
+use serde::{Deserialize, Serialize};
+
+/// Gas limit used for simulated calls, well above what proof verification needs
+const SIMULATION_GAS_LIMIT: u64 = 30_000_000;
+
+/// Outcome of a call executed in the embedded EVM
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub success: bool,
+    pub output: Vec<u8>,
+    pub gas_used: u64,
+    pub revert_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub balance: U256,
+    pub nonce: u64,
+    pub code: Bytes,
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// Runtime bytecode placed at `address` before simulating, e.g. a locally built verifier.
+///
+/// Parsed from `<address>=<path>`, where the file holds the bytecode as hex or raw bytes.
+#[derive(Debug, Clone)]
+pub struct CodeOverride {
+    pub address: Address,
+    pub path: PathBuf,
+}
+
+impl CodeOverride {
+    pub fn read(&self) -> Result<Bytes> {
+        let raw = std::fs::read(&self.path)?;
+        let code = match std::str::from_utf8(&raw).map(str::trim) {
+            Ok(text) => hex::decode(text.strip_prefix("0x").unwrap_or(text)).unwrap_or(raw),
+            Err(_) => raw,
+        };
+        if code.is_empty() {
+            return Err(anyhow!("No bytecode in {}", self.path.display()));
+        }
+        Ok(Bytes::from(code))
+    }
+}
+
+impl FromStr for CodeOverride {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let (address, path) = value.split_once('=')
+            .ok_or_else(|| anyhow!("Expected <address>=<path>, got {}", value))?;
+        Ok(CodeOverride { address: address.parse()?, path: PathBuf::from(path) })
+    }
+}
+
+/// Chain state needed to execute a call offline.
+///
+/// A snapshot is either forked once from an RPC of our choosing and saved to disk, or built
+/// by hand from locally deployed bytecode.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub chain_id: u64,
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub accounts: BTreeMap<Address, AccountSnapshot>,
+}
+
+impl StateSnapshot {
+    /// Snapshots every account and storage slot the call touches at `block`, as reported by
+    /// `eth_createAccessList`.
+    pub async fn fork(rpc_url: &str, from: Address, to: Address, calldata: &[u8], block: Option<u64>) -> Result<Self> {
+        let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+
+        let block_number = match block {
+            Some(block) => block,
+            None => provider.get_block_number().await?,
+        };
+        let block_id = BlockId::number(block_number);
+        let header = provider.get_block_by_number(block_number.into()).await?
+            .ok_or_else(|| anyhow!("Block {} not found", block_number))?
+            .header;
+
+        let tx = TransactionRequest::default()
+            .with_from(from)
+            .with_to(to)
+            .with_input(calldata.to_vec());
+        let access_list = provider.create_access_list(&tx).block_id(block_id).await?.access_list;
+
+        let mut snapshot = StateSnapshot {
+            chain_id: provider.get_chain_id().await?,
+            block_number,
+            timestamp: header.timestamp,
+            accounts: BTreeMap::new(),
+        };
+
+        let mut slots: BTreeMap<Address, Vec<U256>> = BTreeMap::from([(from, vec![]), (to, vec![])]);
+        for item in access_list.0 {
+            slots.entry(item.address).or_default()
+                .extend(item.storage_keys.into_iter().map(|key| U256::from_be_bytes(key.0)));
+        }
+
+        for (address, keys) in slots {
+            let mut account = AccountSnapshot {
+                balance: provider.get_balance(address).block_id(block_id).await?,
+                nonce: provider.get_transaction_count(address).block_id(block_id).await?,
+                code: provider.get_code_at(address).block_id(block_id).await?,
+                storage: BTreeMap::new(),
+            };
+            for key in keys {
+                let value = provider.get_storage_at(address, key).block_id(block_id).await?;
+                account.storage.insert(key, value);
+            }
+            snapshot.accounts.insert(address, account);
+        }
+
+        tracing::info!("Forked {} accounts at block {}", snapshot.accounts.len(), block_number);
+        Ok(snapshot)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Places runtime bytecode at `address`, e.g. a locally built verifier
+    pub fn with_code(mut self, address: Address, code: Bytes) -> Self {
+        self.accounts.entry(address).or_default().code = code;
+        self
+    }
+
+    /// Executes the call against the snapshot without committing any state
+    pub fn simulate(&self, from: Address, to: Address, calldata: &[u8]) -> Result<SimulationResult> {
+        let mut db = CacheDB::new(EmptyDB::default());
+        for (address, account) in &self.accounts {
+            let code = Bytecode::new_raw(account.code.clone());
+            db.insert_account_info(*address, AccountInfo::new(account.balance, account.nonce, code.hash_slow(), code));
+            for (slot, value) in &account.storage {
+                db.insert_account_storage(*address, *slot, *value)?;
+            }
+        }
+
+        let mut evm = Evm::builder()
+            .with_db(db)
+            .modify_cfg_env(|cfg| cfg.chain_id = self.chain_id)
+            .modify_block_env(|block| {
+                block.number = U256::from(self.block_number);
+                block.timestamp = U256::from(self.timestamp);
+                block.gas_limit = U256::from(SIMULATION_GAS_LIMIT);
+                block.basefee = U256::ZERO;
+            })
+            .modify_tx_env(|tx| {
+                tx.caller = from;
+                tx.transact_to = TxKind::Call(to);
+                tx.data = Bytes::copy_from_slice(calldata);
+                tx.value = U256::ZERO;
+                tx.gas_limit = SIMULATION_GAS_LIMIT;
+                tx.gas_price = U256::ZERO;
+                tx.nonce = None;
+            })
+            .build();
+
+        let result = evm.transact()
+            .map_err(|e| anyhow!("Simulation failed: {:?}", e))?
+            .result;
+
+        Ok(match result {
+            ExecutionResult::Success { gas_used, output, .. } => SimulationResult {
+                success: true,
+                output: match output {
+                    Output::Call(bytes) => bytes.to_vec(),
+                    Output::Create(bytes, _) => bytes.to_vec(),
+                },
+                gas_used,
+                revert_reason: None,
+            },
+            ExecutionResult::Revert { gas_used, output } => SimulationResult {
+                success: false,
+                revert_reason: Some(
+                    decode_revert_reason(&output).unwrap_or_else(|| format!("0x{}", hex::encode(&output)))
+                ),
+                output: output.to_vec(),
+                gas_used,
+            },
+            ExecutionResult::Halt { reason, gas_used } => SimulationResult {
+                success: false,
+                output: vec![],
+                gas_used,
+                revert_reason: Some(format!("{:?}", reason)),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, Bytes};
+
+    use super::{CodeOverride, StateSnapshot};
+
+    #[test]
+    fn simulates_local_bytecode() {
+        let contract = Address::repeat_byte(0x42);
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let returns_42 = Bytes::from_static(&[0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+        let snapshot = StateSnapshot::default().with_code(contract, returns_42);
+
+        let result = snapshot.simulate(Address::repeat_byte(0x01), contract, &[]).unwrap();
+        assert!(result.success);
+        assert_eq!(result.output[31], 0x2a);
+        assert!(result.gas_used > 21_000);
+    }
+
+    #[test]
+    fn reports_revert_reason() {
+        let contract = Address::repeat_byte(0x42);
+        // PUSH1 0x00 DUP1 REVERT
+        let reverts = Bytes::from_static(&[0x60, 0x00, 0x80, 0xfd]);
+        let snapshot = StateSnapshot::default().with_code(contract, reverts);
+
+        let result = snapshot.simulate(Address::repeat_byte(0x01), contract, &[]).unwrap();
+        assert!(!result.success);
+        assert!(result.revert_reason.is_some());
+    }
+
+    #[test]
+    fn reads_code_override() {
+        let path = std::env::temp_dir().join(format!("tdx-prover-code-{}.hex", std::process::id()));
+        std::fs::write(&path, "0x60006000fd\n").unwrap();
+
+        let code_override: CodeOverride = format!("0x{}={}", "42".repeat(20), path.display()).parse().unwrap();
+        assert_eq!(code_override.address, Address::repeat_byte(0x42));
+        assert_eq!(code_override.read().unwrap(), Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]));
+        std::fs::remove_file(path).unwrap();
+
+        assert!("0x42".parse::<CodeOverride>().is_err());
+    }
+}
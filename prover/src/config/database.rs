@@ -1,7 +1,9 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use tracing::info;
 
-use crate::config::parameter;
+use crate::config::{migration, parameter};
 use crate::config::pool::{DbPool, Pool};
 
 #[derive(Clone)]
@@ -9,6 +11,15 @@ pub struct Database {
     pool: Pool,
 }
 
+impl Database {
+    /// Pool of connections to `url`, connections are opened on first use
+    pub fn connect(url: &str) -> Result<Self, anyhow::Error> {
+        let wait_timeout = parameter::get("DATABASE_POOL_TIMEOUT_SECS", Some("30")).parse()?;
+        let pool = DbPool::new(url.parse()?, CONNECTION_COUNT, Duration::from_secs(wait_timeout))?;
+        Ok(Self { pool })
+    }
+}
+
 #[async_trait]
 pub trait DatabaseTrait {
     async fn init() -> Result<Self, anyhow::Error>
@@ -23,10 +34,10 @@ const CONNECTION_COUNT: usize = 14;
 impl DatabaseTrait for Database {
     async fn init() -> Result<Self, anyhow::Error> {
         parameter::init();
-        let opts = parameter::get("DATABASE_URL", None).parse().unwrap();
-        let pool = DbPool::new(opts, CONNECTION_COUNT).unwrap();
+        let db = Self::connect(&parameter::get("DATABASE_URL", None))?;
         info!("Connected to the database!");
-        Ok(Self { pool })
+        migration::migrate_on_startup(&db).await?;
+        Ok(db)
     }
 
     fn get_pool(&self) -> &Pool {
@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use sqlx::migrate::{Migrate, Migrator};
+use tokio::sync::OnceCell;
+
+use crate::config::{database::{Database, DatabaseTrait}, parameter};
+
+/// Every migration in `migrations/`, embedded at build time
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+    /// The migration was applied but its file has changed since
+    pub checksum_mismatch: bool,
+}
+
+/// Applies pending migrations once per process when `DATABASE_AUTO_MIGRATE` is true
+pub async fn migrate_on_startup(db: &Database) -> Result<()> {
+    static MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+    if parameter::get("DATABASE_AUTO_MIGRATE", Some("false")) != "true" {
+        return Ok(());
+    }
+    MIGRATED.get_or_try_init(|| run(db)).await?;
+    Ok(())
+}
+
+/// Applies pending migrations. Concurrent runs are serialized by an advisory lock.
+pub async fn run(db: &Database) -> Result<()> {
+    let mut conn = db.get_pool().get().await
+        .map_err(|e| anyhow!("Failed to get a database connection: {}", e))?;
+    MIGRATOR.run(&mut *conn).await?;
+    tracing::info!("Database migrations are up to date");
+    Ok(())
+}
+
+/// Embedded migrations and whether each one has been applied
+pub async fn status(db: &Database) -> Result<Vec<MigrationStatus>> {
+    let mut conn = db.get_pool().get().await
+        .map_err(|e| anyhow!("Failed to get a database connection: {}", e))?;
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+
+    let statuses = MIGRATOR.iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let applied = applied.iter().find(|applied| applied.version == migration.version);
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                applied: applied.is_some(),
+                checksum_mismatch: applied.is_some_and(|applied| applied.checksum != migration.checksum),
+            }
+        })
+        .collect();
+    Ok(statuses)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use sqlx::{migrate::Migrator, types::Uuid, Connection, PgConnection};
+
+    use super::{status, MIGRATOR};
+    use crate::repository::testing::test_database;
+
+    const HARDEN_SCHEMA: i64 = 20250512120000;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn applied_migrations_match_their_files() {
+        let db = test_database().await;
+        let statuses = status(&db).await.unwrap();
+        assert!(statuses.iter().all(|status| status.applied && !status.checksum_mismatch));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn duplicate_requests_are_merged_before_the_unique_constraint() {
+        test_database().await;
+        // Migrated from scratch in a schema of its own, the shared test schema is already up to date
+        let mut conn = PgConnection::connect(&std::env::var("TEST_DATABASE_URL").unwrap()).await.unwrap();
+        let schema = format!("migration_test_{}", rand::random::<u32>());
+        sqlx::query(&format!("CREATE SCHEMA {schema}")).execute(&mut conn).await.unwrap();
+        sqlx::query(&format!("SET search_path TO {schema}")).execute(&mut conn).await.unwrap();
+
+        let before_harden = Migrator {
+            migrations: Cow::Owned(MIGRATOR.iter().filter(|m| m.version < HARDEN_SCHEMA).cloned().collect()),
+            ..Migrator::DEFAULT
+        };
+        before_harden.run_direct(&mut conn).await.unwrap();
+
+        let (first, copy, other) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        sqlx::query(
+            r#"INSERT INTO onchain_request
+            (id, creator_address, operator_address, model_id, fee_wei, nonce, request_id, deadline, is_cancelled, created_at, updated_at)
+            VALUES
+            ($1, '0xa', '0xb', 'model', 0, 1, '\x01', now(), false, now() - interval '1 hour', now()),
+            ($2, '0xa', '0xb', 'model', 0, 1, '\x01', now(), false, now(), now()),
+            ($3, '0xa', '0xb', 'model', 0, 2, '\x02', now(), false, now(), now())"#,
+        )
+        .bind(first)
+        .bind(copy)
+        .bind(other)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"INSERT INTO tdx_quote (id, quote, onchain_request_id, status, created_at, updated_at)
+            VALUES ($1, '\x00', $2, 'pending', now(), now())"#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(copy)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+        MIGRATOR.run_direct(&mut conn).await.unwrap();
+
+        let requests: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM onchain_request ORDER BY nonce")
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(requests, vec![first, other]);
+        let quote_request: Uuid = sqlx::query_scalar("SELECT onchain_request_id FROM tdx_quote")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(quote_request, first);
+
+        sqlx::query(&format!("DROP SCHEMA {schema} CASCADE")).execute(&mut conn).await.unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 #![allow(dead_code)]
 pub mod database;
+pub mod migration;
 pub mod parameter;
 pub mod pool;
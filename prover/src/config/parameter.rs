@@ -12,3 +12,8 @@ pub fn get(parameter: &str, default_value: Option<&str>) -> String {
             .unwrap_or_else(|| panic!("{} is not defined in the environment.", parameter))
             .to_string())
 }
+
+/// Value of `parameter`, `None` when it is not set or empty
+pub fn try_get(parameter: &str) -> Option<String> {
+    std::env::var(parameter).ok().filter(|value| !value.is_empty())
+}
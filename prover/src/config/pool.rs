@@ -2,18 +2,29 @@
 // https://github.com/osuAkatsuki/performance-service/blob/9d40594d7645d38d1bde167fdadedb89cb4b4772/src/models/pool.rs
 // Used under MIT license
 
-use deadpool::managed::{Manager, Metrics, RecycleResult};
+use std::time::Duration;
+
+use deadpool::managed::{Manager, Metrics, Object, RecycleResult};
+use deadpool::Runtime;
 use sqlx::postgres::PgConnectOptions;
 use sqlx::{ConnectOptions, Connection, Error as SqlxError, PgConnection};
 
+use crate::error::db_error::DbError;
+
 #[derive(Clone, Debug)]
 pub struct DbPool {
     options: PgConnectOptions,
 }
 
 impl DbPool {
-    pub fn new(options: PgConnectOptions, max_size: usize) -> anyhow::Result<Pool> {
-        Ok(Pool::builder(Self { options }).max_size(max_size).build()?)
+    /// Callers wait at most `wait_timeout` for a free connection before getting `DbError::Pool`
+    pub fn new(options: PgConnectOptions, max_size: usize, wait_timeout: Duration) -> anyhow::Result<Pool> {
+        Ok(Pool::builder(Self { options })
+            .max_size(max_size)
+            .wait_timeout(Some(wait_timeout))
+            .create_timeout(Some(wait_timeout))
+            .runtime(Runtime::Tokio1)
+            .build()?)
     }
 }
 
@@ -32,11 +43,20 @@ impl Manager for DbPool {
 
 pub type Pool = deadpool::managed::Pool<DbPool>;
 
+/// Checks a connection out of `pool`, failing instead of waiting forever when it is exhausted
+pub async fn checkout(pool: &Pool) -> Result<Object<DbPool>, DbError> {
+    pool.get().await.map_err(|e| {
+        tracing::error!("Failed to get a database connection: {}", e);
+        DbError::Pool(e.to_string())
+    })
+}
+
+/// Returns `DbError::Pool` from the enclosing function when no connection is available
 #[macro_export]
 macro_rules! get_conn {
     ($input:expr) => {{
         use std::ops::DerefMut;
-        $input.get().await.unwrap().deref_mut()
+        $crate::config::pool::checkout($input).await?.deref_mut()
     }};
 }
 #[allow(unused_imports)]
@@ -0,0 +1,97 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::dto::validation::{
+    checksummed, decode_hex, validate_address, validate_fmspc, validate_measurement, validate_measurements,
+};
+use crate::entity::agent::{Agent, AgentStatus, AgentUpdate, NewAgent};
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Measurements and the FMSPC are 0x prefixed hex strings
+#[derive(Clone, Debug, Validate, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AgentRegisterDto {
+    #[validate(custom(function = "validate_address"))]
+    pub operator_address: String,
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+    #[validate(custom(function = "validate_measurement"))]
+    pub expected_mrtd: Option<String>,
+    /// RTMR0 to RTMR3
+    #[validate(custom(function = "validate_measurements"))]
+    pub expected_rtmrs: Option<Vec<String>>,
+    #[validate(custom(function = "validate_fmspc"))]
+    pub fmspc: Option<String>,
+}
+
+impl AgentRegisterDto {
+    pub fn to_new_agent(&self) -> NewAgent {
+        NewAgent {
+            operator_address: checksummed(&self.operator_address),
+            name: self.name.clone(),
+            expected_mrtd: self.expected_mrtd.as_deref().map(decode_hex),
+            expected_rtmrs: self.expected_rtmrs.as_ref()
+                .map(|rtmrs| rtmrs.iter().map(|rtmr| decode_hex(rtmr)).collect()),
+            fmspc: self.fmspc.as_deref().map(|fmspc| hex::encode(decode_hex(fmspc))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Validate, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AgentUpdateDto {
+    #[validate(length(min = 1, max = 128))]
+    pub name: Option<String>,
+    #[validate(custom(function = "validate_measurement"))]
+    pub expected_mrtd: Option<String>,
+    #[validate(custom(function = "validate_measurements"))]
+    pub expected_rtmrs: Option<Vec<String>>,
+    #[validate(custom(function = "validate_fmspc"))]
+    pub fmspc: Option<String>,
+    pub status: Option<AgentStatus>,
+}
+
+impl AgentUpdateDto {
+    pub fn to_update(&self) -> AgentUpdate {
+        AgentUpdate {
+            name: self.name.clone(),
+            expected_mrtd: self.expected_mrtd.as_deref().map(decode_hex),
+            expected_rtmrs: self.expected_rtmrs.as_ref()
+                .map(|rtmrs| rtmrs.iter().map(|rtmr| decode_hex(rtmr)).collect()),
+            fmspc: self.fmspc.as_deref().map(|fmspc| hex::encode(decode_hex(fmspc))),
+            status: self.status,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AgentReadDto {
+    pub id: String,
+    pub operator_address: String,
+    pub name: String,
+    pub expected_mrtd: Option<String>,
+    pub expected_rtmrs: Option<Vec<String>>,
+    pub fmspc: Option<String>,
+    pub status: AgentStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AgentReadDto {
+    pub fn from(agent: Agent) -> AgentReadDto {
+        Self {
+            id: agent.id.to_string(),
+            operator_address: agent.operator_address,
+            name: agent.name,
+            expected_mrtd: agent.expected_mrtd.as_deref().map(to_hex),
+            expected_rtmrs: agent.expected_rtmrs.map(|rtmrs| rtmrs.iter().map(|rtmr| to_hex(rtmr)).collect()),
+            fmspc: agent.fmspc,
+            status: agent.status,
+            created_at: agent.created_at,
+            updated_at: agent.updated_at,
+        }
+    }
+}
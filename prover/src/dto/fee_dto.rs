@@ -0,0 +1,99 @@
+#![allow(dead_code)]
+use crate::entity::{fee::{FeeGroupBy, FeeReportRow, TransactionFee}, quote::ProofType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Wei amounts are rendered as decimal strings since they do not fit into JSON numbers
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TransactionFeeReadDto {
+    pub id: String,
+    pub tdx_quote_id: String,
+    pub onchain_request_id: String,
+    pub proof_type: ProofType,
+    pub txn_hash: String,
+    pub block_number: Option<i64>,
+    pub gas_used: i64,
+    pub effective_gas_price: String,
+    pub fee_wei: String,
+    pub signer: String,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TransactionFeeReadDto {
+    pub fn from(fee: TransactionFee) -> TransactionFeeReadDto {
+        Self {
+            id: fee.id.to_string(),
+            tdx_quote_id: fee.tdx_quote_id.to_string(),
+            onchain_request_id: fee.onchain_request_id.to_string(),
+            proof_type: fee.proof_type,
+            txn_hash: format!("0x{}", hex::encode(&fee.txn_hash)),
+            block_number: fee.block_number,
+            gas_used: fee.gas_used,
+            effective_gas_price: fee.effective_gas_price.to_string(),
+            fee_wei: fee.fee_wei.to_string(),
+            signer: fee.signer,
+            success: fee.success,
+            created_at: fee.created_at,
+        }
+    }
+}
+
+/// Fees of a single request
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RequestFeeReportDto {
+    pub onchain_request_id: String,
+    pub tx_count: usize,
+    pub gas_used: i64,
+    pub fee_wei: String,
+    pub transactions: Vec<TransactionFeeReadDto>,
+}
+
+impl RequestFeeReportDto {
+    pub fn from(onchain_request_id: String, fees: Vec<TransactionFee>) -> RequestFeeReportDto {
+        Self {
+            onchain_request_id,
+            tx_count: fees.len(),
+            gas_used: fees.iter().map(|fee| fee.gas_used).sum(),
+            fee_wei: fees.iter().map(|fee| fee.fee_wei.clone()).sum::<sqlx::types::BigDecimal>().to_string(),
+            transactions: fees.into_iter().map(TransactionFeeReadDto::from).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FeeReportEntryDto {
+    pub key: String,
+    pub tx_count: i64,
+    pub gas_used: String,
+    pub fee_wei: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FeeReportDto {
+    pub group_by: FeeGroupBy,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub entries: Vec<FeeReportEntryDto>,
+}
+
+impl FeeReportDto {
+    pub fn from(
+        group_by: FeeGroupBy,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        rows: Vec<FeeReportRow>,
+    ) -> FeeReportDto {
+        Self {
+            group_by,
+            from,
+            to,
+            entries: rows.into_iter().map(|row| FeeReportEntryDto {
+                key: row.group_key,
+                tx_count: row.tx_count,
+                gas_used: row.gas_used.to_string(),
+                fee_wei: row.fee_wei.to_string(),
+            }).collect(),
+        }
+    }
+}
@@ -1,2 +1,7 @@
+pub mod agent_dto;
+pub mod fee_dto;
 pub mod quote_dto;
 pub mod request_dto;
+pub mod validation;
+pub mod verification_dto;
+pub mod webhook_dto;
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
-use crate::entity::quote::{ProofType, TdxQuote, TdxQuoteStatus};
+use crate::entity::quote::{ProofType, QuoteCursor, TdxQuote, TdxQuoteStatus};
+use crate::entity::quote_event::{QuoteActor, TdxQuoteEvent};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
@@ -18,7 +19,7 @@ pub struct QuoteDto {
     pub request_id: Option<Vec<u8>>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct QuoteReadDto {
     pub id: String,
     pub onchain_request_id: String,
@@ -29,6 +30,9 @@ pub struct QuoteReadDto {
     pub proof_type: Option<ProofType>,
     pub txn_hash: Option<Vec<u8>>,
     pub request_id: Option<Vec<u8>>,
+    pub quote_hash: Vec<u8>,
+    pub duplicate_of: Option<String>,
+    pub agent_id: Option<String>,
 }
 
 impl QuoteReadDto {
@@ -43,6 +47,9 @@ impl QuoteReadDto {
             proof_type: quote.proof_type,
             txn_hash: quote.txn_hash,
             request_id: quote.request_id,
+            quote_hash: quote.quote_hash,
+            duplicate_of: quote.duplicate_of.map(|id| id.to_string()),
+            agent_id: quote.agent_id.map(|id| id.to_string()),
         }
     }
 }
@@ -59,10 +66,13 @@ impl std::fmt::Debug for QuoteReadDto {
             .field("proof_type", &self.proof_type)
             .field("txn_hash", &self.txn_hash)
             .field("request_id", &self.request_id)
+            .field("quote_hash", &self.quote_hash)
+            .field("duplicate_of", &self.duplicate_of)
+            .field("agent_id", &self.agent_id)
             .finish()
     }
 }
-#[derive(Clone, Validate, Serialize, Deserialize)]
+#[derive(Clone, Validate, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct QuoteRegisterDto {
     pub proof_type: Option<ProofType>,
     pub onchain_request_id: String,
@@ -97,3 +107,52 @@ impl std::fmt::Debug for QuoteRegisterDto {
             .finish()
     }
 }
+
+/// One page of quotes, `next_cursor` is set when more quotes match
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct QuoteListDto {
+    pub quotes: Vec<QuoteReadDto>,
+    pub next_cursor: Option<String>,
+}
+
+impl QuoteListDto {
+    pub fn from(quotes: Vec<TdxQuote>, next_cursor: Option<QuoteCursor>) -> QuoteListDto {
+        Self {
+            quotes: quotes.into_iter().map(QuoteReadDto::from).collect(),
+            next_cursor: next_cursor.map(|cursor| cursor.encode()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct QuoteEventReadDto {
+    pub from_status: Option<TdxQuoteStatus>,
+    pub to_status: TdxQuoteStatus,
+    pub actor: QuoteActor,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Every status a quote went through, oldest first
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct QuoteHistoryDto {
+    pub quote_id: String,
+    pub status: TdxQuoteStatus,
+    pub events: Vec<QuoteEventReadDto>,
+}
+
+impl QuoteHistoryDto {
+    pub fn from(quote: &TdxQuote, events: Vec<TdxQuoteEvent>) -> QuoteHistoryDto {
+        Self {
+            quote_id: quote.id.to_string(),
+            status: quote.status,
+            events: events.into_iter().map(|event| QuoteEventReadDto {
+                from_status: event.from_status,
+                to_status: event.to_status,
+                actor: event.actor,
+                detail: event.detail,
+                created_at: event.created_at,
+            }).collect(),
+        }
+    }
+}
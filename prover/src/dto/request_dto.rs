@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 use sqlx::types::Uuid;
 use validator::Validate;
 
-use crate::entity::request::OnchainRequest;
+use crate::dto::quote_dto::QuoteReadDto;
+use crate::dto::validation::{checksummed, validate_address, validate_bytes32, validate_deadline, validate_hex};
+use crate::entity::quote::TdxQuote;
+use crate::entity::request::{NewOnchainRequest, OnchainRequest, OnchainRequestUpdate};
 
 #[derive(Clone, Validate)]
 pub struct RequestDto {
@@ -22,7 +25,7 @@ pub struct RequestDto {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct RequestReadDto {
     pub id: String,
     pub creator_address: String,
@@ -36,6 +39,7 @@ pub struct RequestReadDto {
     pub cancelled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub agent_id: Option<String>,
 }
 
 impl RequestReadDto {
@@ -53,6 +57,7 @@ impl RequestReadDto {
             cancelled_at: request.cancelled_at,
             created_at: request.created_at,
             updated_at: request.updated_at,
+            agent_id: request.agent_id.map(|id| id.to_string()),
         }
     }
 }
@@ -72,6 +77,115 @@ impl std::fmt::Debug for RequestReadDto {
             .field("cancelled_at", &self.cancelled_at)
             .field("created_at", &self.created_at)
             .field("updated_at", &self.updated_at)
+            .field("agent_id", &self.agent_id)
             .finish()
     }
 }
+
+#[derive(Clone, Debug, Validate, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RequestRegisterDto {
+    #[validate(custom(function = "validate_address"))]
+    pub creator_address: String,
+    #[validate(custom(function = "validate_address"))]
+    pub operator_address: String,
+    #[validate(custom(function = "validate_bytes32"))]
+    pub model_id: String,
+    #[validate(range(min = 0))]
+    pub fee_wei: i64,
+    #[validate(range(min = 0))]
+    pub nonce: i64,
+    /// On-chain request id, 0x prefixed bytes32
+    #[validate(custom(function = "validate_bytes32"))]
+    pub request_id: String,
+    #[validate(custom(function = "validate_deadline"))]
+    pub deadline: DateTime<Utc>,
+    /// Hex encoded quote to register together with the request
+    #[validate(custom(function = "validate_hex"))]
+    pub quote: Option<String>,
+}
+
+impl RequestRegisterDto {
+    /// Only valid after `validate`
+    pub fn to_new_request(&self) -> NewOnchainRequest {
+        NewOnchainRequest {
+            creator_address: checksummed(&self.creator_address),
+            operator_address: checksummed(&self.operator_address),
+            model_id: self.model_id.to_lowercase(),
+            fee_wei: self.fee_wei,
+            nonce: self.nonce,
+            request_id: hex::decode(&self.request_id[2..]).unwrap_or_default(),
+            deadline: self.deadline,
+            api_key_id: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Validate, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RequestUpdateDto {
+    #[validate(custom(function = "validate_address"))]
+    pub operator_address: Option<String>,
+    #[validate(range(min = 0))]
+    pub fee_wei: Option<i64>,
+    #[validate(custom(function = "validate_deadline"))]
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+impl RequestUpdateDto {
+    pub fn to_update(&self) -> OnchainRequestUpdate {
+        OnchainRequestUpdate {
+            operator_address: self.operator_address.as_deref().map(checksummed),
+            fee_wei: self.fee_wei,
+            deadline: self.deadline,
+        }
+    }
+}
+
+/// A registered request and the quote registered with it
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RequestRegisteredDto {
+    pub request: RequestReadDto,
+    pub quote: Option<QuoteReadDto>,
+}
+
+impl RequestRegisteredDto {
+    pub fn from(request: OnchainRequest, quote: Option<TdxQuote>) -> RequestRegisteredDto {
+        Self {
+            request: RequestReadDto::from(request),
+            quote: quote.map(QuoteReadDto::from),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use validator::Validate;
+
+    use super::RequestRegisterDto;
+
+    fn payload() -> RequestRegisterDto {
+        RequestRegisterDto {
+            creator_address: "0x6bbc359046bdbfb1596222e6257f0ef24e0fc0b9".to_string(),
+            operator_address: "0xEeE7FB850D28f5cabd5f1EDF540646b5bEA17CE5".to_string(),
+            model_id: "0x682db2fe997945208caa888543ffca2ad2c7edf1ab0b02899b9977e6d18af477".to_string(),
+            fee_wei: 0,
+            nonce: 1,
+            request_id: format!("0x{}", "01".repeat(32)),
+            deadline: Utc::now() + Duration::hours(1),
+            quote: Some("0x04000200".to_string()),
+        }
+    }
+
+    #[test]
+    fn validates_register_payload() {
+        assert!(payload().validate().is_ok());
+        assert!(RequestRegisterDto { creator_address: "0x1234".to_string(), ..payload() }.validate().is_err());
+        assert!(RequestRegisterDto { model_id: "0x1234".to_string(), ..payload() }.validate().is_err());
+        assert!(RequestRegisterDto { deadline: Utc::now() - Duration::hours(1), ..payload() }.validate().is_err());
+        assert!(RequestRegisterDto { quote: Some("xyz".to_string()), ..payload() }.validate().is_err());
+
+        let request = payload().to_new_request();
+        assert_eq!(request.creator_address, "0x6BBC359046BDBFb1596222E6257F0ef24e0Fc0B9");
+        assert_eq!(request.request_id, vec![1; 32]);
+    }
+}
@@ -0,0 +1,75 @@
+//! Validators shared by the request payloads, for `#[validate(custom(function = ...))]`
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+use chrono::{DateTime, Utc};
+use validator::ValidationError;
+
+use crate::entity::webhook::{allows_local_targets, check_webhook_url};
+
+pub(crate) fn validate_address(value: &str) -> Result<(), ValidationError> {
+    Address::from_str(value).map(|_| ()).map_err(|_| ValidationError::new("invalid_address"))
+}
+
+/// 0x prefixed hex string of exactly `len` bytes
+fn validate_hex_len(value: &str, len: usize, code: &'static str) -> Result<(), ValidationError> {
+    match value.strip_prefix("0x").map(hex::decode) {
+        Some(Ok(bytes)) if bytes.len() == len => Ok(()),
+        _ => Err(ValidationError::new(code)),
+    }
+}
+
+/// e.g. a model id
+pub(crate) fn validate_bytes32(value: &str) -> Result<(), ValidationError> {
+    validate_hex_len(value, 32, "invalid_bytes32")
+}
+
+/// MRTD or RTMR, both SHA-384 digests
+pub(crate) fn validate_measurement(value: &str) -> Result<(), ValidationError> {
+    validate_hex_len(value, 48, "invalid_measurement")
+}
+
+pub(crate) fn validate_measurements(values: &[String]) -> Result<(), ValidationError> {
+    match values.len() {
+        4 => values.iter().try_for_each(|value| validate_measurement(value)),
+        _ => Err(ValidationError::new("expected_four_rtmrs")),
+    }
+}
+
+pub(crate) fn validate_fmspc(value: &str) -> Result<(), ValidationError> {
+    validate_hex_len(value, 6, "invalid_fmspc")
+}
+
+/// Decodes a string accepted by one of the hex validators
+pub(crate) fn decode_hex(value: &str) -> Vec<u8> {
+    hex::decode(value.trim_start_matches("0x")).unwrap_or_default()
+}
+
+pub(crate) fn validate_hex(value: &str) -> Result<(), ValidationError> {
+    match hex::decode(value.trim_start_matches("0x")) {
+        Ok(bytes) if !bytes.is_empty() => Ok(()),
+        _ => Err(ValidationError::new("invalid_hex")),
+    }
+}
+
+pub(crate) fn validate_deadline(deadline: &DateTime<Utc>) -> Result<(), ValidationError> {
+    match *deadline > Utc::now() {
+        true => Ok(()),
+        false => Err(ValidationError::new("deadline_in_past")),
+    }
+}
+
+/// Absolute https URL of a public host, see `check_webhook_url`. Names are resolved when the
+/// webhook is registered.
+pub(crate) fn validate_webhook_url(value: &str) -> Result<(), ValidationError> {
+    check_webhook_url(value, allows_local_targets()).map(|_| ()).map_err(ValidationError::new)
+}
+
+pub(crate) fn validate_uuid(value: &str) -> Result<(), ValidationError> {
+    sqlx::types::Uuid::parse_str(value).map(|_| ()).map_err(|_| ValidationError::new("invalid_uuid"))
+}
+
+/// Addresses are stored checksummed
+pub(crate) fn checksummed(address: &str) -> String {
+    Address::from_str(address).map(|address| address.to_checksum(None)).unwrap_or(address.to_string())
+}
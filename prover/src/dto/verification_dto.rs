@@ -0,0 +1,104 @@
+#![allow(dead_code)]
+use crate::entity::verification::{MrtdCount, TcbStatusCount, TdxVerification, VerificationSource};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Measurements are rendered as 0x prefixed hex strings
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct VerificationReadDto {
+    pub id: String,
+    pub tdx_quote_id: String,
+    pub source: VerificationSource,
+    pub quote_version: i32,
+    pub tee_type: i64,
+    pub tcb_status: String,
+    pub fmspc: String,
+    pub advisory_ids: Vec<String>,
+    pub mrtd: Option<String>,
+    pub rtmrs: Option<Vec<String>>,
+    pub report_data: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+impl VerificationReadDto {
+    pub fn from(verification: TdxVerification) -> VerificationReadDto {
+        let rtmrs = [&verification.rtmr0, &verification.rtmr1, &verification.rtmr2, &verification.rtmr3]
+            .into_iter()
+            .map(|rtmr| rtmr.as_deref().map(to_hex))
+            .collect();
+        Self {
+            id: verification.id.to_string(),
+            tdx_quote_id: verification.tdx_quote_id.to_string(),
+            source: verification.source,
+            quote_version: verification.quote_version,
+            tee_type: verification.tee_type,
+            tcb_status: verification.tcb_status,
+            fmspc: verification.fmspc,
+            advisory_ids: verification.advisory_ids,
+            mrtd: verification.mrtd.as_deref().map(to_hex),
+            rtmrs,
+            report_data: to_hex(&verification.report_data),
+            created_at: verification.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TcbStatusReportDto {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub entries: Vec<TcbStatusEntryDto>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct TcbStatusEntryDto {
+    pub fmspc: String,
+    pub tcb_status: String,
+    pub quote_count: i64,
+}
+
+impl TcbStatusReportDto {
+    pub fn from(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>, rows: Vec<TcbStatusCount>) -> TcbStatusReportDto {
+        Self {
+            from,
+            to,
+            entries: rows.into_iter().map(|row| TcbStatusEntryDto {
+                fmspc: row.fmspc,
+                tcb_status: row.tcb_status,
+                quote_count: row.quote_count,
+            }).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MrtdReportDto {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub entries: Vec<MrtdEntryDto>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct MrtdEntryDto {
+    pub mrtd: String,
+    pub quote_count: i64,
+    pub last_verified_at: DateTime<Utc>,
+}
+
+impl MrtdReportDto {
+    pub fn from(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>, rows: Vec<MrtdCount>) -> MrtdReportDto {
+        Self {
+            from,
+            to,
+            entries: rows.into_iter().map(|row| MrtdEntryDto {
+                mrtd: to_hex(&row.mrtd),
+                quote_count: row.quote_count,
+                last_verified_at: row.last_verified_at,
+            }).collect(),
+        }
+    }
+}
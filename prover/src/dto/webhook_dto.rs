@@ -0,0 +1,115 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+use validator::Validate;
+
+use crate::dto::validation::{validate_uuid, validate_webhook_url};
+use crate::entity::webhook::{DeliveryStatus, NewWebhook, Webhook, WebhookDelivery, WebhookEvent};
+
+#[derive(Clone, Debug, Validate, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookRegisterDto {
+    #[validate(length(max = 2048), custom(function = "validate_webhook_url"))]
+    pub url: String,
+    /// Events to deliver, every event when not set
+    #[validate(length(min = 1))]
+    pub events: Option<Vec<WebhookEvent>>,
+    /// Only deliver the events of this request instead of every request of the API key
+    #[validate(custom(function = "validate_uuid"))]
+    pub onchain_request_id: Option<String>,
+}
+
+impl WebhookRegisterDto {
+    /// Only valid after `validate`
+    pub fn to_new_webhook(&self, api_key_id: Option<Uuid>) -> NewWebhook {
+        let events = WebhookEvent::ALL
+            .into_iter()
+            .filter(|event| self.events.as_ref().is_none_or(|events| events.contains(event)))
+            .collect();
+        NewWebhook {
+            api_key_id,
+            onchain_request_id: self.onchain_request_id.as_deref().and_then(|id| Uuid::parse_str(id).ok()),
+            url: self.url.clone(),
+            events,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookReadDto {
+    pub id: String,
+    pub api_key_id: Option<String>,
+    pub onchain_request_id: Option<String>,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: DateTime<Utc>,
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookReadDto {
+    pub fn from(webhook: Webhook) -> WebhookReadDto {
+        Self {
+            id: webhook.id.to_string(),
+            api_key_id: webhook.api_key_id.map(|id| id.to_string()),
+            onchain_request_id: webhook.onchain_request_id.map(|id| id.to_string()),
+            url: webhook.url,
+            events: webhook.events,
+            created_at: webhook.created_at,
+            disabled_at: webhook.disabled_at,
+        }
+    }
+}
+
+/// Returned once on registration, the secret cannot be read again
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookCreatedDto {
+    #[serde(flatten)]
+    pub webhook: WebhookReadDto,
+    /// HMAC-SHA256 key of the `x-tdx-prover-signature` header
+    pub secret: String,
+}
+
+impl WebhookCreatedDto {
+    pub fn from(webhook: Webhook) -> WebhookCreatedDto {
+        let secret = webhook.secret.clone();
+        Self { webhook: WebhookReadDto::from(webhook), secret }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookDeliveryReadDto {
+    pub id: String,
+    pub webhook_id: String,
+    pub quote_id: String,
+    pub event: WebhookEvent,
+    /// JSON body as sent
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub replay_of: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDeliveryReadDto {
+    pub fn from(delivery: WebhookDelivery) -> WebhookDeliveryReadDto {
+        Self {
+            id: delivery.id.to_string(),
+            webhook_id: delivery.webhook_id.to_string(),
+            quote_id: delivery.tdx_quote_id.to_string(),
+            event: delivery.event,
+            payload: delivery.payload,
+            status: delivery.status,
+            attempts: delivery.attempts,
+            next_attempt_at: delivery.next_attempt_at,
+            response_status: delivery.response_status,
+            last_error: delivery.last_error,
+            replay_of: delivery.replay_of.map(|id| id.to_string()),
+            created_at: delivery.created_at,
+            delivered_at: delivery.delivered_at,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, Default, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "agentstatus", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AgentStatus {
+    #[default]
+    Active,
+    Suspended,
+    /// Kept for the history of its requests and quotes, in place of deleting it
+    Retired,
+}
+
+/// An operator that requests attestations, linked to its requests and quotes by operator address
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct Agent {
+    pub id: Uuid,
+    pub operator_address: String,
+    pub name: String,
+    pub expected_mrtd: Option<Vec<u8>>,
+    pub expected_rtmrs: Option<Vec<Vec<u8>>>,
+    /// Hex encoded
+    pub fmspc: Option<String>,
+    pub status: AgentStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewAgent {
+    pub operator_address: String,
+    pub name: String,
+    pub expected_mrtd: Option<Vec<u8>>,
+    pub expected_rtmrs: Option<Vec<Vec<u8>>>,
+    pub fmspc: Option<String>,
+}
+
+/// Changes to an agent, fields that are not set are kept
+#[derive(Clone, Debug, Default)]
+pub struct AgentUpdate {
+    pub name: Option<String>,
+    pub expected_mrtd: Option<Vec<u8>>,
+    pub expected_rtmrs: Option<Vec<Vec<u8>>>,
+    pub fmspc: Option<String>,
+    pub status: Option<AgentStatus>,
+}
@@ -0,0 +1,126 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{
+    postgres::{PgHasArrayType, PgTypeInfo},
+    types::Uuid,
+};
+
+/// Prepended to generated keys so they are easy to spot in configs and logs
+pub const API_KEY_PREFIX: &str = "tdxp_";
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "apiscope", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    Read,
+    Register,
+    Prove,
+    Submit,
+    /// Grants every other scope
+    Admin,
+}
+
+impl PgHasArrayType for ApiScope {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_apiscope")
+    }
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: Vec<u8>,
+    pub scopes: Vec<ApiScope>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.iter().any(|granted| *granted == scope || *granted == ApiScope::Admin)
+    }
+}
+
+/// Keys own what was registered with them, admin keys and calls made with authentication disabled
+/// own everything
+pub fn owns(owner: Option<&ApiKey>, api_key_id: Option<Uuid>) -> bool {
+    match owner {
+        Some(owner) if !owner.allows(ApiScope::Admin) => api_key_id == Some(owner.id),
+        _ => true,
+    }
+}
+
+/// Keys are random 32 byte values, so a plain SHA-256 is enough to store them
+pub fn hash_key(key: &str) -> Vec<u8> {
+    Sha256::digest(key.as_bytes()).to_vec()
+}
+
+/// Returns a new random key, only its hash and prefix are meant to be stored
+pub fn generate_key() -> String {
+    format!("{}{}", API_KEY_PREFIX, hex::encode(rand::random::<[u8; 32]>()))
+}
+
+/// The part of a key that is kept in clear to identify it
+pub fn key_prefix(key: &str) -> String {
+    key.chars().take(API_KEY_PREFIX.len() + 8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admin_grants_every_scope() {
+        let mut key = ApiKey {
+            id: Uuid::nil(),
+            name: "ci".to_string(),
+            prefix: "tdxp_00000000".to_string(),
+            key_hash: hash_key("tdxp_00"),
+            scopes: vec![ApiScope::Read, ApiScope::Prove],
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        assert!(key.allows(ApiScope::Prove));
+        assert!(!key.allows(ApiScope::Submit));
+
+        key.scopes = vec![ApiScope::Admin];
+        assert!(key.allows(ApiScope::Submit));
+    }
+
+    #[test]
+    fn keys_own_what_they_registered() {
+        let mut key = ApiKey {
+            id: Uuid::new_v4(),
+            name: "ci".to_string(),
+            prefix: "tdxp_00000000".to_string(),
+            key_hash: hash_key("tdxp_00"),
+            scopes: vec![ApiScope::Register],
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        };
+        assert!(owns(Some(&key), Some(key.id)));
+        assert!(!owns(Some(&key), Some(Uuid::new_v4())));
+        assert!(!owns(Some(&key), None));
+        assert!(owns(None, Some(Uuid::new_v4())));
+
+        key.scopes = vec![ApiScope::Admin];
+        assert!(owns(Some(&key), None));
+    }
+
+    #[test]
+    fn generated_keys_are_prefixed_and_unique() {
+        let key = generate_key();
+        assert!(key.starts_with(API_KEY_PREFIX));
+        assert_eq!(key.len(), API_KEY_PREFIX.len() + 64);
+        assert_eq!(key_prefix(&key).len(), 13);
+        assert_ne!(hash_key(&key), hash_key(&generate_key()));
+    }
+}
@@ -0,0 +1,195 @@
+#![allow(dead_code)]
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::{
+    config::parameter,
+    entity::{quote::ProofType, zk::ProofSystem},
+    error::{db_error::DbError, prove_error::ProveError},
+};
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "attemptstage", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AttemptStage {
+    Prove,
+    Submit,
+}
+
+/// Why an attempt failed, decides whether it is retried
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[strum(serialize_all = "snake_case")]
+#[sqlx(type_name = "errorcategory", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    CollateralMissing,
+    QuoteParse,
+    ProverNetwork,
+    Timeout,
+    OnchainRevert,
+    Db,
+    Unknown,
+}
+
+impl ErrorCategory {
+    /// Finds the first cause in the chain of `error` with a known category
+    pub fn classify(error: &anyhow::Error) -> Option<Self> {
+        for cause in error.chain() {
+            if let Some(prove_error) = cause.downcast_ref::<ProveError>() {
+                return Some(match prove_error {
+                    ProveError::CollateralMissing(_) => ErrorCategory::CollateralMissing,
+                    ProveError::QuoteParse(_) => ErrorCategory::QuoteParse,
+                    ProveError::ProverNetwork(_) => ErrorCategory::ProverNetwork,
+                    ProveError::Timeout(_) => ErrorCategory::Timeout,
+                    ProveError::OnchainRevert(_) => ErrorCategory::OnchainRevert,
+                });
+            }
+            if cause.is::<DbError>() || cause.is::<sqlx::Error>() {
+                return Some(ErrorCategory::Db);
+            }
+            if cause.is::<tokio::time::error::Elapsed>() {
+                return Some(ErrorCategory::Timeout);
+            }
+        }
+        // Prover SDKs only report timeouts in their messages
+        let message = format!("{:#}", error).to_lowercase();
+        if message.contains("timed out") || message.contains("timeout") {
+            return Some(ErrorCategory::Timeout);
+        }
+        None
+    }
+
+    /// Defaults per category. `RETRY_MAX_ATTEMPTS_<CATEGORY>` overrides the number of attempts,
+    /// e.g. `RETRY_MAX_ATTEMPTS_PROVER_NETWORK=5`.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let (max_attempts, base_delay) = match self {
+            // The same quote never parses, the same transaction reverts again
+            ErrorCategory::QuoteParse | ErrorCategory::OnchainRevert => (1, 0),
+            // Collaterals are upserted out of band, wait before asking again
+            ErrorCategory::CollateralMissing => (3, 300),
+            ErrorCategory::ProverNetwork => (4, 30),
+            ErrorCategory::Timeout => (2, 60),
+            ErrorCategory::Db => (3, 5),
+            ErrorCategory::Unknown => (2, 30),
+        };
+        let param = format!("RETRY_MAX_ATTEMPTS_{}", self.to_string().to_uppercase());
+        RetryPolicy {
+            max_attempts: parameter::get(&param, Some(&max_attempts.to_string())).parse().unwrap_or(max_attempts),
+            base_delay: Duration::from_secs(base_delay),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Whether another attempt may follow attempt number `attempt_number`
+    pub fn allows(&self, attempt_number: i32) -> bool {
+        attempt_number < self.max_attempts
+    }
+
+    /// Exponential backoff before the attempt following `attempt_number`
+    pub fn delay(&self, attempt_number: i32) -> Duration {
+        self.base_delay * 2u32.pow(attempt_number.clamp(1, 6) as u32 - 1)
+    }
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+#[sqlx(type_name = "tdx_proof_attempt", rename_all = "snake_case")]
+pub struct ProofAttempt {
+    pub id: Uuid,
+    pub tdx_quote_id: Uuid,
+    pub stage: AttemptStage,
+    /// Counts attempts of the same stage for the quote, starting at 1
+    pub attempt_number: i32,
+    /// Proof type and system, or the chain for submissions
+    pub backend: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error_category: Option<ErrorCategory>,
+    pub error_chain: Option<String>,
+    /// When the quote is proven again after this failed attempt, `None` if it is not retried
+    pub next_attempt_at: Option<DateTime<Utc>>,
+}
+
+/// Quote whose last proving attempt failed and is due for another one
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct DueRetry {
+    pub tdx_quote_id: Uuid,
+    /// `request_id` of the quote's onchain request
+    pub request_id: Vec<u8>,
+    /// Backend of the failed attempt
+    pub backend: String,
+}
+
+impl DueRetry {
+    /// Proof type and system the failed attempt used, the retry uses the same
+    pub fn proof_backend(&self) -> Result<(ProofType, Option<ProofSystem>), String> {
+        let (proof_type, proof_system) = match self.backend.split_once('/') {
+            Some((proof_type, proof_system)) => (proof_type, Some(proof_system)),
+            None => (self.backend.as_str(), None),
+        };
+        Ok((proof_type.parse()?, proof_system.map(str::parse).transpose()?))
+    }
+}
+
+/// Every message in the chain of `error`, outermost first
+pub fn error_chain(error: &anyhow::Error) -> String {
+    error.chain().map(|cause| cause.to_string()).collect::<Vec<_>>().join("\ncaused by: ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::anyhow;
+
+    use sqlx::types::Uuid;
+
+    use super::{error_chain, DueRetry, ErrorCategory};
+    use crate::{
+        entity::{quote::ProofType, zk::ProofSystem},
+        error::prove_error::ProveError,
+    };
+
+    #[test]
+    fn classifies_error_chain() {
+        let error = anyhow::Error::new(ProveError::QuoteParse("Unsupported quote version 2".to_string()))
+            .context("Failed to fetch collaterals");
+        assert_eq!(ErrorCategory::classify(&error), Some(ErrorCategory::QuoteParse));
+        assert_eq!(error_chain(&error), "Failed to fetch collaterals\ncaused by: Invalid quote: Unsupported quote version 2");
+
+        let error = anyhow!("request timed out after 900s").context("wait_proof");
+        assert_eq!(ErrorCategory::classify(&error), Some(ErrorCategory::Timeout));
+
+        assert_eq!(ErrorCategory::classify(&anyhow!("connection reset")), None);
+    }
+
+    #[test]
+    fn retry_policy() {
+        assert!(!ErrorCategory::QuoteParse.retry_policy().allows(1));
+
+        let policy = ErrorCategory::Db.retry_policy();
+        assert!(policy.allows(2));
+        assert!(!policy.allows(3));
+        assert_eq!(policy.delay(1), Duration::from_secs(5));
+        assert_eq!(policy.delay(3), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn due_retry_keeps_the_backend() {
+        let retry = |backend: &str| DueRetry { tdx_quote_id: Uuid::from_u128(1), request_id: vec![1], backend: backend.to_string() };
+
+        assert_eq!(retry("sp1/groth16").proof_backend(), Ok((ProofType::Sp1, Some(ProofSystem::Groth16))));
+        assert_eq!(retry("rawquote").proof_backend(), Ok((ProofType::RawQuote, None)));
+        assert!(retry("sp1/stark").proof_backend().is_err());
+    }
+}
@@ -2,18 +2,21 @@
 use dcap_rs::types::{quotes::body::QuoteBody, TcbStatus, VerifiedOutput};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, utoipa::ToSchema)]
 pub struct DcapVerifiedOutput {
     pub quote_version: u16,
     pub tee_type: u32,
+    /// TCB status of the platform as reported by dcap-rs
+    #[schema(value_type = String)]
     pub tcb_status: TcbStatus,
+    #[schema(value_type = Vec<u8>)]
     pub fmspc: [u8; 6],
     pub quote_body_type: QuoteBodyType,
     pub quote_body_bytes: Vec<u8>,
     pub advisory_ids: Option<Vec<String>>,
 }
 
-#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
+#[derive(Debug, Copy, Clone, Deserialize, Serialize, utoipa::ToSchema)]
 pub enum QuoteBodyType {
     SGXQuoteBody,
     TD10QuoteBody
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::types::Uuid;
+
+/// Streak of finality checks that found the proof transaction of a quote dropped
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct DroppedCheck {
+    pub tdx_quote_id: Uuid,
+    pub txn_hash: Vec<u8>,
+    pub checks: i32,
+    pub first_checked_at: DateTime<Utc>,
+    pub last_checked_at: DateTime<Utc>,
+}
+
+impl DroppedCheck {
+    /// A single check can hit a node that is behind or missing the transaction, so a drop only
+    /// counts once `required_checks` checks spread over at least `window` agree
+    pub fn confirms_drop(&self, required_checks: i32, window: Duration) -> bool {
+        self.checks >= required_checks && self.last_checked_at - self.first_checked_at >= window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use sqlx::types::Uuid;
+
+    use super::DroppedCheck;
+
+    #[test]
+    fn drop_needs_checks_spread_over_the_window() {
+        let now = Utc::now();
+        let check = |checks, elapsed| DroppedCheck {
+            tdx_quote_id: Uuid::from_u128(1),
+            txn_hash: vec![0xab; 32],
+            checks,
+            first_checked_at: now - elapsed,
+            last_checked_at: now,
+        };
+        let window = Duration::minutes(2);
+
+        assert!(!check(1, Duration::zero()).confirms_drop(3, window));
+        assert!(!check(5, Duration::seconds(10)).confirms_drop(3, window));
+        assert!(!check(2, Duration::minutes(10)).confirms_drop(3, window));
+        assert!(check(3, Duration::minutes(2)).confirms_drop(3, window));
+    }
+}
@@ -0,0 +1,89 @@
+#![allow(dead_code)]
+use std::str::FromStr;
+
+use alloy::{
+    primitives::{Address, TxHash, U256},
+    rpc::types::TransactionReceipt,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::{BigDecimal, Uuid};
+
+use super::quote::ProofType;
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+#[sqlx(type_name = "tdx_transaction_fee", rename_all = "snake_case")]
+pub struct TransactionFee {
+    pub id: Uuid,
+    pub tdx_quote_id: Uuid,
+    pub onchain_request_id: Uuid,
+    pub proof_type: ProofType,
+    pub txn_hash: Vec<u8>,
+    pub block_number: Option<i64>,
+    pub gas_used: i64,
+    pub effective_gas_price: BigDecimal,
+    pub fee_wei: BigDecimal,
+    pub signer: String,
+    pub success: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a submitted transaction cost, as reported by its receipt.
+///
+/// Only the L2 execution fee is covered; the L1 data fee of OP stack chains is not part of the
+/// Ethereum receipt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionCost {
+    pub txn_hash: TxHash,
+    pub block_number: Option<u64>,
+    pub gas_used: u64,
+    pub effective_gas_price: u128,
+    pub fee_wei: U256,
+    pub signer: Address,
+    pub success: bool,
+}
+
+impl TransactionCost {
+    pub fn from_receipt(receipt: &TransactionReceipt) -> Self {
+        Self {
+            txn_hash: receipt.transaction_hash,
+            block_number: receipt.block_number,
+            gas_used: receipt.gas_used,
+            effective_gas_price: receipt.effective_gas_price,
+            fee_wei: U256::from(receipt.gas_used) * U256::from(receipt.effective_gas_price),
+            signer: receipt.from,
+            success: receipt.status(),
+        }
+    }
+}
+
+/// Dimension fee reports are aggregated over
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FeeGroupBy {
+    Day,
+    ModelId,
+    ProofType,
+}
+
+impl FromStr for FeeGroupBy {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<FeeGroupBy, Self::Err> {
+        match input {
+            "day" => Ok(FeeGroupBy::Day),
+            "model_id" => Ok(FeeGroupBy::ModelId),
+            "proof_type" => Ok(FeeGroupBy::ProofType),
+            _ => Err(format!("Unknown fee grouping: {}", input)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct FeeReportRow {
+    pub group_key: String,
+    pub tx_count: i64,
+    pub gas_used: BigDecimal,
+    pub fee_wei: BigDecimal,
+}
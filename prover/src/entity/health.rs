@@ -0,0 +1,76 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// State of a readiness component, ordered from best to worst
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// Working as configured
+    Up,
+    /// Working, but needs attention, e.g. a failing fallback RPC or a low signer balance
+    Degraded,
+    /// Not working, the instance should not receive traffic
+    Down,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ComponentHealth {
+    /// `database`, `network:<name>`, `signer` or `prover:<proof system>`
+    pub name: String,
+    pub status: HealthStatus,
+    /// What was checked or why it failed, never contains URLs or secrets
+    pub detail: Option<String>,
+    pub latency_ms: u64,
+}
+
+impl ComponentHealth {
+    pub fn new(name: impl Into<String>, status: HealthStatus, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), status, detail: Some(detail.into()), latency_ms: 0 }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct HealthReport {
+    /// Worst status of the components
+    pub status: HealthStatus,
+    pub checked_at: DateTime<Utc>,
+    pub components: Vec<ComponentHealth>,
+}
+
+impl HealthReport {
+    pub fn new(components: Vec<ComponentHealth>) -> Self {
+        let status = components.iter().map(|component| component.status).max().unwrap_or(HealthStatus::Up);
+        Self { status, checked_at: Utc::now(), components }
+    }
+
+    /// Degraded instances keep serving, only a component that is down takes them out of rotation
+    pub fn is_ready(&self) -> bool {
+        self.status != HealthStatus::Down
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_takes_worst_status() {
+        let report = HealthReport::new(vec![
+            ComponentHealth::new("database", HealthStatus::Up, "ok"),
+            ComponentHealth::new("signer", HealthStatus::Degraded, "low balance"),
+        ]);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert!(report.is_ready());
+
+        let report = HealthReport::new(vec![
+            ComponentHealth::new("signer", HealthStatus::Degraded, "low balance"),
+            ComponentHealth::new("network:base", HealthStatus::Down, "wrong chain id"),
+        ]);
+        assert_eq!(report.status, HealthStatus::Down);
+        assert!(!report.is_ready());
+
+        assert_eq!(HealthReport::new(vec![]).status, HealthStatus::Up);
+    }
+}
@@ -1,5 +1,17 @@
+pub mod agent;
+pub mod api_key;
+pub mod attempt;
 pub mod evm;
 pub mod dcap;
+pub mod dropped_check;
+pub mod fee;
+pub mod health;
+pub mod proof;
 pub mod quote;
+pub mod quote_event;
+pub mod quote_update;
 pub mod request;
+pub mod usage;
+pub mod verification;
+pub mod webhook;
 pub mod zk;
@@ -0,0 +1,27 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use sqlx::types::Uuid;
+
+use super::{quote::ProofType, zk::DcapProof};
+
+/// A generated proof, stored by the hash of the input it was generated from
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct TdxProof {
+    pub id: Uuid,
+    pub tdx_quote_id: Uuid,
+    pub quote_hash: Vec<u8>,
+    /// SHA-256 of the prover input, the quote together with its collaterals
+    pub input_hash: Vec<u8>,
+    pub proof_type: ProofType,
+    pub proof_system: Option<String>,
+    /// `DcapProof` as JSON
+    pub proof: Vec<u8>,
+    pub prover_request_id: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TdxProof {
+    pub fn dcap_proof(&self) -> Result<DcapProof, serde_json::Error> {
+        serde_json::from_slice(&self.proof)
+    }
+}
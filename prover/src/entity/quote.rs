@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::types::Uuid;
 
 #[derive(Clone, Debug, sqlx::FromRow)]
@@ -17,37 +18,101 @@ pub struct TdxQuote {
     pub proof_type: Option<ProofType>,
     pub txn_hash: Option<Vec<u8>>,
     pub request_id: Option<Vec<u8>>,
+    /// SHA-256 of `quote`
+    pub quote_hash: Vec<u8>,
+    /// First registered quote with the same content
+    pub duplicate_of: Option<Uuid>,
+    /// Agent of the quote's request
+    pub agent_id: Option<Uuid>,
 }
 
-#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+/// Content hash identifying identical quotes
+pub fn quote_hash(quote: &[u8]) -> Vec<u8> {
+    Sha256::digest(quote).to_vec()
+}
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, utoipa::ToSchema)]
 #[strum(serialize_all = "lowercase")]
 #[sqlx(type_name = "prooftype", rename_all = "lowercase")]
 pub enum ProofType {
     Sp1,
     Risc0,
+    /// The quote itself is verified on-chain, no ZK proof is generated
+    RawQuote,
 }
 
 impl FromStr for ProofType {
     type Err = String;
 
+    /// Case insensitive, `Sp1` and `sp1` both parse
     fn from_str(input: &str) -> Result<ProofType, Self::Err> {
-        match input {
+        match input.to_ascii_lowercase().as_str() {
             "sp1" => Ok(ProofType::Sp1),
             "risc0" => Ok(ProofType::Risc0),
+            "rawquote" | "raw_quote" => Ok(ProofType::RawQuote),
             _ => Err(format!("Unknown proof type: {}", input)),
         }
     }
 }
 
-#[derive(strum_macros::Display, Debug, Clone, Copy, sqlx::Type, Serialize, Deserialize)]
+/// Lifecycle of a quote, see `service::lifecycle_service` for the allowed transitions
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, utoipa::ToSchema)]
 #[strum(serialize_all = "lowercase")]
 #[sqlx(type_name = "tdxquotestatus", rename_all = "lowercase")]
 pub enum TdxQuoteStatus {
     Pending,
     Failure,
+    /// Written before the lifecycle existed, no transition leads here anymore
     Success,
+    Expired,
+    Cancelled,
+    /// Proof transaction mined but not yet past the chain's confirmation depth
+    Submitted,
+    /// Proof transaction final at the configured confirmation depth
+    Confirmed,
+    /// Collaterals for the quote were read from the on-chain PCCS
+    CollateralFetched,
+    /// Proof generation was handed to the prover
+    Proving,
+    /// A proof exists but has not been submitted yet
+    Proved,
 }
 
+impl FromStr for TdxQuoteStatus {
+    type Err = String;
+
+    /// Case insensitive, `Pending` and `pending` both parse
+    fn from_str(input: &str) -> Result<TdxQuoteStatus, Self::Err> {
+        match input.to_ascii_lowercase().as_str() {
+            "pending" => Ok(TdxQuoteStatus::Pending),
+            "failure" => Ok(TdxQuoteStatus::Failure),
+            "success" => Ok(TdxQuoteStatus::Success),
+            "expired" => Ok(TdxQuoteStatus::Expired),
+            "cancelled" => Ok(TdxQuoteStatus::Cancelled),
+            "submitted" => Ok(TdxQuoteStatus::Submitted),
+            "confirmed" => Ok(TdxQuoteStatus::Confirmed),
+            "collateralfetched" | "collateral_fetched" => Ok(TdxQuoteStatus::CollateralFetched),
+            "proving" => Ok(TdxQuoteStatus::Proving),
+            "proved" => Ok(TdxQuoteStatus::Proved),
+            _ => Err(format!("Unknown quote status: {}", input)),
+        }
+    }
+}
+
+/// Deserializes through `FromStr`, so query strings like `?status=pending` match the serialized
+/// `Pending` as well
+macro_rules! deserialize_from_str {
+    ($($ty:ty),*) => {$(
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    )*};
+}
+
+deserialize_from_str!(ProofType, TdxQuoteStatus);
+
 #[derive(strum_macros::Display, Debug, Clone, Copy, sqlx::Type, Serialize, Deserialize)]
 #[strum(serialize_all = "lowercase")]
 #[sqlx(type_name = "quote_type", rename_all = "lowercase")]
@@ -55,3 +120,190 @@ pub enum QuoteType {
     DcapV3,
     DcapV4,
 }
+
+/// Criteria for listing quotes, every field that is set must match
+#[derive(Debug, Clone, Default)]
+pub struct QuoteFilter {
+    pub status: Option<TdxQuoteStatus>,
+    pub proof_type: Option<ProofType>,
+    pub onchain_request_id: Option<Uuid>,
+    pub model_id: Option<String>,
+    pub creator_address: Option<String>,
+    pub operator_address: Option<String>,
+    pub agent_id: Option<Uuid>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+}
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteSortBy {
+    #[default]
+    CreatedAt,
+    /// Changes with every transition, so paging by it may skip or repeat quotes updated meanwhile
+    UpdatedAt,
+}
+
+impl FromStr for QuoteSortBy {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<QuoteSortBy, Self::Err> {
+        match input {
+            "created_at" => Ok(QuoteSortBy::CreatedAt),
+            "updated_at" => Ok(QuoteSortBy::UpdatedAt),
+            _ => Err(format!("Unknown sort column: {}", input)),
+        }
+    }
+}
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<SortOrder, Self::Err> {
+        match input {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(format!("Unknown sort order: {}", input)),
+        }
+    }
+}
+
+/// Position after the last quote of a page, the sort column value and the id as a tie breaker.
+/// It also records the sorting of the listing it came from, positions of another sorting would
+/// skip or repeat quotes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuoteCursor {
+    pub sort_by: QuoteSortBy,
+    pub order: SortOrder,
+    pub timestamp: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl QuoteCursor {
+    pub fn from_quote(quote: &TdxQuote, sort_by: QuoteSortBy, order: SortOrder) -> Self {
+        let timestamp = match sort_by {
+            QuoteSortBy::CreatedAt => quote.created_at,
+            QuoteSortBy::UpdatedAt => quote.updated_at,
+        };
+        Self { sort_by, order, timestamp, id: quote.id }
+    }
+
+    /// Opaque string handed to clients
+    pub fn encode(&self) -> String {
+        let timestamp = self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        hex::encode(format!("{}|{}|{}|{}", self.sort_by, self.order, timestamp, self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid cursor: {}", cursor);
+        let decoded = String::from_utf8(hex::decode(cursor).map_err(|_| invalid())?).map_err(|_| invalid())?;
+        let [sort_by, order, timestamp, id] = decoded.split('|').collect::<Vec<_>>()[..] else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            sort_by: sort_by.parse().map_err(|_| invalid())?,
+            order: order.parse().map_err(|_| invalid())?,
+            timestamp: DateTime::parse_from_rfc3339(timestamp).map_err(|_| invalid())?.with_timezone(&Utc),
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct QuotePage {
+    pub sort_by: QuoteSortBy,
+    pub order: SortOrder,
+    pub limit: i64,
+    pub after: Option<QuoteCursor>,
+}
+
+impl QuotePage {
+    /// Page after `cursor`. Sorting that is not given is taken from the cursor, then from the
+    /// defaults, and a cursor of a listing sorted otherwise is rejected.
+    pub fn after(
+        sort_by: Option<QuoteSortBy>,
+        order: Option<SortOrder>,
+        limit: i64,
+        cursor: Option<QuoteCursor>,
+    ) -> Result<Self, String> {
+        let defaults = QuotePage::default();
+        let sort_by = sort_by.or(cursor.map(|cursor| cursor.sort_by)).unwrap_or(defaults.sort_by);
+        let order = order.or(cursor.map(|cursor| cursor.order)).unwrap_or(defaults.order);
+        if let Some(cursor) = cursor.filter(|cursor| (cursor.sort_by, cursor.order) != (sort_by, order)) {
+            return Err(format!(
+                "Cursor belongs to a listing sorted by {} {}, not {} {}",
+                cursor.sort_by, cursor.order, sort_by, order
+            ));
+        }
+        Ok(Self { sort_by, order, limit, after: cursor })
+    }
+}
+
+impl Default for QuotePage {
+    fn default() -> Self {
+        Self {
+            sort_by: QuoteSortBy::default(),
+            order: SortOrder::default(),
+            limit: 50,
+            after: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use sqlx::types::Uuid;
+
+    use super::{ProofType, QuoteCursor, QuotePage, QuoteSortBy, SortOrder, TdxQuoteStatus};
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = QuoteCursor {
+            sort_by: QuoteSortBy::UpdatedAt,
+            order: SortOrder::Asc,
+            timestamp: Utc.with_ymd_and_hms(2025, 5, 1, 12, 30, 0).unwrap(),
+            id: Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap(),
+        };
+
+        assert_eq!(QuoteCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(QuoteCursor::decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn cursor_keeps_its_sorting() {
+        let cursor = QuoteCursor {
+            sort_by: QuoteSortBy::UpdatedAt,
+            order: SortOrder::Asc,
+            timestamp: Utc.with_ymd_and_hms(2025, 5, 1, 12, 30, 0).unwrap(),
+            id: Uuid::from_u128(1),
+        };
+
+        let page = QuotePage::after(None, None, 10, Some(cursor)).unwrap();
+        assert_eq!((page.sort_by, page.order), (QuoteSortBy::UpdatedAt, SortOrder::Asc));
+        assert!(QuotePage::after(Some(QuoteSortBy::UpdatedAt), Some(SortOrder::Asc), 10, Some(cursor)).is_ok());
+        assert!(QuotePage::after(Some(QuoteSortBy::CreatedAt), None, 10, Some(cursor)).is_err());
+        assert!(QuotePage::after(None, Some(SortOrder::Desc), 10, Some(cursor)).is_err());
+    }
+
+    #[test]
+    fn enums_deserialize_in_any_case() {
+        let status: TdxQuoteStatus = serde_json::from_str("\"pending\"").unwrap();
+        assert_eq!(status, TdxQuoteStatus::Pending);
+        let status: TdxQuoteStatus = serde_json::from_str(&serde_json::to_string(&TdxQuoteStatus::CollateralFetched).unwrap()).unwrap();
+        assert_eq!(status, TdxQuoteStatus::CollateralFetched);
+        let proof_type: ProofType = serde_json::from_str("\"RawQuote\"").unwrap();
+        assert_eq!(proof_type, ProofType::RawQuote);
+        assert!(serde_json::from_str::<ProofType>("\"groth16\"").is_err());
+    }
+}
@@ -0,0 +1,44 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use super::quote::{ProofType, TdxQuoteStatus};
+
+/// Component that moved a quote to a new status
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "quoteactor", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteActor {
+    Lambda,
+    Cli,
+    Api,
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+#[sqlx(type_name = "tdx_quote_event", rename_all = "snake_case")]
+pub struct TdxQuoteEvent {
+    pub id: Uuid,
+    pub tdx_quote_id: Uuid,
+    /// Unset for the event recording the quote's creation, equal to `to_status` for a note that
+    /// left the status as it was
+    pub from_status: Option<TdxQuoteStatus>,
+    pub to_status: TdxQuoteStatus,
+    pub actor: QuoteActor,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A validated status change together with the columns it updates
+#[derive(Clone, Debug)]
+pub struct QuoteTransition {
+    pub from: TdxQuoteStatus,
+    pub to: TdxQuoteStatus,
+    /// Kept as is when unset
+    pub proof_type: Option<ProofType>,
+    /// Kept as is when unset
+    pub txn_hash: Option<Vec<u8>>,
+    pub actor: QuoteActor,
+    pub detail: Option<String>,
+}
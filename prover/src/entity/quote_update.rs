@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::entity::{
+    quote::{ProofType, TdxQuote, TdxQuoteStatus},
+    quote_event::QuoteActor,
+};
+
+/// Longest `detail` sent, error chains can be long and Postgres notifications are limited to 8000 bytes
+const MAX_DETAIL_LEN: usize = 1000;
+
+/// What a `QuoteUpdate` reports, also the name of its server-sent event
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteUpdateKind {
+    /// Status of the quote when the stream was opened
+    Snapshot,
+    /// The quote moved to another status
+    Transition,
+    /// The proving network assigned a request id to the proof
+    ProverRequest,
+    /// The proof transaction got another confirmation
+    Confirmations,
+}
+
+/// Progress of a quote as streamed by `GET /quote/{id}/events` and `GET /events`
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct QuoteUpdate {
+    pub kind: QuoteUpdateKind,
+    pub quote_id: String,
+    pub onchain_request_id: String,
+    pub agent_id: Option<String>,
+    /// Status of the quote after the update
+    pub status: TdxQuoteStatus,
+    /// Status the quote left, only set on transitions
+    pub from: Option<TdxQuoteStatus>,
+    pub proof_type: Option<ProofType>,
+    /// 0x prefixed hash of the proof transaction
+    pub txn_hash: Option<String>,
+    /// 0x prefixed id of the request on the proving network
+    pub prover_request_id: Option<String>,
+    pub confirmations: Option<u64>,
+    pub required_confirmations: Option<u64>,
+    pub actor: Option<QuoteActor>,
+    pub detail: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl QuoteUpdate {
+    fn of(kind: QuoteUpdateKind, quote: &TdxQuote) -> Self {
+        Self {
+            kind,
+            quote_id: quote.id.to_string(),
+            onchain_request_id: quote.onchain_request_id.to_string(),
+            agent_id: quote.agent_id.map(|id| id.to_string()),
+            status: quote.status,
+            from: None,
+            proof_type: quote.proof_type,
+            txn_hash: quote.txn_hash.as_ref().map(|hash| format!("0x{}", hex::encode(hash))),
+            prover_request_id: None,
+            confirmations: None,
+            required_confirmations: None,
+            actor: None,
+            detail: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn snapshot(quote: &TdxQuote) -> Self {
+        Self {
+            occurred_at: quote.updated_at,
+            ..Self::of(QuoteUpdateKind::Snapshot, quote)
+        }
+    }
+
+    /// `quote` as it is after moving from `from`
+    pub fn transition(from: TdxQuoteStatus, quote: &TdxQuote, actor: QuoteActor, detail: Option<String>) -> Self {
+        Self {
+            from: Some(from),
+            actor: Some(actor),
+            detail: detail.map(truncate),
+            occurred_at: quote.updated_at,
+            ..Self::of(QuoteUpdateKind::Transition, quote)
+        }
+    }
+
+    pub fn prover_request(quote: &TdxQuote, prover_request_id: &[u8]) -> Self {
+        Self {
+            prover_request_id: Some(format!("0x{}", hex::encode(prover_request_id))),
+            ..Self::of(QuoteUpdateKind::ProverRequest, quote)
+        }
+    }
+
+    pub fn confirmations(quote: &TdxQuote, confirmations: u64, required: u64) -> Self {
+        Self {
+            confirmations: Some(confirmations),
+            required_confirmations: Some(required),
+            ..Self::of(QuoteUpdateKind::Confirmations, quote)
+        }
+    }
+}
+
+fn truncate(mut detail: String) -> String {
+    if detail.len() > MAX_DETAIL_LEN {
+        let mut end = MAX_DETAIL_LEN;
+        while !detail.is_char_boundary(end) {
+            end -= 1;
+        }
+        detail.truncate(end);
+    }
+    detail
+}
+
+/// Selects the updates a stream receives, filters that are not set match every update
+#[derive(Debug, Clone, Default)]
+pub struct QuoteUpdateFilter {
+    pub quote_id: Option<Uuid>,
+    pub onchain_request_id: Option<Uuid>,
+    pub agent_id: Option<Uuid>,
+    pub status: Option<TdxQuoteStatus>,
+    pub kind: Option<QuoteUpdateKind>,
+}
+
+impl QuoteUpdateFilter {
+    pub fn matches(&self, update: &QuoteUpdate) -> bool {
+        let id_matches = |filter: Option<Uuid>, id: Option<&str>| match filter {
+            Some(filter) => id.and_then(|id| Uuid::parse_str(id).ok()) == Some(filter),
+            None => true,
+        };
+        id_matches(self.quote_id, Some(update.quote_id.as_str()))
+            && id_matches(self.onchain_request_id, Some(update.onchain_request_id.as_str()))
+            && id_matches(self.agent_id, update.agent_id.as_deref())
+            && self.status.is_none_or(|status| status == update.status)
+            && self.kind.is_none_or(|kind| kind == update.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::quote::quote_hash;
+
+    fn quote(status: TdxQuoteStatus) -> TdxQuote {
+        TdxQuote {
+            id: Uuid::from_u128(1),
+            onchain_request_id: Uuid::from_u128(2),
+            status,
+            quote: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            proof_type: Some(ProofType::Sp1),
+            txn_hash: Some(vec![0xab; 32]),
+            request_id: None,
+            quote_hash: quote_hash(&[]),
+            duplicate_of: None,
+            agent_id: None,
+        }
+    }
+
+    #[test]
+    fn filter_matches_updates() {
+        let update = QuoteUpdate::transition(
+            TdxQuoteStatus::Proved,
+            &quote(TdxQuoteStatus::Submitted),
+            QuoteActor::Api,
+            Some("x".repeat(5000)),
+        );
+        assert_eq!(update.detail.as_ref().map(String::len), Some(MAX_DETAIL_LEN));
+        assert_eq!(update.txn_hash.as_deref().map(str::len), Some(66));
+
+        assert!(QuoteUpdateFilter::default().matches(&update));
+        let by_request = QuoteUpdateFilter { onchain_request_id: Some(Uuid::from_u128(2)), ..Default::default() };
+        assert!(by_request.matches(&update));
+        let by_status = QuoteUpdateFilter { status: Some(TdxQuoteStatus::Confirmed), ..Default::default() };
+        assert!(!by_status.matches(&update));
+        let by_agent = QuoteUpdateFilter { agent_id: Some(Uuid::from_u128(3)), ..Default::default() };
+        assert!(!by_agent.matches(&update));
+        let by_kind = QuoteUpdateFilter { kind: Some(QuoteUpdateKind::Transition), ..Default::default() };
+        assert!(by_kind.matches(&update));
+    }
+}
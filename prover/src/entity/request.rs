@@ -2,6 +2,8 @@
 use chrono::{DateTime, Utc};
 use sqlx::types::Uuid;
 
+use crate::error::request_error::RequestError;
+
 #[derive(Clone, sqlx::FromRow)]
 #[sqlx(type_name = "onchain_request", rename_all = "snake_case")]
 pub struct OnchainRequest {
@@ -17,6 +19,82 @@ pub struct OnchainRequest {
     pub cancelled_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Agent registered for `operator_address`
+    pub agent_id: Option<Uuid>,
+    /// API key the request was registered with, its webhooks receive the request's events
+    pub api_key_id: Option<Uuid>,
+}
+
+/// A request registered through the API instead of being read from the chain
+#[derive(Clone, Debug)]
+pub struct NewOnchainRequest {
+    pub creator_address: String,
+    pub operator_address: String,
+    pub model_id: String,
+    pub fee_wei: i64,
+    pub nonce: i64,
+    pub request_id: Vec<u8>,
+    pub deadline: DateTime<Utc>,
+    pub api_key_id: Option<Uuid>,
+}
+
+/// Changes to a request, fields that are not set are kept
+#[derive(Clone, Debug, Default)]
+pub struct OnchainRequestUpdate {
+    pub operator_address: Option<String>,
+    pub fee_wei: Option<i64>,
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+/// Criteria for listing requests, every field that is set must match
+#[derive(Clone, Debug, Default)]
+pub struct RequestFilter {
+    pub model_id: Option<String>,
+    pub creator_address: Option<String>,
+    pub operator_address: Option<String>,
+    pub agent_id: Option<Uuid>,
+    pub include_cancelled: bool,
+}
+
+impl OnchainRequest {
+    /// Returns true if the request deadline has passed at `now`
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.deadline <= now
+    }
+
+    /// Checks that the request can still be proven and submitted on-chain.
+    /// Cancellation takes precedence over expiry.
+    pub fn ensure_active(&self) -> Result<(), RequestError> {
+        if self.is_cancelled {
+            return Err(RequestError::Cancelled);
+        }
+        if self.is_expired_at(Utc::now()) {
+            return Err(RequestError::Expired);
+        }
+        Ok(())
+    }
+
+    /// An active request due in an hour, tests override the fields they care about
+    #[cfg(test)]
+    pub(crate) fn fixture() -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::nil(),
+            creator_address: "0x6BBC359046BDBFb1596222E6257F0ef24e0Fc0B9".to_string(),
+            operator_address: "0xEeE7FB850D28f5cabd5f1EDF540646b5bEA17CE5".to_string(),
+            model_id: "0x682db2fe997945208caa888543ffca2ad2c7edf1ab0b02899b9977e6d18af477".to_string(),
+            fee_wei: 0,
+            nonce: 1,
+            request_id: vec![1; 32],
+            deadline: now + chrono::Duration::hours(1),
+            is_cancelled: false,
+            cancelled_at: None,
+            created_at: now,
+            updated_at: now,
+            agent_id: None,
+            api_key_id: None,
+        }
+    }
 }
 
 impl std::fmt::Debug for OnchainRequest {
@@ -34,6 +112,27 @@ impl std::fmt::Debug for OnchainRequest {
             .field("cancelled_at", &self.cancelled_at)
             .field("created_at", &self.created_at)
             .field("updated_at", &self.updated_at)
+            .field("agent_id", &self.agent_id)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::OnchainRequest;
+    use crate::error::request_error::RequestError;
+
+    fn request(deadline_offset: Duration, is_cancelled: bool) -> OnchainRequest {
+        OnchainRequest { deadline: Utc::now() + deadline_offset, is_cancelled, ..OnchainRequest::fixture() }
+    }
+
+    #[test]
+    fn ensure_active() {
+        assert!(request(Duration::hours(1), false).ensure_active().is_ok());
+        assert!(matches!(request(Duration::hours(-1), false).ensure_active(), Err(RequestError::Expired)));
+        assert!(matches!(request(Duration::hours(1), true).ensure_active(), Err(RequestError::Cancelled)));
+        assert!(matches!(request(Duration::hours(-1), true).ensure_active(), Err(RequestError::Cancelled)));
+    }
+}
@@ -0,0 +1,111 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+use crate::{config::parameter, entity::api_key::ApiScope};
+
+const DEFAULT_RATE_LIMIT_PER_MINUTE: i32 = 60;
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "quotaperiod", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaPeriod {
+    Day,
+    Month,
+}
+
+impl QuotaPeriod {
+    /// Start of the calendar day or month, in UTC, that `now` falls in and the start of the next one
+    pub fn window(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = now.date_naive();
+        let (start, end) = match self {
+            QuotaPeriod::Day => (today, today + Duration::days(1)),
+            QuotaPeriod::Month => {
+                let start = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap();
+                let end = match today.month() {
+                    12 => NaiveDate::from_ymd_opt(today.year() + 1, 1, 1),
+                    month => NaiveDate::from_ymd_opt(today.year(), month + 1, 1),
+                };
+                (start, end.unwrap())
+            }
+        };
+        (start.and_hms_opt(0, 0, 0).unwrap().and_utc(), end.and_hms_opt(0, 0, 0).unwrap().and_utc())
+    }
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct ProvingQuota {
+    pub id: Uuid,
+    pub subject: String,
+    pub api_key_id: Option<Uuid>,
+    pub creator_address: Option<String>,
+    pub period: QuotaPeriod,
+    pub proof_limit: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Whom a proving quota applies to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QuotaSubject {
+    ApiKey(Uuid),
+    Creator(String),
+}
+
+impl QuotaSubject {
+    pub fn key(&self) -> String {
+        match self {
+            QuotaSubject::ApiKey(id) => format!("key:{}", id),
+            QuotaSubject::Creator(address) => format!("creator:{}", address.to_lowercase()),
+        }
+    }
+}
+
+/// Limit and what is left of it in the current window, reported in response headers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Allowance {
+    pub limit: i32,
+    pub remaining: i32,
+    pub reset_at: DateTime<Utc>,
+}
+
+/// Start and end of the minute `now` falls in
+pub fn rate_window(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = now.with_second(0).and_then(|now| now.with_nanosecond(0)).unwrap_or(now);
+    (start, start + Duration::minutes(1))
+}
+
+/// Requests per minute an API key may send to a single route of `scope`. `RATE_LIMIT_PER_MINUTE`
+/// sets the default and e.g. `RATE_LIMIT_PER_MINUTE_PROVE` overrides it, 0 disables the limit.
+pub fn rate_limit(scope: ApiScope) -> i32 {
+    let default = parameter::get("RATE_LIMIT_PER_MINUTE", Some(&DEFAULT_RATE_LIMIT_PER_MINUTE.to_string()))
+        .parse()
+        .unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE);
+    let param = format!("RATE_LIMIT_PER_MINUTE_{}", scope.to_string().to_uppercase());
+    parameter::get(&param, Some(&default.to_string())).parse().unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn windows_follow_the_calendar() {
+        let now = Utc.with_ymd_and_hms(2025, 12, 31, 17, 42, 9).unwrap();
+
+        let (start, end) = QuotaPeriod::Day.window(now);
+        assert_eq!(start, Utc.with_ymd_and_hms(2025, 12, 31, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+
+        let (start, end) = QuotaPeriod::Month.window(now);
+        assert_eq!(start, Utc.with_ymd_and_hms(2025, 12, 1, 0, 0, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+
+        let (start, end) = rate_window(now);
+        assert_eq!(start, Utc.with_ymd_and_hms(2025, 12, 31, 17, 42, 0).unwrap());
+        assert_eq!(end, Utc.with_ymd_and_hms(2025, 12, 31, 17, 43, 0).unwrap());
+    }
+}
@@ -0,0 +1,96 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Utc};
+use dcap_rs::types::{quotes::body::QuoteBody, VerifiedOutput};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Uuid;
+
+/// Where a verified output came from
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "verificationsource", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationSource {
+    /// `QuoteService::verify_dcap` against the on-chain PCCS collaterals
+    Dcap,
+    /// Journal of a generated proof
+    Zkvm,
+    /// Output returned or accepted by the DCAP contract
+    Onchain,
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct TdxVerification {
+    pub id: Uuid,
+    pub tdx_quote_id: Uuid,
+    pub source: VerificationSource,
+    pub quote_version: i32,
+    pub tee_type: i64,
+    pub tcb_status: String,
+    /// Hex encoded
+    pub fmspc: String,
+    pub advisory_ids: Vec<String>,
+    pub mrtd: Option<Vec<u8>>,
+    pub rtmr0: Option<Vec<u8>>,
+    pub rtmr1: Option<Vec<u8>>,
+    pub rtmr2: Option<Vec<u8>>,
+    pub rtmr3: Option<Vec<u8>>,
+    pub report_data: Vec<u8>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The fields of a `VerifiedOutput` that are stored
+#[derive(Clone, Debug)]
+pub struct DecodedOutput {
+    pub quote_version: i32,
+    pub tee_type: i64,
+    pub tcb_status: String,
+    pub fmspc: String,
+    pub advisory_ids: Vec<String>,
+    pub mrtd: Option<Vec<u8>>,
+    pub rtmrs: Option<[Vec<u8>; 4]>,
+    pub report_data: Vec<u8>,
+}
+
+impl DecodedOutput {
+    pub fn from_output(output: &VerifiedOutput) -> Self {
+        let (mrtd, rtmrs, report_data) = match &output.quote_body {
+            QuoteBody::TD10QuoteBody(body) => (
+                Some(body.mrtd.to_vec()),
+                Some([body.rtmr0.to_vec(), body.rtmr1.to_vec(), body.rtmr2.to_vec(), body.rtmr3.to_vec()]),
+                body.report_data.to_vec(),
+            ),
+            QuoteBody::SGXQuoteBody(body) => (None, None, body.report_data.to_vec()),
+        };
+        Self {
+            quote_version: output.quote_version as i32,
+            tee_type: output.tee_type as i64,
+            tcb_status: format!("{:?}", output.tcb_status),
+            fmspc: hex::encode(output.fmspc),
+            advisory_ids: output.advisory_ids.clone().unwrap_or_default(),
+            mrtd,
+            rtmrs,
+            report_data,
+        }
+    }
+
+    /// Decodes the ABI encoded output of a proof journal or the DCAP contract
+    pub fn from_bytes(output: &[u8]) -> Self {
+        Self::from_output(&VerifiedOutput::from_bytes(output))
+    }
+}
+
+/// Quotes per FMSPC and TCB status, each quote counted with its latest verification
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct TcbStatusCount {
+    pub fmspc: String,
+    pub tcb_status: String,
+    pub quote_count: i64,
+}
+
+/// Quotes per MRTD and when it was last verified
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct MrtdCount {
+    pub mrtd: Vec<u8>,
+    pub quote_count: i64,
+    pub last_verified_at: DateTime<Utc>,
+}
@@ -0,0 +1,320 @@
+#![allow(dead_code)]
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use reqwest::Url;
+use sha2::Sha256;
+use std::net::{IpAddr, Ipv4Addr};
+use sqlx::{
+    postgres::{PgHasArrayType, PgTypeInfo},
+    types::Uuid,
+};
+
+use crate::entity::quote::{ProofType, TdxQuote, TdxQuoteStatus};
+
+/// Prepended to generated secrets so they are easy to tell apart from API keys
+pub const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+/// Header carrying `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`
+pub const SIGNATURE_HEADER: &str = "x-tdx-prover-signature";
+pub const EVENT_HEADER: &str = "x-tdx-prover-event";
+pub const DELIVERY_HEADER: &str = "x-tdx-prover-delivery";
+
+/// Lifecycle events a webhook can subscribe to
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "webhookevent", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    /// The proof of a quote was generated
+    Proved,
+    /// The proof transaction was sent
+    Submitted,
+    /// The proof transaction is final
+    Confirmed,
+    /// Proving or submitting the quote failed for good
+    Failed,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [
+        WebhookEvent::Proved,
+        WebhookEvent::Submitted,
+        WebhookEvent::Confirmed,
+        WebhookEvent::Failed,
+    ];
+
+    /// Event reported when a quote moves to `status`, other statuses are internal steps
+    pub fn from_status(status: TdxQuoteStatus) -> Option<Self> {
+        match status {
+            TdxQuoteStatus::Proved => Some(WebhookEvent::Proved),
+            TdxQuoteStatus::Submitted => Some(WebhookEvent::Submitted),
+            TdxQuoteStatus::Confirmed => Some(WebhookEvent::Confirmed),
+            TdxQuoteStatus::Failure => Some(WebhookEvent::Failed),
+            _ => None,
+        }
+    }
+}
+
+impl PgHasArrayType for WebhookEvent {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_webhookevent")
+    }
+}
+
+#[derive(strum_macros::Display, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize, utoipa::ToSchema)]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "deliverystatus", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Every attempt failed, only a replay sends it again
+    Failed,
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    /// Key that registered the webhook, unset when it was registered without authentication
+    pub api_key_id: Option<Uuid>,
+    /// Set for webhooks of a single request, otherwise every request of `api_key_id` is delivered
+    pub onchain_request_id: Option<Uuid>,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: DateTime<Utc>,
+    pub disabled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewWebhook {
+    pub api_key_id: Option<Uuid>,
+    pub onchain_request_id: Option<Uuid>,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub tdx_quote_id: Uuid,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    /// HTTP status of the last attempt, unset when no response was received
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub replay_of: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A delivery claimed by the dispatcher together with where to send it
+#[derive(Clone, Debug, sqlx::FromRow)]
+pub struct DueDelivery {
+    pub id: Uuid,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
+
+/// Body of every webhook request. `id` identifies the event, replays and retries repeat it so
+/// receivers can drop duplicates.
+#[derive(Clone, Debug, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WebhookPayload {
+    pub id: String,
+    pub event: WebhookEvent,
+    pub occurred_at: DateTime<Utc>,
+    pub quote_id: String,
+    pub onchain_request_id: String,
+    pub status: TdxQuoteStatus,
+    pub proof_type: Option<ProofType>,
+    /// 0x prefixed hash of the proof transaction
+    pub txn_hash: Option<String>,
+    /// Why the quote moved, e.g. the error of a failure
+    pub detail: Option<String>,
+}
+
+impl WebhookPayload {
+    pub fn from(event: WebhookEvent, quote: &TdxQuote, detail: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            event,
+            occurred_at: quote.updated_at,
+            quote_id: quote.id.to_string(),
+            onchain_request_id: quote.onchain_request_id.to_string(),
+            status: quote.status,
+            proof_type: quote.proof_type,
+            txn_hash: quote.txn_hash.as_ref().map(|hash| format!("0x{}", hex::encode(hash))),
+            detail,
+        }
+    }
+}
+
+/// Returns a new random signing secret
+pub fn generate_secret() -> String {
+    format!("{}{}", WEBHOOK_SECRET_PREFIX, hex::encode(rand::random::<[u8; 32]>()))
+}
+
+/// Value of `SIGNATURE_HEADER`. The timestamp is signed too so receivers can reject old requests.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks a `SIGNATURE_HEADER` value against `body`, for receivers written in Rust
+pub fn verify_signature(secret: &str, header: &str, body: &str) -> bool {
+    let timestamp = header
+        .split(',')
+        .find_map(|part| part.strip_prefix("t="))
+        .and_then(|timestamp| timestamp.parse().ok());
+    match timestamp {
+        Some(timestamp) => sign(secret, timestamp, body) == header,
+        None => false,
+    }
+}
+
+/// Wait before attempt `attempts + 1`: `base` doubled after every failed attempt, at most six hours
+pub fn retry_delay(base: Duration, attempts: i32) -> Duration {
+    let delay = base * 2i32.pow(attempts.clamp(1, 16) as u32 - 1);
+    delay.min(Duration::hours(6))
+}
+
+/// Whether webhooks may use plain http and reach loopback, private and link-local addresses,
+/// only meant for local development
+pub fn allows_local_targets() -> bool {
+    crate::config::parameter::get("WEBHOOK_ALLOW_LOCAL", Some("false")) == "true"
+}
+
+/// Addresses reachable from the internet, webhooks never reach into the network the prover
+/// runs in
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // shared address space of carrier-grade NAT
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 192 && b == 0 && c == 0)
+                || (a == 198 && (18..20).contains(&b))
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(ip));
+            }
+            let segments = ip.segments();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // unique local fc00::/7 and link-local fe80::/10
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+                // NAT64 of an IPv4 address
+                || (segments[0] == 0x0064 && segments[1] == 0xff9b && {
+                    let [a, b] = segments[6].to_be_bytes();
+                    let [c, d] = segments[7].to_be_bytes();
+                    !is_public_address(IpAddr::V4(Ipv4Addr::new(a, b, c, d)))
+                }))
+        }
+    }
+}
+
+/// Checks what can be told from the URL alone: an absolute https URL whose host is not a local
+/// name or address. `allow_local` also accepts plain http and local hosts. Returns the parsed URL
+/// or a validation code.
+pub fn check_webhook_url(value: &str, allow_local: bool) -> Result<Url, &'static str> {
+    let url = Url::parse(value).map_err(|_| "invalid_url")?;
+    match url.scheme() {
+        "https" => {}
+        "http" if allow_local => {}
+        "http" => return Err("insecure_url"),
+        _ => return Err("invalid_url"),
+    }
+    // Parsing normalizes IP hosts, e.g. `0x7f.1` becomes `127.0.0.1`, IPv6 hosts keep brackets
+    let host = url.host_str().ok_or("invalid_url")?;
+    let local = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+        Ok(ip) => !is_public_address(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost") || domain.ends_with(".internal")
+        }
+    };
+    match local && !allow_local {
+        true => Err("local_url"),
+        false => Ok(url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_roundtrip() {
+        let body = r#"{"event":"proved"}"#;
+        let header = sign("whsec_test", 1_700_000_000, body);
+        assert!(header.starts_with("t=1700000000,v1="));
+        assert!(verify_signature("whsec_test", &header, body));
+        assert!(!verify_signature("whsec_other", &header, body));
+        assert!(!verify_signature("whsec_test", &header, r#"{"event":"failed"}"#));
+    }
+
+    #[test]
+    fn retry_delay_backs_off() {
+        let base = Duration::seconds(30);
+        assert_eq!(retry_delay(base, 1), Duration::seconds(30));
+        assert_eq!(retry_delay(base, 3), Duration::seconds(120));
+        assert_eq!(retry_delay(base, 20), Duration::hours(6));
+    }
+
+    #[test]
+    fn webhooks_only_reach_public_https_urls() {
+        assert!(check_webhook_url("https://hooks.example.com/tdx", false).is_ok());
+        assert!(check_webhook_url("https://93.184.216.34/tdx", false).is_ok());
+        assert_eq!(check_webhook_url("http://hooks.example.com/tdx", false).unwrap_err(), "insecure_url");
+        assert_eq!(check_webhook_url("ftp://hooks.example.com", false).unwrap_err(), "invalid_url");
+        for url in [
+            "https://localhost/tdx",
+            "https://api.localhost/tdx",
+            "https://127.0.0.1/tdx",
+            "https://0x7f.1/tdx",
+            "https://10.0.0.8/tdx",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/tdx",
+            "https://[::1]/tdx",
+            "https://[fd00::1]/tdx",
+            "https://[fe80::1]/tdx",
+            "https://[::ffff:192.168.0.1]/tdx",
+            "https://metadata.google.internal/tdx",
+        ] {
+            assert_eq!(check_webhook_url(url, false).unwrap_err(), "local_url", "{}", url);
+        }
+        assert!(check_webhook_url("http://127.0.0.1:8080/tdx", true).is_ok());
+    }
+
+    #[test]
+    fn public_addresses() {
+        assert!(is_public_address("1.1.1.1".parse().unwrap()));
+        assert!(is_public_address("2606:4700:4700::1111".parse().unwrap()));
+        assert!(!is_public_address("172.16.0.1".parse().unwrap()));
+        assert!(!is_public_address("0.0.0.0".parse().unwrap()));
+        assert!(!is_public_address("64:ff9b::a00:1".parse().unwrap()));
+        assert!(is_public_address("64:ff9b::101:101".parse().unwrap()));
+    }
+}
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use sp1_sdk::{HashableKey, SP1ProofWithPublicValues, SP1VerifyingKey};
 use validator::Validate;
 
-use super::quote::{ProofType, TdxQuoteStatus};
+use super::{fee::TransactionCost, quote::{ProofType, TdxQuoteStatus}};
 
 /// DCAP ELF for Sp1
 pub const DCAP_SP1_ELF: &[u8] = include_bytes!("../../elf/dcap-sp1");
@@ -21,11 +21,24 @@ pub enum ProofSystem {
     Plonk,
 }
 
+impl std::str::FromStr for ProofSystem {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<ProofSystem, Self::Err> {
+        match input.to_ascii_lowercase().as_str() {
+            "groth16" => Ok(ProofSystem::Groth16),
+            "plonk" => Ok(ProofSystem::Plonk),
+            _ => Err(format!("Unknown proof system: {}", input)),
+        }
+    }
+}
+
 /// Enum representing the available proof systems
 #[derive(Clone, Serialize, Deserialize)]
 pub enum ZkvmProof {
     Sp1((Vec<u8> /* journal */, SP1VerifyingKey /* vk */, SP1ProofWithPublicValues /* proof */)),
     Risc0((Receipt /* receipt: contains journal */, Digest /* image_id */, Vec<u8> /* seal */)),
+    RawQuote(Vec<u8> /* quote */),
 }
 
 impl std::fmt::Debug for ZkvmProof {
@@ -37,13 +50,17 @@ impl std::fmt::Debug for ZkvmProof {
             ZkvmProof::Risc0((receipt, image_id, seal)) =>
                 write!(f, "Risc0 {{ journal: {:?}, image_id: {:?}, seal: {:?} }}",
                     hex::encode(receipt.journal.bytes.clone()), hex::encode(image_id), hex::encode(seal)),
+            ZkvmProof::RawQuote(quote) =>
+                write!(f, "RawQuote {{ quote: {:?} }}", hex::encode(quote)),
         }
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Validate)]
+#[derive(Clone, Debug, Serialize, Deserialize, Validate, utoipa::ToSchema)]
 pub struct DcapProof {
     pub verified_output: Vec<u8>,
+    /// Serialized `ZkvmProof`, an externally tagged `Sp1`, `Risc0` or `RawQuote` proof
+    #[schema(value_type = Object)]
     pub proof: ZkvmProof,
 }
 
@@ -59,4 +76,5 @@ pub struct SubmitProofResponse {
     pub transaction_hash: TxHash,
     pub proof_type: ProofType,
     pub status: TdxQuoteStatus,
+    pub cost: TransactionCost,
 }
@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+use crate::error::db_error::DbError;
+
+#[derive(Error, Debug)]
+pub enum AgentError {
+    #[error("Agent not found")]
+    NotFound,
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+use crate::entity::api_key::ApiScope;
+use crate::error::db_error::DbError;
+
+#[derive(Error, Debug)]
+pub enum AuthError {
+    #[error("Missing API key")]
+    MissingKey,
+    #[error("Invalid API key")]
+    InvalidKey,
+    #[error("API key is missing the {0} scope")]
+    MissingScope(ApiScope),
+    #[error("API key not found")]
+    NotFound,
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
@@ -6,4 +6,6 @@ pub enum DbError {
     SomethingWentWrong(String),
     #[error("Duplicate entry exists")]
     UniqueConstraintViolation(String),
+    #[error("Database connection unavailable: {0}")]
+    Pool(String),
 }
@@ -1,5 +1,10 @@
 #![allow(dead_code)]
 
+pub mod agent_error;
+pub mod auth_error;
 pub mod db_error;
+pub mod prove_error;
 pub mod quote_error;
 pub mod request_error;
+pub mod usage_error;
+pub mod webhook_error;
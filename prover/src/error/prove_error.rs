@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+/// Failures of a proving or submission attempt whose cause is known, see `ErrorCategory::classify`
+#[derive(Error, Debug)]
+pub enum ProveError {
+    #[error("Collateral missing: {0}")]
+    CollateralMissing(String),
+    #[error("Invalid quote: {0}")]
+    QuoteParse(String),
+    #[error("Prover network error: {0}")]
+    ProverNetwork(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("Proof transaction reverted: {0}")]
+    OnchainRevert(String),
+}
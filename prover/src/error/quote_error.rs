@@ -1,5 +1,11 @@
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
+use crate::{
+    entity::{attempt::ErrorCategory, quote::TdxQuoteStatus},
+    error::db_error::DbError,
+};
+
 #[derive(Error, Debug)]
 pub enum QuoteError {
     #[error("Quote not found")]
@@ -18,4 +24,18 @@ pub enum QuoteError {
     Prove,
     #[error("Failed to verify proof")]
     VerifyProof,
+    #[error("Quote cannot move from {0} to {1}")]
+    InvalidTransition(TdxQuoteStatus, TdxQuoteStatus),
+    #[error("Quote status was changed concurrently")]
+    StaleStatus,
+    #[error("Failed to update quote status")]
+    UpdateStatus,
+    #[error("{0} error: {1}")]
+    Attempt(ErrorCategory, String),
+    #[error("Last attempt failed with a {0} error, which is not retried")]
+    RetryNotAllowed(ErrorCategory),
+    #[error("{0} error, retrying at {1}")]
+    RetryScheduled(ErrorCategory, DateTime<Utc>),
+    #[error(transparent)]
+    Db(#[from] DbError),
 }
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::{entity::quote::TdxQuoteStatus, error::db_error::DbError};
+
 #[derive(Error, Debug)]
 pub enum RequestError {
     #[error("Request not found")]
@@ -8,4 +10,21 @@ pub enum RequestError {
     Invalid,
     #[error("Request unauthorized")]
     Unauthorized,
+    #[error("Request deadline has passed")]
+    Expired,
+    #[error("Request has been cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
+
+impl RequestError {
+    /// Maps an inactive request error to the terminal quote status it implies
+    pub fn quote_status(&self) -> Option<TdxQuoteStatus> {
+        match self {
+            RequestError::Expired => Some(TdxQuoteStatus::Expired),
+            RequestError::Cancelled => Some(TdxQuoteStatus::Cancelled),
+            _ => None,
+        }
+    }
 }
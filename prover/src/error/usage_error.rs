@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+use crate::entity::usage::Allowance;
+use crate::error::db_error::DbError;
+
+#[derive(Error, Debug)]
+pub enum UsageError {
+    #[error("Rate limit of {} requests per minute exceeded", .0.limit)]
+    RateLimited(Allowance),
+    #[error("Proving quota of {} proofs exceeded", .0.limit)]
+    QuotaExceeded(Allowance),
+    #[error("Proving quota not found")]
+    NotFound,
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+use crate::error::db_error::DbError;
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("Webhook not found")]
+    NotFound,
+    #[error("Webhook delivery not found")]
+    DeliveryNotFound,
+    /// Without an API key a webhook can only be registered for a single request
+    #[error("A webhook needs an API key or a request id")]
+    MissingTarget,
+    #[error("Request not found")]
+    RequestNotFound,
+    /// Webhooks only reach public https receivers, see `check_webhook_url`
+    #[error("Webhook URL is not allowed: {0}")]
+    ForbiddenUrl(String),
+    #[error(transparent)]
+    Db(#[from] DbError),
+}
@@ -0,0 +1,104 @@
+//! Live quote updates for the server-sent event streams of the API.
+//!
+//! Updates are published by the service layer once the change they report is committed. By
+//! default they only reach subscribers of the same process. With `EVENTS_NOTIFY_ENABLED` they are
+//! sent as Postgres notifications instead and `listen` relays them to the subscribers, so the API
+//! also streams the progress of quotes proven by the lambda and the CLI.
+
+use std::{sync::OnceLock, time::Duration};
+
+use sqlx::{postgres::PgListener, PgConnection};
+use tokio::sync::broadcast;
+
+use crate::{
+    config::{parameter, pool::{checkout, Pool}},
+    entity::quote_update::QuoteUpdate,
+    error::db_error::DbError,
+};
+
+/// Postgres notification channel carrying JSON encoded updates
+pub const NOTIFY_CHANNEL: &str = "quote_updates";
+
+static SENDER: OnceLock<broadcast::Sender<QuoteUpdate>> = OnceLock::new();
+
+fn sender() -> &'static broadcast::Sender<QuoteUpdate> {
+    SENDER.get_or_init(|| {
+        let capacity = parameter::get("EVENTS_CHANNEL_CAPACITY", Some("1024")).parse().unwrap_or(1024);
+        broadcast::channel(capacity).0
+    })
+}
+
+pub fn notify_enabled() -> bool {
+    parameter::get("EVENTS_NOTIFY_ENABLED", Some("false")) == "true"
+}
+
+/// Receives every update published from now on. Receivers that fall more than
+/// `EVENTS_CHANNEL_CAPACITY` updates behind skip the oldest ones.
+pub fn subscribe() -> broadcast::Receiver<QuoteUpdate> {
+    sender().subscribe()
+}
+
+/// Hands `update` to the subscribers of this process, it is dropped when there are none
+fn broadcast(update: QuoteUpdate) {
+    let _ = sender().send(update);
+}
+
+/// Publishes `update` on `conn`. Notifications sent inside a transaction are only delivered once
+/// it commits.
+pub async fn publish_on(conn: &mut PgConnection, update: QuoteUpdate) -> Result<(), DbError> {
+    if !notify_enabled() {
+        broadcast(update);
+        return Ok(());
+    }
+    let payload = serde_json::to_string(&update).map_err(|e| DbError::SomethingWentWrong(e.to_string()))?;
+    sqlx::query("SELECT pg_notify($1, $2)")
+        .bind(NOTIFY_CHANNEL)
+        .bind(payload)
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to notify quote update: {}", e);
+            DbError::SomethingWentWrong("Failed to notify quote update".to_string())
+        })?;
+    Ok(())
+}
+
+/// `publish_on` with a connection of `pool`. Live updates are best effort, failures are only logged.
+pub async fn publish(pool: &Pool, update: QuoteUpdate) {
+    if !notify_enabled() {
+        broadcast(update);
+        return;
+    }
+    let quote_id = update.quote_id.clone();
+    let published = match checkout(pool).await {
+        Ok(mut conn) => publish_on(&mut *conn, update).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = published {
+        tracing::warn!("Failed to publish update of quote {}: {}", quote_id, e);
+    }
+}
+
+/// Relays the notifications of every process to the subscribers of this one, reconnecting for as
+/// long as the process runs. Updates sent while disconnected are lost.
+pub async fn listen() {
+    loop {
+        if let Err(e) = relay().await {
+            tracing::error!("Quote update listener failed, reconnecting: {}", e);
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+async fn relay() -> anyhow::Result<()> {
+    let mut listener = PgListener::connect(&parameter::get("DATABASE_URL", None)).await?;
+    listener.listen(NOTIFY_CHANNEL).await?;
+    tracing::info!("Listening for quote updates on {}", NOTIFY_CHANNEL);
+    loop {
+        let notification = listener.recv().await?;
+        match serde_json::from_str(notification.payload()) {
+            Ok(update) => broadcast(update),
+            Err(e) => tracing::warn!("Ignoring unreadable quote update: {}", e),
+        }
+    }
+}
@@ -7,3 +7,5 @@ pub mod service;
 pub mod chain;
 pub mod zk;
 pub mod state;
+pub mod events;
+pub mod telemetry;
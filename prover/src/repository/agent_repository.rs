@@ -0,0 +1,185 @@
+#[allow(dead_code)]
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::agent::{Agent, AgentStatus, AgentUpdate, NewAgent},
+    get_conn,
+};
+use async_trait::async_trait;
+use sqlx::{types::Uuid, PgConnection};
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct AgentRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait AgentRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    async fn create_in(&self, conn: &mut PgConnection, agent: &NewAgent) -> Result<Agent, DbError>;
+    /// Links the requests and quotes of the agent's operator that have no agent yet, returns
+    /// the number of linked requests
+    async fn link_in(&self, conn: &mut PgConnection, agent: &Agent) -> Result<u64, DbError>;
+    async fn find(&self, id: Uuid) -> Result<Option<Agent>, DbError>;
+    async fn find_all(&self, status: Option<AgentStatus>) -> Result<Vec<Agent>, DbError>;
+    async fn update(&self, id: Uuid, update: &AgentUpdate) -> Result<Option<Agent>, DbError>;
+}
+
+#[async_trait]
+impl AgentRepositoryTrait for AgentRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn create_in(&self, conn: &mut PgConnection, agent: &NewAgent) -> Result<Agent, DbError> {
+        let agent = sqlx::query_as::<_, Agent>(
+            r#"INSERT INTO agent (operator_address, name, expected_mrtd, expected_rtmrs, fmspc)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *"#,
+        )
+        .bind(&agent.operator_address)
+        .bind(&agent.name)
+        .bind(&agent.expected_mrtd)
+        .bind(&agent.expected_rtmrs)
+        .bind(&agent.fmspc)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert agent: {}", e);
+            match e.as_database_error().and_then(|e| e.code()).as_deref() {
+                Some("23505") => DbError::UniqueConstraintViolation("Agent already exists for this operator".to_string()),
+                _ => DbError::SomethingWentWrong("Failed to insert agent".to_string()),
+            }
+        })?;
+        Ok(agent)
+    }
+
+    async fn link_in(&self, conn: &mut PgConnection, agent: &Agent) -> Result<u64, DbError> {
+        let linked = sqlx::query(
+            r#"UPDATE onchain_request SET agent_id = $1
+            WHERE LOWER(operator_address) = LOWER($2) AND agent_id IS NULL"#,
+        )
+        .bind(agent.id)
+        .bind(&agent.operator_address)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to link requests to agent: {}", e);
+            DbError::SomethingWentWrong("Failed to link requests to agent".to_string())
+        })?;
+        sqlx::query(
+            r#"UPDATE tdx_quote quote SET agent_id = request.agent_id
+            FROM onchain_request request
+            WHERE request.id = quote.onchain_request_id AND request.agent_id = $1 AND quote.agent_id IS NULL"#,
+        )
+        .bind(agent.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to link quotes to agent: {}", e);
+            DbError::SomethingWentWrong("Failed to link quotes to agent".to_string())
+        })?;
+        Ok(linked.rows_affected())
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<Agent>, DbError> {
+        let agent = sqlx::query_as::<_, Agent>("SELECT * FROM agent WHERE id = $1")
+            .bind(id)
+            .fetch_optional(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to fetch agent: {}", e);
+                DbError::SomethingWentWrong("Failed to fetch agent".to_string())
+            })?;
+        Ok(agent)
+    }
+
+    async fn find_all(&self, status: Option<AgentStatus>) -> Result<Vec<Agent>, DbError> {
+        let agents = sqlx::query_as::<_, Agent>(
+            r#"SELECT * FROM agent WHERE ($1::agentstatus IS NULL OR status = $1) ORDER BY created_at"#,
+        )
+        .bind(status)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch agents: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch agents".to_string())
+        })?;
+        Ok(agents)
+    }
+
+    async fn update(&self, id: Uuid, update: &AgentUpdate) -> Result<Option<Agent>, DbError> {
+        let agent = sqlx::query_as::<_, Agent>(
+            r#"UPDATE agent
+            SET name = COALESCE($2, name),
+                expected_mrtd = COALESCE($3, expected_mrtd),
+                expected_rtmrs = COALESCE($4, expected_rtmrs),
+                fmspc = COALESCE($5, fmspc),
+                status = COALESCE($6, status)
+            WHERE id = $1
+            RETURNING *"#,
+        )
+        .bind(id)
+        .bind(&update.name)
+        .bind(&update.expected_mrtd)
+        .bind(&update.expected_rtmrs)
+        .bind(&update.fmspc)
+        .bind(update.status)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to update agent: {}", e);
+            DbError::SomethingWentWrong("Failed to update agent".to_string())
+        })?;
+        Ok(agent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AgentRepository, AgentRepositoryTrait};
+    use crate::{
+        entity::agent::NewAgent,
+        repository::{
+            quote_repository::{QuoteRepository, QuoteRepositoryTrait},
+            request_repository::{OnchainRequestRepository, OnchainRequestRepositoryTrait},
+            testing::{create_quote, create_request, insert_quote, random_bytes, test_database},
+            unit_of_work::UnitOfWork,
+        },
+    };
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn link_claims_the_operator_requests_and_quotes() {
+        let db = test_database().await;
+        let repo = AgentRepository::new(&db);
+        // Operators are unique across agents, a fresh one keeps runs on the same database apart
+        let operator = format!("0x{}", hex::encode(&random_bytes()[..20]));
+        let request = create_request(&db, "agent-link", &operator).await;
+        let quote = insert_quote(&db, &request, &random_bytes()).await.unwrap();
+        let (_, other_quote) = create_quote(&db, "agent-link").await;
+
+        let mut uow = UnitOfWork::begin(&db).await.unwrap();
+        let agent = repo.create_in(uow.conn(), &NewAgent {
+            operator_address: operator.to_uppercase().replacen("0X", "0x", 1),
+            name: "agent".to_string(),
+            expected_mrtd: None,
+            expected_rtmrs: None,
+            fmspc: None,
+        }).await.unwrap();
+        assert_eq!(repo.link_in(uow.conn(), &agent).await.unwrap(), 1);
+        uow.commit().await.unwrap();
+
+        let (request_repo, quote_repo) = (OnchainRequestRepository::new(&db), QuoteRepository::new(&db));
+        assert_eq!(request_repo.find(request.id).await.unwrap().agent_id, Some(agent.id));
+        assert_eq!(quote_repo.find(quote.id).await.unwrap().agent_id, Some(agent.id));
+        assert_eq!(quote_repo.find(other_quote.id).await.unwrap().agent_id, None);
+
+        // Quotes registered later take the agent of their request
+        let later = insert_quote(&db, &request, &random_bytes()).await.unwrap();
+        assert_eq!(later.agent_id, Some(agent.id));
+    }
+}
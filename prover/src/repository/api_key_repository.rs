@@ -0,0 +1,109 @@
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::api_key::{ApiKey, ApiScope},
+    get_conn,
+};
+use async_trait::async_trait;
+use sqlx::types::Uuid;
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait ApiKeyRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    async fn create(&self, name: &str, prefix: &str, key_hash: &[u8], scopes: &[ApiScope]) -> Result<ApiKey, DbError>;
+    /// Looks up a key that was not revoked by the hash of the presented key
+    async fn find_active_by_hash(&self, key_hash: &[u8]) -> Result<Option<ApiKey>, DbError>;
+    async fn find_all(&self) -> Result<Vec<ApiKey>, DbError>;
+    async fn touch(&self, id: Uuid) -> Result<(), DbError>;
+    async fn revoke(&self, id: Uuid) -> Result<Option<ApiKey>, DbError>;
+}
+
+#[async_trait]
+impl ApiKeyRepositoryTrait for ApiKeyRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn create(&self, name: &str, prefix: &str, key_hash: &[u8], scopes: &[ApiScope]) -> Result<ApiKey, DbError> {
+        let api_key = sqlx::query_as::<_, ApiKey>(
+            r#"INSERT INTO api_key (name, prefix, key_hash, scopes)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *"#,
+        )
+        .bind(name)
+        .bind(prefix)
+        .bind(key_hash)
+        .bind(scopes)
+        .fetch_one(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert api key: {}", e);
+            match e.as_database_error().and_then(|e| e.code()).as_deref() {
+                Some("23505") => DbError::UniqueConstraintViolation("Api key already exists".to_string()),
+                _ => DbError::SomethingWentWrong("Failed to insert api key".to_string()),
+            }
+        })?;
+        Ok(api_key)
+    }
+
+    async fn find_active_by_hash(&self, key_hash: &[u8]) -> Result<Option<ApiKey>, DbError> {
+        let api_key = sqlx::query_as::<_, ApiKey>(
+            "SELECT * FROM api_key WHERE key_hash = $1 AND revoked_at IS NULL",
+        )
+        .bind(key_hash)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch api key: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch api key".to_string())
+        })?;
+        Ok(api_key)
+    }
+
+    async fn find_all(&self) -> Result<Vec<ApiKey>, DbError> {
+        let api_keys = sqlx::query_as::<_, ApiKey>("SELECT * FROM api_key ORDER BY created_at")
+            .fetch_all(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to fetch api keys: {}", e);
+                DbError::SomethingWentWrong("Failed to fetch api keys".to_string())
+            })?;
+        Ok(api_keys)
+    }
+
+    async fn touch(&self, id: Uuid) -> Result<(), DbError> {
+        sqlx::query("UPDATE api_key SET last_used_at = now() WHERE id = $1")
+            .bind(id)
+            .execute(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to update api key usage: {}", e);
+                DbError::SomethingWentWrong("Failed to update api key usage".to_string())
+            })?;
+        Ok(())
+    }
+
+    async fn revoke(&self, id: Uuid) -> Result<Option<ApiKey>, DbError> {
+        let api_key = sqlx::query_as::<_, ApiKey>(
+            r#"UPDATE api_key SET revoked_at = COALESCE(revoked_at, now())
+            WHERE id = $1
+            RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to revoke api key: {}", e);
+            DbError::SomethingWentWrong("Failed to revoke api key".to_string())
+        })?;
+        Ok(api_key)
+    }
+}
@@ -0,0 +1,204 @@
+#[allow(dead_code)]
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::attempt::{AttemptStage, DueRetry, ErrorCategory, ProofAttempt},
+    get_conn,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Uuid, PgConnection};
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ProofAttemptRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait ProofAttemptRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    async fn start(&self, tdx_quote_id: Uuid, stage: AttemptStage, backend: &str) -> Result<ProofAttempt, DbError>;
+    async fn finish(
+        &self,
+        id: Uuid,
+        error_category: Option<ErrorCategory>,
+        error_chain: Option<String>,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<ProofAttempt, DbError>;
+    async fn finish_in(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        error_category: Option<ErrorCategory>,
+        error_chain: Option<String>,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<ProofAttempt, DbError>;
+    async fn find_all_by_quote_id(&self, tdx_quote_id: Uuid) -> Result<Vec<ProofAttempt>, DbError>;
+    async fn find_latest(&self, tdx_quote_id: Uuid) -> Result<Option<ProofAttempt>, DbError>;
+    /// Pending quotes whose last proving attempt is due for a retry, leased for `lease_secs` so
+    /// other schedulers skip them until the new attempt has started
+    async fn claim_due(&self, limit: i64, lease_secs: i64) -> Result<Vec<DueRetry>, DbError>;
+}
+
+#[async_trait]
+impl ProofAttemptRepositoryTrait for ProofAttemptRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn start(&self, tdx_quote_id: Uuid, stage: AttemptStage, backend: &str) -> Result<ProofAttempt, DbError> {
+        let attempt = sqlx::query_as::<_, ProofAttempt>(
+            r#"INSERT INTO tdx_proof_attempt (tdx_quote_id, stage, attempt_number, backend)
+            SELECT $1, $2, COALESCE(MAX(attempt_number), 0) + 1, $3
+            FROM tdx_proof_attempt WHERE tdx_quote_id = $1 AND stage = $2
+            RETURNING *"#,
+        )
+        .bind(tdx_quote_id)
+        .bind(stage)
+        .bind(backend)
+        .fetch_one(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert proof attempt: {}", e);
+            DbError::SomethingWentWrong("Failed to insert proof attempt".to_string())
+        })?;
+        Ok(attempt)
+    }
+
+    async fn finish(
+        &self,
+        id: Uuid,
+        error_category: Option<ErrorCategory>,
+        error_chain: Option<String>,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<ProofAttempt, DbError> {
+        self.finish_in(get_conn!(self.db_conn.get_pool()), id, error_category, error_chain, next_attempt_at).await
+    }
+
+    async fn finish_in(
+        &self,
+        conn: &mut PgConnection,
+        id: Uuid,
+        error_category: Option<ErrorCategory>,
+        error_chain: Option<String>,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<ProofAttempt, DbError> {
+        let attempt = sqlx::query_as::<_, ProofAttempt>(
+            r#"UPDATE tdx_proof_attempt
+            SET finished_at = now(), error_category = $2, error_chain = $3, next_attempt_at = $4
+            WHERE id = $1
+            RETURNING *"#,
+        )
+        .bind(id)
+        .bind(error_category)
+        .bind(error_chain)
+        .bind(next_attempt_at)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to update proof attempt: {}", e);
+            DbError::SomethingWentWrong("Failed to update proof attempt".to_string())
+        })?;
+        Ok(attempt)
+    }
+
+    async fn find_all_by_quote_id(&self, tdx_quote_id: Uuid) -> Result<Vec<ProofAttempt>, DbError> {
+        let attempts = sqlx::query_as::<_, ProofAttempt>(
+            r#"SELECT * FROM tdx_proof_attempt WHERE tdx_quote_id = $1 ORDER BY started_at"#,
+        )
+        .bind(tdx_quote_id)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch proof attempts: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch proof attempts".to_string())
+        })?;
+        Ok(attempts)
+    }
+
+    async fn find_latest(&self, tdx_quote_id: Uuid) -> Result<Option<ProofAttempt>, DbError> {
+        let attempt = sqlx::query_as::<_, ProofAttempt>(
+            r#"SELECT * FROM tdx_proof_attempt WHERE tdx_quote_id = $1 ORDER BY started_at DESC LIMIT 1"#,
+        )
+        .bind(tdx_quote_id)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch proof attempt: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch proof attempt".to_string())
+        })?;
+        Ok(attempt)
+    }
+
+    async fn claim_due(&self, limit: i64, lease_secs: i64) -> Result<Vec<DueRetry>, DbError> {
+        let retries = sqlx::query_as::<_, DueRetry>(
+            r#"WITH due AS (
+                SELECT attempt.id
+                FROM tdx_proof_attempt attempt
+                JOIN tdx_quote quote ON quote.id = attempt.tdx_quote_id
+                WHERE attempt.next_attempt_at <= now()
+                AND attempt.stage = 'prove'
+                AND quote.status = 'pending'
+                AND NOT EXISTS (
+                    SELECT 1 FROM tdx_proof_attempt later
+                    WHERE later.tdx_quote_id = attempt.tdx_quote_id AND later.started_at > attempt.started_at
+                )
+                ORDER BY attempt.next_attempt_at
+                LIMIT $1
+                FOR UPDATE OF attempt SKIP LOCKED
+            )
+            UPDATE tdx_proof_attempt attempt
+            SET next_attempt_at = now() + make_interval(secs => $2)
+            FROM due, tdx_quote quote, onchain_request request
+            WHERE attempt.id = due.id AND quote.id = attempt.tdx_quote_id AND request.id = quote.onchain_request_id
+            RETURNING attempt.tdx_quote_id, request.request_id, attempt.backend"#,
+        )
+        .bind(limit)
+        .bind(lease_secs as f64)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to claim due proof retries: {}", e);
+            DbError::SomethingWentWrong("Failed to claim due proof retries".to_string())
+        })?;
+        Ok(retries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::{ProofAttemptRepository, ProofAttemptRepositoryTrait};
+    use crate::{
+        entity::attempt::{AttemptStage, ErrorCategory},
+        repository::testing::{create_quote, test_database},
+    };
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn due_retries_are_claimed_once() {
+        let db = test_database().await;
+        let repo = ProofAttemptRepository::new(&db);
+        let (request, quote) = create_quote(&db, "attempt-retry").await;
+
+        let attempt = repo.start(quote.id, AttemptStage::Prove, "sp1/groth16").await.unwrap();
+        let later = Utc::now() + Duration::hours(1);
+        repo.finish(attempt.id, Some(ErrorCategory::ProverNetwork), None, Some(later)).await.unwrap();
+        let claimed = repo.claim_due(1000, 600).await.unwrap();
+        assert!(claimed.iter().all(|retry| retry.tdx_quote_id != quote.id));
+
+        let earlier = Utc::now() - Duration::seconds(1);
+        repo.finish(attempt.id, Some(ErrorCategory::ProverNetwork), None, Some(earlier)).await.unwrap();
+        let claimed = repo.claim_due(1000, 600).await.unwrap();
+        let retry = claimed.into_iter().find(|retry| retry.tdx_quote_id == quote.id).unwrap();
+        assert_eq!((retry.request_id, retry.backend.as_str()), (request.request_id, "sp1/groth16"));
+
+        // The lease hides it from other schedulers
+        let claimed = repo.claim_due(1000, 600).await.unwrap();
+        assert!(claimed.iter().all(|retry| retry.tdx_quote_id != quote.id));
+    }
+}
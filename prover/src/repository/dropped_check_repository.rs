@@ -0,0 +1,96 @@
+#[allow(dead_code)]
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{entity::dropped_check::DroppedCheck, get_conn};
+use async_trait::async_trait;
+use sqlx::{types::Uuid, PgConnection};
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct DroppedCheckRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait DroppedCheckRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    /// Counts another check that found `txn_hash` dropped, a different hash starts a new streak
+    async fn record(&self, tdx_quote_id: Uuid, txn_hash: &[u8]) -> Result<DroppedCheck, DbError>;
+    /// Ends the streak of the quote, once its transaction is seen again or the quote is retried
+    async fn clear(&self, tdx_quote_id: Uuid) -> Result<(), DbError>;
+    async fn clear_in(&self, conn: &mut PgConnection, tdx_quote_id: Uuid) -> Result<(), DbError>;
+}
+
+#[async_trait]
+impl DroppedCheckRepositoryTrait for DroppedCheckRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn record(&self, tdx_quote_id: Uuid, txn_hash: &[u8]) -> Result<DroppedCheck, DbError> {
+        let check = sqlx::query_as::<_, DroppedCheck>(
+            r#"INSERT INTO tdx_dropped_check AS streak (tdx_quote_id, txn_hash)
+            VALUES ($1, $2)
+            ON CONFLICT (tdx_quote_id) DO UPDATE SET
+                checks = CASE WHEN streak.txn_hash = EXCLUDED.txn_hash THEN streak.checks + 1 ELSE 1 END,
+                first_checked_at = CASE WHEN streak.txn_hash = EXCLUDED.txn_hash THEN streak.first_checked_at ELSE now() END,
+                last_checked_at = now(),
+                txn_hash = EXCLUDED.txn_hash
+            RETURNING *"#,
+        )
+        .bind(tdx_quote_id)
+        .bind(txn_hash)
+        .fetch_one(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to record dropped check: {}", e);
+            DbError::SomethingWentWrong("Failed to record dropped check".to_string())
+        })?;
+        Ok(check)
+    }
+
+    async fn clear(&self, tdx_quote_id: Uuid) -> Result<(), DbError> {
+        self.clear_in(get_conn!(self.db_conn.get_pool()), tdx_quote_id).await
+    }
+
+    async fn clear_in(&self, conn: &mut PgConnection, tdx_quote_id: Uuid) -> Result<(), DbError> {
+        sqlx::query(r#"DELETE FROM tdx_dropped_check WHERE tdx_quote_id = $1"#)
+            .bind(tdx_quote_id)
+            .execute(conn)
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to clear dropped checks: {}", e);
+                DbError::SomethingWentWrong("Failed to clear dropped checks".to_string())
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DroppedCheckRepository, DroppedCheckRepositoryTrait};
+    use crate::repository::testing::{create_quote, random_bytes, test_database};
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn streak_counts_checks_of_one_transaction() {
+        let db = test_database().await;
+        let repo = DroppedCheckRepository::new(&db);
+        let (_, quote) = create_quote(&db, "dropped-check").await;
+        let (first_hash, second_hash) = (random_bytes(), random_bytes());
+
+        repo.record(quote.id, &first_hash).await.unwrap();
+        let check = repo.record(quote.id, &first_hash).await.unwrap();
+        assert_eq!(check.checks, 2);
+        assert!(check.last_checked_at >= check.first_checked_at);
+
+        // A resubmitted proof is checked from scratch
+        let check = repo.record(quote.id, &second_hash).await.unwrap();
+        assert_eq!((check.checks, check.txn_hash), (1, second_hash.clone()));
+
+        repo.clear(quote.id).await.unwrap();
+        assert_eq!(repo.record(quote.id, &second_hash).await.unwrap().checks, 1);
+    }
+}
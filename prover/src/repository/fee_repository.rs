@@ -0,0 +1,217 @@
+#[allow(dead_code)]
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::{fee::{FeeGroupBy, FeeReportRow, TransactionCost, TransactionFee}, quote::ProofType},
+    get_conn,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{types::{BigDecimal, Uuid}, PgConnection};
+use crate::error::db_error::DbError;
+use std::{str::FromStr, sync::Arc};
+
+#[derive(Clone)]
+pub struct TransactionFeeRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait TransactionFeeRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    /// Records what `cost.txn_hash` cost, `None` when it was already recorded
+    async fn create(
+        &self,
+        tdx_quote_id: Uuid,
+        onchain_request_id: Uuid,
+        proof_type: ProofType,
+        cost: &TransactionCost,
+    ) -> Result<Option<TransactionFee>, DbError>;
+    async fn create_in(
+        &self,
+        conn: &mut PgConnection,
+        tdx_quote_id: Uuid,
+        onchain_request_id: Uuid,
+        proof_type: ProofType,
+        cost: &TransactionCost,
+    ) -> Result<Option<TransactionFee>, DbError>;
+    async fn find_all_by_onchain_request_id(&self, onchain_request_id: Uuid) -> Result<Vec<TransactionFee>, DbError>;
+    async fn report(
+        &self,
+        group_by: FeeGroupBy,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<FeeReportRow>, DbError>;
+}
+
+#[async_trait]
+impl TransactionFeeRepositoryTrait for TransactionFeeRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn create(
+        &self,
+        tdx_quote_id: Uuid,
+        onchain_request_id: Uuid,
+        proof_type: ProofType,
+        cost: &TransactionCost,
+    ) -> Result<Option<TransactionFee>, DbError> {
+        self.create_in(get_conn!(self.db_conn.get_pool()), tdx_quote_id, onchain_request_id, proof_type, cost).await
+    }
+
+    async fn create_in(
+        &self,
+        conn: &mut PgConnection,
+        tdx_quote_id: Uuid,
+        onchain_request_id: Uuid,
+        proof_type: ProofType,
+        cost: &TransactionCost,
+    ) -> Result<Option<TransactionFee>, DbError> {
+        let fee = sqlx::query_as::<_, TransactionFee>(
+            r#"INSERT INTO tdx_transaction_fee (
+                tdx_quote_id, onchain_request_id, proof_type, txn_hash, block_number,
+                gas_used, effective_gas_price, fee_wei, signer, success
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (txn_hash) DO NOTHING
+            RETURNING *"#,
+        )
+        .bind(tdx_quote_id)
+        .bind(onchain_request_id)
+        .bind(proof_type)
+        .bind(cost.txn_hash.to_vec())
+        .bind(cost.block_number.map(|number| number as i64))
+        .bind(cost.gas_used as i64)
+        .bind(BigDecimal::from_str(&cost.effective_gas_price.to_string()).unwrap())
+        .bind(BigDecimal::from_str(&cost.fee_wei.to_string()).unwrap())
+        .bind(cost.signer.to_checksum(None))
+        .bind(cost.success)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert transaction fee: {}", e);
+            DbError::SomethingWentWrong("Failed to insert transaction fee".to_string())
+        })?;
+        Ok(fee)
+    }
+
+    async fn find_all_by_onchain_request_id(&self, onchain_request_id: Uuid) -> Result<Vec<TransactionFee>, DbError> {
+        let fees = sqlx::query_as::<_, TransactionFee>(
+            r#"SELECT * FROM tdx_transaction_fee WHERE onchain_request_id = $1 ORDER BY created_at"#,
+        )
+        .bind(onchain_request_id)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch transaction fees: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch transaction fees".to_string())
+        })?;
+        Ok(fees)
+    }
+
+    async fn report(
+        &self,
+        group_by: FeeGroupBy,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<FeeReportRow>, DbError> {
+        // The grouping expression comes from a fixed set, never from user input
+        let group_key = match group_by {
+            FeeGroupBy::Day => "to_char(date_trunc('day', fee.created_at AT TIME ZONE 'UTC'), 'YYYY-MM-DD')",
+            FeeGroupBy::ModelId => "request.model_id",
+            FeeGroupBy::ProofType => "fee.proof_type::text",
+        };
+        let query = format!(
+            r#"SELECT
+            {group_key} AS group_key,
+            COUNT(*) AS tx_count,
+            SUM(fee.gas_used)::numeric AS gas_used,
+            SUM(fee.fee_wei) AS fee_wei
+            FROM tdx_transaction_fee fee
+            JOIN onchain_request request ON request.id = fee.onchain_request_id
+            WHERE ($1::timestamptz IS NULL OR fee.created_at >= $1)
+            AND ($2::timestamptz IS NULL OR fee.created_at < $2)
+            GROUP BY 1
+            ORDER BY 1"#,
+        );
+
+        let rows = sqlx::query_as::<_, FeeReportRow>(&query)
+            .bind(from)
+            .bind(to)
+            .fetch_all(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to aggregate transaction fees: {}", e);
+                DbError::SomethingWentWrong("Failed to aggregate transaction fees".to_string())
+            })?;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, TxHash, U256};
+    use sqlx::types::BigDecimal;
+
+    use super::{TransactionFeeRepository, TransactionFeeRepositoryTrait};
+    use crate::{
+        entity::{fee::{FeeGroupBy, TransactionCost}, quote::ProofType},
+        repository::testing::{create_quote, random_bytes, test_database},
+    };
+
+    fn cost(gas_used: u64, success: bool) -> TransactionCost {
+        TransactionCost {
+            txn_hash: TxHash::from_slice(&random_bytes()),
+            block_number: Some(1),
+            gas_used,
+            effective_gas_price: 2,
+            fee_wei: U256::from(gas_used * 2),
+            signer: Address::repeat_byte(0x11),
+            success,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn records_each_transaction_once() {
+        let db = test_database().await;
+        let fee_repo = TransactionFeeRepository::new(&db);
+        let (request, quote) = create_quote(&db, "fee-records").await;
+
+        let reverted = cost(100, false);
+        let fee = fee_repo.create(quote.id, request.id, ProofType::Sp1, &reverted).await.unwrap().unwrap();
+        assert_eq!(fee.fee_wei, BigDecimal::from(200));
+        assert!(!fee.success);
+        // The finality check records transactions the submission could not, a second record is skipped
+        assert!(fee_repo.create(quote.id, request.id, ProofType::Sp1, &reverted).await.unwrap().is_none());
+
+        fee_repo.create(quote.id, request.id, ProofType::Sp1, &cost(300, true)).await.unwrap().unwrap();
+        let fees = fee_repo.find_all_by_onchain_request_id(request.id).await.unwrap();
+        assert_eq!(fees.iter().map(|fee| fee.gas_used).collect::<Vec<_>>(), vec![100, 300]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn report_aggregates_by_model() {
+        let db = test_database().await;
+        let fee_repo = TransactionFeeRepository::new(&db);
+        let model_id = format!("fee-report-{}", hex::encode(random_bytes()));
+        let (first, first_quote) = create_quote(&db, &model_id).await;
+        let (second, second_quote) = create_quote(&db, &model_id).await;
+
+        fee_repo.create(first_quote.id, first.id, ProofType::Sp1, &cost(100, true)).await.unwrap();
+        fee_repo.create(first_quote.id, first.id, ProofType::Sp1, &cost(50, false)).await.unwrap();
+        fee_repo.create(second_quote.id, second.id, ProofType::Risc0, &cost(250, true)).await.unwrap();
+
+        let rows = fee_repo.report(FeeGroupBy::ModelId, None, None).await.unwrap();
+        let row = rows.iter().find(|row| row.group_key == model_id).unwrap();
+        assert_eq!(row.tx_count, 3);
+        assert_eq!(row.gas_used, BigDecimal::from(400));
+        assert_eq!(row.fee_wei, BigDecimal::from(800));
+
+        let future = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+        let rows = fee_repo.report(FeeGroupBy::ModelId, future, None).await.unwrap();
+        assert!(rows.iter().all(|row| row.group_key != model_id));
+    }
+}
@@ -0,0 +1,543 @@
+//! In-memory quote and request repositories for unit tests of handlers and of code that reads
+//! quotes and requests or changes them one statement at a time.
+//!
+//! They cannot take part in a `UnitOfWork`, so their `*_in` variants fail instead of applying a
+//! change a rollback would not undo. The services that group writes in a unit of work
+//! (`LifecycleService`, `RequestService`, `FinalityService`) and the lambda and CLI proving flows
+//! built on them therefore still need Postgres, and a deployment without a database is not
+//! supported.
+
+use crate::config::database::Database;
+use crate::{
+    entity::{
+        agent::Agent,
+        quote::{quote_hash, QuoteCursor, QuoteFilter, QuotePage, SortOrder, TdxQuote, TdxQuoteStatus},
+        quote_event::QuoteTransition,
+        request::{NewOnchainRequest, OnchainRequest, OnchainRequestUpdate, RequestFilter},
+    },
+    repository::{
+        quote_repository::QuoteRepositoryTrait,
+        request_repository::{OnchainRequestId, OnchainRequestRepositoryTrait},
+    },
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{types::Uuid, PgConnection};
+use crate::error::db_error::DbError;
+use std::sync::{Arc, RwLock};
+
+/// Quotes, requests and agents kept in process memory, shared by the in-memory repositories
+/// built from the same store. Nothing survives a restart.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    quotes: Arc<RwLock<Vec<TdxQuote>>>,
+    requests: Arc<RwLock<Vec<OnchainRequest>>>,
+    agents: Arc<RwLock<Vec<Agent>>>,
+}
+
+impl MemoryStore {
+    pub fn insert_quote(&self, quote: TdxQuote) {
+        self.quotes.write().unwrap().push(quote);
+    }
+
+    pub fn insert_request(&self, request: OnchainRequest) {
+        self.requests.write().unwrap().push(request);
+    }
+
+    pub fn insert_agent(&self, agent: Agent) {
+        self.agents.write().unwrap().push(agent);
+    }
+
+    /// Agent registered for `operator_address`, like the subquery of the Postgres repository
+    fn agent_id(&self, operator_address: &str) -> Option<Uuid> {
+        self.agents.read().unwrap().iter()
+            .find(|agent| agent.operator_address.eq_ignore_ascii_case(operator_address))
+            .map(|agent| agent.id)
+    }
+
+    /// Quotes that match `filter` together with their request, like the inner join of the
+    /// Postgres repository
+    fn select_quotes(&self, filter: &QuoteFilter) -> Vec<(TdxQuote, OnchainRequest)> {
+        let requests = self.requests.read().unwrap();
+        self.quotes.read().unwrap().iter()
+            .filter_map(|quote| {
+                let request = requests.iter().find(|request| request.id == quote.onchain_request_id)?;
+                matches(filter, quote, request).then(|| (quote.clone(), request.clone()))
+            })
+            .collect()
+    }
+}
+
+fn matches(filter: &QuoteFilter, quote: &TdxQuote, request: &OnchainRequest) -> bool {
+    filter.status.is_none_or(|status| quote.status == status)
+        && filter.proof_type.is_none_or(|proof_type| quote.proof_type == Some(proof_type))
+        && filter.onchain_request_id.is_none_or(|id| quote.onchain_request_id == id)
+        && filter.model_id.as_ref().is_none_or(|model_id| &request.model_id == model_id)
+        && filter.creator_address.as_ref()
+            .is_none_or(|address| request.creator_address.eq_ignore_ascii_case(address))
+        && filter.operator_address.as_ref()
+            .is_none_or(|address| request.operator_address.eq_ignore_ascii_case(address))
+        && filter.agent_id.is_none_or(|agent_id| quote.agent_id == Some(agent_id))
+        && filter.created_from.is_none_or(|from| quote.created_at >= from)
+        && filter.created_to.is_none_or(|to| quote.created_at < to)
+}
+
+fn not_found(what: &str) -> DbError {
+    DbError::SomethingWentWrong(format!("Failed to fetch {}", what))
+}
+
+fn no_transactions() -> DbError {
+    DbError::SomethingWentWrong("In-memory repositories cannot take part in a database transaction".to_string())
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryQuoteRepository {
+    store: MemoryStore,
+}
+
+impl InMemoryQuoteRepository {
+    pub fn with_store(store: &MemoryStore) -> Self {
+        Self { store: store.clone() }
+    }
+}
+
+#[async_trait]
+impl QuoteRepositoryTrait for InMemoryQuoteRepository {
+    /// Starts from an empty store, the database is not used
+    fn new(_db_conn: &Arc<Database>) -> Self {
+        Self::default()
+    }
+
+    async fn find(&self, id: Uuid) -> Result<TdxQuote, DbError> {
+        self.store.quotes.read().unwrap().iter()
+            .find(|quote| quote.id == id)
+            .cloned()
+            .ok_or_else(|| not_found("quote"))
+    }
+
+    async fn find_optional(&self, id: Uuid) -> Result<Option<TdxQuote>, DbError> {
+        Ok(self.store.quotes.read().unwrap().iter().find(|quote| quote.id == id).cloned())
+    }
+
+    async fn find_all_by_onchain_request_id(&self,
+        onchain_request_id: Uuid,
+        verification_status: Option<TdxQuoteStatus>
+    ) -> Vec<TdxQuote> {
+        let filter = QuoteFilter {
+            onchain_request_id: Some(onchain_request_id),
+            status: verification_status,
+            ..Default::default()
+        };
+        let mut quotes: Vec<TdxQuote> = self.store.select_quotes(&filter).into_iter().map(|(quote, _)| quote).collect();
+        quotes.sort_by_key(|quote| quote.created_at);
+        quotes
+    }
+
+    async fn find_by_onchain_request_id(&self, onchain_request_id: Uuid) -> Result<TdxQuote, DbError> {
+        self.store.quotes.read().unwrap().iter()
+            .find(|quote| quote.onchain_request_id == onchain_request_id)
+            .cloned()
+            .ok_or_else(|| not_found("quote"))
+    }
+
+    async fn create(&self, onchain_request_id: Uuid, status: TdxQuoteStatus, quote: &[u8]) -> Result<TdxQuote, DbError> {
+        let agent_id = self.store.requests.read().unwrap().iter()
+            .find(|request| request.id == onchain_request_id)
+            .ok_or_else(|| DbError::SomethingWentWrong("Failed to insert quote".to_string()))?
+            .agent_id;
+        let mut quotes = self.store.quotes.write().unwrap();
+        let quote_hash = quote_hash(quote);
+        let duplicate_of = quotes.iter()
+            .filter(|existing| existing.quote_hash == quote_hash && existing.duplicate_of.is_none())
+            .min_by_key(|existing| (existing.created_at, existing.id))
+            .map(|original| original.id);
+        let now = Utc::now();
+        let quote = TdxQuote {
+            id: Uuid::new_v4(),
+            onchain_request_id,
+            status,
+            quote: quote.to_vec(),
+            created_at: now,
+            updated_at: now,
+            proof_type: None,
+            txn_hash: None,
+            request_id: None,
+            quote_hash,
+            duplicate_of,
+            agent_id,
+        };
+        quotes.push(quote.clone());
+        Ok(quote)
+    }
+
+    /// Fails, the change would outlive a rollback of the transaction
+    async fn create_in(
+        &self,
+        _conn: &mut PgConnection,
+        _onchain_request_id: Uuid,
+        _status: TdxQuoteStatus,
+        _quote: &[u8],
+    ) -> Result<TdxQuote, DbError> {
+        Err(no_transactions())
+    }
+
+    async fn transition(&self, id: Uuid, transition: &QuoteTransition) -> Result<Option<TdxQuote>, DbError> {
+        let mut quotes = self.store.quotes.write().unwrap();
+        let Some(quote) = quotes.iter_mut().find(|quote| quote.id == id && quote.status == transition.from) else {
+            return Ok(None);
+        };
+        quote.status = transition.to;
+        quote.proof_type = transition.proof_type.or(quote.proof_type);
+        if let Some(txn_hash) = &transition.txn_hash {
+            quote.txn_hash = Some(txn_hash.clone());
+        }
+        quote.updated_at = Utc::now();
+        Ok(Some(quote.clone()))
+    }
+
+    /// Fails, the change would outlive a rollback of the transaction
+    async fn transition_in(
+        &self,
+        _conn: &mut PgConnection,
+        _id: Uuid,
+        _transition: &QuoteTransition,
+    ) -> Result<Option<TdxQuote>, DbError> {
+        Err(no_transactions())
+    }
+
+    async fn find_request_ids(&self, filter: &QuoteFilter, max_count: Option<i64>) -> Vec<OnchainRequestId> {
+        let mut rows = self.store.select_quotes(filter);
+        rows.sort_by(|(a, _), (b, _)| b.created_at.cmp(&a.created_at));
+        rows.into_iter()
+            .take(max_count.map_or(usize::MAX, |count| count as usize))
+            .map(|(_, request)| OnchainRequestId::new(request.request_id))
+            .collect()
+    }
+
+    async fn find_page(&self, filter: &QuoteFilter, page: &QuotePage) -> Result<(Vec<TdxQuote>, Option<QuoteCursor>), DbError> {
+        let key = |quote: &TdxQuote| {
+            let cursor = QuoteCursor::from_quote(quote, page.sort_by, page.order);
+            (cursor.timestamp, cursor.id)
+        };
+        let mut quotes: Vec<TdxQuote> = self.store.select_quotes(filter).into_iter().map(|(quote, _)| quote).collect();
+        quotes.sort_by_key(key);
+        if page.order == SortOrder::Desc {
+            quotes.reverse();
+        }
+        if let Some(cursor) = page.after {
+            let after = (cursor.timestamp, cursor.id);
+            quotes.retain(|quote| match page.order {
+                SortOrder::Asc => key(quote) > after,
+                SortOrder::Desc => key(quote) < after,
+            });
+        }
+
+        let next_cursor = match quotes.len() as i64 > page.limit {
+            true => {
+                quotes.truncate(page.limit as usize);
+                quotes.last().map(|quote| QuoteCursor::from_quote(quote, page.sort_by, page.order))
+            }
+            false => None,
+        };
+        Ok((quotes, next_cursor))
+    }
+
+    async fn find_all_by_status(&self, status: TdxQuoteStatus, max_count: i64) -> Result<Vec<TdxQuote>, DbError> {
+        let mut quotes: Vec<TdxQuote> = self.store.quotes.read().unwrap().iter()
+            .filter(|quote| quote.status == status)
+            .cloned()
+            .collect();
+        quotes.sort_by_key(|quote| quote.updated_at);
+        quotes.truncate(max_count as usize);
+        Ok(quotes)
+    }
+
+    async fn count_by_status(&self) -> Result<Vec<(TdxQuoteStatus, i64)>, DbError> {
+        let mut counts: Vec<(TdxQuoteStatus, i64)> = vec![];
+        for quote in self.store.quotes.read().unwrap().iter() {
+            match counts.iter_mut().find(|(status, _)| *status == quote.status) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((quote.status, 1)),
+            }
+        }
+        Ok(counts)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryOnchainRequestRepository {
+    store: MemoryStore,
+}
+
+impl InMemoryOnchainRequestRepository {
+    pub fn with_store(store: &MemoryStore) -> Self {
+        Self { store: store.clone() }
+    }
+}
+
+#[async_trait]
+impl OnchainRequestRepositoryTrait for InMemoryOnchainRequestRepository {
+    /// Starts from an empty store, the database is not used
+    fn new(_db_conn: &Arc<Database>) -> Self {
+        Self::default()
+    }
+
+    async fn find_all_by_model_id(&self, model_id: String) -> Vec<OnchainRequest> {
+        self.store.requests.read().unwrap().iter()
+            .filter(|request| request.model_id == model_id)
+            .cloned()
+            .collect()
+    }
+
+    async fn find(&self, id: Uuid) -> Result<OnchainRequest, DbError> {
+        self.store.requests.read().unwrap().iter()
+            .find(|request| request.id == id)
+            .cloned()
+            .ok_or_else(|| not_found("onchain request"))
+    }
+
+    async fn find_by_request_id(&self, request_id: Vec<u8>) -> Result<OnchainRequest, DbError> {
+        self.store.requests.read().unwrap().iter()
+            .find(|request| request.request_id == request_id)
+            .cloned()
+            .ok_or_else(|| not_found("onchain request"))
+    }
+
+    async fn find_request_ids_by_status(&self, status: Option<TdxQuoteStatus>, max_count: Option<i64>) -> Vec<OnchainRequestId> {
+        let filter = QuoteFilter { status, ..Default::default() };
+        let mut rows = self.store.select_quotes(&filter);
+        rows.sort_by(|(a, _), (b, _)| b.created_at.cmp(&a.created_at));
+        rows.into_iter()
+            .take(max_count.map_or(usize::MAX, |count| count as usize))
+            .map(|(_, request)| OnchainRequestId::new(request.request_id))
+            .collect()
+    }
+
+    async fn find_all(&self, filter: &RequestFilter, limit: i64) -> Result<Vec<OnchainRequest>, DbError> {
+        let mut requests: Vec<OnchainRequest> = self.store.requests.read().unwrap().iter()
+            .filter(|request| {
+                filter.model_id.as_ref().is_none_or(|model_id| &request.model_id == model_id)
+                    && filter.creator_address.as_ref()
+                        .is_none_or(|address| request.creator_address.eq_ignore_ascii_case(address))
+                    && filter.operator_address.as_ref()
+                        .is_none_or(|address| request.operator_address.eq_ignore_ascii_case(address))
+                    && filter.agent_id.is_none_or(|agent_id| request.agent_id == Some(agent_id))
+                    && (filter.include_cancelled || !request.is_cancelled)
+            })
+            .cloned()
+            .collect();
+        requests.sort_by(|a, b| (b.created_at, b.id).cmp(&(a.created_at, a.id)));
+        requests.truncate(limit as usize);
+        Ok(requests)
+    }
+
+    async fn create(&self, request: &NewOnchainRequest) -> Result<OnchainRequest, DbError> {
+        let agent_id = self.store.agent_id(&request.operator_address);
+        let mut requests = self.store.requests.write().unwrap();
+        if requests.iter().any(|existing| existing.request_id == request.request_id) {
+            return Err(DbError::UniqueConstraintViolation("Onchain request already exists".to_string()));
+        }
+        let now = Utc::now();
+        let onchain_request = OnchainRequest {
+            id: Uuid::new_v4(),
+            creator_address: request.creator_address.clone(),
+            operator_address: request.operator_address.clone(),
+            model_id: request.model_id.clone(),
+            fee_wei: request.fee_wei,
+            nonce: request.nonce,
+            request_id: request.request_id.clone(),
+            deadline: request.deadline,
+            is_cancelled: false,
+            cancelled_at: None,
+            created_at: now,
+            updated_at: now,
+            agent_id,
+            api_key_id: request.api_key_id,
+        };
+        requests.push(onchain_request.clone());
+        Ok(onchain_request)
+    }
+
+    /// Fails, the change would outlive a rollback of the transaction
+    async fn create_in(&self, _conn: &mut PgConnection, _request: &NewOnchainRequest) -> Result<OnchainRequest, DbError> {
+        Err(no_transactions())
+    }
+
+    async fn update(&self, id: Uuid, update: &OnchainRequestUpdate) -> Result<Option<OnchainRequest>, DbError> {
+        let agent_id = update.operator_address.as_deref().map(|address| self.store.agent_id(address));
+        let mut requests = self.store.requests.write().unwrap();
+        let Some(request) = requests.iter_mut().find(|request| request.id == id && !request.is_cancelled) else {
+            return Ok(None);
+        };
+        if let Some(operator_address) = &update.operator_address {
+            request.operator_address = operator_address.clone();
+        }
+        request.agent_id = agent_id.unwrap_or(request.agent_id);
+        request.fee_wei = update.fee_wei.unwrap_or(request.fee_wei);
+        request.deadline = update.deadline.unwrap_or(request.deadline);
+        request.updated_at = Utc::now();
+        Ok(Some(request.clone()))
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<Option<OnchainRequest>, DbError> {
+        let mut requests = self.store.requests.write().unwrap();
+        let Some(request) = requests.iter_mut().find(|request| request.id == id && !request.is_cancelled) else {
+            return Ok(None);
+        };
+        let now = Utc::now();
+        request.is_cancelled = true;
+        request.cancelled_at = Some(now);
+        request.updated_at = now;
+        Ok(Some(request.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use sqlx::types::Uuid;
+
+    use super::{InMemoryOnchainRequestRepository, InMemoryQuoteRepository, MemoryStore};
+    use crate::{
+        entity::{
+            agent::{Agent, AgentStatus},
+            quote::{quote_hash, QuoteFilter, QuotePage, TdxQuote, TdxQuoteStatus},
+            quote_event::{QuoteActor, QuoteTransition},
+            request::{NewOnchainRequest, OnchainRequest, OnchainRequestUpdate},
+        },
+        repository::{quote_repository::QuoteRepositoryTrait, request_repository::OnchainRequestRepositoryTrait},
+    };
+
+    fn request(n: u128, model_id: &str) -> OnchainRequest {
+        OnchainRequest {
+            id: Uuid::from_u128(n),
+            model_id: model_id.to_string(),
+            request_id: n.to_be_bytes().to_vec(),
+            ..OnchainRequest::fixture()
+        }
+    }
+
+    fn quote(request: &OnchainRequest, minutes_ago: i64) -> TdxQuote {
+        let created_at = Utc::now() - Duration::minutes(minutes_ago);
+        TdxQuote {
+            id: Uuid::from_u128(request.id.as_u128() << 8 | minutes_ago as u128),
+            onchain_request_id: request.id,
+            status: TdxQuoteStatus::Pending,
+            quote: vec![],
+            created_at,
+            updated_at: created_at,
+            proof_type: None,
+            txn_hash: None,
+            request_id: None,
+            quote_hash: quote_hash(&[]),
+            duplicate_of: None,
+            agent_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn filters_and_pages_quotes() {
+        let store = MemoryStore::default();
+        let (a, b) = (request(1, "model-a"), request(2, "model-b"));
+        for minutes_ago in 0..3 {
+            store.insert_quote(quote(&a, minutes_ago));
+        }
+        store.insert_quote(quote(&b, 10));
+        store.insert_request(a.clone());
+        store.insert_request(b);
+
+        let quote_repo = InMemoryQuoteRepository::with_store(&store);
+        let filter = QuoteFilter {
+            model_id: Some("model-a".to_string()),
+            creator_address: Some(a.creator_address.to_lowercase()),
+            ..Default::default()
+        };
+        let page = QuotePage { limit: 2, ..Default::default() };
+        let (first, cursor) = quote_repo.find_page(&filter, &page).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first[0].created_at > first[1].created_at);
+
+        let page = QuotePage { after: cursor, ..page };
+        let (second, cursor) = quote_repo.find_page(&filter, &page).await.unwrap();
+        assert_eq!(second.len(), 1);
+        assert!(cursor.is_none());
+
+        let request_repo = InMemoryOnchainRequestRepository::with_store(&store);
+        assert_eq!(request_repo.find_by_request_id(a.request_id.clone()).await.unwrap().id, a.id);
+    }
+
+    #[tokio::test]
+    async fn transition_requires_current_status() {
+        let store = MemoryStore::default();
+        let request = request(1, "model");
+        let quote = quote(&request, 0);
+        store.insert_quote(quote.clone());
+        store.insert_request(request);
+        let quote_repo = InMemoryQuoteRepository::with_store(&store);
+
+        let transition = QuoteTransition {
+            from: TdxQuoteStatus::Pending,
+            to: TdxQuoteStatus::CollateralFetched,
+            proof_type: None,
+            txn_hash: None,
+            actor: QuoteActor::Cli,
+            detail: None,
+        };
+        let updated = quote_repo.transition(quote.id, &transition).await.unwrap().unwrap();
+        assert_eq!(updated.status, TdxQuoteStatus::CollateralFetched);
+        // already moved on, a second identical transition is stale
+        assert!(quote_repo.transition(quote.id, &transition).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn create_links_equal_quotes_to_the_first() {
+        let store = MemoryStore::default();
+        let agent_id = Uuid::from_u128(9);
+        let request = OnchainRequest { agent_id: Some(agent_id), ..request(1, "model") };
+        store.insert_request(request.clone());
+        let quote_repo = InMemoryQuoteRepository::with_store(&store);
+
+        let first = quote_repo.create(request.id, TdxQuoteStatus::Pending, &[1]).await.unwrap();
+        let copy = quote_repo.create(request.id, TdxQuoteStatus::Pending, &[1]).await.unwrap();
+        let other = quote_repo.create(request.id, TdxQuoteStatus::Pending, &[2]).await.unwrap();
+        assert_eq!((first.duplicate_of, copy.duplicate_of, other.duplicate_of), (None, Some(first.id), None));
+        assert_eq!(copy.agent_id, Some(agent_id));
+    }
+
+    #[tokio::test]
+    async fn requests_follow_the_agent_of_their_operator() {
+        let store = MemoryStore::default();
+        let agent = Agent {
+            id: Uuid::from_u128(9),
+            operator_address: "0xAbC".to_string(),
+            name: "agent".to_string(),
+            expected_mrtd: None,
+            expected_rtmrs: None,
+            fmspc: None,
+            status: AgentStatus::Active,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        store.insert_agent(agent.clone());
+        let request_repo = InMemoryOnchainRequestRepository::with_store(&store);
+        let fixture = OnchainRequest::fixture();
+        let new = NewOnchainRequest {
+            creator_address: fixture.creator_address,
+            operator_address: "0xabc".to_string(),
+            model_id: fixture.model_id,
+            fee_wei: fixture.fee_wei,
+            nonce: fixture.nonce,
+            request_id: fixture.request_id,
+            deadline: fixture.deadline,
+            api_key_id: None,
+        };
+
+        let request = request_repo.create(&new).await.unwrap();
+        assert_eq!(request.agent_id, Some(agent.id));
+
+        let kept = OnchainRequestUpdate { fee_wei: Some(7), ..Default::default() };
+        assert_eq!(request_repo.update(request.id, &kept).await.unwrap().unwrap().agent_id, Some(agent.id));
+        let moved = OnchainRequestUpdate { operator_address: Some("0xdef".to_string()), ..Default::default() };
+        assert_eq!(request_repo.update(request.id, &moved).await.unwrap().unwrap().agent_id, None);
+    }
+}
@@ -1,3 +1,17 @@
 #![allow(dead_code)]
+pub mod agent_repository;
+pub mod api_key_repository;
+pub mod attempt_repository;
+pub mod dropped_check_repository;
+pub mod fee_repository;
+pub mod memory_repository;
+pub mod proof_repository;
+pub mod quote_event_repository;
 pub mod quote_repository;
 pub mod request_repository;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod unit_of_work;
+pub mod usage_repository;
+pub mod verification_repository;
+pub mod webhook_repository;
@@ -0,0 +1,154 @@
+#[allow(dead_code)]
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::{
+        proof::TdxProof,
+        quote::{ProofType, TdxQuote},
+        zk::{ProofResponse, ProofSystem},
+    },
+    get_conn,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ProofRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait ProofRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    async fn create_in(
+        &self,
+        conn: &mut PgConnection,
+        quote: &TdxQuote,
+        input_hash: &[u8],
+        proof_system: Option<ProofSystem>,
+        response: &ProofResponse,
+    ) -> Result<TdxProof, DbError>;
+    /// Latest proof of the same input and backend created at or after `since`
+    async fn find_fresh(
+        &self,
+        input_hash: &[u8],
+        proof_type: ProofType,
+        proof_system: Option<ProofSystem>,
+        since: DateTime<Utc>,
+    ) -> Result<Option<TdxProof>, DbError>;
+}
+
+#[async_trait]
+impl ProofRepositoryTrait for ProofRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn create_in(
+        &self,
+        conn: &mut PgConnection,
+        quote: &TdxQuote,
+        input_hash: &[u8],
+        proof_system: Option<ProofSystem>,
+        response: &ProofResponse,
+    ) -> Result<TdxProof, DbError> {
+        let proof = serde_json::to_vec(&response.proof).map_err(|e| {
+            tracing::info!("Failed to serialize proof: {}", e);
+            DbError::SomethingWentWrong("Failed to serialize proof".to_string())
+        })?;
+        let proof = sqlx::query_as::<_, TdxProof>(
+            r#"INSERT INTO tdx_proof (tdx_quote_id, quote_hash, input_hash, proof_type, proof_system, proof, prover_request_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *"#,
+        )
+        .bind(quote.id)
+        .bind(&quote.quote_hash)
+        .bind(input_hash)
+        .bind(response.proof_type)
+        .bind(proof_system.map(|proof_system| proof_system.to_string()))
+        .bind(proof)
+        .bind(&response.prover_request_id)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert proof: {}", e);
+            DbError::SomethingWentWrong("Failed to insert proof".to_string())
+        })?;
+        Ok(proof)
+    }
+
+    async fn find_fresh(
+        &self,
+        input_hash: &[u8],
+        proof_type: ProofType,
+        proof_system: Option<ProofSystem>,
+        since: DateTime<Utc>,
+    ) -> Result<Option<TdxProof>, DbError> {
+        let proof = sqlx::query_as::<_, TdxProof>(
+            r#"SELECT * FROM tdx_proof
+            WHERE input_hash = $1 AND proof_type = $2 AND proof_system IS NOT DISTINCT FROM $3 AND created_at >= $4
+            ORDER BY created_at DESC LIMIT 1"#,
+        )
+        .bind(input_hash)
+        .bind(proof_type)
+        .bind(proof_system.map(|proof_system| proof_system.to_string()))
+        .bind(since)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch proof: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch proof".to_string())
+        })?;
+        Ok(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::{ProofRepository, ProofRepositoryTrait};
+    use crate::{
+        entity::{
+            quote::ProofType,
+            zk::{DcapProof, ProofResponse, ProofSystem, ZkvmProof},
+        },
+        repository::{
+            testing::{create_quote, random_bytes, test_database},
+            unit_of_work::UnitOfWork,
+        },
+    };
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn fresh_proofs_match_input_backend_and_age() {
+        let db = test_database().await;
+        let repo = ProofRepository::new(&db);
+        let (_, quote) = create_quote(&db, "proof-reuse").await;
+        let input_hash = random_bytes();
+        let response = ProofResponse {
+            proof: DcapProof { verified_output: vec![], proof: ZkvmProof::RawQuote(quote.quote.clone()) },
+            proof_type: ProofType::RawQuote,
+            prover_request_id: None,
+        };
+
+        let mut uow = UnitOfWork::begin(&db).await.unwrap();
+        let stored = repo.create_in(uow.conn(), &quote, &input_hash, None, &response).await.unwrap();
+        uow.commit().await.unwrap();
+
+        let since = stored.created_at - Duration::minutes(1);
+        let fresh = repo.find_fresh(&input_hash, ProofType::RawQuote, None, since).await.unwrap();
+        assert_eq!(fresh.map(|proof| proof.id), Some(stored.id));
+        assert!(repo.find_fresh(&random_bytes(), ProofType::RawQuote, None, since).await.unwrap().is_none());
+        let other_backend = repo.find_fresh(&input_hash, ProofType::RawQuote, Some(ProofSystem::Groth16), since);
+        assert!(other_backend.await.unwrap().is_none());
+
+        // Older than the reuse window
+        let since = stored.created_at + Duration::seconds(1);
+        assert!(repo.find_fresh(&input_hash, ProofType::RawQuote, None, since).await.unwrap().is_none());
+    }
+}
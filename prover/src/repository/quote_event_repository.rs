@@ -0,0 +1,100 @@
+#[allow(dead_code)]
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::{quote::TdxQuoteStatus, quote_event::{QuoteActor, TdxQuoteEvent}},
+    get_conn,
+};
+use async_trait::async_trait;
+use sqlx::{types::Uuid, PgConnection};
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct QuoteEventRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait QuoteEventRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    async fn create(
+        &self,
+        tdx_quote_id: Uuid,
+        from_status: Option<TdxQuoteStatus>,
+        to_status: TdxQuoteStatus,
+        actor: QuoteActor,
+        detail: Option<String>,
+    ) -> Result<TdxQuoteEvent, DbError>;
+    async fn create_in(
+        &self,
+        conn: &mut PgConnection,
+        tdx_quote_id: Uuid,
+        from_status: Option<TdxQuoteStatus>,
+        to_status: TdxQuoteStatus,
+        actor: QuoteActor,
+        detail: Option<String>,
+    ) -> Result<TdxQuoteEvent, DbError>;
+    async fn find_all_by_quote_id(&self, tdx_quote_id: Uuid) -> Result<Vec<TdxQuoteEvent>, DbError>;
+}
+
+#[async_trait]
+impl QuoteEventRepositoryTrait for QuoteEventRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn create(
+        &self,
+        tdx_quote_id: Uuid,
+        from_status: Option<TdxQuoteStatus>,
+        to_status: TdxQuoteStatus,
+        actor: QuoteActor,
+        detail: Option<String>,
+    ) -> Result<TdxQuoteEvent, DbError> {
+        self.create_in(get_conn!(self.db_conn.get_pool()), tdx_quote_id, from_status, to_status, actor, detail).await
+    }
+
+    async fn create_in(
+        &self,
+        conn: &mut PgConnection,
+        tdx_quote_id: Uuid,
+        from_status: Option<TdxQuoteStatus>,
+        to_status: TdxQuoteStatus,
+        actor: QuoteActor,
+        detail: Option<String>,
+    ) -> Result<TdxQuoteEvent, DbError> {
+        let event = sqlx::query_as::<_, TdxQuoteEvent>(
+            r#"INSERT INTO tdx_quote_event (tdx_quote_id, from_status, to_status, actor, detail)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *"#,
+        )
+        .bind(tdx_quote_id)
+        .bind(from_status)
+        .bind(to_status)
+        .bind(actor)
+        .bind(detail)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert quote event: {}", e);
+            DbError::SomethingWentWrong("Failed to insert quote event".to_string())
+        })?;
+        Ok(event)
+    }
+
+    async fn find_all_by_quote_id(&self, tdx_quote_id: Uuid) -> Result<Vec<TdxQuoteEvent>, DbError> {
+        let events = sqlx::query_as::<_, TdxQuoteEvent>(
+            r#"SELECT * FROM tdx_quote_event WHERE tdx_quote_id = $1 ORDER BY created_at, id"#,
+        )
+        .bind(tdx_quote_id)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch quote events: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch quote events".to_string())
+        })?;
+        Ok(events)
+    }
+}
@@ -1,39 +1,95 @@
 #[allow(dead_code)]
-use crate::config::database::{Database, DatabaseTrait};
-use crate::{entity::quote::{ProofType, TdxQuote, TdxQuoteStatus}, get_conn};
+use crate::config::{database::{Database, DatabaseTrait}, pool::checkout};
+use crate::{
+    entity::{
+        quote::{quote_hash, QuoteCursor, QuoteFilter, QuotePage, QuoteSortBy, SortOrder, TdxQuote, TdxQuoteStatus},
+        quote_event::QuoteTransition,
+    },
+    get_conn,
+};
 use async_trait::async_trait;
-use sqlx::types::Uuid;
+use sqlx::{types::Uuid, PgConnection, Postgres, QueryBuilder};
 use crate::error::db_error::DbError;
 use std::sync::Arc;
 use crate::repository::request_repository::OnchainRequestId;
 
+/// Starts a `SELECT {columns}` over quotes joined with their request, restricted by `filter`
+fn select_quotes<'a>(columns: &str, filter: &'a QuoteFilter) -> QueryBuilder<'a, Postgres> {
+    let mut builder = QueryBuilder::new(format!(
+        "SELECT {columns} FROM tdx_quote quote JOIN onchain_request request ON request.id = quote.onchain_request_id WHERE TRUE"
+    ));
+    if let Some(status) = filter.status {
+        builder.push(" AND quote.status = ").push_bind(status);
+    }
+    if let Some(proof_type) = filter.proof_type {
+        builder.push(" AND quote.proof_type = ").push_bind(proof_type);
+    }
+    if let Some(onchain_request_id) = filter.onchain_request_id {
+        builder.push(" AND quote.onchain_request_id = ").push_bind(onchain_request_id);
+    }
+    if let Some(model_id) = &filter.model_id {
+        builder.push(" AND request.model_id = ").push_bind(model_id);
+    }
+    // Addresses are stored checksummed, compare case insensitively
+    if let Some(creator_address) = &filter.creator_address {
+        builder.push(" AND LOWER(request.creator_address) = LOWER(").push_bind(creator_address).push(")");
+    }
+    if let Some(operator_address) = &filter.operator_address {
+        builder.push(" AND LOWER(request.operator_address) = LOWER(").push_bind(operator_address).push(")");
+    }
+    if let Some(agent_id) = filter.agent_id {
+        builder.push(" AND quote.agent_id = ").push_bind(agent_id);
+    }
+    if let Some(created_from) = filter.created_from {
+        builder.push(" AND quote.created_at >= ").push_bind(created_from);
+    }
+    if let Some(created_to) = filter.created_to {
+        builder.push(" AND quote.created_at < ").push_bind(created_to);
+    }
+    builder
+}
+
 #[derive(Clone)]
 pub struct QuoteRepository {
     pub(crate) db_conn: Arc<Database>,
 }
 
 #[async_trait]
-pub trait QuoteRepositoryTrait {
-    fn new(db_conn: &Arc<Database>) -> Self;
+pub trait QuoteRepositoryTrait: Send + Sync {
+    fn new(db_conn: &Arc<Database>) -> Self
+    where
+        Self: Sized;
     async fn find(&self, id: Uuid) -> Result<TdxQuote, DbError>;
+    /// `find` that tells a missing quote apart from a failed query
+    async fn find_optional(&self, id: Uuid) -> Result<Option<TdxQuote>, DbError>;
     async fn find_all_by_onchain_request_id(&self,
         onchain_request_id: Uuid,
         verification_status: Option<TdxQuoteStatus>
     ) -> Vec<TdxQuote>;
     async fn find_by_onchain_request_id(&self, onchain_request_id: Uuid) -> Result<TdxQuote, DbError>;
-    async fn update_status(
+    /// Inserts `quote`, linked to the first registration of the same content and to the agent of
+    /// its request
+    async fn create(&self, onchain_request_id: Uuid, status: TdxQuoteStatus, quote: &[u8]) -> Result<TdxQuote, DbError>;
+    async fn create_in(
         &self,
-        id: Uuid,
-        proof_type: ProofType,
+        conn: &mut PgConnection,
+        onchain_request_id: Uuid,
         status: TdxQuoteStatus,
-        transaction_hash: Option<Vec<u8>>,
-        prover_request_id: Option<Vec<u8>>
-    ) -> Result<(), DbError>;
-    async fn find_request_ids_by_status(
+        quote: &[u8],
+    ) -> Result<TdxQuote, DbError>;
+    /// Applies `transition` if the quote is still in `transition.from`, returns `None` otherwise
+    async fn transition(&self, id: Uuid, transition: &QuoteTransition) -> Result<Option<TdxQuote>, DbError>;
+    async fn transition_in(
         &self,
-        status: Option<TdxQuoteStatus>,
-        max_count: Option<i64>
-    ) -> Vec<OnchainRequestId>;
+        conn: &mut PgConnection,
+        id: Uuid,
+        transition: &QuoteTransition,
+    ) -> Result<Option<TdxQuote>, DbError>;
+    async fn find_request_ids(&self, filter: &QuoteFilter, max_count: Option<i64>) -> Vec<OnchainRequestId>;
+    async fn find_page(&self, filter: &QuoteFilter, page: &QuotePage) -> Result<(Vec<TdxQuote>, Option<QuoteCursor>), DbError>;
+    async fn find_all_by_status(&self, status: TdxQuoteStatus, max_count: i64) -> Result<Vec<TdxQuote>, DbError>;
+    /// Number of quotes in each status that has any
+    async fn count_by_status(&self) -> Result<Vec<(TdxQuoteStatus, i64)>, DbError>;
 }
 
 #[async_trait]
@@ -45,21 +101,8 @@ impl QuoteRepositoryTrait for QuoteRepository {
     }
 
     async fn find(&self, id: Uuid) -> Result<TdxQuote, DbError> {
-        let quote = sqlx::query_as!(
-            TdxQuote,
-            r#"SELECT
-            id,
-            proof_type as "proof_type: crate::entity::quote::ProofType",
-            request_id,
-            txn_hash,
-            onchain_request_id,
-            quote,
-            created_at as "created_at: _",
-            updated_at as "updated_at: _",
-            status as "status: crate::entity::quote::TdxQuoteStatus"
-            FROM tdx_quote WHERE id = $1"#,
-            id,
-        )
+        let quote = sqlx::query_as::<_, TdxQuote>("SELECT * FROM tdx_quote WHERE id = $1")
+        .bind(id)
         .fetch_one(get_conn!(self.db_conn.get_pool()))
         .await
         .map_err(|e| {
@@ -69,47 +112,43 @@ impl QuoteRepositoryTrait for QuoteRepository {
         return Ok(quote);
     }
 
+    async fn find_optional(&self, id: Uuid) -> Result<Option<TdxQuote>, DbError> {
+        let quote = sqlx::query_as::<_, TdxQuote>("SELECT * FROM tdx_quote WHERE id = $1")
+            .bind(id)
+            .fetch_optional(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to fetch quote: {}", e);
+                DbError::SomethingWentWrong("Failed to fetch quote".to_string())
+            })?;
+        Ok(quote)
+    }
+
     async fn find_all_by_onchain_request_id(&self,
         onchain_request_id: Uuid,
         verification_status: Option<TdxQuoteStatus>
     ) -> Vec<TdxQuote> {
-        match verification_status {
-            Some(status) => {
-                let quotes = sqlx::query_as::<_, TdxQuote>("SELECT * FROM tdx_quote WHERE onchain_request_id = ? AND status = ?")
-                    .bind(onchain_request_id)
-                    .bind(status)
-                    .fetch_all(get_conn!(self.db_conn.get_pool()))
-                    .await
-                    .unwrap_or(vec![]);
-                return quotes;
-            }
-            None => {
-                let quotes = sqlx::query_as::<_, TdxQuote>("SELECT * FROM tdx_quote WHERE onchain_request_id = ?")
-                    .bind(onchain_request_id)
-                    .fetch_all(get_conn!(self.db_conn.get_pool()))
-                    .await
-                    .unwrap_or(vec![]);
-                return quotes;
-            }
-        }
+        // These lookups report no rows on any failure, including an exhausted pool
+        let Ok(mut conn) = checkout(self.db_conn.get_pool()).await else {
+            return vec![];
+        };
+        let filter = QuoteFilter {
+            onchain_request_id: Some(onchain_request_id),
+            status: verification_status,
+            ..Default::default()
+        };
+        let mut builder = select_quotes("quote.*", &filter);
+        builder.push(" ORDER BY quote.created_at");
+        let quotes = builder.build_query_as::<TdxQuote>()
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap_or(vec![]);
+        quotes
     }
 
     async fn find_by_onchain_request_id(&self, onchain_request_id: Uuid) -> Result<TdxQuote, DbError> {
-        let quote = sqlx::query_as!(
-            TdxQuote,
-            r#"SELECT
-            id,
-            proof_type as "proof_type: crate::entity::quote::ProofType",
-            request_id,
-            txn_hash,
-            onchain_request_id,
-            quote,
-            created_at as "created_at: _",
-            updated_at as "updated_at: _",
-            status as "status: crate::entity::quote::TdxQuoteStatus"
-            FROM tdx_quote WHERE onchain_request_id = $1"#,
-            onchain_request_id,
-        )
+        let quote = sqlx::query_as::<_, TdxQuote>("SELECT * FROM tdx_quote WHERE onchain_request_id = $1")
+        .bind(onchain_request_id)
         .fetch_one(get_conn!(self.db_conn.get_pool()))
         .await
         .map_err(|e| {
@@ -119,93 +158,195 @@ impl QuoteRepositoryTrait for QuoteRepository {
         return Ok(quote);
     }
 
-    async fn update_status(
+    async fn create(&self, onchain_request_id: Uuid, status: TdxQuoteStatus, quote: &[u8]) -> Result<TdxQuote, DbError> {
+        self.create_in(get_conn!(self.db_conn.get_pool()), onchain_request_id, status, quote).await
+    }
+
+    async fn create_in(
         &self,
-        id: Uuid,
-        proof_type: ProofType,
+        conn: &mut PgConnection,
+        onchain_request_id: Uuid,
         status: TdxQuoteStatus,
-        transaction_hash: Option<Vec<u8>>,
-        _prover_request_id: Option<Vec<u8>>
-    ) -> Result<(), DbError> {
-        tracing::debug!("Updating quote status for id: {} with status: {}", id, status);
-        match transaction_hash {
-            Some(txn_hash) => {
-                sqlx::query!(
-                    r#"UPDATE tdx_quote SET status = $2, txn_hash = $3, proof_type = $4 WHERE id = $1"#,
-                    id,
-                    status as TdxQuoteStatus,
-                    txn_hash,
-                    proof_type as ProofType,
-                )
-                .execute(get_conn!(self.db_conn.get_pool()))
-                .await
-                .map_err(|e| {
-                    tracing::info!("Failed to update quote status: {}", e);
-                    DbError::SomethingWentWrong("Failed to update quote status".to_string())
-                })?;
-            }
-            None => {
-                sqlx::query!(
-                    r#"UPDATE tdx_quote SET status = $2, proof_type = $3 WHERE id = $1"#,
-                    id,
-                    status as TdxQuoteStatus,
-                    proof_type as ProofType,
-                )
-                .execute(get_conn!(self.db_conn.get_pool()))
-                .await
-                .map_err(|e| {
-                    tracing::info!("Failed to update quote status: {}", e);
-                    DbError::SomethingWentWrong("Failed to update quote status".to_string())
-                })?;
+        quote: &[u8],
+    ) -> Result<TdxQuote, DbError> {
+        let quote = sqlx::query_as::<_, TdxQuote>(
+            r#"INSERT INTO tdx_quote (onchain_request_id, status, quote, quote_hash, duplicate_of, agent_id)
+            VALUES ($1, $2, $3, $4, (
+                SELECT id FROM tdx_quote WHERE quote_hash = $4 AND duplicate_of IS NULL
+                ORDER BY created_at, id LIMIT 1
+            ), (
+                SELECT agent_id FROM onchain_request WHERE id = $1
+            ))
+            RETURNING *"#,
+        )
+        .bind(onchain_request_id)
+        .bind(status)
+        .bind(quote)
+        .bind(quote_hash(quote))
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert quote: {}", e);
+            match e.as_database_error().and_then(|e| e.code()).as_deref() {
+                Some("23505") => DbError::UniqueConstraintViolation("Quote already exists".to_string()),
+                _ => DbError::SomethingWentWrong("Failed to insert quote".to_string()),
             }
-        }
-        Ok(())
+        })?;
+        Ok(quote)
+    }
+
+    async fn transition(&self, id: Uuid, transition: &QuoteTransition) -> Result<Option<TdxQuote>, DbError> {
+        self.transition_in(get_conn!(self.db_conn.get_pool()), id, transition).await
     }
 
-    async fn find_request_ids_by_status(
+    async fn transition_in(
         &self,
-        status: Option<TdxQuoteStatus>,
-        max_count: Option<i64>
-    ) -> Vec<OnchainRequestId> {
-        match status {
-            Some(status) => {
-                let quotes = match max_count {
-                    Some(count) => sqlx::query_as::<_, OnchainRequestId>(
-                            r#"SELECT onchain_request_id FROM tdx_quote WHERE status = ? LIMIT ?"#
-                        )
-                        .bind(status)
-                        .bind(count)
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
-                        .await
-                        .unwrap_or(vec![]),
-                    None => sqlx::query_as::<_, OnchainRequestId>(
-                            r#"SELECT onchain_request_id FROM tdx_quote WHERE status = ?"#
-                        )
-                        .bind(status)
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
-                        .await
-                        .unwrap_or(vec![]),
-                };
-                return quotes;
-            },
-            None => {
-                let quotes = match max_count {
-                    Some(count) => sqlx::query_as::<_, OnchainRequestId>(
-                            r#"SELECT onchain_request_id FROM tdx_quote LIMIT ? ORDER BY created_at DESC"#
-                        )
-                        .bind(count)
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
-                        .await
-                        .unwrap_or(vec![]),
-                    None => sqlx::query_as::<_, OnchainRequestId>(
-                            r#"SELECT onchain_request_id FROM tdx_quote ORDER BY created_at DESC"#
-                        )
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
-                        .await
-                        .unwrap_or(vec![]),
-                };
-                return quotes;
-            },
+        conn: &mut PgConnection,
+        id: Uuid,
+        transition: &QuoteTransition,
+    ) -> Result<Option<TdxQuote>, DbError> {
+        tracing::debug!("Moving quote {} from {} to {}", id, transition.from, transition.to);
+        // Only applies while the quote is still in `from`, the event is written in the same statement
+        let quote = sqlx::query_as::<_, TdxQuote>(
+            r#"WITH updated AS (
+                UPDATE tdx_quote
+                SET status = $3,
+                proof_type = COALESCE($4, proof_type),
+                txn_hash = COALESCE($5, txn_hash),
+                updated_at = now()
+                WHERE id = $1 AND status = $2
+                RETURNING *
+            ), event AS (
+                INSERT INTO tdx_quote_event (tdx_quote_id, from_status, to_status, actor, detail)
+                SELECT id, $2, $3, $6, $7 FROM updated
+            )
+            SELECT * FROM updated"#,
+        )
+        .bind(id)
+        .bind(transition.from)
+        .bind(transition.to)
+        .bind(transition.proof_type)
+        .bind(transition.txn_hash.clone())
+        .bind(transition.actor)
+        .bind(transition.detail.clone())
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to update quote status: {}", e);
+            DbError::SomethingWentWrong("Failed to update quote status".to_string())
+        })?;
+        Ok(quote)
+    }
+
+    async fn find_request_ids(&self, filter: &QuoteFilter, max_count: Option<i64>) -> Vec<OnchainRequestId> {
+        let Ok(mut conn) = checkout(self.db_conn.get_pool()).await else {
+            return vec![];
+        };
+        let mut builder = select_quotes("request.request_id", filter);
+        builder.push(" ORDER BY quote.created_at DESC");
+        if let Some(count) = max_count {
+            builder.push(" LIMIT ").push_bind(count);
+        }
+        let request_ids = builder.build_query_as::<OnchainRequestId>()
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap_or(vec![]);
+        request_ids
+    }
+
+    async fn find_page(&self, filter: &QuoteFilter, page: &QuotePage) -> Result<(Vec<TdxQuote>, Option<QuoteCursor>), DbError> {
+        // Column and direction come from fixed sets, only values are bound
+        let column = match page.sort_by {
+            QuoteSortBy::CreatedAt => "quote.created_at",
+            QuoteSortBy::UpdatedAt => "quote.updated_at",
+        };
+        let (direction, comparison) = match page.order {
+            SortOrder::Asc => ("ASC", ">"),
+            SortOrder::Desc => ("DESC", "<"),
+        };
+
+        let mut builder = select_quotes("quote.*", filter);
+        if let Some(cursor) = page.after {
+            builder.push(format!(" AND ({column}, quote.id) {comparison} ("))
+                .push_bind(cursor.timestamp)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
         }
+        // One extra row tells whether there is a next page
+        builder.push(format!(" ORDER BY {column} {direction}, quote.id {direction} LIMIT "))
+            .push_bind(page.limit + 1);
+
+        let mut quotes = builder.build_query_as::<TdxQuote>()
+            .fetch_all(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to list quotes: {}", e);
+                DbError::SomethingWentWrong("Failed to list quotes".to_string())
+            })?;
+
+        let next_cursor = match quotes.len() as i64 > page.limit {
+            true => {
+                quotes.truncate(page.limit as usize);
+                quotes.last().map(|quote| QuoteCursor::from_quote(quote, page.sort_by, page.order))
+            }
+            false => None,
+        };
+        Ok((quotes, next_cursor))
+    }
+
+    async fn find_all_by_status(&self, status: TdxQuoteStatus, max_count: i64) -> Result<Vec<TdxQuote>, DbError> {
+        let quotes = sqlx::query_as::<_, TdxQuote>(
+            r#"SELECT * FROM tdx_quote WHERE status = $1 ORDER BY updated_at LIMIT $2"#
+        )
+        .bind(status)
+        .bind(max_count)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch quotes by status: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch quotes by status".to_string())
+        })?;
+        Ok(quotes)
+    }
+
+    async fn count_by_status(&self) -> Result<Vec<(TdxQuoteStatus, i64)>, DbError> {
+        let counts = sqlx::query_as::<_, (TdxQuoteStatus, i64)>(
+            "SELECT status, COUNT(*) FROM tdx_quote GROUP BY status"
+        )
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to count quotes by status: {}", e);
+            DbError::SomethingWentWrong("Failed to count quotes by status".to_string())
+        })?;
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuoteRepository, QuoteRepositoryTrait};
+    use crate::{
+        entity::quote::TdxQuoteStatus,
+        repository::testing::{create_quote, insert_quote, random_bytes, test_database},
+    };
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn create_links_equal_quotes_to_the_first() {
+        let db = test_database().await;
+        let repo = QuoteRepository::new(&db);
+        let (request, first) = create_quote(&db, "quote-dedup").await;
+
+        let copy = repo.create(request.id, TdxQuoteStatus::Pending, &first.quote).await.unwrap();
+        assert_eq!((copy.duplicate_of, copy.quote_hash.clone()), (Some(first.id), first.quote_hash.clone()));
+        // Later copies point at the first registration too, not at the copy before them
+        let copy = insert_quote(&db, &request, &first.quote).await.unwrap();
+        assert_eq!(copy.duplicate_of, Some(first.id));
+
+        let other = repo.create(request.id, TdxQuoteStatus::Pending, &random_bytes()).await.unwrap();
+        assert_eq!(other.duplicate_of, None);
+        assert_ne!(other.quote_hash, first.quote_hash);
     }
 }
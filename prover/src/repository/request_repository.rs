@@ -1,8 +1,14 @@
 #[allow(dead_code)]
-use crate::config::database::{Database, DatabaseTrait};
-use crate::{entity::{quote::TdxQuoteStatus, request::OnchainRequest}, get_conn};
+use crate::config::{database::{Database, DatabaseTrait}, pool::checkout};
+use crate::{
+    entity::{
+        quote::TdxQuoteStatus,
+        request::{NewOnchainRequest, OnchainRequest, OnchainRequestUpdate, RequestFilter},
+    },
+    get_conn,
+};
 use async_trait::async_trait;
-use sqlx::{types::Uuid, FromRow};
+use sqlx::{types::Uuid, FromRow, PgConnection, QueryBuilder};
 use crate::error::db_error::DbError;
 use std::sync::Arc;
 
@@ -23,12 +29,22 @@ pub struct OnchainRequestRepository {
 }
 
 #[async_trait]
-pub trait OnchainRequestRepositoryTrait {
-    fn new(db_conn: &Arc<Database>) -> Self;
+pub trait OnchainRequestRepositoryTrait: Send + Sync {
+    fn new(db_conn: &Arc<Database>) -> Self
+    where
+        Self: Sized;
     async fn find_all_by_model_id(&self, model_id: String) -> Vec<OnchainRequest>;
     async fn find(&self, id: Uuid) -> Result<OnchainRequest, DbError>;
     async fn find_by_request_id(&self, request_id: Vec<u8>) -> Result<OnchainRequest, DbError>;
     async fn find_request_ids_by_status(&self, status: Option<TdxQuoteStatus>, max_count: Option<i64>) -> Vec<OnchainRequestId>;
+    /// Latest requests first
+    async fn find_all(&self, filter: &RequestFilter, limit: i64) -> Result<Vec<OnchainRequest>, DbError>;
+    async fn create(&self, request: &NewOnchainRequest) -> Result<OnchainRequest, DbError>;
+    async fn create_in(&self, conn: &mut PgConnection, request: &NewOnchainRequest) -> Result<OnchainRequest, DbError>;
+    /// Applies `update` unless the request is cancelled, returns `None` otherwise
+    async fn update(&self, id: Uuid, update: &OnchainRequestUpdate) -> Result<Option<OnchainRequest>, DbError>;
+    /// Marks the request cancelled, returns `None` if it already was
+    async fn cancel(&self, id: Uuid) -> Result<Option<OnchainRequest>, DbError>;
 }
 
 #[async_trait]
@@ -40,48 +56,20 @@ impl OnchainRequestRepositoryTrait for OnchainRequestRepository {
     }
 
     async fn find_all_by_model_id(&self, model_id: String) -> Vec<OnchainRequest> {
-        let onchain_requests = sqlx::query_as!(
-            OnchainRequest,
-            r#"SELECT
-            id,
-            creator_address,
-            operator_address,
-            model_id,
-            fee_wei,
-            nonce,
-            request_id,
-            deadline,
-            is_cancelled,
-            cancelled_at,
-            created_at as "created_at: _",
-            updated_at as "updated_at: _"
-            FROM onchain_request WHERE model_id = $1"#,
-            model_id
-        ).fetch_all(get_conn!(self.db_conn.get_pool()))
+        // These lookups report no rows on any failure, including an exhausted pool
+        let Ok(mut conn) = checkout(self.db_conn.get_pool()).await else {
+            return vec![];
+        };
+        let onchain_requests = sqlx::query_as::<_, OnchainRequest>("SELECT * FROM onchain_request WHERE model_id = $1")
+        .bind(model_id).fetch_all(&mut *conn)
         .await
         .unwrap_or(vec![]);
         return onchain_requests;
     }
 
     async fn find(&self, id: Uuid) -> Result<OnchainRequest, DbError> {
-        let onchain_request = sqlx::query_as!(
-            OnchainRequest,
-            r#"SELECT
-            id,
-            creator_address,
-            operator_address,
-            model_id,
-            fee_wei,
-            nonce,
-            request_id,
-            deadline,
-            is_cancelled,
-            cancelled_at,
-            created_at as "created_at: _",
-            updated_at as "updated_at: _"
-            FROM onchain_request WHERE id = $1"#,
-            id
-        ).fetch_one(get_conn!(self.db_conn.get_pool()))
+        let onchain_request = sqlx::query_as::<_, OnchainRequest>("SELECT * FROM onchain_request WHERE id = $1")
+        .bind(id).fetch_one(get_conn!(self.db_conn.get_pool()))
         .await
         .map_err(|e| {
             tracing::info!("Failed to fetch onchain request: {}", e);
@@ -91,24 +79,8 @@ impl OnchainRequestRepositoryTrait for OnchainRequestRepository {
     }
 
     async fn find_by_request_id(&self, request_id: Vec<u8>) -> Result<OnchainRequest, DbError> {
-        let onchain_request = sqlx::query_as!(
-            OnchainRequest,
-            r#"SELECT
-            id,
-            creator_address,
-            operator_address,
-            model_id,
-            fee_wei,
-            nonce,
-            request_id,
-            deadline,
-            is_cancelled,
-            cancelled_at,
-            created_at as "created_at: _",
-            updated_at as "updated_at: _"
-            FROM onchain_request WHERE request_id = $1"#,
-            request_id
-        ).fetch_one(get_conn!(self.db_conn.get_pool()))
+        let onchain_request = sqlx::query_as::<_, OnchainRequest>("SELECT * FROM onchain_request WHERE request_id = $1")
+        .bind(request_id).fetch_one(get_conn!(self.db_conn.get_pool()))
         .await
         .map_err(|e| {
             tracing::info!("Failed to fetch onchain request: {}", e);
@@ -118,6 +90,9 @@ impl OnchainRequestRepositoryTrait for OnchainRequestRepository {
     }
 
     async fn find_request_ids_by_status(&self, status: Option<TdxQuoteStatus>, max_count: Option<i64>) -> Vec<OnchainRequestId> {
+        let Ok(mut conn) = checkout(self.db_conn.get_pool()).await else {
+            return vec![];
+        };
         match status {
             Some(status) => {
                 match max_count {
@@ -133,7 +108,7 @@ impl OnchainRequestRepositoryTrait for OnchainRequestRepository {
                         status as TdxQuoteStatus,
                         count
                     )
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
+                        .fetch_all(&mut *conn)
                         .await
                         .unwrap_or(vec![]),
                     None => sqlx::query_as!(
@@ -146,7 +121,7 @@ impl OnchainRequestRepositoryTrait for OnchainRequestRepository {
                         ORDER BY tdx_quote.created_at DESC"#,
                         status as TdxQuoteStatus
                     )
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
+                        .fetch_all(&mut *conn)
                         .await
                         .unwrap_or(vec![]),
                 }
@@ -163,7 +138,7 @@ impl OnchainRequestRepositoryTrait for OnchainRequestRepository {
                         LIMIT $1"#,
                         count
                     )
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
+                        .fetch_all(&mut *conn)
                         .await
                         .unwrap_or(vec![]),
                     None => sqlx::query_as!(
@@ -173,11 +148,116 @@ impl OnchainRequestRepositoryTrait for OnchainRequestRepository {
                         JOIN tdx_quote
                         ON onchain_request.id = tdx_quote.onchain_request_id
                         ORDER BY tdx_quote.created_at DESC"#)
-                        .fetch_all(get_conn!(self.db_conn.get_pool()))
+                        .fetch_all(&mut *conn)
                         .await
                         .unwrap_or(vec![]),
                 }
             }
         }
     }
+
+    async fn find_all(&self, filter: &RequestFilter, limit: i64) -> Result<Vec<OnchainRequest>, DbError> {
+        let mut builder = QueryBuilder::new("SELECT * FROM onchain_request WHERE TRUE");
+        if let Some(model_id) = &filter.model_id {
+            builder.push(" AND model_id = ").push_bind(model_id);
+        }
+        // Addresses are stored checksummed, compare case insensitively
+        if let Some(creator_address) = &filter.creator_address {
+            builder.push(" AND LOWER(creator_address) = LOWER(").push_bind(creator_address).push(")");
+        }
+        if let Some(operator_address) = &filter.operator_address {
+            builder.push(" AND LOWER(operator_address) = LOWER(").push_bind(operator_address).push(")");
+        }
+        if let Some(agent_id) = filter.agent_id {
+            builder.push(" AND agent_id = ").push_bind(agent_id);
+        }
+        if !filter.include_cancelled {
+            builder.push(" AND NOT is_cancelled");
+        }
+        builder.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit);
+
+        let requests = builder.build_query_as::<OnchainRequest>()
+            .fetch_all(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to fetch onchain requests: {}", e);
+                DbError::SomethingWentWrong("Failed to fetch onchain requests".to_string())
+            })?;
+        Ok(requests)
+    }
+
+    async fn create(&self, request: &NewOnchainRequest) -> Result<OnchainRequest, DbError> {
+        self.create_in(get_conn!(self.db_conn.get_pool()), request).await
+    }
+
+    async fn create_in(&self, conn: &mut PgConnection, request: &NewOnchainRequest) -> Result<OnchainRequest, DbError> {
+        let onchain_request = sqlx::query_as::<_, OnchainRequest>(
+            r#"INSERT INTO onchain_request (
+                creator_address, operator_address, model_id, fee_wei, nonce, request_id, deadline, agent_id, api_key_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, (SELECT id FROM agent WHERE LOWER(operator_address) = LOWER($2)), $8)
+            RETURNING *"#,
+        )
+        .bind(&request.creator_address)
+        .bind(&request.operator_address)
+        .bind(&request.model_id)
+        .bind(request.fee_wei)
+        .bind(request.nonce)
+        .bind(&request.request_id)
+        .bind(request.deadline)
+        .bind(request.api_key_id)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert onchain request: {}", e);
+            match e.as_database_error().and_then(|e| e.code()).as_deref() {
+                Some("23505") => DbError::UniqueConstraintViolation("Onchain request already exists".to_string()),
+                _ => DbError::SomethingWentWrong("Failed to insert onchain request".to_string()),
+            }
+        })?;
+        Ok(onchain_request)
+    }
+
+    async fn update(&self, id: Uuid, update: &OnchainRequestUpdate) -> Result<Option<OnchainRequest>, DbError> {
+        let onchain_request = sqlx::query_as::<_, OnchainRequest>(
+            r#"UPDATE onchain_request
+            SET operator_address = COALESCE($2, operator_address),
+                agent_id = CASE
+                    WHEN $2 IS NULL THEN agent_id
+                    ELSE (SELECT id FROM agent WHERE LOWER(agent.operator_address) = LOWER($2))
+                END,
+                fee_wei = COALESCE($3, fee_wei),
+                deadline = COALESCE($4, deadline)
+            WHERE id = $1 AND NOT is_cancelled
+            RETURNING *"#,
+        )
+        .bind(id)
+        .bind(&update.operator_address)
+        .bind(update.fee_wei)
+        .bind(update.deadline)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to update onchain request: {}", e);
+            DbError::SomethingWentWrong("Failed to update onchain request".to_string())
+        })?;
+        Ok(onchain_request)
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<Option<OnchainRequest>, DbError> {
+        let onchain_request = sqlx::query_as::<_, OnchainRequest>(
+            r#"UPDATE onchain_request
+            SET is_cancelled = TRUE, cancelled_at = now()
+            WHERE id = $1 AND NOT is_cancelled
+            RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to cancel onchain request: {}", e);
+            DbError::SomethingWentWrong("Failed to cancel onchain request".to_string())
+        })?;
+        Ok(onchain_request)
+    }
 }
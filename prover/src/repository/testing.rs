@@ -0,0 +1,66 @@
+//! Postgres backed test helpers. Tests using them are `#[ignore]`d and run with `--ignored` once
+//! `TEST_DATABASE_URL` points to a disposable database, which gets every migration applied on first
+//! use.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use sqlx::types::Uuid;
+use tokio::sync::OnceCell;
+
+use crate::{
+    config::{database::Database, migration},
+    entity::{
+        quote::{TdxQuote, TdxQuoteStatus},
+        request::{NewOnchainRequest, OnchainRequest},
+    },
+    error::db_error::DbError,
+    repository::{
+        quote_repository::{QuoteRepository, QuoteRepositoryTrait},
+        request_repository::{OnchainRequestRepository, OnchainRequestRepositoryTrait},
+    },
+};
+
+/// The migrated test database. Panics when `TEST_DATABASE_URL` is not set, so a database test
+/// never passes without a database.
+pub(crate) async fn test_database() -> Arc<Database> {
+    static MIGRATED: OnceCell<()> = OnceCell::const_new();
+
+    let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is set for database tests");
+    let db = Arc::new(Database::connect(&url).expect("TEST_DATABASE_URL is a valid database url"));
+    MIGRATED.get_or_init(|| async {
+        migration::run(&db).await.expect("migrations apply to the test database");
+    }).await;
+    db
+}
+
+/// A new request for `model_id` with a `Pending` quote. Ids and contents are random, so tests
+/// sharing the database never see each other's rows.
+pub(crate) async fn create_quote(db: &Arc<Database>, model_id: &str) -> (OnchainRequest, TdxQuote) {
+    let request = create_request(db, model_id, "0x548df1990b444F0b658c838bE334149C1eA79833").await;
+    let quote = insert_quote(db, &request, &random_bytes()).await.expect("quote is created");
+    (request, quote)
+}
+
+/// A new request of `operator_address` for `model_id`, without quotes
+pub(crate) async fn create_request(db: &Arc<Database>, model_id: &str, operator_address: &str) -> OnchainRequest {
+    OnchainRequestRepository::new(db).create(&NewOnchainRequest {
+        creator_address: "0x97761a76a6e78135F899aD76C00333D07f9E76B8".to_string(),
+        operator_address: operator_address.to_string(),
+        model_id: model_id.to_string(),
+        fee_wei: 0,
+        nonce: rand::random::<u32>() as i64,
+        request_id: random_bytes(),
+        deadline: Utc::now() + Duration::hours(1),
+        api_key_id: None,
+    }).await.expect("request is created")
+}
+
+/// Registers `quote` for `request` the way the API does, linking it to an earlier equal quote
+pub(crate) async fn insert_quote(db: &Arc<Database>, request: &OnchainRequest, quote: &[u8]) -> Result<TdxQuote, DbError> {
+    QuoteRepository::new(db).create(request.id, TdxQuoteStatus::Pending, quote).await
+}
+
+pub(crate) fn random_bytes() -> Vec<u8> {
+    Uuid::from_u128(rand::random()).as_bytes().repeat(2)
+}
@@ -0,0 +1,176 @@
+use deadpool::managed::Object;
+use sqlx::{Executor, PgConnection};
+
+use crate::{
+    config::{database::{Database, DatabaseTrait}, pool::{checkout, DbPool}},
+    entity::quote_update::QuoteUpdate,
+    error::db_error::DbError,
+    events,
+};
+
+/// A database transaction shared by several repository calls.
+///
+/// Repository methods ending in `_in` take `uow.conn()` and only become visible to others once
+/// `commit` succeeds. A unit of work dropped without `commit` or `rollback` is rolled back by
+/// closing its connection instead of returning it to the pool.
+pub struct UnitOfWork {
+    conn: Option<Object<DbPool>>,
+    /// Published once the transaction committed, so subscribers never see rolled back changes
+    updates: Vec<QuoteUpdate>,
+}
+
+impl UnitOfWork {
+    pub async fn begin(db_conn: &Database) -> Result<Self, DbError> {
+        let mut conn = checkout(db_conn.get_pool()).await?;
+        conn.execute("BEGIN").await.map_err(|e| {
+            tracing::info!("Failed to begin transaction: {}", e);
+            DbError::SomethingWentWrong("Failed to begin transaction".to_string())
+        })?;
+        Ok(Self { conn: Some(conn), updates: vec![] })
+    }
+
+    pub fn conn(&mut self) -> &mut PgConnection {
+        self.conn.as_mut().expect("unit of work is still open")
+    }
+
+    /// Publishes `update` after `commit`, it is dropped on rollback
+    pub fn publish_on_commit(&mut self, update: QuoteUpdate) {
+        self.updates.push(update);
+    }
+
+    pub async fn commit(mut self) -> Result<(), DbError> {
+        let mut conn = self.end("COMMIT").await?;
+        for update in self.updates.drain(..) {
+            let quote_id = update.quote_id.clone();
+            if let Err(e) = events::publish_on(&mut *conn, update).await {
+                tracing::warn!("Failed to publish update of quote {}: {}", quote_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn rollback(mut self) -> Result<(), DbError> {
+        self.end("ROLLBACK").await?;
+        Ok(())
+    }
+
+    /// Runs `statement` and hands back the connection, which returns to the pool once dropped
+    async fn end(&mut self, statement: &str) -> Result<Object<DbPool>, DbError> {
+        let mut conn = self.conn.take().expect("unit of work is still open");
+        match conn.execute(statement).await {
+            Ok(_) => Ok(conn),
+            Err(e) => {
+                tracing::info!("Failed to run {}: {}", statement, e);
+                // The connection may still be inside the transaction, never hand it out again
+                drop(Object::take(conn));
+                Err(DbError::SomethingWentWrong(format!("Failed to run {}", statement)))
+            }
+        }
+    }
+}
+
+impl Drop for UnitOfWork {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            tracing::warn!("Unit of work dropped without commit, rolling back");
+            drop(Object::take(conn));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use sqlx::types::Uuid;
+
+    use super::UnitOfWork;
+    use crate::{
+        config::database::{Database, DatabaseTrait},
+        error::db_error::DbError,
+        get_conn,
+        repository::testing::{create_quote, random_bytes, test_database},
+    };
+
+    async fn set_txn_hash(uow: &mut UnitOfWork, quote_id: Uuid, txn_hash: &[u8]) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE tdx_quote SET txn_hash = $2 WHERE id = $1")
+            .bind(quote_id)
+            .bind(txn_hash)
+            .execute(uow.conn())
+            .await?;
+        Ok(())
+    }
+
+    /// The hash seen outside the unit of work
+    async fn txn_hash(db: &Arc<Database>, quote_id: Uuid) -> Result<Option<Vec<u8>>, DbError> {
+        let txn_hash = sqlx::query_scalar("SELECT txn_hash FROM tdx_quote WHERE id = $1")
+            .bind(quote_id)
+            .fetch_one(get_conn!(db.get_pool()))
+            .await
+            .map_err(|e| DbError::SomethingWentWrong(e.to_string()))?;
+        Ok(txn_hash)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn changes_are_visible_once_committed() {
+        let db = test_database().await;
+        let (_, quote) = create_quote(&db, "uow-commit").await;
+        let hash = random_bytes();
+
+        let mut uow = UnitOfWork::begin(&db).await.unwrap();
+        set_txn_hash(&mut uow, quote.id, &hash).await.unwrap();
+        assert_eq!(txn_hash(&db, quote.id).await.unwrap(), None);
+        uow.commit().await.unwrap();
+        assert_eq!(txn_hash(&db, quote.id).await.unwrap(), Some(hash));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn rollback_discards_changes() {
+        let db = test_database().await;
+        let (_, quote) = create_quote(&db, "uow-rollback").await;
+
+        let mut uow = UnitOfWork::begin(&db).await.unwrap();
+        set_txn_hash(&mut uow, quote.id, &random_bytes()).await.unwrap();
+        uow.rollback().await.unwrap();
+        assert_eq!(txn_hash(&db, quote.id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn failed_statement_discards_earlier_changes() {
+        let db = test_database().await;
+        let (_, quote) = create_quote(&db, "uow-error").await;
+
+        // Callers return the error with `?`, dropping the unit of work
+        let result: Result<(), sqlx::Error> = async {
+            let mut uow = UnitOfWork::begin(&db).await.unwrap();
+            set_txn_hash(&mut uow, quote.id, &random_bytes()).await?;
+            sqlx::query("SELECT 1 / 0").execute(uow.conn()).await?;
+            uow.commit().await.unwrap();
+            Ok(())
+        }
+        .await;
+        assert!(result.is_err());
+        assert_eq!(txn_hash(&db, quote.id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn dropped_without_commit_is_rolled_back() {
+        let db = test_database().await;
+        let (_, quote) = create_quote(&db, "uow-drop").await;
+
+        let mut uow = UnitOfWork::begin(&db).await.unwrap();
+        set_txn_hash(&mut uow, quote.id, &random_bytes()).await.unwrap();
+        drop(uow);
+        assert_eq!(txn_hash(&db, quote.id).await.unwrap(), None);
+
+        // The pool only hands out connections outside a transaction
+        let mut uow = UnitOfWork::begin(&db).await.unwrap();
+        set_txn_hash(&mut uow, quote.id, &[1]).await.unwrap();
+        uow.commit().await.unwrap();
+        assert_eq!(txn_hash(&db, quote.id).await.unwrap(), Some(vec![1]));
+    }
+}
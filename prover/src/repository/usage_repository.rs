@@ -0,0 +1,180 @@
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::usage::{ProvingQuota, QuotaPeriod, QuotaSubject},
+    get_conn,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Uuid, PgConnection};
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct UsageRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait UsageRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    /// Counts one use of `bucket` in the window unless it already reached `limit`, returns the
+    /// new count or `None` when the limit was reached
+    async fn increment_in(
+        &self,
+        conn: &mut PgConnection,
+        bucket: &str,
+        window_start: DateTime<Utc>,
+        limit: i32,
+    ) -> Result<Option<i32>, DbError>;
+    /// Gives back one use of `bucket` in the window, never below zero
+    async fn decrement_in(&self, conn: &mut PgConnection, bucket: &str, window_start: DateTime<Utc>) -> Result<(), DbError>;
+    async fn find_count(&self, bucket: &str, window_start: DateTime<Utc>) -> Result<i32, DbError>;
+    /// Deletes counters of windows that started before `before`, returns the number deleted
+    async fn prune(&self, before: DateTime<Utc>) -> Result<u64, DbError>;
+    async fn set_quota(&self, subject: &QuotaSubject, period: QuotaPeriod, proof_limit: i32) -> Result<ProvingQuota, DbError>;
+    async fn find_quotas(&self, subjects: &[QuotaSubject]) -> Result<Vec<ProvingQuota>, DbError>;
+    async fn find_all_quotas(&self) -> Result<Vec<ProvingQuota>, DbError>;
+    async fn delete_quota(&self, id: Uuid) -> Result<bool, DbError>;
+}
+
+#[async_trait]
+impl UsageRepositoryTrait for UsageRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn increment_in(
+        &self,
+        conn: &mut PgConnection,
+        bucket: &str,
+        window_start: DateTime<Utc>,
+        limit: i32,
+    ) -> Result<Option<i32>, DbError> {
+        // The conditional update keeps concurrent API instances and lambdas from overshooting
+        let count = sqlx::query_scalar::<_, i32>(
+            r#"INSERT INTO usage_counter (bucket, window_start, count)
+            SELECT $1, $2, 1 WHERE $3 > 0
+            ON CONFLICT (bucket, window_start) DO UPDATE SET count = usage_counter.count + 1
+            WHERE usage_counter.count < $3
+            RETURNING count"#,
+        )
+        .bind(bucket)
+        .bind(window_start)
+        .bind(limit)
+        .fetch_optional(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to increment usage counter: {}", e);
+            DbError::SomethingWentWrong("Failed to increment usage counter".to_string())
+        })?;
+        Ok(count)
+    }
+
+    async fn decrement_in(&self, conn: &mut PgConnection, bucket: &str, window_start: DateTime<Utc>) -> Result<(), DbError> {
+        sqlx::query(
+            r#"UPDATE usage_counter SET count = count - 1
+            WHERE bucket = $1 AND window_start = $2 AND count > 0"#,
+        )
+        .bind(bucket)
+        .bind(window_start)
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to decrement usage counter: {}", e);
+            DbError::SomethingWentWrong("Failed to decrement usage counter".to_string())
+        })?;
+        Ok(())
+    }
+
+    async fn find_count(&self, bucket: &str, window_start: DateTime<Utc>) -> Result<i32, DbError> {
+        let count = sqlx::query_scalar::<_, i32>(
+            "SELECT count FROM usage_counter WHERE bucket = $1 AND window_start = $2",
+        )
+        .bind(bucket)
+        .bind(window_start)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch usage counter: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch usage counter".to_string())
+        })?;
+        Ok(count.unwrap_or(0))
+    }
+
+    async fn prune(&self, before: DateTime<Utc>) -> Result<u64, DbError> {
+        let pruned = sqlx::query("DELETE FROM usage_counter WHERE window_start < $1")
+            .bind(before)
+            .execute(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to prune usage counters: {}", e);
+                DbError::SomethingWentWrong("Failed to prune usage counters".to_string())
+            })?;
+        Ok(pruned.rows_affected())
+    }
+
+    async fn set_quota(&self, subject: &QuotaSubject, period: QuotaPeriod, proof_limit: i32) -> Result<ProvingQuota, DbError> {
+        let (api_key_id, creator_address) = match subject {
+            QuotaSubject::ApiKey(id) => (Some(*id), None),
+            QuotaSubject::Creator(address) => (None, Some(address.as_str())),
+        };
+        let quota = sqlx::query_as::<_, ProvingQuota>(
+            r#"INSERT INTO proving_quota (subject, api_key_id, creator_address, period, proof_limit)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (subject, period) DO UPDATE SET proof_limit = EXCLUDED.proof_limit
+            RETURNING *"#,
+        )
+        .bind(subject.key())
+        .bind(api_key_id)
+        .bind(creator_address)
+        .bind(period)
+        .bind(proof_limit)
+        .fetch_one(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to set proving quota: {}", e);
+            DbError::SomethingWentWrong("Failed to set proving quota".to_string())
+        })?;
+        Ok(quota)
+    }
+
+    async fn find_quotas(&self, subjects: &[QuotaSubject]) -> Result<Vec<ProvingQuota>, DbError> {
+        let subjects = subjects.iter().map(QuotaSubject::key).collect::<Vec<_>>();
+        let quotas = sqlx::query_as::<_, ProvingQuota>(
+            "SELECT * FROM proving_quota WHERE subject = ANY($1) ORDER BY subject, period",
+        )
+        .bind(&subjects)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch proving quotas: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch proving quotas".to_string())
+        })?;
+        Ok(quotas)
+    }
+
+    async fn find_all_quotas(&self) -> Result<Vec<ProvingQuota>, DbError> {
+        let quotas = sqlx::query_as::<_, ProvingQuota>("SELECT * FROM proving_quota ORDER BY created_at")
+            .fetch_all(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to fetch proving quotas: {}", e);
+                DbError::SomethingWentWrong("Failed to fetch proving quotas".to_string())
+            })?;
+        Ok(quotas)
+    }
+
+    async fn delete_quota(&self, id: Uuid) -> Result<bool, DbError> {
+        let deleted = sqlx::query("DELETE FROM proving_quota WHERE id = $1")
+            .bind(id)
+            .execute(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to delete proving quota: {}", e);
+                DbError::SomethingWentWrong("Failed to delete proving quota".to_string())
+            })?;
+        Ok(deleted.rows_affected() > 0)
+    }
+}
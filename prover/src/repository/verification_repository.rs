@@ -0,0 +1,196 @@
+#[allow(dead_code)]
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::verification::{DecodedOutput, MrtdCount, TcbStatusCount, TdxVerification, VerificationSource},
+    get_conn,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::types::Uuid;
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct VerificationRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait VerificationRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    async fn create(
+        &self,
+        tdx_quote_id: Uuid,
+        source: VerificationSource,
+        output: &DecodedOutput,
+    ) -> Result<TdxVerification, DbError>;
+    async fn find_all_by_quote_id(&self, tdx_quote_id: Uuid) -> Result<Vec<TdxVerification>, DbError>;
+    async fn tcb_status_report(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TcbStatusCount>, DbError>;
+    async fn mrtd_report(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<MrtdCount>, DbError>;
+}
+
+#[async_trait]
+impl VerificationRepositoryTrait for VerificationRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn create(
+        &self,
+        tdx_quote_id: Uuid,
+        source: VerificationSource,
+        output: &DecodedOutput,
+    ) -> Result<TdxVerification, DbError> {
+        let [rtmr0, rtmr1, rtmr2, rtmr3] = match &output.rtmrs {
+            Some(rtmrs) => rtmrs.clone().map(Some),
+            None => [None, None, None, None],
+        };
+        let verification = sqlx::query_as::<_, TdxVerification>(
+            r#"INSERT INTO tdx_verification (
+                tdx_quote_id, source, quote_version, tee_type, tcb_status, fmspc, advisory_ids,
+                mrtd, rtmr0, rtmr1, rtmr2, rtmr3, report_data
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            RETURNING *"#,
+        )
+        .bind(tdx_quote_id)
+        .bind(source)
+        .bind(output.quote_version)
+        .bind(output.tee_type)
+        .bind(&output.tcb_status)
+        .bind(&output.fmspc)
+        .bind(&output.advisory_ids)
+        .bind(&output.mrtd)
+        .bind(rtmr0)
+        .bind(rtmr1)
+        .bind(rtmr2)
+        .bind(rtmr3)
+        .bind(&output.report_data)
+        .fetch_one(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert verification: {}", e);
+            DbError::SomethingWentWrong("Failed to insert verification".to_string())
+        })?;
+        Ok(verification)
+    }
+
+    async fn find_all_by_quote_id(&self, tdx_quote_id: Uuid) -> Result<Vec<TdxVerification>, DbError> {
+        let verifications = sqlx::query_as::<_, TdxVerification>(
+            r#"SELECT * FROM tdx_verification WHERE tdx_quote_id = $1 ORDER BY created_at"#,
+        )
+        .bind(tdx_quote_id)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch verifications: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch verifications".to_string())
+        })?;
+        Ok(verifications)
+    }
+
+    async fn tcb_status_report(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TcbStatusCount>, DbError> {
+        // A quote verified several times counts once, with its latest TCB status
+        let rows = sqlx::query_as::<_, TcbStatusCount>(
+            r#"SELECT fmspc, tcb_status, COUNT(*) AS quote_count
+            FROM (
+                SELECT DISTINCT ON (tdx_quote_id) fmspc, tcb_status
+                FROM tdx_verification
+                WHERE ($1::timestamptz IS NULL OR created_at >= $1)
+                AND ($2::timestamptz IS NULL OR created_at < $2)
+                ORDER BY tdx_quote_id, created_at DESC
+            ) latest
+            GROUP BY fmspc, tcb_status
+            ORDER BY fmspc, tcb_status"#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to aggregate TCB statuses: {}", e);
+            DbError::SomethingWentWrong("Failed to aggregate TCB statuses".to_string())
+        })?;
+        Ok(rows)
+    }
+
+    async fn mrtd_report(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<MrtdCount>, DbError> {
+        let rows = sqlx::query_as::<_, MrtdCount>(
+            r#"SELECT mrtd, COUNT(DISTINCT tdx_quote_id) AS quote_count, MAX(created_at) AS last_verified_at
+            FROM tdx_verification
+            WHERE mrtd IS NOT NULL
+            AND ($1::timestamptz IS NULL OR created_at >= $1)
+            AND ($2::timestamptz IS NULL OR created_at < $2)
+            GROUP BY mrtd
+            ORDER BY last_verified_at DESC"#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to aggregate MRTDs: {}", e);
+            DbError::SomethingWentWrong("Failed to aggregate MRTDs".to_string())
+        })?;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VerificationRepository, VerificationRepositoryTrait};
+    use crate::{
+        entity::verification::{DecodedOutput, VerificationSource},
+        repository::testing::{create_quote, random_bytes, test_database},
+    };
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn report_counts_the_latest_status_of_each_quote() {
+        let db = test_database().await;
+        let repo = VerificationRepository::new(&db);
+        let (_, quote) = create_quote(&db, "verification-report").await;
+        let fmspc = hex::encode(&random_bytes()[..6]);
+        let output = |tcb_status: &str| DecodedOutput {
+            quote_version: 4,
+            tee_type: 0x81,
+            tcb_status: tcb_status.to_string(),
+            fmspc: fmspc.clone(),
+            advisory_ids: vec![],
+            mrtd: Some(random_bytes()),
+            rtmrs: None,
+            report_data: vec![0; 64],
+        };
+
+        repo.create(quote.id, VerificationSource::Dcap, &output("OutOfDate")).await.unwrap();
+        let latest = repo.create(quote.id, VerificationSource::Onchain, &output("UpToDate")).await.unwrap();
+        let stored = repo.find_all_by_quote_id(quote.id).await.unwrap();
+        assert_eq!(stored.last().map(|verification| verification.id), Some(latest.id));
+        assert_eq!(stored.len(), 2);
+
+        let report = repo.tcb_status_report(None, None).await.unwrap();
+        let counts: Vec<(&str, i64)> = report.iter()
+            .filter(|count| count.fmspc == fmspc)
+            .map(|count| (count.tcb_status.as_str(), count.quote_count))
+            .collect();
+        assert_eq!(counts, vec![("UpToDate", 1)]);
+    }
+}
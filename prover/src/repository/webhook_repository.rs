@@ -0,0 +1,268 @@
+use crate::config::database::{Database, DatabaseTrait};
+use crate::{
+    entity::{
+        quote::TdxQuote,
+        webhook::{DueDelivery, NewWebhook, Webhook, WebhookDelivery, WebhookEvent},
+    },
+    get_conn,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Uuid, PgConnection};
+use crate::error::db_error::DbError;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct WebhookRepository {
+    pub(crate) db_conn: Arc<Database>,
+}
+
+#[async_trait]
+pub trait WebhookRepositoryTrait {
+    fn new(db_conn: &Arc<Database>) -> Self;
+    async fn create(&self, webhook: &NewWebhook, secret: &str) -> Result<Webhook, DbError>;
+    async fn find(&self, id: Uuid) -> Result<Option<Webhook>, DbError>;
+    /// Webhooks registered with `api_key_id`, or every webhook when it is `None`
+    async fn find_all(&self, api_key_id: Option<Uuid>) -> Result<Vec<Webhook>, DbError>;
+    /// Stops deliveries to the webhook, returns `None` if it was already disabled
+    async fn disable(&self, id: Uuid) -> Result<Option<Webhook>, DbError>;
+    /// Queues `payload` for every enabled webhook subscribed to `event` of the quote's request,
+    /// returns the number of deliveries queued
+    async fn enqueue_in(&self, conn: &mut PgConnection, quote: &TdxQuote, event: WebhookEvent, payload: &str) -> Result<u64, DbError>;
+    async fn find_deliveries(&self, webhook_id: Uuid, limit: i64) -> Result<Vec<WebhookDelivery>, DbError>;
+    async fn find_delivery(&self, id: Uuid) -> Result<Option<WebhookDelivery>, DbError>;
+    /// Queues a copy of the delivery with the same payload
+    async fn replay(&self, id: Uuid) -> Result<Option<WebhookDelivery>, DbError>;
+    /// Takes up to `limit` pending deliveries that are due, hiding them from other dispatchers for
+    /// `lease_secs` in case this one stops before recording the outcome
+    async fn claim_due(&self, limit: i64, lease_secs: i64) -> Result<Vec<DueDelivery>, DbError>;
+    async fn mark_delivered(&self, id: Uuid, attempts: i32, response_status: i32) -> Result<(), DbError>;
+    /// Records a failed attempt, the delivery is retried at `next_attempt_at` or failed for good
+    /// when it is `None`
+    async fn mark_attempt_failed(
+        &self,
+        id: Uuid,
+        attempts: i32,
+        response_status: Option<i32>,
+        error: &str,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DbError>;
+}
+
+#[async_trait]
+impl WebhookRepositoryTrait for WebhookRepository {
+    fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    async fn create(&self, webhook: &NewWebhook, secret: &str) -> Result<Webhook, DbError> {
+        let webhook = sqlx::query_as::<_, Webhook>(
+            r#"INSERT INTO webhook (api_key_id, onchain_request_id, url, secret, events)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *"#,
+        )
+        .bind(webhook.api_key_id)
+        .bind(webhook.onchain_request_id)
+        .bind(&webhook.url)
+        .bind(secret)
+        .bind(&webhook.events)
+        .fetch_one(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to insert webhook: {}", e);
+            DbError::SomethingWentWrong("Failed to insert webhook".to_string())
+        })?;
+        Ok(webhook)
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Option<Webhook>, DbError> {
+        let webhook = sqlx::query_as::<_, Webhook>("SELECT * FROM webhook WHERE id = $1")
+            .bind(id)
+            .fetch_optional(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to fetch webhook: {}", e);
+                DbError::SomethingWentWrong("Failed to fetch webhook".to_string())
+            })?;
+        Ok(webhook)
+    }
+
+    async fn find_all(&self, api_key_id: Option<Uuid>) -> Result<Vec<Webhook>, DbError> {
+        let webhooks = sqlx::query_as::<_, Webhook>(
+            "SELECT * FROM webhook WHERE $1::uuid IS NULL OR api_key_id = $1 ORDER BY created_at",
+        )
+        .bind(api_key_id)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch webhooks: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch webhooks".to_string())
+        })?;
+        Ok(webhooks)
+    }
+
+    async fn disable(&self, id: Uuid) -> Result<Option<Webhook>, DbError> {
+        let webhook = sqlx::query_as::<_, Webhook>(
+            "UPDATE webhook SET disabled_at = now() WHERE id = $1 AND disabled_at IS NULL RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to disable webhook: {}", e);
+            DbError::SomethingWentWrong("Failed to disable webhook".to_string())
+        })?;
+        Ok(webhook)
+    }
+
+    async fn enqueue_in(&self, conn: &mut PgConnection, quote: &TdxQuote, event: WebhookEvent, payload: &str) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            r#"INSERT INTO webhook_delivery (webhook_id, tdx_quote_id, event, payload)
+            SELECT webhook.id, $1, $2, $3
+            FROM webhook
+            JOIN onchain_request ON onchain_request.id = $4
+            WHERE webhook.disabled_at IS NULL
+            AND $2 = ANY(webhook.events)
+            AND (
+                webhook.onchain_request_id = onchain_request.id
+                OR (webhook.onchain_request_id IS NULL AND webhook.api_key_id = onchain_request.api_key_id)
+            )"#,
+        )
+        .bind(quote.id)
+        .bind(event)
+        .bind(payload)
+        .bind(quote.onchain_request_id)
+        .execute(conn)
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to queue webhook deliveries: {}", e);
+            DbError::SomethingWentWrong("Failed to queue webhook deliveries".to_string())
+        })?;
+        Ok(result.rows_affected())
+    }
+
+    async fn find_deliveries(&self, webhook_id: Uuid, limit: i64) -> Result<Vec<WebhookDelivery>, DbError> {
+        let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+            "SELECT * FROM webhook_delivery WHERE webhook_id = $1 ORDER BY created_at DESC LIMIT $2",
+        )
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to fetch webhook deliveries: {}", e);
+            DbError::SomethingWentWrong("Failed to fetch webhook deliveries".to_string())
+        })?;
+        Ok(deliveries)
+    }
+
+    async fn find_delivery(&self, id: Uuid) -> Result<Option<WebhookDelivery>, DbError> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>("SELECT * FROM webhook_delivery WHERE id = $1")
+            .bind(id)
+            .fetch_optional(get_conn!(self.db_conn.get_pool()))
+            .await
+            .map_err(|e| {
+                tracing::info!("Failed to fetch webhook delivery: {}", e);
+                DbError::SomethingWentWrong("Failed to fetch webhook delivery".to_string())
+            })?;
+        Ok(delivery)
+    }
+
+    async fn replay(&self, id: Uuid) -> Result<Option<WebhookDelivery>, DbError> {
+        let delivery = sqlx::query_as::<_, WebhookDelivery>(
+            r#"INSERT INTO webhook_delivery (webhook_id, tdx_quote_id, event, payload, replay_of)
+            SELECT webhook_id, tdx_quote_id, event, payload, id
+            FROM webhook_delivery
+            WHERE id = $1
+            RETURNING *"#,
+        )
+        .bind(id)
+        .fetch_optional(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to replay webhook delivery: {}", e);
+            DbError::SomethingWentWrong("Failed to replay webhook delivery".to_string())
+        })?;
+        Ok(delivery)
+    }
+
+    async fn claim_due(&self, limit: i64, lease_secs: i64) -> Result<Vec<DueDelivery>, DbError> {
+        let deliveries = sqlx::query_as::<_, DueDelivery>(
+            r#"WITH due AS (
+                SELECT webhook_delivery.id
+                FROM webhook_delivery
+                JOIN webhook ON webhook.id = webhook_delivery.webhook_id
+                WHERE webhook_delivery.status = 'pending'
+                AND webhook_delivery.next_attempt_at <= now()
+                AND webhook.disabled_at IS NULL
+                ORDER BY webhook_delivery.next_attempt_at
+                LIMIT $1
+                FOR UPDATE OF webhook_delivery SKIP LOCKED
+            )
+            UPDATE webhook_delivery
+            SET next_attempt_at = now() + make_interval(secs => $2)
+            FROM due, webhook
+            WHERE webhook_delivery.id = due.id AND webhook.id = webhook_delivery.webhook_id
+            RETURNING webhook_delivery.id, webhook_delivery.event, webhook_delivery.payload,
+                webhook_delivery.attempts, webhook.url, webhook.secret"#,
+        )
+        .bind(limit)
+        .bind(lease_secs as f64)
+        .fetch_all(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to claim webhook deliveries: {}", e);
+            DbError::SomethingWentWrong("Failed to claim webhook deliveries".to_string())
+        })?;
+        Ok(deliveries)
+    }
+
+    async fn mark_delivered(&self, id: Uuid, attempts: i32, response_status: i32) -> Result<(), DbError> {
+        sqlx::query(
+            r#"UPDATE webhook_delivery
+            SET status = 'delivered', attempts = $2, response_status = $3, last_error = NULL, delivered_at = now()
+            WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(attempts)
+        .bind(response_status)
+        .execute(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to update webhook delivery: {}", e);
+            DbError::SomethingWentWrong("Failed to update webhook delivery".to_string())
+        })?;
+        Ok(())
+    }
+
+    async fn mark_attempt_failed(
+        &self,
+        id: Uuid,
+        attempts: i32,
+        response_status: Option<i32>,
+        error: &str,
+        next_attempt_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DbError> {
+        sqlx::query(
+            r#"UPDATE webhook_delivery
+            SET status = CASE WHEN $5::timestamptz IS NULL THEN 'failed'::deliverystatus ELSE status END,
+                attempts = $2, response_status = $3, last_error = $4,
+                next_attempt_at = COALESCE($5, next_attempt_at)
+            WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(attempts)
+        .bind(response_status)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(get_conn!(self.db_conn.get_pool()))
+        .await
+        .map_err(|e| {
+            tracing::info!("Failed to update webhook delivery: {}", e);
+            DbError::SomethingWentWrong("Failed to update webhook delivery".to_string())
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{
+    config::database::Database,
+    entity::agent::{Agent, AgentStatus, AgentUpdate, NewAgent},
+    error::agent_error::AgentError,
+    repository::{
+        agent_repository::{AgentRepository, AgentRepositoryTrait},
+        unit_of_work::UnitOfWork,
+    },
+};
+use sqlx::types::Uuid;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct AgentService {
+    agent_repo: AgentRepository,
+    db_conn: Arc<Database>,
+}
+
+impl AgentService {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            agent_repo: AgentRepository::new(db_conn),
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// Inserts the agent and links the requests and quotes its operator already has
+    pub async fn register(&self, agent: &NewAgent) -> Result<Agent, AgentError> {
+        let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+        let agent = self.agent_repo.create_in(uow.conn(), agent).await?;
+        let linked = self.agent_repo.link_in(uow.conn(), &agent).await?;
+        uow.commit().await?;
+        tracing::info!("Registered agent {} for {}, linked {} requests", agent.id, agent.operator_address, linked);
+        Ok(agent)
+    }
+
+    pub async fn find(&self, id: Uuid) -> Result<Agent, AgentError> {
+        self.agent_repo.find(id).await?.ok_or(AgentError::NotFound)
+    }
+
+    pub async fn update(&self, id: Uuid, update: &AgentUpdate) -> Result<Agent, AgentError> {
+        self.agent_repo.update(id, update).await?.ok_or(AgentError::NotFound)
+    }
+
+    /// Agents are never deleted, their requests and quotes keep pointing at them
+    pub async fn retire(&self, id: Uuid) -> Result<Agent, AgentError> {
+        let update = AgentUpdate { status: Some(AgentStatus::Retired), ..Default::default() };
+        self.update(id, &update).await
+    }
+}
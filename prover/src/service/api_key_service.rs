@@ -0,0 +1,62 @@
+use crate::{
+    config::database::Database,
+    entity::api_key::{generate_key, hash_key, key_prefix, ApiKey, ApiScope},
+    error::auth_error::AuthError,
+    repository::api_key_repository::{ApiKeyRepository, ApiKeyRepositoryTrait},
+};
+use sqlx::types::Uuid;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct ApiKeyService {
+    api_key_repo: ApiKeyRepository,
+}
+
+impl ApiKeyService {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            api_key_repo: ApiKeyRepository::new(db_conn),
+        }
+    }
+
+    /// Stores a new key and returns it along with the key in clear, which cannot be recovered later
+    pub async fn create(&self, name: &str, scopes: &[ApiScope]) -> Result<(ApiKey, String), AuthError> {
+        let key = generate_key();
+        let api_key = self.api_key_repo.create(name, &key_prefix(&key), &hash_key(&key), scopes).await?;
+        tracing::info!("Created api key {} ({}) with scopes {:?}", api_key.id, api_key.prefix, api_key.scopes);
+        Ok((api_key, key))
+    }
+
+    /// Resolves the presented key and checks that it grants `scope`
+    pub async fn authenticate(&self, key: Option<&str>, scope: ApiScope) -> Result<ApiKey, AuthError> {
+        let key = key.filter(|key| !key.is_empty()).ok_or(AuthError::MissingKey)?;
+        let api_key = self
+            .api_key_repo
+            .find_active_by_hash(&hash_key(key))
+            .await?
+            .ok_or(AuthError::InvalidKey)?;
+        if !api_key.allows(scope) {
+            return Err(AuthError::MissingScope(scope));
+        }
+
+        // Usage tracking is best effort and must not hold up the request
+        let api_key_repo = self.api_key_repo.clone();
+        let id = api_key.id;
+        tokio::spawn(async move {
+            if let Err(e) = api_key_repo.touch(id).await {
+                tracing::warn!("Failed to record usage of api key {}: {}", id, e);
+            }
+        });
+        Ok(api_key)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<ApiKey>, AuthError> {
+        Ok(self.api_key_repo.find_all().await?)
+    }
+
+    pub async fn revoke(&self, id: Uuid) -> Result<ApiKey, AuthError> {
+        let api_key = self.api_key_repo.revoke(id).await?.ok_or(AuthError::NotFound)?;
+        tracing::info!("Revoked api key {} ({})", api_key.id, api_key.prefix);
+        Ok(api_key)
+    }
+}
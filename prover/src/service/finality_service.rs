@@ -0,0 +1,150 @@
+use crate::{
+    chain::{finality::{check_finality, TxFinality}, rpc::RpcPool},
+    config::{database::{Database, DatabaseTrait}, parameter},
+    entity::{quote::TdxQuoteStatus, quote_event::QuoteActor, quote_update::QuoteUpdate},
+    events,
+    repository::{
+        dropped_check_repository::{DroppedCheckRepository, DroppedCheckRepositoryTrait},
+        quote_repository::{QuoteRepository, QuoteRepositoryTrait},
+        unit_of_work::UnitOfWork,
+    },
+    service::lifecycle_service::LifecycleService,
+};
+use alloy::primitives::TxHash;
+use anyhow::Result;
+use chrono::Duration as ChronoDuration;
+use std::{sync::Arc, time::Duration};
+
+/// Outcome of one pass over the submitted quotes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FinalitySummary {
+    pub confirmed: usize,
+    pub failed: usize,
+    pub reverted_to_pending: usize,
+    pub still_submitted: usize,
+}
+
+/// Moves `Submitted` quotes to `Confirmed` once their proof transaction is deep enough, and back
+/// to `Pending` for a retry when it disappears from the canonical chain.
+///
+/// A transaction only counts as gone after `FINALITY_DROPPED_CHECKS` consecutive checks spanning
+/// at least `FINALITY_DROPPED_WINDOW_SECS` seconds found it neither mined nor in the mempool.
+///
+/// Proofs are only submitted to the network of `DEFAULT_RPC_URL`, so that is the only chain
+/// checked. Supporting several submission chains needs the chain stored with the quote.
+#[derive(Clone)]
+pub struct FinalityService {
+    db_conn: Arc<Database>,
+    quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    dropped_check_repo: DroppedCheckRepository,
+    lifecycle: LifecycleService,
+}
+
+impl FinalityService {
+    pub fn new(db_conn: &Arc<Database>, actor: QuoteActor) -> Self {
+        Self::with_repo(db_conn, Arc::new(QuoteRepository::new(db_conn)), actor)
+    }
+
+    pub fn with_repo(db_conn: &Arc<Database>, quote_repo: Arc<dyn QuoteRepositoryTrait>, actor: QuoteActor) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+            dropped_check_repo: DroppedCheckRepository::new(db_conn),
+            lifecycle: LifecycleService::with_repo(db_conn, Arc::clone(&quote_repo), actor),
+            quote_repo,
+        }
+    }
+
+    pub async fn check_submitted(&self, max_count: i64) -> Result<FinalitySummary> {
+        let pool = RpcPool::from_param("DEFAULT_RPC_URL", "https://mainnet.base.org")?;
+        let dropped_checks = parameter::get("FINALITY_DROPPED_CHECKS", Some("3")).parse().unwrap_or(3);
+        let dropped_window = ChronoDuration::seconds(
+            parameter::get("FINALITY_DROPPED_WINDOW_SECS", Some("300")).parse().unwrap_or(300),
+        );
+        let quotes = self.quote_repo.find_all_by_status(TdxQuoteStatus::Submitted, max_count).await?;
+        let mut summary = FinalitySummary::default();
+
+        for quote in quotes {
+            let Some(txn_hash) = quote.txn_hash.as_ref().filter(|hash| hash.len() == 32) else {
+                tracing::warn!("Submitted quote {} has no transaction hash, retrying", quote.id);
+                self.lifecycle.restart(&quote).await?;
+                summary.reverted_to_pending += 1;
+                continue;
+            };
+            let tx_hash = TxHash::from_slice(txn_hash);
+
+            match check_finality(&pool, tx_hash).await {
+                Ok(TxFinality::Final { cost }) => {
+                    // Transactions submitted without a receipt get their fee recorded here, reverted ones included
+                    let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+                    self.dropped_check_repo.clear_in(uow.conn(), quote.id).await?;
+                    match quote.proof_type {
+                        Some(proof_type) => self.lifecycle.record_fee_in(&mut uow, &quote, proof_type, &cost).await?,
+                        None => tracing::warn!("Submitted quote {} has no proof type, its fee is not recorded", quote.id),
+                    }
+                    let hash = hex::encode(tx_hash);
+                    match cost.success {
+                        true => {
+                            tracing::info!("Quote {} confirmed by {}", quote.id, tx_hash);
+                            let detail = Some(format!("Transaction 0x{} is final", hash));
+                            self.lifecycle.transition_in(&mut uow, &quote, TdxQuoteStatus::Confirmed, None, None, detail).await?;
+                            summary.confirmed += 1;
+                        }
+                        false => {
+                            tracing::warn!("Proof transaction {} for quote {} reverted", tx_hash, quote.id);
+                            let detail = Some(format!("Transaction 0x{} reverted", hash));
+                            self.lifecycle.transition_in(&mut uow, &quote, TdxQuoteStatus::Failure, None, None, detail).await?;
+                            summary.failed += 1;
+                        }
+                    }
+                    uow.commit().await?;
+                }
+                Ok(TxFinality::Dropped) => {
+                    let check = self.dropped_check_repo.record(quote.id, txn_hash).await?;
+                    if !check.confirms_drop(dropped_checks, dropped_window) {
+                        tracing::warn!(
+                            "Proof transaction {} for quote {} not found ({} of {} checks), checking again",
+                            tx_hash, quote.id, check.checks, dropped_checks
+                        );
+                        summary.still_submitted += 1;
+                        continue;
+                    }
+                    tracing::warn!("Proof transaction {} for quote {} left the canonical chain, retrying", tx_hash, quote.id);
+                    let detail = format!(
+                        "Transaction 0x{} left the canonical chain, not found by {} checks since {}",
+                        hex::encode(tx_hash), check.checks, check.first_checked_at
+                    );
+                    let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+                    self.dropped_check_repo.clear_in(uow.conn(), quote.id).await?;
+                    self.lifecycle.transition_in(&mut uow, &quote, TdxQuoteStatus::Pending, None, None, Some(detail)).await?;
+                    uow.commit().await?;
+                    summary.reverted_to_pending += 1;
+                }
+                Ok(TxFinality::Pending { confirmations, required }) => {
+                    self.dropped_check_repo.clear(quote.id).await?;
+                    tracing::debug!("Quote {} has {}/{} confirmations", quote.id, confirmations, required);
+                    events::publish(self.db_conn.get_pool(), QuoteUpdate::confirmations(&quote, confirmations, required)).await;
+                    summary.still_submitted += 1;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to check finality of {} for quote {}: {}", tx_hash, quote.id, e);
+                    summary.still_submitted += 1;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Re-checks submitted quotes every `FINALITY_CHECK_INTERVAL_SECS` seconds until the task is dropped
+    pub async fn run(self) {
+        let interval = parameter::get("FINALITY_CHECK_INTERVAL_SECS", Some("60")).parse().unwrap_or(60);
+        let mut ticker = tokio::time::interval(Duration::from_secs(interval));
+        loop {
+            ticker.tick().await;
+            match self.check_submitted(100).await {
+                Ok(summary) => tracing::info!("Finality check: {:?}", summary),
+                Err(e) => tracing::error!("Finality check failed: {}", e),
+            }
+        }
+    }
+}
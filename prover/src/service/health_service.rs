@@ -0,0 +1,270 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    primitives::utils::{format_ether, parse_ether},
+    providers::{Provider, ProviderBuilder},
+};
+use alloy_chains::NamedChain;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use tokio::task::JoinSet;
+
+use crate::{
+    chain::{rpc::RpcPool, signer::SignerConfig},
+    config::{database::{Database, DatabaseTrait}, parameter},
+    entity::health::{ComponentHealth, HealthReport, HealthStatus},
+    get_conn,
+};
+
+/// Testnet the PCCS DAOs are read from in verify only mode
+const AUTOMATA_TESTNET_CHAIN_ID: u64 = 1398243;
+
+/// An RPC endpoint list and the chain id every endpoint of it must report
+struct Network {
+    name: &'static str,
+    pool: Result<RpcPool>,
+    chain_id: u64,
+}
+
+/// Networks used in the current mode: the PCCS reads and proof submissions go to `DEFAULT_RPC_URL`,
+/// or to `AUTOMATA_DEFAULT_RPC_URL` when `VERIFY_ONLY` is set
+fn networks() -> Vec<Network> {
+    let chain_id = |param: &str, default_value: u64| {
+        parameter::get(param, Some(&default_value.to_string())).parse().unwrap_or(default_value)
+    };
+    if verify_only() {
+        return vec![Network {
+            name: "automata",
+            pool: RpcPool::from_param("AUTOMATA_DEFAULT_RPC_URL", "https://1rpc.io/ata/testnet"),
+            chain_id: chain_id("AUTOMATA_CHAIN_ID", AUTOMATA_TESTNET_CHAIN_ID),
+        }];
+    }
+
+    let base_chain_id = chain_id("DEFAULT_CHAIN_ID", NamedChain::Base as u64);
+    let mut networks = vec![Network {
+        name: "base",
+        pool: RpcPool::from_param("DEFAULT_RPC_URL", "https://mainnet.base.org"),
+        chain_id: base_chain_id,
+    }];
+    // Simulations fork the submission network, so the fork source must be on the same chain
+    if let Ok(urls) = std::env::var("SIMULATION_RPC_URL") {
+        networks.push(Network { name: "simulation", pool: RpcPool::new(&urls), chain_id: base_chain_id });
+    }
+    networks
+}
+
+fn verify_only() -> bool {
+    parameter::get("VERIFY_ONLY", Some("false")) == "true"
+}
+
+/// Readiness of the process and of everything a proof goes through: the database, the RPC
+/// networks, the signer account and the proving services.
+#[derive(Clone)]
+pub struct HealthService {
+    db_conn: Arc<Database>,
+    last_report: Arc<Mutex<Option<HealthReport>>>,
+}
+
+impl HealthService {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+            last_report: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Runs every check concurrently. Reports are reused for `HEALTH_CACHE_SECS` so frequent
+    /// load balancer probes do not turn into RPC traffic.
+    pub async fn readiness(&self) -> HealthReport {
+        let cache_secs = parameter::get("HEALTH_CACHE_SECS", Some("5")).parse().unwrap_or(5);
+        if let Some(report) = self.last_report.lock().unwrap().as_ref() {
+            if (Utc::now() - report.checked_at).num_seconds() < cache_secs {
+                return report.clone();
+            }
+        }
+
+        let (database, networks, signer) = tokio::join!(
+            timed("database", self.check_database()),
+            check_networks(),
+            timed("signer", check_signer()),
+        );
+        let mut components = vec![database];
+        components.extend(networks);
+        components.push(signer);
+        components.extend(check_provers());
+
+        let report = HealthReport::new(components);
+        if !report.is_ready() {
+            tracing::warn!("Readiness check failed: {:?}", report.components.iter()
+                .filter(|component| component.status == HealthStatus::Down)
+                .map(|component| &component.name)
+                .collect::<Vec<_>>());
+        }
+        *self.last_report.lock().unwrap() = Some(report.clone());
+        report
+    }
+
+    async fn check_database(&self) -> Result<ComponentHealth> {
+        let pool = self.db_conn.get_pool();
+        sqlx::query("SELECT 1").execute(get_conn!(pool)).await?;
+        let status = pool.status();
+        let detail = format!(
+            "{} of {} connections open, {} idle, {} waiting",
+            status.size, status.max_size, status.available, status.waiting
+        );
+        Ok(ComponentHealth::new("database", HealthStatus::Up, detail))
+    }
+}
+
+/// Bounds `check` by `HEALTH_CHECK_TIMEOUT_SECS` and records how long it took, an error or
+/// timeout marks the component down
+async fn timed<F>(name: &str, check: F) -> ComponentHealth
+where
+    F: Future<Output = Result<ComponentHealth>>,
+{
+    let timeout = parameter::get("HEALTH_CHECK_TIMEOUT_SECS", Some("5")).parse().unwrap_or(5);
+    let started = Instant::now();
+    let mut component = match tokio::time::timeout(Duration::from_secs(timeout), check).await {
+        Ok(Ok(component)) => component,
+        Ok(Err(e)) => ComponentHealth::new(name, HealthStatus::Down, e.to_string()),
+        Err(_) => ComponentHealth::new(name, HealthStatus::Down, format!("No answer within {}s", timeout)),
+    };
+    component.latency_ms = started.elapsed().as_millis() as u64;
+    component
+}
+
+async fn check_networks() -> Vec<ComponentHealth> {
+    let mut components = vec![];
+    for network in networks() {
+        let name = format!("network:{}", network.name);
+        components.push(timed(&name, check_network(name.clone(), network)).await);
+    }
+    components
+}
+
+/// Every endpoint is asked for its chain id. Endpoints are referred to by position since their
+/// URLs usually embed an API key.
+async fn check_network(name: String, network: Network) -> Result<ComponentHealth> {
+    let pool = network.pool?;
+    let mut calls = JoinSet::new();
+    for (index, url) in pool.urls().iter().cloned().enumerate() {
+        calls.spawn(async move {
+            let chain_id = async {
+                let provider = ProviderBuilder::new().on_http(url.parse()?);
+                anyhow::Ok(provider.get_chain_id().await?)
+            };
+            (index, chain_id.await)
+        });
+    }
+    let mut answers = calls.join_all().await;
+    answers.sort_by_key(|(index, _)| *index);
+
+    let mut healthy = 0;
+    let mut problems = vec![];
+    for (index, answer) in answers {
+        match answer {
+            Ok(chain_id) if chain_id == network.chain_id => healthy += 1,
+            Ok(chain_id) => problems.push(format!(
+                "endpoint {} is on chain {} instead of {}", index + 1, chain_id, network.chain_id
+            )),
+            Err(e) => {
+                tracing::warn!("Health check of {} endpoint {} failed: {}", name, index + 1, e);
+                problems.push(format!("endpoint {} unreachable", index + 1));
+            }
+        }
+    }
+
+    let total = pool.urls().len();
+    let status = match healthy {
+        0 => HealthStatus::Down,
+        healthy if healthy < total => HealthStatus::Degraded,
+        _ => HealthStatus::Up,
+    };
+    let mut detail = format!("{} of {} endpoints on chain {}", healthy, total, network.chain_id);
+    if !problems.is_empty() {
+        detail = format!("{}; {}", detail, problems.join("; "));
+    }
+    Ok(ComponentHealth::new(name, status, detail))
+}
+
+/// The prover account must hold at least `SIGNER_MIN_BALANCE` ether on the submission network
+async fn check_signer() -> Result<ComponentHealth> {
+    if verify_only() {
+        return Ok(ComponentHealth::new("signer", HealthStatus::Up, "verify only mode sends no transactions"));
+    }
+    let address = SignerConfig::from_env()?.address()?;
+
+    let threshold = parse_ether(&parameter::get("SIGNER_MIN_BALANCE", Some("0.005")))?;
+    let pool = RpcPool::from_param("DEFAULT_RPC_URL", "https://mainnet.base.org")?;
+    let balance = pool.with_failover(|rpc_url| async move {
+        let provider = ProviderBuilder::new().on_http(rpc_url.parse()?);
+        Ok(provider.get_balance(address).await?)
+    }).await.map_err(|_| anyhow!("Balance of {} could not be read", address))?;
+
+    let status = match balance < threshold {
+        true => HealthStatus::Degraded,
+        false => HealthStatus::Up,
+    };
+    let detail = format!("{} holds {} ETH, minimum {} ETH", address, format_ether(balance), format_ether(threshold));
+    Ok(ComponentHealth::new("signer", status, detail))
+}
+
+/// Credentials of the proving services listed in `HEALTH_PROVERS`, only their presence is
+/// checked since both services charge for requests
+fn check_provers() -> Vec<ComponentHealth> {
+    let provers = parameter::get("HEALTH_PROVERS", Some("sp1,risc0"));
+    prover_components(&provers, |param| parameter::get(param, Some("")))
+}
+
+/// Health of each prover in the comma separated `provers`, `credential` returns the value of a
+/// credential parameter, empty when it is not set
+fn prover_components(provers: &str, credential: impl Fn(&str) -> String) -> Vec<ComponentHealth> {
+    provers
+        .split(',')
+        .map(|prover| prover.trim().to_lowercase())
+        .filter(|prover| !prover.is_empty())
+        .map(|prover| {
+            let credentials: &[&str] = match prover.as_str() {
+                // The SP1 client always proves on the Succinct prover network
+                "sp1" => &["NETWORK_PRIVATE_KEY"],
+                // RISC Zero proofs are always requested from Bonsai
+                "risc0" => &["BONSAI_API_KEY", "BONSAI_API_URL"],
+                _ => {
+                    let detail = format!("Unknown prover {}, expected sp1 or risc0", prover);
+                    return ComponentHealth::new(format!("prover:{}", prover), HealthStatus::Down, detail);
+                }
+            };
+            let missing = credentials.iter()
+                .filter(|param| credential(param).is_empty())
+                .copied()
+                .collect::<Vec<_>>();
+            let (status, detail) = match missing.is_empty() {
+                true => (HealthStatus::Up, format!("{} set", credentials.join(", "))),
+                false => (HealthStatus::Down, format!("{} not set", missing.join(", "))),
+            };
+            ComponentHealth::new(format!("prover:{}", prover), status, detail)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn provers_need_their_credentials() {
+        let credentials = HashMap::from([("NETWORK_PRIVATE_KEY", "0x01"), ("BONSAI_API_KEY", "")]);
+        let credential = |param: &str| credentials.get(param).copied().unwrap_or_default().to_string();
+
+        let components = prover_components("sp1, risc0", credential);
+        assert_eq!(components[0].name, "prover:sp1");
+        assert_eq!(components[0].status, HealthStatus::Up);
+        assert_eq!(components[1].status, HealthStatus::Down);
+        assert_eq!(components[1].detail.as_deref(), Some("BONSAI_API_KEY, BONSAI_API_URL not set"));
+    }
+}
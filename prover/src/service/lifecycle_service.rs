@@ -0,0 +1,636 @@
+use crate::{
+    config::{database::{Database, DatabaseTrait}, parameter, pool::checkout},
+    entity::{
+        attempt::{error_chain, AttemptStage, DueRetry, ErrorCategory, ProofAttempt},
+        fee::TransactionCost,
+        proof::TdxProof,
+        quote::{ProofType, TdxQuote, TdxQuoteStatus},
+        quote_event::{QuoteActor, QuoteTransition},
+        quote_update::QuoteUpdate,
+        verification::{DecodedOutput, VerificationSource},
+        webhook::{WebhookEvent, WebhookPayload},
+        zk::{DcapProof, ProofResponse, ProofSystem, SubmitProofResponse},
+    },
+    error::{db_error::DbError, prove_error::ProveError, quote_error::QuoteError, request_error::RequestError},
+    events,
+    repository::{
+        attempt_repository::{ProofAttemptRepository, ProofAttemptRepositoryTrait},
+        fee_repository::{TransactionFeeRepository, TransactionFeeRepositoryTrait},
+        proof_repository::{ProofRepository, ProofRepositoryTrait},
+        quote_event_repository::{QuoteEventRepository, QuoteEventRepositoryTrait},
+        quote_repository::{QuoteRepository, QuoteRepositoryTrait},
+        unit_of_work::UnitOfWork,
+        verification_repository::{VerificationRepository, VerificationRepositoryTrait},
+        webhook_repository::{WebhookRepository, WebhookRepositoryTrait},
+    },
+    telemetry,
+    zk,
+};
+use alloy::primitives::TxHash;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use std::{sync::Arc, time::Duration};
+
+use TdxQuoteStatus::*;
+
+/// Statuses a quote in `from` may move to.
+///
+/// ```text
+/// Pending -> CollateralFetched -> Proving -> Proved -> Submitted -> Confirmed
+/// ```
+///
+/// Raw quotes go from `Pending` to `Proved` directly, `can_transition` only allows that for them.
+/// Any unfinished quote may fail, be sent back
+/// to `Pending` for a retry, or expire or be cancelled together with its request before it is
+/// submitted.
+pub fn allowed_transitions(from: TdxQuoteStatus) -> &'static [TdxQuoteStatus] {
+    match from {
+        Pending => &[CollateralFetched, Proved, Failure, Expired, Cancelled],
+        CollateralFetched => &[Proving, Pending, Failure, Expired, Cancelled],
+        Proving => &[Proved, Pending, Failure, Expired, Cancelled],
+        Proved => &[Submitted, Pending, Failure, Expired, Cancelled],
+        Submitted => &[Confirmed, Pending, Failure],
+        Failure => &[Pending, Expired, Cancelled],
+        Confirmed | Expired | Cancelled | Success => &[],
+    }
+}
+
+/// Whether a quote proven with `proof_type` may move from `from` to `to`
+pub fn can_transition(from: TdxQuoteStatus, to: TdxQuoteStatus, proof_type: Option<ProofType>) -> bool {
+    match (from, to) {
+        // ZK proofs need the collaterals fetched first
+        (Pending, Proved) => proof_type == Some(ProofType::RawQuote),
+        _ => allowed_transitions(from).contains(&to),
+    }
+}
+
+/// Quotes in a terminal status are never proven again
+pub fn is_terminal(status: TdxQuoteStatus) -> bool {
+    allowed_transitions(status).is_empty()
+}
+
+/// The only way quote statuses change. Every transition is validated, recorded in the quote's
+/// history together with the actor that made it and published to the live update streams.
+#[derive(Clone)]
+pub struct LifecycleService {
+    db_conn: Arc<Database>,
+    quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    event_repo: QuoteEventRepository,
+    attempt_repo: ProofAttemptRepository,
+    fee_repo: TransactionFeeRepository,
+    proof_repo: ProofRepository,
+    verification_repo: VerificationRepository,
+    webhook_repo: WebhookRepository,
+    actor: QuoteActor,
+}
+
+impl LifecycleService {
+    pub fn new(db_conn: &Arc<Database>, actor: QuoteActor) -> Self {
+        Self::with_repo(db_conn, Arc::new(QuoteRepository::new(db_conn)), actor)
+    }
+
+    pub fn with_repo(db_conn: &Arc<Database>, quote_repo: Arc<dyn QuoteRepositoryTrait>, actor: QuoteActor) -> Self {
+        Self {
+            db_conn: Arc::clone(db_conn),
+            quote_repo,
+            event_repo: QuoteEventRepository::new(db_conn),
+            attempt_repo: ProofAttemptRepository::new(db_conn),
+            fee_repo: TransactionFeeRepository::new(db_conn),
+            proof_repo: ProofRepository::new(db_conn),
+            verification_repo: VerificationRepository::new(db_conn),
+            webhook_repo: WebhookRepository::new(db_conn),
+            actor,
+        }
+    }
+
+    /// Moves `quote` to `to` and returns the updated quote. Moving a quote to the status it
+    /// already has is a no-op.
+    pub async fn transition(
+        &self,
+        quote: &TdxQuote,
+        to: TdxQuoteStatus,
+        proof_type: Option<ProofType>,
+        txn_hash: Option<Vec<u8>>,
+        detail: Option<String>,
+    ) -> Result<TdxQuote, QuoteError> {
+        self.apply(None, quote, to, proof_type, txn_hash, detail).await
+    }
+
+    /// `transition` as part of `uow`, the change is only visible once `uow` commits
+    pub async fn transition_in(
+        &self,
+        uow: &mut UnitOfWork,
+        quote: &TdxQuote,
+        to: TdxQuoteStatus,
+        proof_type: Option<ProofType>,
+        txn_hash: Option<Vec<u8>>,
+        detail: Option<String>,
+    ) -> Result<TdxQuote, QuoteError> {
+        self.apply(Some(uow), quote, to, proof_type, txn_hash, detail).await
+    }
+
+    async fn apply(
+        &self,
+        uow: Option<&mut UnitOfWork>,
+        quote: &TdxQuote,
+        to: TdxQuoteStatus,
+        proof_type: Option<ProofType>,
+        txn_hash: Option<Vec<u8>>,
+        detail: Option<String>,
+    ) -> Result<TdxQuote, QuoteError> {
+        if quote.status == to {
+            return Ok(quote.clone());
+        }
+        if !can_transition(quote.status, to, proof_type.or(quote.proof_type)) {
+            tracing::warn!("Rejected transition of quote {} from {} to {}", quote.id, quote.status, to);
+            return Err(QuoteError::InvalidTransition(quote.status, to));
+        }
+
+        let transition = QuoteTransition {
+            from: quote.status,
+            to,
+            proof_type,
+            txn_hash,
+            actor: self.actor,
+            detail,
+        };
+        let detail = transition.detail.clone();
+        let updated = match uow {
+            Some(uow) => {
+                let updated = self.quote_repo.transition_in(uow.conn(), quote.id, &transition).await;
+                if let Ok(Some(updated)) = &updated {
+                    // Queued in the same transaction, so an event is delivered exactly when it happened
+                    self.queue_webhooks(uow.conn(), updated, detail.clone()).await.map_err(|_| QuoteError::UpdateStatus)?;
+                    uow.publish_on_commit(QuoteUpdate::transition(transition.from, updated, self.actor, detail));
+                }
+                updated
+            }
+            None => {
+                let updated = self.quote_repo.transition(quote.id, &transition).await;
+                if let Ok(Some(updated)) = &updated {
+                    if let Err(e) = self.queue_webhooks_now(updated, detail.clone()).await {
+                        tracing::error!("Failed to queue webhooks of quote {}: {}", quote.id, e);
+                    }
+                    let update = QuoteUpdate::transition(transition.from, updated, self.actor, detail);
+                    events::publish(self.db_conn.get_pool(), update).await;
+                }
+                updated
+            }
+        };
+        match updated {
+            Ok(Some(updated)) => {
+                tracing::info!("Quote {} moved from {} to {} by {}", quote.id, transition.from, to, self.actor);
+                Ok(updated)
+            }
+            Ok(None) => {
+                tracing::warn!("Quote {} is no longer {}", quote.id, transition.from);
+                Err(QuoteError::StaleStatus)
+            }
+            Err(e) => {
+                tracing::error!("Failed to move quote {} to {}: {}", quote.id, to, e);
+                Err(QuoteError::UpdateStatus)
+            }
+        }
+    }
+
+    /// Queues the deliveries of the webhook event `quote` just reached, if it is one
+    async fn queue_webhooks(&self, conn: &mut PgConnection, quote: &TdxQuote, detail: Option<String>) -> Result<(), DbError> {
+        let Some(event) = WebhookEvent::from_status(quote.status) else {
+            return Ok(());
+        };
+        let payload = serde_json::to_string(&WebhookPayload::from(event, quote, detail))
+            .map_err(|e| DbError::SomethingWentWrong(e.to_string()))?;
+        let queued = self.webhook_repo.enqueue_in(conn, quote, event, &payload).await?;
+        if queued > 0 {
+            tracing::info!("Queued {} {} webhook deliveries of quote {}", queued, event, quote.id);
+        }
+        Ok(())
+    }
+
+    async fn queue_webhooks_now(&self, quote: &TdxQuote, detail: Option<String>) -> Result<(), DbError> {
+        if WebhookEvent::from_status(quote.status).is_none() {
+            return Ok(());
+        }
+        let mut conn = checkout(self.db_conn.get_pool()).await?;
+        self.queue_webhooks(&mut *conn, quote, detail).await
+    }
+
+    pub async fn fail(&self, quote: &TdxQuote, detail: String) -> Result<TdxQuote, QuoteError> {
+        self.transition(quote, Failure, None, None, Some(detail)).await
+    }
+
+    /// Records in the history of `quote` why it was left as it is, e.g. a proof that was never
+    /// started. The event goes from and to the current status and is not published.
+    pub async fn note(&self, quote: &TdxQuote, detail: String) -> Result<(), QuoteError> {
+        self.event_repo.create(quote.id, Some(quote.status), quote.status, self.actor, Some(detail)).await.map_err(|e| {
+            tracing::error!("Failed to record a note on quote {}: {}", quote.id, e);
+            QuoteError::UpdateStatus
+        })?;
+        Ok(())
+    }
+
+    /// Moves the quote of a request that expired or was cancelled to the matching status. Quotes
+    /// of requests failing for another reason are left as they are.
+    pub async fn mark_inactive(&self, quote: &TdxQuote, error: &RequestError) -> Result<TdxQuote, QuoteError> {
+        match error.quote_status() {
+            Some(status) => self.transition(quote, status, None, None, Some(error.to_string())).await,
+            None => Ok(quote.clone()),
+        }
+    }
+
+    /// Sends an unfinished quote back to `Pending` so it can be proven from scratch, unless its
+    /// last attempt failed in a way that is not retried
+    pub async fn restart(&self, quote: &TdxQuote) -> Result<TdxQuote, QuoteError> {
+        self.ensure_retry_allowed(quote).await?;
+        let detail = format!("Restarted from {}", quote.status);
+        self.transition(quote, Pending, None, None, Some(detail)).await
+    }
+
+    async fn latest_attempt(&self, quote: &TdxQuote) -> Result<Option<ProofAttempt>, QuoteError> {
+        self.attempt_repo.find_latest(quote.id).await.map_err(|e| {
+            tracing::error!("Failed to fetch attempts of quote {}: {}", quote.id, e);
+            QuoteError::Attempt(ErrorCategory::Db, e.to_string())
+        })
+    }
+
+    pub async fn ensure_retry_allowed(&self, quote: &TdxQuote) -> Result<(), QuoteError> {
+        match self.latest_attempt(quote).await? {
+            Some(ProofAttempt { error_category: Some(category), attempt_number, .. })
+                if !category.retry_policy().allows(attempt_number) =>
+            {
+                tracing::warn!("Quote {} gave up after {} {} failures", quote.id, attempt_number, category);
+                Err(QuoteError::RetryNotAllowed(category))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns `QuoteError::RetryScheduled` while a `Pending` quote waits for the retry of its
+    /// failed attempt, the retry scheduler proves it once it is due
+    pub async fn ensure_retry_due(&self, quote: &TdxQuote) -> Result<(), QuoteError> {
+        if quote.status != Pending {
+            return Ok(());
+        }
+        match self.latest_attempt(quote).await? {
+            Some(ProofAttempt { error_category: Some(category), next_attempt_at: Some(at), .. }) if at > Utc::now() => {
+                Err(QuoteError::RetryScheduled(category, at))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn start_attempt(
+        &self,
+        quote: &TdxQuote,
+        stage: AttemptStage,
+        backend: &str,
+    ) -> Result<ProofAttempt, QuoteError> {
+        self.attempt_repo.start(quote.id, stage, backend).await.map_err(|e| {
+            tracing::error!("Failed to record {} attempt of quote {}: {}", stage, quote.id, e);
+            QuoteError::Attempt(ErrorCategory::Db, e.to_string())
+        })
+    }
+
+    /// Closes `attempt`, keeping the category and the full chain of `failure` when it failed
+    pub async fn finish_attempt(
+        &self,
+        attempt: &ProofAttempt,
+        failure: Option<(ErrorCategory, &anyhow::Error)>,
+    ) -> Result<(), QuoteError> {
+        let (category, chain) = attempt_failure(attempt, failure);
+        self.attempt_repo.finish(attempt.id, category, chain, None).await.map_err(|e| {
+            tracing::error!("Failed to finish attempt {}: {}", attempt.id, e);
+            QuoteError::Attempt(ErrorCategory::Db, e.to_string())
+        })?;
+        Ok(())
+    }
+
+    /// Closes `attempt` as a database failure after the quote could not be moved on, so no
+    /// attempt is left open. Returns `error`.
+    async fn abort_attempt(&self, attempt: &ProofAttempt, error: QuoteError) -> QuoteError {
+        let failure = anyhow::anyhow!(error.to_string());
+        if let Err(e) = self.finish_attempt(attempt, Some((ErrorCategory::Db, &failure))).await {
+            tracing::error!("Failed to abort attempt {}: {}", attempt.id, e);
+        }
+        error
+    }
+
+    pub async fn finish_attempt_in(
+        &self,
+        uow: &mut UnitOfWork,
+        attempt: &ProofAttempt,
+        failure: Option<(ErrorCategory, &anyhow::Error)>,
+    ) -> Result<(), QuoteError> {
+        let (category, chain) = attempt_failure(attempt, failure);
+        self.attempt_repo.finish_in(uow.conn(), attempt.id, category, chain, None).await.map_err(|e| {
+            tracing::error!("Failed to finish attempt {}: {}", attempt.id, e);
+            QuoteError::Attempt(ErrorCategory::Db, e.to_string())
+        })?;
+        Ok(())
+    }
+
+    /// Closes the failed `attempt` and sends `quote` back to `Pending` together, the retry
+    /// scheduler proves it again once `next_attempt_at` is due
+    async fn schedule_retry(
+        &self,
+        quote: &TdxQuote,
+        attempt: &ProofAttempt,
+        category: ErrorCategory,
+        error: &anyhow::Error,
+        next_attempt_at: DateTime<Utc>,
+    ) -> Result<TdxQuote, QuoteError> {
+        let (category_column, chain) = attempt_failure(attempt, Some((category, error)));
+        let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+        self.attempt_repo.finish_in(uow.conn(), attempt.id, category_column, chain, Some(next_attempt_at)).await.map_err(|e| {
+            tracing::error!("Failed to finish attempt {}: {}", attempt.id, e);
+            QuoteError::Attempt(ErrorCategory::Db, e.to_string())
+        })?;
+        let detail = format!("Retrying after {} error at {}", category, next_attempt_at.to_rfc3339());
+        let quote = self.transition_in(&mut uow, quote, Pending, None, None, Some(detail)).await?;
+        uow.commit().await?;
+        Ok(quote)
+    }
+
+    /// Takes the next quote due for a retry, `None` when there is none. It is leased for
+    /// `PROVE_RETRY_LEASE_SECS` seconds, long enough to start the new attempt.
+    pub async fn claim_due_retry(&self) -> Result<Option<DueRetry>, QuoteError> {
+        let lease_secs = parameter::get("PROVE_RETRY_LEASE_SECS", Some("600")).parse().unwrap_or(600);
+        let mut retries = self.attempt_repo.claim_due(1, lease_secs).await.map_err(|e| {
+            tracing::error!("Failed to claim due proof retries: {}", e);
+            QuoteError::Attempt(ErrorCategory::Db, e.to_string())
+        })?;
+        Ok(retries.pop())
+    }
+
+    /// Records what the proof transaction of `quote` cost as part of `uow`, so the fee is stored
+    /// together with the status the transaction led to. Transactions recorded before are skipped.
+    pub async fn record_fee_in(
+        &self,
+        uow: &mut UnitOfWork,
+        quote: &TdxQuote,
+        proof_type: ProofType,
+        cost: &TransactionCost,
+    ) -> Result<(), QuoteError> {
+        match self.fee_repo.create_in(uow.conn(), quote.id, quote.onchain_request_id, proof_type, cost).await {
+            Ok(Some(fee)) => {
+                tracing::info!("Transaction fee of quote {} recorded: {} wei (gas_used: {})", quote.id, fee.fee_wei, fee.gas_used);
+                Ok(())
+            }
+            Ok(None) => Ok(()),
+            Err(e) => {
+                tracing::error!("Failed to record transaction fee of quote {}: {}", quote.id, e);
+                Err(QuoteError::UpdateStatus)
+            }
+        }
+    }
+
+    /// Stores the outcome of submitting the proof of a `Proved` quote: closes `attempt` and moves
+    /// the quote on, recording the fee when the transaction has a receipt. Transactions without
+    /// one are left `Submitted` for the finality check.
+    pub async fn record_submission(
+        &self,
+        quote: &TdxQuote,
+        attempt: &ProofAttempt,
+        proof_type: ProofType,
+        verify_only: bool,
+        tx_hash: Option<TxHash>,
+        response: Option<SubmitProofResponse>,
+    ) -> Result<TdxQuote, QuoteError> {
+        match (response, tx_hash) {
+            (Some(response), _) => {
+                let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+                // The quote only becomes final once the finality check sees enough confirmations
+                let (status, detail) = match response.cost.success {
+                    true => {
+                        self.finish_attempt_in(&mut uow, attempt, None).await?;
+                        (Submitted, None)
+                    }
+                    false => {
+                        let revert = ProveError::OnchainRevert(format!("0x{}", hex::encode(response.transaction_hash)));
+                        self.finish_attempt_in(&mut uow, attempt, Some((ErrorCategory::OnchainRevert, &revert.into()))).await?;
+                        (Failure, Some("Proof transaction reverted".to_string()))
+                    }
+                };
+                self.record_fee_in(&mut uow, quote, proof_type, &response.cost).await?;
+                let txn_hash = Some(response.transaction_hash.to_vec());
+                let quote = self.transition_in(&mut uow, quote, status, None, txn_hash, detail).await?;
+                uow.commit().await?;
+                Ok(quote)
+            }
+            (None, Some(tx_hash)) => {
+                // The finality check confirms the transaction and records its fee, or sends the quote back for a retry
+                let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+                self.finish_attempt_in(&mut uow, attempt, None).await?;
+                let detail = Some("No receipt yet".to_string());
+                let quote = self.transition_in(&mut uow, quote, Submitted, None, Some(tx_hash.to_vec()), detail).await?;
+                uow.commit().await?;
+                Ok(quote)
+            }
+            (None, None) if !verify_only => {
+                let not_sent = anyhow::anyhow!("Proof transaction was not sent");
+                self.finish_attempt(attempt, Some((ErrorCategory::Unknown, &not_sent))).await?;
+                self.fail(quote, not_sent.to_string()).await
+            }
+            (None, None) => {
+                self.finish_attempt(attempt, None).await?;
+                Ok(quote.clone())
+            }
+        }
+    }
+
+    /// Stores the decoded `output` of a successful verification of `quote`. Raw quotes have an
+    /// empty output until the contract verified them, nothing is stored for those. Failures are
+    /// only logged.
+    pub async fn record_verification(&self, quote: &TdxQuote, source: VerificationSource, output: &[u8]) {
+        if output.is_empty() {
+            return;
+        }
+        let output = DecodedOutput::from_bytes(output);
+        if let Err(e) = self.verification_repo.create(quote.id, source, &output).await {
+            tracing::error!("Failed to record {} verification of quote {}: {}", source, quote.id, e);
+        }
+    }
+
+    /// A proof of the same input generated within `PROOF_REUSE_WINDOW_SECS`, the collaterals
+    /// are part of the input so the proof is only reused while they are unchanged. A window of
+    /// 0 disables reuse.
+    async fn find_reusable_proof(
+        &self,
+        input_hash: &[u8],
+        proof_type: ProofType,
+        proof_system: Option<ProofSystem>,
+    ) -> Option<(TdxProof, DcapProof)> {
+        let window: i64 = parameter::get("PROOF_REUSE_WINDOW_SECS", Some("3600")).parse().unwrap_or(3600);
+        if window <= 0 {
+            return None;
+        }
+        let since = chrono::Utc::now() - chrono::Duration::seconds(window);
+        // Proving again is always correct, a failed lookup only costs time
+        let stored = match self.proof_repo.find_fresh(input_hash, proof_type, proof_system, since).await {
+            Ok(stored) => stored?,
+            Err(e) => {
+                tracing::warn!("Failed to look up a reusable proof: {}", e);
+                return None;
+            }
+        };
+        match stored.dcap_proof() {
+            Ok(proof) => Some((stored, proof)),
+            Err(e) => {
+                tracing::warn!("Stored proof {} is unreadable: {}", stored.id, e);
+                None
+            }
+        }
+    }
+
+    /// Fetches collaterals and generates the proof of a `Pending` quote, recording every step and
+    /// the attempt. A failure its category's policy retries sends the quote back to `Pending` with
+    /// the time of the next attempt and returns `QuoteError::RetryScheduled`, the retry scheduler
+    /// picks it up from there. Otherwise the quote is marked `Failure`.
+    pub async fn prove(
+        &self,
+        mut quote: TdxQuote,
+        proof_type: ProofType,
+        proof_system: Option<ProofSystem>,
+    ) -> Result<(TdxQuote, ProofResponse), QuoteError> {
+        let backend = match proof_system {
+            Some(proof_system) => format!("{}/{}", proof_type, proof_system),
+            None => proof_type.to_string(),
+        };
+
+        let attempt = self.start_attempt(&quote, AttemptStage::Prove, &backend).await?;
+        // Set when a new proof was generated and should be stored for reuse
+        let mut input_hash = None;
+        let result = match proof_type {
+            ProofType::RawQuote => zk::prove(quote.quote.clone(), proof_type, proof_system).await
+                .map_err(|e| (ErrorCategory::classify(&e).unwrap_or(ErrorCategory::QuoteParse), e)),
+            _ => match zk::fetch_input(&quote.quote, proof_type).await {
+                Ok(input) => {
+                    quote = match self.transition(&quote, CollateralFetched, Some(proof_type), None, None).await {
+                        Ok(quote) => quote,
+                        Err(e) => return Err(self.abort_attempt(&attempt, e).await),
+                    };
+                    let hash = Sha256::digest(&input).to_vec();
+                    match self.find_reusable_proof(&hash, proof_type, proof_system).await {
+                        Some((stored, proof)) => {
+                            let detail = Some(format!("Reusing proof {}", stored.id));
+                            quote = match self.transition(&quote, Proving, None, None, detail).await {
+                                Ok(quote) => quote,
+                                Err(e) => return Err(self.abort_attempt(&attempt, e).await),
+                            };
+                            Ok(ProofResponse { proof, proof_type, prover_request_id: stored.prover_request_id })
+                        }
+                        None => {
+                            input_hash = Some(hash);
+                            let detail = proof_system.map(|proof_system| proof_system.to_string());
+                            quote = match self.transition(&quote, Proving, None, None, detail).await {
+                                Ok(quote) => quote,
+                                Err(e) => return Err(self.abort_attempt(&attempt, e).await),
+                            };
+                            prove_input(input, proof_type, proof_system).await
+                                .map_err(|e| (ErrorCategory::classify(&e).unwrap_or(ErrorCategory::ProverNetwork), e))
+                        }
+                    }
+                }
+                Err(e) => Err((ErrorCategory::classify(&e).unwrap_or(ErrorCategory::Unknown), e)),
+            },
+        };
+
+        let (category, error) = match result {
+            Ok(proof) => {
+                let detail = match proof_type {
+                    ProofType::RawQuote => Some("Raw quote, verified on-chain".to_string()),
+                    _ => proof.prover_request_id.as_ref().map(|id| format!("Prover request 0x{}", hex::encode(id))),
+                };
+                let proved = async {
+                    let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+                    self.finish_attempt_in(&mut uow, &attempt, None).await?;
+                    if let Some(prover_request_id) = &proof.prover_request_id {
+                        uow.publish_on_commit(QuoteUpdate::prover_request(&quote, prover_request_id));
+                    }
+                    if let Some(input_hash) = &input_hash {
+                        self.proof_repo.create_in(uow.conn(), &quote, input_hash, proof_system, &proof).await
+                            .map_err(|e| QuoteError::Attempt(ErrorCategory::Db, e.to_string()))?;
+                    }
+                    let quote = self.transition_in(&mut uow, &quote, Proved, Some(proof_type), None, detail).await?;
+                    uow.commit().await?;
+                    Ok(quote)
+                }.await;
+                let quote = match proved {
+                    Ok(quote) => quote,
+                    Err(e) => return Err(self.abort_attempt(&attempt, e).await),
+                };
+                self.record_verification(&quote, VerificationSource::Zkvm, &proof.proof.verified_output).await;
+                return Ok((quote, proof));
+            }
+            Err(failure) => failure,
+        };
+        let policy = category.retry_policy();
+        if !policy.allows(attempt.attempt_number) {
+            let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+            self.finish_attempt_in(&mut uow, &attempt, Some((category, &error))).await?;
+            let detail = format!("{} error: {}", category, error);
+            self.transition_in(&mut uow, &quote, Failure, None, None, Some(detail)).await?;
+            uow.commit().await?;
+            return Err(QuoteError::Attempt(category, error_chain(&error)));
+        }
+        let delay = chrono::Duration::from_std(policy.delay(attempt.attempt_number)).unwrap_or(chrono::Duration::zero());
+        let next_attempt_at = Utc::now() + delay;
+        tracing::warn!(
+            "Attempt {} of quote {} failed with a {} error, retrying at {}: {}",
+            attempt.attempt_number, quote.id, category, next_attempt_at, error
+        );
+        self.schedule_retry(&quote, &attempt, category, &error, next_attempt_at).await?;
+        Err(QuoteError::RetryScheduled(category, next_attempt_at))
+    }
+}
+
+/// Category and error chain to store for `attempt`, failures are counted in the metrics here
+fn attempt_failure(
+    attempt: &ProofAttempt,
+    failure: Option<(ErrorCategory, &anyhow::Error)>,
+) -> (Option<ErrorCategory>, Option<String>) {
+    match failure {
+        Some((category, error)) => {
+            telemetry::record_attempt_failure(attempt.stage, category);
+            (Some(category), Some(error_chain(error)))
+        }
+        None => (None, None),
+    }
+}
+
+/// `zk::prove_input` bounded by `PROVE_TIMEOUT_SECS`
+async fn prove_input(input: Vec<u8>, proof_type: ProofType, proof_system: Option<ProofSystem>) -> anyhow::Result<ProofResponse> {
+    let timeout = Duration::from_secs(parameter::get("PROVE_TIMEOUT_SECS", Some("3600")).parse().unwrap_or(3600));
+    tokio::time::timeout(timeout, zk::prove_input(input, proof_type, proof_system)).await
+        .map_err(|_| ProveError::Timeout(format!("No proof after {}s", timeout.as_secs())))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{can_transition, is_terminal};
+    use crate::entity::quote::{ProofType, TdxQuoteStatus::*};
+
+    #[test]
+    fn lifecycle_transitions() {
+        assert!(can_transition(Pending, CollateralFetched, None));
+        assert!(can_transition(Proving, Proved, None));
+        assert!(can_transition(Submitted, Confirmed, None));
+        // reorged out proofs are retried
+        assert!(can_transition(Submitted, Pending, None));
+
+        assert!(!can_transition(Pending, Submitted, None));
+        assert!(!can_transition(Submitted, Cancelled, None));
+        assert!(!can_transition(Confirmed, Pending, None));
+
+        // only raw quotes skip fetching collaterals and proving
+        assert!(can_transition(Pending, Proved, Some(ProofType::RawQuote)));
+        assert!(!can_transition(Pending, Proved, Some(ProofType::Sp1)));
+        assert!(!can_transition(Pending, Proved, None));
+        assert!(can_transition(Proving, Proved, Some(ProofType::Sp1)));
+
+        assert!(is_terminal(Confirmed));
+        assert!(is_terminal(Expired));
+        assert!(!is_terminal(Failure));
+    }
+}
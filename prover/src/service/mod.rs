@@ -1,3 +1,10 @@
 #![allow(dead_code)]
+pub mod agent_service;
+pub mod api_key_service;
+pub mod finality_service;
+pub mod health_service;
+pub mod lifecycle_service;
 pub mod quote_service;
 pub mod request_service;
+pub mod usage_service;
+pub mod webhook_service;
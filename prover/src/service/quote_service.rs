@@ -1,17 +1,20 @@
-use crate::config::database::{Database, DatabaseTrait};
+use crate::config::database::Database;
 use crate::dto::quote_dto::QuoteRegisterDto;
-use crate::entity::quote::{ProofType, QuoteType, TdxQuote, TdxQuoteStatus};
+use crate::entity::quote::{ProofType, QuoteType, TdxQuote};
 use crate::entity::zk::DcapProof;
 use crate::error::db_error::DbError;
 use crate::error::quote_error::QuoteError;
-use crate::get_conn;
+use crate::entity::quote_event::QuoteActor;
+use crate::entity::verification::{DecodedOutput, VerificationSource};
+use crate::repository::quote_event_repository::{QuoteEventRepository, QuoteEventRepositoryTrait};
 use crate::repository::quote_repository::{QuoteRepository, QuoteRepositoryTrait};
+use crate::repository::unit_of_work::UnitOfWork;
+use crate::repository::verification_repository::{VerificationRepository, VerificationRepositoryTrait};
 use crate::zk::{prove, verify_proof};
 
 use dcap_rs::types::quotes::version_4::QuoteV4;
 use dcap_rs::types::VerifiedOutput;
 use sqlx::types::Uuid;
-use sqlx::Error as SqlxError;
 use std::sync::Arc;
 
 use dcap_rs::types::quotes::version_3::QuoteV3;
@@ -24,68 +27,55 @@ use dcap_rs::utils::quotes::{
 
 #[derive(Clone)]
 pub struct QuoteService {
-    quote_repo: QuoteRepository,
+    quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    quote_event_repo: QuoteEventRepository,
+    verification_repo: VerificationRepository,
     db_conn: Arc<Database>,
 }
 
 impl QuoteService {
     pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self::with_repo(db_conn, Arc::new(QuoteRepository::new(db_conn)))
+    }
+
+    pub fn with_repo(db_conn: &Arc<Database>, quote_repo: Arc<dyn QuoteRepositoryTrait>) -> Self {
         Self {
-            quote_repo: QuoteRepository::new(db_conn),
+            quote_repo,
+            quote_event_repo: QuoteEventRepository::new(db_conn),
+            verification_repo: VerificationRepository::new(db_conn),
             db_conn: Arc::clone(db_conn),
         }
     }
 
+    /// Inserts the quote and its first history entry in one transaction
     pub async fn create_quote(&self, payload: QuoteRegisterDto) -> Result<TdxQuote, DbError> {
-        let quote = self.add_quote(payload).await;
-
-        match quote {
-            Ok(quote) => Ok(quote),
-            Err(e) => match e {
-                SqlxError::Database(e) => match e.code() {
-                    Some(code) => {
-                        if code == "23000" {
-                            Err(DbError::UniqueConstraintViolation(e.to_string()))?
-                        } else {
-                            Err(DbError::SomethingWentWrong(e.to_string()))?
-                        }
-                    }
-                    _ => Err(DbError::SomethingWentWrong(e.to_string()))?,
-                },
-                _ => Err(DbError::SomethingWentWrong(e.to_string()))?,
-            },
-        }
+        let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+        let quote = self.create_quote_in(&mut uow, payload).await?;
+        uow.commit().await?;
+        Ok(quote)
     }
 
-    async fn add_quote(&self, payload: QuoteRegisterDto) -> Result<TdxQuote, SqlxError> {
+    /// `create_quote` as part of `uow`, e.g. together with the quote's request
+    pub async fn create_quote_in(&self, uow: &mut UnitOfWork, payload: QuoteRegisterDto) -> Result<TdxQuote, DbError> {
         let onchain_request_id = Uuid::parse_str(&payload.onchain_request_id).unwrap();
-        let quote = sqlx::query_as!(
-            TdxQuote,
-            r#"
-                INSERT INTO tdx_quote (onchain_request_id, status, quote)
-                VALUES ($1, $2, decode($3, 'hex'))
-                RETURNING
-                id,
-                onchain_request_id,
-                status as "status: crate::entity::quote::TdxQuoteStatus",
-                quote,
-                proof_type as "proof_type: crate::entity::quote::ProofType",
-                txn_hash,
-                created_at as "created_at: _",
-                updated_at as "updated_at: _",
-                request_id as "request_id: _"
-            "#,
-            onchain_request_id,
-            payload.status as TdxQuoteStatus,
-            String::from_utf8(payload.quote.to_vec()).unwrap(),
-        )
-        .fetch_one(get_conn!(self.db_conn.get_pool()))
-        .await?;
+        let quote = hex::decode(&payload.quote).map_err(|e| DbError::SomethingWentWrong(e.to_string()))?;
+        // A quote seen before is linked to its first registration
+        let quote = self.quote_repo.create_in(uow.conn(), onchain_request_id, payload.status, &quote).await?;
+
+        // The first history entry records where the quote started
+        let detail = match quote.duplicate_of {
+            Some(original) => format!("Registered as duplicate of {}", original),
+            None => "Registered".to_string(),
+        };
+        self.quote_event_repo
+            .create_in(uow.conn(), quote.id, None, quote.status, QuoteActor::Api, Some(detail))
+            .await?;
         Ok(quote)
     }
 
     // Verify using onchain pccs collateral
-    pub fn verify_dcap(&self, quote: TdxQuote, quote_type: Option<QuoteType>) -> Result<VerifiedOutput, QuoteError> {
+    pub async fn verify_dcap(&self, quote: TdxQuote, quote_type: Option<QuoteType>) -> Result<VerifiedOutput, QuoteError> {
+        let quote_id = quote.id;
         let quote = quote.quote;
         let quote_type = if let Some(quote_type) = quote_type {
             quote_type
@@ -96,18 +86,18 @@ impl QuoteService {
         let collateral = self.get_collateral(quote_type)?;
         let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
 
-        match quote_type {
+        let verified_output = match quote_type {
             QuoteType::DcapV3 => {
                 let dcap_quote = QuoteV3::from_bytes(&quote);
-                let verified_output = verify_quote_dcapv3(&dcap_quote, &collateral, now);
-                Ok(verified_output)
+                verify_quote_dcapv3(&dcap_quote, &collateral, now)
             }
             QuoteType::DcapV4 => {
                 let dcap_quote = QuoteV4::from_bytes(&quote);
-                let verified_output = verify_quote_dcapv4(&dcap_quote, &collateral, now);
-                Ok(verified_output)
+                verify_quote_dcapv4(&dcap_quote, &collateral, now)
             }
-        }
+        };
+        self.record_verification(quote_id, VerificationSource::Dcap, &DecodedOutput::from_output(&verified_output)).await;
+        Ok(verified_output)
     }
 
     pub fn get_collateral(&self, quote_type: QuoteType) -> Result<IntelCollateral, QuoteError> {
@@ -143,7 +133,14 @@ impl QuoteService {
             Ok(quote) => {
                 let proof = prove(quote.quote, proof_type, None).await;
                 match proof {
-                    Ok(proof) => Ok(proof.proof),
+                    Ok(proof) => {
+                        // Raw quotes carry no verified output until they are verified on-chain
+                        if !proof.proof.verified_output.is_empty() {
+                            let output = DecodedOutput::from_bytes(&proof.proof.verified_output);
+                            self.record_verification(quote.id, VerificationSource::Zkvm, &output).await;
+                        }
+                        Ok(proof.proof)
+                    }
                     _ => Err(QuoteError::Invalid),
                 }
             },
@@ -151,6 +148,13 @@ impl QuoteService {
         }
     }
 
+    /// Stores a decoded verified output, the verification itself succeeded even if this fails
+    async fn record_verification(&self, quote_id: Uuid, source: VerificationSource, output: &DecodedOutput) {
+        if let Err(e) = self.verification_repo.create(quote_id, source, output).await {
+            tracing::error!("Failed to record {} verification of quote {}: {}", source, quote_id, e);
+        }
+    }
+
     pub async fn verify(&self, proof: &DcapProof) -> Result<VerifiedOutput, QuoteError> {
         let result = verify_proof(proof).await;
         match result {
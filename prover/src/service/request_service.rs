@@ -1,23 +1,191 @@
 use crate::{
     config::database::Database,
-    repository::request_repository::{
-        OnchainRequestRepository,
-        OnchainRequestRepositoryTrait
-    }
+    dto::quote_dto::QuoteRegisterDto,
+    entity::{
+        api_key::{owns, ApiKey},
+        quote::{TdxQuote, TdxQuoteStatus},
+        quote_event::QuoteActor,
+        request::{NewOnchainRequest, OnchainRequest, OnchainRequestUpdate},
+    },
+    error::request_error::RequestError,
+    repository::{
+        quote_repository::{QuoteRepository, QuoteRepositoryTrait},
+        request_repository::{
+            OnchainRequestRepository,
+            OnchainRequestRepositoryTrait
+        },
+        unit_of_work::UnitOfWork,
+    },
+    service::{
+        lifecycle_service::{can_transition, LifecycleService},
+        quote_service::QuoteService,
+    },
 };
+use sqlx::types::Uuid;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct RequestService {
-    request_repo: OnchainRequestRepository,
+    request_repo: Arc<dyn OnchainRequestRepositoryTrait>,
+    quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    quote_service: QuoteService,
+    lifecycle: LifecycleService,
     db_conn: Arc<Database>,
 }
 
 impl RequestService {
     pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self::with_repo(
+            db_conn,
+            Arc::new(OnchainRequestRepository::new(db_conn)),
+            Arc::new(QuoteRepository::new(db_conn)),
+        )
+    }
+
+    pub fn with_repo(
+        db_conn: &Arc<Database>,
+        request_repo: Arc<dyn OnchainRequestRepositoryTrait>,
+        quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    ) -> Self {
         Self {
-            request_repo: OnchainRequestRepository::new(db_conn),
+            request_repo,
+            quote_service: QuoteService::with_repo(db_conn, Arc::clone(&quote_repo)),
+            lifecycle: LifecycleService::with_repo(db_conn, Arc::clone(&quote_repo), QuoteActor::Api),
+            quote_repo,
             db_conn: Arc::clone(db_conn),
         }
     }
+
+    /// Inserts the request and, when `quote` is set, its hex encoded quote in one transaction
+    pub async fn register(
+        &self,
+        request: NewOnchainRequest,
+        quote: Option<String>,
+    ) -> Result<(OnchainRequest, Option<TdxQuote>), RequestError> {
+        let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+        let request = self.request_repo.create_in(uow.conn(), &request).await?;
+        let quote = match quote {
+            Some(quote) => {
+                let payload = QuoteRegisterDto {
+                    proof_type: None,
+                    onchain_request_id: request.id.to_string(),
+                    quote: quote.trim_start_matches("0x").as_bytes().to_vec(),
+                    txn_hash: None,
+                    request_id: None,
+                    status: TdxQuoteStatus::Pending,
+                };
+                Some(self.quote_service.create_quote_in(&mut uow, payload).await?)
+            }
+            None => None,
+        };
+        uow.commit().await?;
+        tracing::info!("Registered onchain request {} (0x{})", request.id, hex::encode(&request.request_id));
+        Ok((request, quote))
+    }
+
+    /// Changes a request registered with `owner`, any request for admin keys
+    pub async fn update(
+        &self,
+        id: Uuid,
+        update: &OnchainRequestUpdate,
+        owner: Option<&ApiKey>,
+    ) -> Result<OnchainRequest, RequestError> {
+        self.ensure_owned(id, owner).await?;
+        match self.request_repo.update(id, update).await? {
+            Some(request) => Ok(request),
+            None => Err(self.inactive_error(id).await),
+        }
+    }
+
+    /// Cancels a request registered with `owner`, and every quote of it that was not submitted yet
+    pub async fn cancel(&self, id: Uuid, owner: Option<&ApiKey>) -> Result<OnchainRequest, RequestError> {
+        self.ensure_owned(id, owner).await?;
+        let Some(request) = self.request_repo.cancel(id).await? else {
+            return Err(self.inactive_error(id).await);
+        };
+        for quote in self.quote_repo.find_all_by_onchain_request_id(id, None).await {
+            if !can_transition(quote.status, TdxQuoteStatus::Cancelled, quote.proof_type) {
+                continue;
+            }
+            // The request stays cancelled, a quote left behind is cancelled once it is picked up
+            let detail = Some("Request cancelled".to_string());
+            if let Err(e) = self.lifecycle.transition(&quote, TdxQuoteStatus::Cancelled, None, None, detail).await {
+                tracing::warn!("Failed to cancel quote {} of request {}: {}", quote.id, id, e);
+            }
+        }
+        tracing::info!("Cancelled onchain request {}", id);
+        Ok(request)
+    }
+
+    /// Requests of other keys are reported missing, so their ids are not confirmed either
+    async fn ensure_owned(&self, id: Uuid, owner: Option<&ApiKey>) -> Result<(), RequestError> {
+        match self.request_repo.find(id).await {
+            Ok(request) if owns(owner, request.api_key_id) => Ok(()),
+            _ => Err(RequestError::NotFound),
+        }
+    }
+
+    /// Why a request could not be changed, it is either missing or cancelled
+    async fn inactive_error(&self, id: Uuid) -> RequestError {
+        match self.request_repo.find(id).await {
+            Ok(request) if request.is_cancelled => RequestError::Cancelled,
+            _ => RequestError::NotFound,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+    use sqlx::types::Uuid;
+
+    use super::RequestService;
+    use crate::{
+        config::database::Database,
+        entity::{
+            api_key::{hash_key, ApiKey, ApiScope},
+            request::{OnchainRequest, OnchainRequestUpdate},
+        },
+        error::request_error::RequestError,
+        repository::memory_repository::{InMemoryOnchainRequestRepository, InMemoryQuoteRepository, MemoryStore},
+    };
+
+    fn api_key(scope: ApiScope) -> ApiKey {
+        ApiKey {
+            id: Uuid::new_v4(),
+            name: "ci".to_string(),
+            prefix: "tdxp_00000000".to_string(),
+            key_hash: hash_key("tdxp_00"),
+            scopes: vec![scope],
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn only_the_owner_changes_a_request() {
+        let owner = api_key(ApiScope::Register);
+        let store = MemoryStore::default();
+        let request = OnchainRequest { id: Uuid::new_v4(), api_key_id: Some(owner.id), ..OnchainRequest::fixture() };
+        store.insert_request(request.clone());
+        // Nothing reaches the database, the pool never connects
+        let db = Arc::new(Database::connect("postgres://localhost/unused").unwrap());
+        let service = RequestService::with_repo(
+            &db,
+            Arc::new(InMemoryOnchainRequestRepository::with_store(&store)),
+            Arc::new(InMemoryQuoteRepository::with_store(&store)),
+        );
+        let update = OnchainRequestUpdate { fee_wei: Some(7), ..Default::default() };
+
+        let other = api_key(ApiScope::Register);
+        assert!(matches!(service.update(request.id, &update, Some(&other)).await, Err(RequestError::NotFound)));
+        assert!(matches!(service.cancel(request.id, Some(&other)).await, Err(RequestError::NotFound)));
+
+        assert_eq!(service.update(request.id, &update, Some(&owner)).await.unwrap().fee_wei, 7);
+        let admin = api_key(ApiScope::Admin);
+        assert!(service.cancel(request.id, Some(&admin)).await.unwrap().is_cancelled);
+    }
 }
@@ -0,0 +1,164 @@
+use crate::{
+    config::database::Database,
+    entity::{
+        api_key::{ApiKey, ApiScope},
+        usage::{rate_limit, rate_window, Allowance, ProvingQuota, QuotaPeriod, QuotaSubject},
+    },
+    error::usage_error::UsageError,
+    repository::{
+        unit_of_work::UnitOfWork,
+        usage_repository::{UsageRepository, UsageRepositoryTrait},
+    },
+};
+use chrono::{DateTime, Utc};
+use sqlx::types::Uuid;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct UsageService {
+    usage_repo: UsageRepository,
+    db_conn: Arc<Database>,
+}
+
+impl UsageService {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            usage_repo: UsageRepository::new(db_conn),
+            db_conn: Arc::clone(db_conn),
+        }
+    }
+
+    /// Counts a request of the key to `route`, `None` when the scope of the route has no limit
+    pub async fn hit_rate_limit(&self, api_key: &ApiKey, route: &str, scope: ApiScope) -> Result<Option<Allowance>, UsageError> {
+        let limit = rate_limit(scope);
+        if limit <= 0 {
+            return Ok(None);
+        }
+        let (window_start, reset_at) = rate_window(Utc::now());
+        let bucket = format!("rate:{}:{}", api_key.id, route);
+
+        let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+        let count = self.usage_repo.increment_in(uow.conn(), &bucket, window_start, limit).await?;
+        uow.commit().await?;
+        match count {
+            Some(count) => Ok(Some(Allowance { limit, remaining: limit - count, reset_at })),
+            None => Err(UsageError::RateLimited(Allowance { limit, remaining: 0, reset_at })),
+        }
+    }
+
+    /// Counts a proof against every quota of the subjects, either all of them or none. Returns
+    /// the quota with the least left, `None` when no quota applies.
+    pub async fn consume_proof(&self, subjects: &[QuotaSubject]) -> Result<Option<Allowance>, UsageError> {
+        self.consume_proof_at(subjects, Utc::now()).await
+    }
+
+    /// `consume_proof` in the windows that `now` falls in
+    pub async fn consume_proof_at(&self, subjects: &[QuotaSubject], now: DateTime<Utc>) -> Result<Option<Allowance>, UsageError> {
+        let quotas = self.usage_repo.find_quotas(subjects).await?;
+        if quotas.is_empty() {
+            return Ok(None);
+        }
+
+        let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+        let mut tightest: Option<Allowance> = None;
+        for quota in &quotas {
+            let (window_start, reset_at) = quota.period.window(now);
+            let count = self
+                .usage_repo
+                .increment_in(uow.conn(), &proof_bucket(quota), window_start, quota.proof_limit)
+                .await?;
+            let Some(count) = count else {
+                uow.rollback().await?;
+                tracing::info!("Proving quota {} of {} exhausted", quota.period, quota.subject);
+                return Err(UsageError::QuotaExceeded(Allowance { limit: quota.proof_limit, remaining: 0, reset_at }));
+            };
+            let allowance = Allowance { limit: quota.proof_limit, remaining: quota.proof_limit - count, reset_at };
+            if tightest.is_none_or(|tightest| allowance.remaining < tightest.remaining) {
+                tightest = Some(allowance);
+            }
+        }
+        uow.commit().await?;
+        Ok(tightest)
+    }
+
+    /// Gives back a proof counted by `consume_proof` at `charged_at`, e.g. when proving failed
+    pub async fn refund_proof(&self, subjects: &[QuotaSubject], charged_at: DateTime<Utc>) -> Result<(), UsageError> {
+        let quotas = self.usage_repo.find_quotas(subjects).await?;
+        if quotas.is_empty() {
+            return Ok(());
+        }
+
+        let mut uow = UnitOfWork::begin(&self.db_conn).await?;
+        for quota in &quotas {
+            let (window_start, _) = quota.period.window(charged_at);
+            self.usage_repo.decrement_in(uow.conn(), &proof_bucket(quota), window_start).await?;
+        }
+        uow.commit().await?;
+        Ok(())
+    }
+
+    pub async fn set_quota(&self, subject: &QuotaSubject, period: QuotaPeriod, proof_limit: i32) -> Result<ProvingQuota, UsageError> {
+        let quota = self.usage_repo.set_quota(subject, period, proof_limit).await?;
+        tracing::info!("Set {} proving quota of {} to {}", quota.period, quota.subject, quota.proof_limit);
+        Ok(quota)
+    }
+
+    /// Every quota with the proofs counted in its current window
+    pub async fn find_all_quotas(&self) -> Result<Vec<(ProvingQuota, i32)>, UsageError> {
+        let now = Utc::now();
+        let mut quotas = vec![];
+        for quota in self.usage_repo.find_all_quotas().await? {
+            let (window_start, _) = quota.period.window(now);
+            let used = self.usage_repo.find_count(&proof_bucket(&quota), window_start).await?;
+            quotas.push((quota, used));
+        }
+        Ok(quotas)
+    }
+
+    pub async fn delete_quota(&self, id: Uuid) -> Result<(), UsageError> {
+        match self.usage_repo.delete_quota(id).await? {
+            true => Ok(()),
+            false => Err(UsageError::NotFound),
+        }
+    }
+
+    /// Deletes counters of windows that started before `before`, only current windows are read
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<u64, UsageError> {
+        Ok(self.usage_repo.prune(before).await?)
+    }
+}
+
+/// Day and month quotas of a subject are counted separately
+fn proof_bucket(quota: &ProvingQuota) -> String {
+    format!("prove:{}:{}", quota.subject, quota.period)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::UsageService;
+    use crate::{
+        entity::usage::{QuotaPeriod, QuotaSubject},
+        error::usage_error::UsageError,
+        repository::testing::{random_bytes, test_database},
+    };
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn refunded_proofs_free_the_quota() {
+        let db = test_database().await;
+        let service = UsageService::new(&db);
+        let creator = QuotaSubject::Creator(format!("0x{}", hex::encode(&random_bytes()[..20])));
+        service.set_quota(&creator, QuotaPeriod::Day, 1).await.unwrap();
+        let subjects = [creator];
+
+        let charged_at = Utc::now();
+        let allowance = service.consume_proof_at(&subjects, charged_at).await.unwrap().unwrap();
+        assert_eq!((allowance.limit, allowance.remaining), (1, 0));
+        assert!(matches!(service.consume_proof(&subjects).await, Err(UsageError::QuotaExceeded(_))));
+
+        service.refund_proof(&subjects, charged_at).await.unwrap();
+        assert_eq!(service.consume_proof(&subjects).await.unwrap().map(|allowance| allowance.remaining), Some(0));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{
+    config::{database::Database, parameter},
+    entity::{
+        api_key::{owns, ApiKey, ApiScope},
+        webhook::{
+            allows_local_targets, check_webhook_url, generate_secret, is_public_address, retry_delay, sign,
+            DueDelivery, NewWebhook, Webhook, WebhookDelivery, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER,
+        },
+    },
+    error::webhook_error::WebhookError,
+    repository::{
+        request_repository::{OnchainRequestRepository, OnchainRequestRepositoryTrait},
+        webhook_repository::{WebhookRepository, WebhookRepositoryTrait},
+    },
+};
+use anyhow::Result;
+use chrono::Utc;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use sqlx::types::Uuid;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+/// Outcome of one dispatch pass
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DispatchSummary {
+    pub delivered: usize,
+    pub retrying: usize,
+    pub failed: usize,
+}
+
+/// Resolves receiver names to their public addresses only, so a name registered while public
+/// cannot later be pointed at the network the prover runs in
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?
+                .filter(|addr| is_public_address(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Registers webhooks and sends the deliveries `LifecycleService` queues on status changes
+#[derive(Clone)]
+pub struct WebhookService {
+    webhook_repo: WebhookRepository,
+    request_repo: OnchainRequestRepository,
+    http: reqwest::Client,
+    /// `WEBHOOK_ALLOW_LOCAL`, lets local development deliver to plain http and local receivers
+    allow_local: bool,
+}
+
+impl WebhookService {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        let timeout = parameter::get("WEBHOOK_TIMEOUT_SECS", Some("10")).parse().unwrap_or(10);
+        let allow_local = allows_local_targets();
+        // A redirect could lead anywhere, receivers have to answer themselves
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(timeout))
+            .redirect(reqwest::redirect::Policy::none());
+        let http = match allow_local {
+            true => http,
+            false => http.dns_resolver(Arc::new(PublicResolver)),
+        };
+        Self {
+            webhook_repo: WebhookRepository::new(db_conn),
+            request_repo: OnchainRequestRepository::new(db_conn),
+            http: http.build().unwrap_or_default(),
+            allow_local,
+        }
+    }
+
+    /// Rejects URLs that are, or whose host resolves to, anything but a public address
+    async fn ensure_public_url(&self, url: &str) -> Result<(), WebhookError> {
+        let url = check_webhook_url(url, self.allow_local).map_err(|code| WebhookError::ForbiddenUrl(code.to_string()))?;
+        if self.allow_local {
+            return Ok(());
+        }
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| WebhookError::ForbiddenUrl(format!("{} could not be resolved", host)))?
+            .collect();
+        match addrs.iter().all(|addr| is_public_address(addr.ip())) {
+            true => Ok(()),
+            false => Err(WebhookError::ForbiddenUrl(format!("{} resolves to a local address", host))),
+        }
+    }
+
+    /// Stores the webhook with a new signing secret. Webhooks of a request may only be registered
+    /// by the key the request was registered with.
+    pub async fn register(&self, owner: Option<&ApiKey>, webhook: NewWebhook) -> Result<Webhook, WebhookError> {
+        match webhook.onchain_request_id {
+            Some(id) => {
+                let request = self.request_repo.find(id).await.map_err(|_| WebhookError::RequestNotFound)?;
+                if request.api_key_id.is_some() && !owns(owner, request.api_key_id) {
+                    return Err(WebhookError::RequestNotFound);
+                }
+            }
+            None if webhook.api_key_id.is_none() => return Err(WebhookError::MissingTarget),
+            None => {}
+        }
+        self.ensure_public_url(&webhook.url).await?;
+        let webhook = self.webhook_repo.create(&webhook, &generate_secret()).await?;
+        tracing::info!("Registered webhook {} for {:?}", webhook.id, webhook.events);
+        Ok(webhook)
+    }
+
+    pub async fn find_all(&self, owner: Option<&ApiKey>) -> Result<Vec<Webhook>, WebhookError> {
+        let api_key_id = owner.filter(|owner| !owner.allows(ApiScope::Admin)).map(|owner| owner.id);
+        Ok(self.webhook_repo.find_all(api_key_id).await?)
+    }
+
+    pub async fn find(&self, owner: Option<&ApiKey>, id: Uuid) -> Result<Webhook, WebhookError> {
+        match self.webhook_repo.find(id).await? {
+            Some(webhook) if owns(owner, webhook.api_key_id) => Ok(webhook),
+            _ => Err(WebhookError::NotFound),
+        }
+    }
+
+    pub async fn disable(&self, owner: Option<&ApiKey>, id: Uuid) -> Result<Webhook, WebhookError> {
+        self.find(owner, id).await?;
+        let webhook = self.webhook_repo.disable(id).await?.ok_or(WebhookError::NotFound)?;
+        tracing::info!("Disabled webhook {}", webhook.id);
+        Ok(webhook)
+    }
+
+    /// Most recent deliveries of the webhook first
+    pub async fn deliveries(&self, owner: Option<&ApiKey>, id: Uuid, limit: i64) -> Result<Vec<WebhookDelivery>, WebhookError> {
+        self.find(owner, id).await?;
+        Ok(self.webhook_repo.find_deliveries(id, limit).await?)
+    }
+
+    /// Queues the delivery again as a new delivery, whatever the outcome of the original was
+    pub async fn replay(&self, owner: Option<&ApiKey>, delivery_id: Uuid) -> Result<WebhookDelivery, WebhookError> {
+        let delivery = self.webhook_repo.find_delivery(delivery_id).await?.ok_or(WebhookError::DeliveryNotFound)?;
+        self.find(owner, delivery.webhook_id).await.map_err(|_| WebhookError::DeliveryNotFound)?;
+        let replay = self.webhook_repo.replay(delivery.id).await?.ok_or(WebhookError::DeliveryNotFound)?;
+        tracing::info!("Replaying webhook delivery {} as {}", delivery.id, replay.id);
+        Ok(replay)
+    }
+
+    /// Sends up to `max_count` due deliveries. Failed attempts are retried after
+    /// `WEBHOOK_RETRY_BASE_SECS`, doubled after every attempt, until `WEBHOOK_MAX_ATTEMPTS`.
+    pub async fn dispatch_due(&self, max_count: i64) -> Result<DispatchSummary> {
+        let max_attempts = parameter::get("WEBHOOK_MAX_ATTEMPTS", Some("8")).parse().unwrap_or(8);
+        let base = chrono::Duration::seconds(parameter::get("WEBHOOK_RETRY_BASE_SECS", Some("30")).parse().unwrap_or(30));
+        // Long enough for every claimed delivery to time out before another dispatcher takes it
+        let lease_secs = 60 + max_count * parameter::get("WEBHOOK_TIMEOUT_SECS", Some("10")).parse().unwrap_or(10);
+        let deliveries = self.webhook_repo.claim_due(max_count, lease_secs).await?;
+        let mut summary = DispatchSummary::default();
+
+        for delivery in deliveries {
+            let attempts = delivery.attempts + 1;
+            // Literal addresses skip the resolver, webhooks registered before the check are caught here
+            let sent = match check_webhook_url(&delivery.url, self.allow_local) {
+                Ok(_) => send(&self.http, &delivery).await,
+                Err(code) => Err((None, format!("Webhook URL is not allowed: {}", code))),
+            };
+            match sent {
+                Ok(status) => {
+                    tracing::info!("Delivered {} webhook {} ({})", delivery.event, delivery.id, status);
+                    self.webhook_repo.mark_delivered(delivery.id, attempts, status as i32).await?;
+                    summary.delivered += 1;
+                }
+                Err((status, error)) => {
+                    let next_attempt_at = match attempts < max_attempts {
+                        true => Some(Utc::now() + retry_delay(base, attempts)),
+                        false => None,
+                    };
+                    match next_attempt_at {
+                        Some(at) => {
+                            tracing::warn!("Webhook delivery {} failed (attempt {}), retrying at {}: {}", delivery.id, attempts, at, error);
+                            summary.retrying += 1;
+                        }
+                        None => {
+                            tracing::error!("Webhook delivery {} failed after {} attempts: {}", delivery.id, attempts, error);
+                            summary.failed += 1;
+                        }
+                    }
+                    let status = status.map(|status| status as i32);
+                    self.webhook_repo.mark_attempt_failed(delivery.id, attempts, status, &error, next_attempt_at).await?;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Dispatches every `WEBHOOK_DISPATCH_INTERVAL_SECS` for as long as the process runs
+    pub async fn run(self) {
+        let interval = parameter::get("WEBHOOK_DISPATCH_INTERVAL_SECS", Some("5")).parse().unwrap_or(5);
+        loop {
+            match self.dispatch_due(100).await {
+                Ok(summary) if summary.delivered + summary.retrying + summary.failed > 0 => {
+                    tracing::info!("Webhook dispatch: {:?}", summary);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("Webhook dispatch failed: {}", e),
+            }
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    }
+}
+
+/// POSTs the signed payload, any 2xx answer counts as delivered. Errors carry the response status
+/// when one was received.
+pub async fn send(http: &reqwest::Client, delivery: &DueDelivery) -> Result<u16, (Option<u16>, String)> {
+    let signature = sign(&delivery.secret, Utc::now().timestamp(), &delivery.payload);
+    let response = http
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .header(EVENT_HEADER, delivery.event.to_string())
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(|e| (None, e.without_url().to_string()))?;
+    let status = response.status();
+    match status.is_success() {
+        true => Ok(status.as_u16()),
+        false => Err((Some(status.as_u16()), format!("Receiver answered {}", status))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    use super::*;
+    use crate::entity::webhook::{verify_signature, WebhookEvent};
+
+    /// Minimal stand-in for a webhook receiver that answers `status` and passes on every request
+    async fn spawn_receiver(status: u16) -> (String, mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (requests, received) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 8192];
+                let mut len = 0;
+                // read until the JSON body has been received
+                while !String::from_utf8_lossy(&buf[..len]).ends_with('}') {
+                    len += socket.read(&mut buf[len..]).await.unwrap();
+                }
+                requests.send(String::from_utf8_lossy(&buf[..len]).to_string()).unwrap();
+                let response = format!("HTTP/1.1 {} Test\r\ncontent-length: 0\r\nconnection: close\r\n\r\n", status);
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn delivery(url: String) -> DueDelivery {
+        DueDelivery {
+            id: Uuid::from_u128(7),
+            event: WebhookEvent::Confirmed,
+            payload: r#"{"event":"confirmed"}"#.to_string(),
+            attempts: 0,
+            url,
+            secret: "whsec_test".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_signed_payload() {
+        let (url, mut received) = spawn_receiver(204).await;
+        let delivery = delivery(url);
+
+        assert_eq!(send(&reqwest::Client::new(), &delivery).await, Ok(204));
+
+        let request = received.recv().await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /hook "));
+        assert!(head.contains("x-tdx-prover-event: confirmed"));
+        assert!(head.contains(&format!("x-tdx-prover-delivery: {}", delivery.id)));
+        let signature = head.lines()
+            .find_map(|line| line.strip_prefix("x-tdx-prover-signature: "))
+            .unwrap();
+        assert!(verify_signature("whsec_test", signature, body));
+        assert_eq!(body, delivery.payload);
+    }
+
+    #[tokio::test]
+    async fn rejected_delivery_keeps_status() {
+        let (url, _received) = spawn_receiver(500).await;
+
+        let (status, _) = send(&reqwest::Client::new(), &delivery(url)).await.unwrap_err();
+        assert_eq!(status, Some(500));
+
+        let (status, _) = send(&reqwest::Client::new(), &delivery("http://127.0.0.1:9/hook".to_string())).await.unwrap_err();
+        assert_eq!(status, None);
+    }
+
+    #[tokio::test]
+    async fn names_of_local_receivers_are_not_resolved() {
+        let (url, mut received) = spawn_receiver(204).await;
+        let http = reqwest::Client::builder().dns_resolver(Arc::new(PublicResolver)).build().unwrap();
+
+        let url = url.replace("127.0.0.1", "localhost");
+        let (status, _) = send(&http, &delivery(url)).await.unwrap_err();
+        assert_eq!(status, None);
+        assert!(received.try_recv().is_err());
+    }
+}
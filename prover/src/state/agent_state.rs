@@ -0,0 +1,26 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::repository::agent_repository::{AgentRepository, AgentRepositoryTrait};
+use crate::repository::quote_repository::{QuoteRepository, QuoteRepositoryTrait};
+use crate::repository::request_repository::{OnchainRequestRepository, OnchainRequestRepositoryTrait};
+use crate::service::agent_service::AgentService;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct AgentState {
+    pub agent_service: AgentService,
+    pub agent_repo: AgentRepository,
+    pub quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    pub request_repo: Arc<dyn OnchainRequestRepositoryTrait>,
+}
+
+impl AgentState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            agent_service: AgentService::new(db_conn),
+            agent_repo: AgentRepository::new(db_conn),
+            quote_repo: Arc::new(QuoteRepository::new(db_conn)),
+            request_repo: Arc::new(OnchainRequestRepository::new(db_conn)),
+        }
+    }
+}
@@ -0,0 +1,21 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::config::parameter;
+use crate::service::api_key_service::ApiKeyService;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct AuthState {
+    pub api_key_service: ApiKeyService,
+    /// API keys are required unless `API_AUTH_ENABLED` is set to false, e.g. for local development
+    pub enabled: bool,
+}
+
+impl AuthState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            api_key_service: ApiKeyService::new(db_conn),
+            enabled: parameter::get("API_AUTH_ENABLED", Some("true")) != "false",
+        }
+    }
+}
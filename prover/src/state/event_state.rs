@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::repository::quote_repository::{QuoteRepository, QuoteRepositoryTrait};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct EventState {
+    pub quote_repo: Arc<dyn QuoteRepositoryTrait>,
+}
+
+impl EventState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            quote_repo: Arc::new(QuoteRepository::new(db_conn)),
+        }
+    }
+}
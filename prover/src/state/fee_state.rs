@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::repository::fee_repository::{TransactionFeeRepository, TransactionFeeRepositoryTrait};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct FeeState {
+    pub fee_repo: TransactionFeeRepository,
+}
+
+impl FeeState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            fee_repo: TransactionFeeRepository::new(db_conn),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::service::health_service::HealthService;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct HealthState {
+    pub health_service: HealthService,
+}
+
+impl HealthState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            health_service: HealthService::new(db_conn),
+        }
+    }
+}
@@ -1,2 +1,10 @@
+pub mod agent_state;
+pub mod auth_state;
+pub mod event_state;
+pub mod fee_state;
+pub mod health_state;
 pub mod request_state;
 pub mod quote_state;
+pub mod usage_state;
+pub mod verification_state;
+pub mod webhook_state;
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 use crate::config::database::Database;
+use crate::repository::quote_event_repository::{QuoteEventRepository, QuoteEventRepositoryTrait};
 use crate::repository::quote_repository::{QuoteRepository, QuoteRepositoryTrait};
 use crate::service::quote_service::QuoteService;
 use std::sync::Arc;
@@ -7,14 +8,22 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct QuoteState {
     pub quote_service: QuoteService,
-    pub quote_repo: QuoteRepository,
+    pub quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    pub quote_event_repo: QuoteEventRepository,
 }
 
 impl QuoteState {
     pub fn new(db_conn: &Arc<Database>) -> QuoteState {
+        Self::with_repo(db_conn, Arc::new(QuoteRepository::new(db_conn)))
+    }
+
+    /// Reads and transitions quotes through `quote_repo`, e.g. an `InMemoryQuoteRepository` in
+    /// handler tests. Creating quotes still takes a database transaction.
+    pub fn with_repo(db_conn: &Arc<Database>, quote_repo: Arc<dyn QuoteRepositoryTrait>) -> QuoteState {
         Self {
-            quote_service: QuoteService::new(db_conn),
-            quote_repo: QuoteRepository::new(db_conn),
+            quote_service: QuoteService::with_repo(db_conn, Arc::clone(&quote_repo)),
+            quote_repo,
+            quote_event_repo: QuoteEventRepository::new(db_conn),
         }
     }
 }
@@ -1,21 +1,34 @@
 #![allow(dead_code)]
 use crate::config::database::Database;
+use crate::repository::quote_repository::{QuoteRepository, QuoteRepositoryTrait};
 use crate::repository::request_repository::{OnchainRequestRepository, OnchainRequestRepositoryTrait};
 use crate::service::request_service::RequestService;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct RequestState {
-    #[allow(dead_code)]
     pub request_service: RequestService,
-    pub request_repo: OnchainRequestRepository,
+    pub request_repo: Arc<dyn OnchainRequestRepositoryTrait>,
 }
 
 impl RequestState {
     pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self::with_repo(
+            db_conn,
+            Arc::new(OnchainRequestRepository::new(db_conn)),
+            Arc::new(QuoteRepository::new(db_conn)),
+        )
+    }
+
+    /// Reads requests through `request_repo`, e.g. an `InMemoryOnchainRequestRepository`
+    pub fn with_repo(
+        db_conn: &Arc<Database>,
+        request_repo: Arc<dyn OnchainRequestRepositoryTrait>,
+        quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    ) -> Self {
         Self {
-            request_service: RequestService::new(db_conn),
-            request_repo: OnchainRequestRepository::new(db_conn),
+            request_service: RequestService::with_repo(db_conn, Arc::clone(&request_repo), quote_repo),
+            request_repo,
         }
     }
 }
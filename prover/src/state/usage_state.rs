@@ -0,0 +1,23 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::repository::quote_repository::{QuoteRepository, QuoteRepositoryTrait};
+use crate::repository::request_repository::{OnchainRequestRepository, OnchainRequestRepositoryTrait};
+use crate::service::usage_service::UsageService;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct UsageState {
+    pub usage_service: UsageService,
+    pub quote_repo: Arc<dyn QuoteRepositoryTrait>,
+    pub request_repo: Arc<dyn OnchainRequestRepositoryTrait>,
+}
+
+impl UsageState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            usage_service: UsageService::new(db_conn),
+            quote_repo: Arc::new(QuoteRepository::new(db_conn)),
+            request_repo: Arc::new(OnchainRequestRepository::new(db_conn)),
+        }
+    }
+}
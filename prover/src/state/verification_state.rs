@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::repository::verification_repository::{VerificationRepository, VerificationRepositoryTrait};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct VerificationState {
+    pub verification_repo: VerificationRepository,
+}
+
+impl VerificationState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            verification_repo: VerificationRepository::new(db_conn),
+        }
+    }
+}
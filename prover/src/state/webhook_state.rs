@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+use crate::config::database::Database;
+use crate::service::webhook_service::WebhookService;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct WebhookState {
+    pub webhook_service: WebhookService,
+}
+
+impl WebhookState {
+    pub fn new(db_conn: &Arc<Database>) -> Self {
+        Self {
+            webhook_service: WebhookService::new(db_conn),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Prometheus metrics of the API and the proving pipeline.
+//!
+//! Nothing is recorded until `install` is called, so library users and tests pay nothing. The
+//! API renders the metrics on `/api/metrics`, the lambda and the CLI workers push them to the
+//! Pushgateway at `METRICS_PUSHGATEWAY_URL`.
+
+use std::{
+    future::Future,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::{
+    config::parameter,
+    entity::{
+        attempt::{AttemptStage, ErrorCategory},
+        fee::TransactionCost,
+        quote::{ProofType, TdxQuoteStatus},
+        zk::ProofSystem,
+    },
+    repository::quote_repository::QuoteRepositoryTrait,
+};
+
+/// Seconds, from a fast RPC read up to a slow proving network request
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.025, 0.1, 0.5, 1.0, 2.5, 5.0, 15.0, 60.0, 180.0, 600.0, 1800.0];
+
+const QUOTE_STATUSES: &[TdxQuoteStatus] = &[
+    TdxQuoteStatus::Pending,
+    TdxQuoteStatus::Failure,
+    TdxQuoteStatus::Success,
+    TdxQuoteStatus::Expired,
+    TdxQuoteStatus::Cancelled,
+    TdxQuoteStatus::Submitted,
+    TdxQuoteStatus::Confirmed,
+    TdxQuoteStatus::CollateralFetched,
+    TdxQuoteStatus::Proving,
+    TdxQuoteStatus::Proved,
+];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+fn builder() -> PrometheusBuilder {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)
+        .expect("latency buckets are not empty")
+}
+
+/// Installs the global recorder on the first call, later calls return the same handle
+pub fn install() -> PrometheusHandle {
+    HANDLE
+        .get_or_init(|| builder().install_recorder().expect("no other metrics recorder is installed"))
+        .clone()
+}
+
+/// `install` for the lambda and the CLI workers. With `METRICS_PUSHGATEWAY_URL` set, the exporter
+/// replaces the metrics of `job` on the Pushgateway every `METRICS_PUSH_INTERVAL_SECS`. Must be
+/// called inside the tokio runtime.
+pub fn install_push(job: &str) -> PrometheusHandle {
+    let Some(endpoint) = push_endpoint(job) else {
+        return install();
+    };
+    HANDLE
+        .get_or_init(|| {
+            let interval = parameter::get("METRICS_PUSH_INTERVAL_SECS", Some("30")).parse().unwrap_or(30);
+            let exporter = builder()
+                .with_push_gateway(&endpoint, Duration::from_secs(interval.max(1)), None, None)
+                .and_then(|builder| builder.build());
+            match exporter {
+                Ok((recorder, exporter)) => {
+                    let handle = recorder.handle();
+                    metrics::set_global_recorder(recorder).expect("no other metrics recorder is installed");
+                    // Runs until the process exits, failed pushes are logged by the exporter
+                    tokio::spawn(exporter);
+                    handle
+                }
+                Err(e) => {
+                    tracing::warn!("Metrics are not pushed, invalid METRICS_PUSHGATEWAY_URL: {}", e);
+                    builder().install_recorder().expect("no other metrics recorder is installed")
+                }
+            }
+        })
+        .clone()
+}
+
+/// Pushgateway group of this process, `None` without `METRICS_PUSHGATEWAY_URL`. Each lambda
+/// container and CLI process counts from zero, so each gets its own group.
+fn push_endpoint(job: &str) -> Option<String> {
+    let url = parameter::get("METRICS_PUSHGATEWAY_URL", Some(""));
+    if url.is_empty() {
+        return None;
+    }
+    let instance = std::env::var("METRICS_INSTANCE")
+        .or_else(|_| std::env::var("AWS_LAMBDA_LOG_STREAM_NAME"))
+        .unwrap_or_else(|_| std::process::id().to_string());
+    Some(format!("{}/metrics/job/{}/instance/{}", url.trim_end_matches('/'), job, instance.replace('/', "_")))
+}
+
+/// Text exposition of every metric, empty when `install` was never called
+pub fn render() -> String {
+    match HANDLE.get() {
+        Some(handle) => {
+            handle.run_upkeep();
+            handle.render()
+        }
+        None => String::new(),
+    }
+}
+
+pub fn record_http(method: &str, route: &str, status: u16, elapsed: Duration) {
+    let labels = [
+        ("method", method.to_string()),
+        ("route", route.to_string()),
+        ("status", status.to_string()),
+    ];
+    counter!("http_requests_total", &labels).increment(1);
+    histogram!("http_request_duration_seconds", &labels).record(elapsed.as_secs_f64());
+}
+
+/// Runs a read of the on-chain PCCS and records its latency under `dao`, e.g. `pcs`
+pub async fn time_collateral<T>(dao: &'static str, fetch: impl Future<Output = anyhow::Result<T>>) -> anyhow::Result<T> {
+    let started = Instant::now();
+    let result = fetch.await;
+    let labels = [("dao", dao), ("outcome", outcome(result.is_ok()))];
+    histogram!("collateral_fetch_duration_seconds", &labels).record(started.elapsed().as_secs_f64());
+    result
+}
+
+pub fn record_proof(proof_type: ProofType, proof_system: Option<ProofSystem>, elapsed: Duration, success: bool) {
+    let labels = [
+        ("backend", proof_type.to_string()),
+        ("proof_system", proof_system.unwrap_or(ProofSystem::Groth16).to_string()),
+        ("outcome", outcome(success).to_string()),
+    ];
+    histogram!("proof_duration_seconds", &labels).record(elapsed.as_secs_f64());
+}
+
+pub fn record_attempt_failure(stage: AttemptStage, category: ErrorCategory) {
+    counter!("prover_failures_total", "stage" => stage.to_string(), "category" => category.to_string()).increment(1);
+}
+
+/// `outcome` is one of `mined`, `reverted`, `no_receipt`, `send_failed`, `verified` or `rejected`
+pub fn record_submission(outcome: &'static str) {
+    counter!("proof_submissions_total", "outcome" => outcome).increment(1);
+}
+
+pub fn record_gas(cost: &TransactionCost) {
+    let success = cost.success.to_string();
+    counter!("gas_used_total", "success" => success.clone()).increment(cost.gas_used);
+    counter!("fee_wei_total", "success" => success).increment(cost.fee_wei.saturating_to::<u64>());
+}
+
+/// Sets the `quotes` gauge of every status from the database
+pub async fn refresh_quote_statuses(quote_repo: &dyn QuoteRepositoryTrait) {
+    let counts = match quote_repo.count_by_status().await {
+        Ok(counts) => counts,
+        Err(e) => {
+            tracing::warn!("Failed to refresh quote status gauges: {}", e);
+            return;
+        }
+    };
+    for status in QUOTE_STATUSES {
+        let count = counts.iter().find(|(counted, _)| counted == status).map_or(0, |(_, count)| *count);
+        gauge!("quotes", "status" => status.to_string()).set(count as f64);
+    }
+}
+
+/// Pushes right away instead of waiting for the exporter's next interval, for processes that
+/// exit or are frozen before it. Does nothing without `METRICS_PUSHGATEWAY_URL`, failures are
+/// only logged.
+pub async fn push(job: &str) {
+    let Some(url) = push_endpoint(job).filter(|_| HANDLE.get().is_some()) else {
+        return;
+    };
+    let response = reqwest::Client::new().put(&url).body(render()).send().await;
+    match response.and_then(|response| response.error_for_status()) {
+        Ok(_) => tracing::debug!("Pushed metrics to {}", url),
+        Err(e) => tracing::warn!("Failed to push metrics to {}: {}", url, e),
+    }
+}
+
+fn outcome(success: bool) -> &'static str {
+    match success {
+        true => "success",
+        false => "failure",
+    }
+}
@@ -4,21 +4,27 @@ pub mod risc0;
 pub mod sp1;
 
 use crate::entity::request::OnchainRequest;
+use crate::entity::fee::TransactionCost;
 use crate::entity::quote::{ProofType, TdxQuoteStatus};
 use crate::chain::attestation::{decode_attestation_ret_data, generate_attestation_calldata, generate_prove_calldata};
 use crate::chain::pccs::enclave_id::{get_enclave_identity, EnclaveIdType};
 use crate::chain::pccs::fmspc_tcb::get_tcb_info;
 use crate::chain::pccs::pcs::get_certificate_by_id;
 use crate::chain::pccs::pcs::IPCSDao::CA;
-use crate::chain::TxSender;
+use crate::chain::{rpc::RpcPool, signer::SignerConfig, simulation::{CodeOverride, SimulationResult, StateSnapshot}, TxSender};
 use crate::config::parameter;
+use crate::telemetry;
+use crate::error::prove_error::ProveError;
 use crate::chain::constants::AUTOMATA_DEFAULT_DCAP_CONTRACT;
 use crate::chain::pccs::parser::get_pck_fmspc_and_issuer;
 use crate::entity::zk::{DcapProof, ProofResponse, ProofSystem, SubmitProofResponse, ZkvmProof};
 use crate::zk::sp1::prove as sp1_prove;
 use crate::zk::risc0::prove as risc0_prove;
 
-use alloy::primitives::TxHash;
+use std::path::Path;
+use std::time::Instant;
+
+use alloy::primitives::{Address, TxHash};
 use alloy_chains::NamedChain;
 use anyhow::{anyhow, Result};
 use dcap_rs::constants::{SGX_TEE_TYPE, TDX_TEE_TYPE};
@@ -27,8 +33,29 @@ use sp1_sdk::ProverClient;
 
 // proof_system: [Optional] The proof system to use. Default: Groth16
 pub async fn prove(quote: Vec<u8>, proof_type: ProofType, proof_system: Option<ProofSystem>) -> Result<ProofResponse> {
-    tracing::info!("Begin fetching the necessary collaterals...");
-    // Step 1: Determine quote version and TEE type
+    // Raw quotes are verified by the contract against the on-chain PCCS, nothing to prove
+    if proof_type == ProofType::RawQuote {
+        read_quote_header(&quote)?;
+        tracing::info!("Raw quote requested, skipping proof generation");
+        return Ok(ProofResponse {
+            proof: DcapProof {
+                verified_output: vec![],
+                proof: ZkvmProof::RawQuote(quote),
+            },
+            proof_type,
+            prover_request_id: None,
+        });
+    }
+
+    let input = fetch_input(&quote, proof_type).await?;
+    prove_input(input, proof_type, proof_system).await
+}
+
+/// Returns the quote version and TEE type after checking both are supported
+fn read_quote_header(quote: &[u8]) -> Result<(u16, u32)> {
+    if quote.len() < 8 {
+        return Err(ProveError::QuoteParse("Quote too short".to_string()).into());
+    }
     let quote_version = u16::from_le_bytes([quote[0], quote[1]]);
     let tee_type = u32::from_le_bytes([quote[4], quote[5], quote[6], quote[7]]);
 
@@ -36,27 +63,35 @@ pub async fn prove(quote: Vec<u8>, proof_type: ProofType, proof_system: Option<P
     tracing::info!("TEE Type: {}", tee_type);
 
     if !(3..=4).contains(&quote_version) {
-        return Err(anyhow!("Unsupported quote version {}", quote_version));
+        return Err(ProveError::QuoteParse(format!("Unsupported quote version {}", quote_version)).into());
     }
 
     if tee_type != SGX_TEE_TYPE && tee_type != TDX_TEE_TYPE {
-        return Err(anyhow!("Unsupported tee type {}", tee_type));
+        return Err(ProveError::QuoteParse(format!("Unsupported tee type {}", tee_type)).into());
     }
+    Ok((quote_version, tee_type))
+}
+
+/// Fetches the collaterals of `quote` from the on-chain PCCS and builds the zkVM input
+pub async fn fetch_input(quote: &[u8], proof_type: ProofType) -> Result<Vec<u8>> {
+    tracing::info!("Begin fetching the necessary collaterals...");
+    // Step 1: Determine quote version and TEE type
+    let (quote_version, tee_type) = read_quote_header(quote)?;
 
     tracing::debug!("Quote read successfully. Begin fetching collaterals from the on-chain PCCS");
 
-    let (root_ca, root_ca_crl) = get_certificate_by_id(CA::ROOT).await?;
+    let (root_ca, root_ca_crl) = telemetry::time_collateral("pcs", get_certificate_by_id(CA::ROOT)).await?;
     if root_ca.is_empty() || root_ca_crl.is_empty() {
-        return Err(anyhow!("Intel SGX Root CA is missing"));
+        return Err(ProveError::CollateralMissing("Intel SGX Root CA".to_string()).into());
     } else {
         tracing::debug!("Fetched Intel SGX RootCA and CRL");
     }
 
-    let (fmspc, pck_type, pck_issuer) = get_pck_fmspc_and_issuer(&quote, quote_version, tee_type);
+    let (fmspc, pck_type, pck_issuer) = get_pck_fmspc_and_issuer(quote, quote_version, tee_type);
 
     let tcb_type: u8 = if tee_type == TDX_TEE_TYPE { 1 } else { 0 };
     let tcb_version: u32 = if quote_version < 4 { 2 } else { 3 };
-    let tcb_info = get_tcb_info(tcb_type, fmspc.as_str(), tcb_version).await?;
+    let tcb_info = telemetry::time_collateral("fmspc_tcb", get_tcb_info(tcb_type, fmspc.as_str(), tcb_version)).await?;
 
     tracing::debug!("Fetched TCBInfo JSON for FMSPC: {}", fmspc);
 
@@ -65,19 +100,19 @@ pub async fn prove(quote: Vec<u8>, proof_type: ProofType, proof_system: Option<P
     } else {
         EnclaveIdType::QE
     };
-    let qe_identity = get_enclave_identity(qe_id_type, quote_version as u32).await?;
+    let qe_identity = telemetry::time_collateral("enclave_id", get_enclave_identity(qe_id_type, quote_version as u32)).await?;
     tracing::debug!("Fetched QEIdentity JSON");
 
-    let (signing_ca, _) = get_certificate_by_id(CA::SIGNING).await?;
+    let (signing_ca, _) = telemetry::time_collateral("pcs", get_certificate_by_id(CA::SIGNING)).await?;
     if signing_ca.is_empty() {
-        return Err(anyhow!("Intel TCB Signing CA is missing"));
+        return Err(ProveError::CollateralMissing("Intel TCB Signing CA".to_string()).into());
     } else {
         tracing::debug!("Fetched Intel TCB Signing CA");
     }
 
-    let (_, pck_crl) = get_certificate_by_id(pck_type).await?;
+    let (_, pck_crl) = telemetry::time_collateral("pcs", get_certificate_by_id(pck_type)).await?;
     if pck_crl.is_empty() {
-        return Err(anyhow!("CRL for {} is missing", pck_issuer));
+        return Err(ProveError::CollateralMissing(format!("CRL for {}", pck_issuer)).into());
     } else {
         tracing::debug!("Fetched Intel PCK CRL for {}", pck_issuer);
     }
@@ -108,14 +143,22 @@ pub async fn prove(quote: Vec<u8>, proof_type: ProofType, proof_system: Option<P
     let intel_collaterals_bytes = serialize_collaterals(proof_type, &intel_collaterals, pck_type)?;
 
     // Step 3: Generate the input to upload to Proving Server
-    let input = generate_input(&quote, &intel_collaterals_bytes);
+    tracing::info!("All collaterals found!");
+    Ok(generate_input(quote, &intel_collaterals_bytes))
+}
 
-    tracing::info!("All collaterals found! Begin uploading input to Proving Server...");
+/// Uploads an input built by `fetch_input` to the Proving Server
+pub async fn prove_input(input: Vec<u8>, proof_type: ProofType, proof_system: Option<ProofSystem>) -> Result<ProofResponse> {
+    tracing::info!("Begin uploading input to Proving Server...");
 
-    match proof_type {
+    let started = Instant::now();
+    let result = match proof_type {
         ProofType::Sp1 => sp1_prove(input, proof_system).await,
-        ProofType::Risc0 => risc0_prove(input, proof_system).await
-    }
+        ProofType::Risc0 => risc0_prove(input, proof_system).await,
+        ProofType::RawQuote => return Err(anyhow!("Raw quotes are not proven")),
+    };
+    telemetry::record_proof(proof_type, proof_system, started.elapsed(), result.is_ok());
+    result
 }
 
 // Modified from https://github.com/automata-network/dcap-rs/blob/b218a9dcdf2aec8ee05f4d2bd055116947ddfced/src/types/collaterals.rs#L35-L105
@@ -220,6 +263,9 @@ pub async fn verify_proof(proof: &DcapProof) -> Result<VerifiedOutput> {
         ZkvmProof::Risc0((receipt, image_id, _seal)) => {
             receipt.verify(*image_id)?;
         }
+        ZkvmProof::RawQuote(_) => {
+            return Err(anyhow!("Raw quotes are only verified on-chain"));
+        }
     };
     Ok(VerifiedOutput::from_bytes(&proof.verified_output))
 }
@@ -238,13 +284,15 @@ pub async fn submit_proof(
     let verified_output = proof.verified_output;
     let (program_output, proof) = match proof.proof {
         ZkvmProof::Sp1((proof_bytes, _, sp1_proof)) => (proof_bytes, sp1_proof.bytes()),
-        ZkvmProof::Risc0((receipt, _image_id, seal)) => (receipt.journal.bytes, seal)
+        ZkvmProof::Risc0((receipt, _image_id, seal)) => (receipt.journal.bytes, seal),
+        ZkvmProof::RawQuote(quote) => (vec![], quote),
     };
 
     match verify_only {
         true => {
             tracing::info!("Verify only mode enabled");
 
+            // Staticcalls are not signed, so no prover account is needed
             let tx_sender = TxSender::new(
                 parameter::get(
                     "AUTOMATA_DEFAULT_RPC_URL",
@@ -252,8 +300,8 @@ pub async fn submit_proof(
                 ).as_str(),
                 AUTOMATA_DEFAULT_DCAP_CONTRACT,
                 None,
-                Some(parameter::get("PROVER_PRIVATE_KEY", None).as_str())
-            ).expect("Failed to create txSender");
+                None
+            )?;
 
             // staticcall to the Halo prove request contract to verify proof
             let calldata = generate_attestation_calldata(&program_output, proof_type, &proof);
@@ -266,15 +314,20 @@ pub async fn submit_proof(
 
             if chain_verified && verified_output != chain_raw_verified_output {
                 tracing::info!("On-chain verification succeed.");
+                telemetry::record_submission("verified");
                 return Ok((true, chain_raw_verified_output, None, None));
             }
             tracing::error!("On-chain verification fail!");
+            telemetry::record_submission("rejected");
             Ok((chain_verified, chain_raw_verified_output, None, None))
         },
         false => {
+            // The request may have been cancelled or expired while the proof was being generated
+            request.ensure_active()?;
+
             tracing::info!("Submitting proof transaction...");
 
-            let tx_sender = TxSender::new(
+            let tx_sender = TxSender::from_config(
                 parameter::get(
                     "DEFAULT_RPC_URL",
                     Some("https://mainnet.base.org")
@@ -284,8 +337,8 @@ pub async fn submit_proof(
                     Some("0x9E4a45c40e06CE0653C33769138dF48802c1CF1e")
                 ).as_str(),
                 Some(NamedChain::Base),
-                Some(parameter::get("PROVER_PRIVATE_KEY", None).as_str())
-            ).expect("Failed to create txSender");
+                &SignerConfig::from_env()?
+            ).await?;
 
             let calldata = generate_prove_calldata(&request, proof_type, &program_output, &proof);
             tracing::info!("Calldata: {}", hex::encode(&calldata));
@@ -295,20 +348,30 @@ pub async fn submit_proof(
                     tracing::info!("Transaction hash: {}", tx_hash);
                     tracing::info!("Transaction receipt: {:#?}", receipt);
                     match receipt {
-                        Some(_receipt) => {
+                        Some(receipt) => {
+                            let cost = TransactionCost::from_receipt(&receipt);
+                            tracing::info!(
+                                "Transaction cost: gas_used: {} effective_gas_price: {} fee_wei: {} block: {:?} signer: {}",
+                                cost.gas_used, cost.effective_gas_price, cost.fee_wei, cost.block_number, cost.signer
+                            );
+                            telemetry::record_submission(if cost.success { "mined" } else { "reverted" });
+                            telemetry::record_gas(&cost);
                             Ok((true, verified_output, Some(tx_hash), Some(SubmitProofResponse {
                                 transaction_hash: tx_hash,
                                 proof_type: ProofType::Sp1,
-                                status: TdxQuoteStatus::Success
+                                status: TdxQuoteStatus::Submitted,
+                                cost,
                             })))
                         },
                         None => {
+                            telemetry::record_submission("no_receipt");
                             Ok((false, verified_output, Some(tx_hash), None))
                         }
                     }
                 },
                 Err(e) => {
                     tracing::error!("Failed to submit proof transaction: {}", e);
+                    telemetry::record_submission("send_failed");
                     Ok((false, verified_output, None, None))
                 }
             }
@@ -316,6 +379,68 @@ pub async fn submit_proof(
     }
 }
 
+/// Runs the calldata `submit_proof` would send in an embedded EVM instead of a live RPC.
+///
+/// The state is read from `snapshot_path` when it exists. Otherwise it is forked once from
+/// `SIMULATION_RPC_URL` and saved to `snapshot_path` for later offline runs. `code` replaces the
+/// runtime bytecode of contracts in the simulated state only, the snapshot file is left as forked.
+pub async fn simulate_proof(
+    request: &OnchainRequest,
+    proof_type: ProofType,
+    proof: &DcapProof,
+    verify_only: bool,
+    snapshot_path: Option<&Path>,
+    code: &[CodeOverride],
+) -> Result<SimulationResult> {
+    let (program_output, proof) = match &proof.proof {
+        ZkvmProof::Sp1((proof_bytes, _, sp1_proof)) => (proof_bytes.clone(), sp1_proof.bytes()),
+        ZkvmProof::Risc0((receipt, _image_id, seal)) => (receipt.journal.bytes.clone(), seal.clone()),
+        ZkvmProof::RawQuote(quote) => (vec![], quote.clone()),
+    };
+
+    let (contract, calldata, from) = match verify_only {
+        // Staticcalls are not signed, so no prover account is needed
+        true => (
+            AUTOMATA_DEFAULT_DCAP_CONTRACT.to_string(),
+            generate_attestation_calldata(&program_output, proof_type, &proof),
+            Address::ZERO,
+        ),
+        false => (
+            parameter::get("DEFAULT_DCAP_CONTRACT", Some("0x9E4a45c40e06CE0653C33769138dF48802c1CF1e")),
+            generate_prove_calldata(request, proof_type, &program_output, &proof),
+            SignerConfig::from_env()?.address()?,
+        ),
+    };
+    let contract = contract.parse::<Address>()?;
+
+    let mut snapshot = match snapshot_path {
+        Some(path) if path.exists() => StateSnapshot::load(path)?,
+        _ => {
+            // Forking issues many requests, so it never falls back to the submission endpoints
+            let rpc_url = parameter::try_get("SIMULATION_RPC_URL")
+                .ok_or_else(|| anyhow!("Simulating needs SIMULATION_RPC_URL or an existing snapshot file"))?;
+            let rpc_url = RpcPool::new(&rpc_url)?.ranked_urls().into_iter().next()
+                .ok_or_else(|| anyhow!("No RPC endpoint configured"))?;
+            let snapshot = StateSnapshot::fork(&rpc_url, from, contract, &calldata, None).await?;
+            if let Some(path) = snapshot_path {
+                snapshot.save(path)?;
+            }
+            snapshot
+        }
+    };
+    for code_override in code {
+        tracing::info!("Simulating with the bytecode of {} at {}", code_override.path.display(), code_override.address);
+        snapshot = snapshot.with_code(code_override.address, code_override.read()?);
+    }
+
+    let result = snapshot.simulate(from, contract, &calldata)?;
+    tracing::info!(
+        "Simulation at block {}: success: {} gas_used: {} revert_reason: {:?}",
+        snapshot.block_number, result.success, result.gas_used, result.revert_reason
+    );
+    Ok(result)
+}
+
 pub fn extract_proof_output(execution_output: Vec<u8>) -> Vec<u8> {
     let output_len = u16::from_be_bytes([execution_output[0], execution_output[1]]) as usize;
     let mut output = Vec::with_capacity(output_len);
@@ -323,15 +448,18 @@ pub fn extract_proof_output(execution_output: Vec<u8>) -> Vec<u8> {
     output
 }
 
-pub fn deserialize_output(proof: DcapProof) -> VerifiedOutput {
+/// Verified output committed by the zkVM program, `None` for raw quotes whose output is only
+/// returned by the verifier contract
+pub fn deserialize_output(proof: DcapProof) -> Option<VerifiedOutput> {
     let program_output = match proof.proof {
         ZkvmProof::Sp1((proof_bytes, _, _)) => proof_bytes,
-        ZkvmProof::Risc0((receipt, _, _)) => receipt.journal.bytes
+        ZkvmProof::Risc0((receipt, _, _)) => receipt.journal.bytes,
+        ZkvmProof::RawQuote(_) => return None,
     };
     let proof_output = extract_proof_output(program_output);
     let deserialized_output = VerifiedOutput::from_bytes(&proof_output);
     tracing::debug!("Deserialized output: {:?}", deserialized_output);
-    deserialized_output
+    Some(deserialized_output)
 }
 
 pub fn generate_input(quote: &[u8], collaterals: &[u8]) -> Vec<u8> {
@@ -463,4 +591,13 @@ mod tests {
             Err(e) => assert!(false, "Failed to submit proof transaction: {}", e)
         }
     }
+
+    #[test]
+    fn raw_quotes_have_no_program_output() {
+        let proof = crate::entity::zk::DcapProof {
+            verified_output: vec![],
+            proof: crate::entity::zk::ZkvmProof::RawQuote(vec![0x04, 0x00]),
+        };
+        assert!(super::deserialize_output(proof).is_none());
+    }
 }
@@ -6,6 +6,7 @@ use risc0_zkvm::{
 use tokio::task;
 use crate::{
     entity::{quote::ProofType, zk::{DcapProof, ProofResponse, ProofSystem, ZkvmProof, DCAP_RISC0_ELF}},
+    error::prove_error::ProveError,
     zk::extract_proof_output
 };
 
@@ -20,12 +21,13 @@ pub async fn prove(collateral_input: Vec<u8>, proof_system: Option<ProofSystem>)
 
     // BonsaiProver uses the reqwest blocking client as the default (and only option).
     // It will cause issues when running in async contexts unless explicitly ran in a task that can block (context)
-    let receipt = task::spawn_blocking(move || {
-        let env = ExecutorEnv::builder().write_slice(&collateral_input).build().unwrap();
-        default_prover()
-            .prove_with_opts(env, DCAP_RISC0_ELF, &ProverOpts::groth16()).unwrap()
-            .receipt
-    }).await?;
+    let receipt = task::spawn_blocking(move || -> Result<_> {
+        let env = ExecutorEnv::builder().write_slice(&collateral_input).build()?;
+        let prove_info = default_prover()
+            .prove_with_opts(env, DCAP_RISC0_ELF, &ProverOpts::groth16())
+            .map_err(|e| ProveError::ProverNetwork(e.to_string()))?;
+        Ok(prove_info.receipt)
+    }).await??;
 
     let image_id = compute_image_id(DCAP_RISC0_ELF).unwrap();
     receipt.verify(image_id)?;
@@ -5,7 +5,7 @@ use std::time::Duration;
 use crate::{entity::{
     quote::ProofType,
     zk::{DcapProof, ProofResponse, ProofSystem, ZkvmProof, DCAP_SP1_ELF}
-}, zk::extract_proof_output};
+}, error::prove_error::ProveError, zk::extract_proof_output};
 
 use anyhow::Result;
 use sp1_sdk::{network::FulfillmentStrategy, HashableKey, Prover, ProverClient, SP1Stdin};
@@ -39,7 +39,7 @@ pub async fn prove(collateral_input: Vec<u8>, proof_system: Option<ProofSystem>)
                 .strategy(FulfillmentStrategy::Reserved)
                 .request_async()
                 .await
-                .unwrap()
+                .map_err(|e| ProveError::ProverNetwork(e.to_string()))?
         } else {
             client.prove(&pk, &stdin)
                 .plonk()
@@ -47,7 +47,7 @@ pub async fn prove(collateral_input: Vec<u8>, proof_system: Option<ProofSystem>)
                 .strategy(FulfillmentStrategy::Reserved)
                 .request_async()
                 .await
-                .unwrap()
+                .map_err(|e| ProveError::ProverNetwork(e.to_string()))?
         }
     } else {
         client.prove(&pk, &stdin)
@@ -56,7 +56,7 @@ pub async fn prove(collateral_input: Vec<u8>, proof_system: Option<ProofSystem>)
             .strategy(FulfillmentStrategy::Reserved)
             .request_async()
             .await
-            .unwrap()
+            .map_err(|e| ProveError::ProverNetwork(e.to_string()))?
     };
     tracing::info!("Prover Request ID: {}", hex::encode(prover_request_id));
 
@@ -64,7 +64,7 @@ pub async fn prove(collateral_input: Vec<u8>, proof_system: Option<ProofSystem>)
     let proof = client.wait_proof(
         prover_request_id,
         Some(Duration::from_secs(15 * 60))
-    ).await.unwrap();
+    ).await?;
 
     let journal = proof.public_values.as_slice();
     let raw_verified_output = extract_proof_output(journal.to_vec());